@@ -0,0 +1,319 @@
+/*-
+ * #%L
+ * OBKV Table Client Framework
+ * %%
+ * Copyright (C) 2021 OceanBase
+ * %%
+ * OBKV Table Client Framework is licensed under Mulan PSL v2.
+ * You can use this software according to the terms and conditions of the
+ * Mulan PSL v2. You may obtain a copy of Mulan PSL v2 at:
+ *          http://license.coscl.org.cn/MulanPSL2
+ * THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ * KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ * NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ * See the Mulan PSL v2 for more details.
+ * #L%
+ */
+
+//! A blocking client, for the synchronous callers.
+//!
+//! The operations of [`ObTableClient`] block the current thread until they
+//! complete, running on the background runtime of the wrapped asynchronous
+//! client. They fail with [`CommonErrCode::BlockingInRuntime`] on the threads
+//! of a tokio runtime, where the asynchronous client must be used instead.
+//!
+//! This includes the blocking threads of `spawn_blocking`, which tokio 1.28
+//! can't tell from the workers of a runtime. Code running there can block on
+//! the asynchronous client with `Handle::current().block_on`, e.g. on
+//! `client.as_async().get(..)`.
+
+use std::{collections::HashMap, future::Future, time::Duration};
+
+use tokio::runtime::Handle;
+
+use super::{
+    query::QueryResultSet,
+    table_client::{self, Builder, ObTableAggregation, ObTableClientQueryImpl},
+    TableOpResult,
+};
+use crate::{
+    error::{CommonErrCode, Error::Common as CommonErr, Result},
+    rpc::protocol::payloads::{ObTableBatchOperation, ObTableConsistencyLevel},
+    runtime::RuntimeRef,
+    serde_obkv::value::Value,
+};
+
+/// Fails if the current thread runs in a tokio runtime, where blocking on an
+/// operation could stall the runtime or panic.
+fn check_blocking_allowed(op: &str) -> Result<()> {
+    if Handle::try_current().is_ok() {
+        return Err(CommonErr(
+            CommonErrCode::BlockingInRuntime,
+            format!(
+                "blocking::ObTableClient::{op} can't be called inside a tokio runtime, even from \
+                 spawn_blocking, use the async ObTableClient instead, or block on it with \
+                 Handle::current().block_on in spawn_blocking"
+            ),
+        ));
+    }
+    Ok(())
+}
+
+fn block_on<F: Future>(runtime: &RuntimeRef, op: &str, future: F) -> Result<F::Output> {
+    check_blocking_allowed(op)?;
+    Ok(runtime.block_on(future))
+}
+
+/// A blocking OBKV Table client, see [the module docs](self).
+#[derive(Clone)]
+pub struct ObTableClient {
+    client: table_client::ObTableClient,
+    runtime: RuntimeRef,
+}
+
+impl From<table_client::ObTableClient> for ObTableClient {
+    fn from(client: table_client::ObTableClient) -> Self {
+        let runtime = client.runtimes().bg_runtime.clone();
+        ObTableClient { client, runtime }
+    }
+}
+
+impl ObTableClient {
+    /// Build and initialize a client with `builder`.
+    pub fn build(builder: Builder) -> Result<Self> {
+        check_blocking_allowed("build")?;
+        let client = builder.build()?;
+        client.init()?;
+        Ok(client.into())
+    }
+
+    /// The asynchronous client wrapped, sharing the connections.
+    pub fn as_async(&self) -> &table_client::ObTableClient {
+        &self.client
+    }
+
+    /// A client sharing this one, whose single and batch operations are
+    /// given `deadline` in total, see
+    /// [`table_client::ObTableClient::with_deadline`].
+    pub fn with_deadline(&self, deadline: Duration) -> ObTableClient {
+        self.client.with_deadline(deadline).into()
+    }
+
+    /// A client sharing this one, whose gets and queries are read with
+    /// `consistency`, see
+    /// [`table_client::ObTableClient::with_read_consistency`].
+    pub fn with_read_consistency(&self, consistency: ObTableConsistencyLevel) -> ObTableClient {
+        self.client.with_read_consistency(consistency).into()
+    }
+
+    pub fn insert(
+        &self,
+        table_name: &str,
+        row_keys: Vec<Value>,
+        columns: Vec<String>,
+        properties: Vec<Value>,
+    ) -> Result<i64> {
+        block_on(
+            &self.runtime,
+            "insert",
+            self.client
+                .insert(table_name, row_keys, columns, properties),
+        )?
+    }
+
+    pub fn update(
+        &self,
+        table_name: &str,
+        row_keys: Vec<Value>,
+        columns: Vec<String>,
+        properties: Vec<Value>,
+    ) -> Result<i64> {
+        block_on(
+            &self.runtime,
+            "update",
+            self.client
+                .update(table_name, row_keys, columns, properties),
+        )?
+    }
+
+    pub fn insert_or_update(
+        &self,
+        table_name: &str,
+        row_keys: Vec<Value>,
+        columns: Vec<String>,
+        properties: Vec<Value>,
+    ) -> Result<i64> {
+        block_on(
+            &self.runtime,
+            "insert_or_update",
+            self.client
+                .insert_or_update(table_name, row_keys, columns, properties),
+        )?
+    }
+
+    pub fn replace(
+        &self,
+        table_name: &str,
+        row_keys: Vec<Value>,
+        columns: Vec<String>,
+        properties: Vec<Value>,
+    ) -> Result<i64> {
+        block_on(
+            &self.runtime,
+            "replace",
+            self.client
+                .replace(table_name, row_keys, columns, properties),
+        )?
+    }
+
+    pub fn append(
+        &self,
+        table_name: &str,
+        row_keys: Vec<Value>,
+        columns: Vec<String>,
+        properties: Vec<Value>,
+    ) -> Result<i64> {
+        block_on(
+            &self.runtime,
+            "append",
+            self.client
+                .append(table_name, row_keys, columns, properties),
+        )?
+    }
+
+    pub fn increment(
+        &self,
+        table_name: &str,
+        row_keys: Vec<Value>,
+        columns: Vec<String>,
+        properties: Vec<Value>,
+    ) -> Result<i64> {
+        block_on(
+            &self.runtime,
+            "increment",
+            self.client
+                .increment(table_name, row_keys, columns, properties),
+        )?
+    }
+
+    pub fn delete(&self, table_name: &str, row_keys: Vec<Value>) -> Result<i64> {
+        block_on(
+            &self.runtime,
+            "delete",
+            self.client.delete(table_name, row_keys),
+        )?
+    }
+
+    pub fn get(
+        &self,
+        table_name: &str,
+        row_keys: Vec<Value>,
+        columns: Vec<String>,
+    ) -> Result<HashMap<String, Value>> {
+        block_on(
+            &self.runtime,
+            "get",
+            self.client.get(table_name, row_keys, columns),
+        )?
+    }
+
+    #[inline]
+    pub fn batch_operation(&self, ops_num_hint: usize) -> ObTableBatchOperation {
+        self.client.batch_operation(ops_num_hint)
+    }
+
+    pub fn execute_batch(
+        &self,
+        table_name: &str,
+        batch_op: ObTableBatchOperation,
+    ) -> Result<Vec<TableOpResult>> {
+        block_on(
+            &self.runtime,
+            "execute_batch",
+            self.client.execute_batch(table_name, batch_op),
+        )?
+    }
+
+    /// A query to build and run with [`ObTableClient::execute_query`].
+    #[inline]
+    pub fn query(&self, table_name: &str) -> ObTableClientQueryImpl {
+        self.client.query(table_name)
+    }
+
+    /// Run `query`, iterating over the rows found.
+    pub fn execute_query(&self, query: ObTableClientQueryImpl) -> Result<QueryResultIter> {
+        let result_set = block_on(&self.runtime, "execute_query", query.execute())??;
+        Ok(QueryResultIter {
+            result_set,
+            runtime: self.runtime.clone(),
+        })
+    }
+
+    /// An aggregation to build and run with
+    /// [`ObTableClient::execute_aggregation`].
+    #[inline]
+    pub fn aggregate(&self, table_name: &str) -> ObTableAggregation {
+        self.client.aggregate(table_name)
+    }
+
+    pub fn execute_aggregation(
+        &self,
+        aggregation: ObTableAggregation,
+    ) -> Result<HashMap<String, Value>> {
+        block_on(&self.runtime, "execute_aggregation", aggregation.execute())?
+    }
+
+    /// Shut the client down, see [`table_client::ObTableClient::shutdown`].
+    pub fn shutdown(&self, timeout: Duration) -> Result<()> {
+        block_on(&self.runtime, "shutdown", self.client.shutdown(timeout))?
+    }
+}
+
+/// Iterator over the rows found by a query of the blocking client, each
+/// fetched on demand. The stream of rows is closed once the iteration ends,
+/// fails or the iterator is dropped.
+pub struct QueryResultIter {
+    result_set: QueryResultSet,
+    runtime: RuntimeRef,
+}
+
+impl QueryResultIter {
+    /// Close the stream of rows before the end.
+    pub fn close(&mut self) -> Result<()> {
+        block_on(&self.runtime, "close", self.result_set.close())?
+    }
+}
+
+impl Iterator for QueryResultIter {
+    type Item = Result<HashMap<String, Value>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let row = match block_on(&self.runtime, "next", self.result_set.next()) {
+            Ok(row) => row,
+            Err(e) => return Some(Err(e)),
+        };
+        if !matches!(row, Some(Ok(_))) {
+            if let Err(e) = self.close() {
+                warn!(
+                    "QueryResultIter::next fail to close the result set, err:{}",
+                    e
+                );
+            }
+        }
+        row
+    }
+}
+
+impl Drop for QueryResultIter {
+    fn drop(&mut self) {
+        if self.result_set.check_close().is_ok() {
+            return;
+        }
+        if let Err(e) = self.close() {
+            error!(
+                "QueryResultIter::drop fail to close the result set, err:{}",
+                e
+            );
+        }
+    }
+}
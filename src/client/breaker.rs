@@ -0,0 +1,422 @@
+/*-
+ * #%L
+ * OBKV Table Client Framework
+ * %%
+ * Copyright (C) 2021 OceanBase
+ * %%
+ * OBKV Table Client Framework is licensed under Mulan PSL v2.
+ * You can use this software according to the terms and conditions of the
+ * Mulan PSL v2. You may obtain a copy of Mulan PSL v2 at:
+ *          http://license.coscl.org.cn/MulanPSL2
+ * THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ * KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ * NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ * See the Mulan PSL v2 for more details.
+ * #L%
+ */
+
+//! Circuit breakers of the servers.
+
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    error::{CommonErrCode, Error, Error::Common as CommonErr, Result},
+    monitors::{
+        breaker_metrics::{CircuitBreakerMetrics, ServerLabels},
+        prometheus::OBKV_CLIENT_REGISTRY,
+    },
+    rpc::protocol::codes::ResultCodes,
+};
+
+lazy_static! {
+    pub static ref OBKV_BREAKER_METRICS: CircuitBreakerMetrics = {
+        let breaker_metrics = CircuitBreakerMetrics::default();
+        breaker_metrics.register(&mut OBKV_CLIENT_REGISTRY.lock().unwrap().registry);
+        breaker_metrics
+    };
+}
+
+/// Settings of the circuit breakers of the servers, see
+/// [`crate::ClientConfig::circuit_breaker`].
+///
+/// A breaker opens once the latest requests to its server failed too often or
+/// too many of them failed in a row, the requests failing fast while it's
+/// open. After `open_duration`, it's half-open: `half_open_probes` requests
+/// probe the server and close the breaker if they all succeed, any failure
+/// opens it again. Only the failures of the servers count, such as the
+/// timeouts and the broken connections, not the errors of the operations.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CircuitBreakerConfig {
+    /// Latest requests the failure rate is computed over.
+    pub window_size: usize,
+    /// Requests in the window before the failure rate may open the breaker.
+    pub min_requests: usize,
+    /// Failure rate of the window opening the breaker, in percent.
+    pub failure_rate_percent: usize,
+    /// Failures in a row opening the breaker whatever the rate.
+    pub consecutive_failures: usize,
+    /// Time the breaker stays open before probing the server.
+    pub open_duration: Duration,
+    /// Probe requests closing a half-open breaker.
+    pub half_open_probes: usize,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        CircuitBreakerConfig {
+            window_size: 50,
+            min_requests: 20,
+            failure_rate_percent: 50,
+            consecutive_failures: 10,
+            open_duration: Duration::from_secs(5),
+            half_open_probes: 3,
+        }
+    }
+}
+
+/// State of a circuit breaker.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CircuitState {
+    /// The requests are sent.
+    Closed,
+    /// The requests fail fast.
+    Open,
+    /// Probe requests are sent, the others fail fast.
+    HalfOpen,
+}
+
+impl CircuitState {
+    fn as_str(&self) -> &str {
+        match self {
+            CircuitState::Closed => "closed",
+            CircuitState::Open => "open",
+            CircuitState::HalfOpen => "half_open",
+        }
+    }
+
+    fn metric_value(&self) -> i64 {
+        match self {
+            CircuitState::Closed => 0,
+            CircuitState::Open => 1,
+            CircuitState::HalfOpen => 2,
+        }
+    }
+}
+
+/// Whether `error` tells that the server failed, rather than the operation.
+/// Server side timeouts are left out, they are mostly slow operations, such
+/// as lock waits, rather than servers down.
+pub(crate) fn is_server_failure(error: &Error) -> bool {
+    match error {
+        Error::AmbiguousOutcome(e) => is_server_failure(e),
+        Error::Shared(e) => is_server_failure(e),
+        Error::IO(_) | Error::Canceled(_) => true,
+        Error::Common(CommonErrCode::ObException(code), _) => matches!(
+            code,
+            ResultCodes::OB_RPC_CONNECT_ERROR
+                | ResultCodes::OB_RS_SHUTDOWN
+                | ResultCodes::OB_SERVER_IS_INIT
+                | ResultCodes::OB_SERVER_IS_STOPPING
+        ),
+        Error::Common(code, _) => matches!(
+            code,
+            CommonErrCode::Rpc | CommonErrCode::ConnPool | CommonErrCode::BrokenPipe
+        ),
+        _ => false,
+    }
+}
+
+struct BreakerState {
+    state: CircuitState,
+    // latest outcomes while closed, true for the failures
+    outcomes: VecDeque<bool>,
+    failures: usize,
+    consecutive_failures: usize,
+    opened_at: Instant,
+    // probes let through and succeeded while half-open
+    probes: usize,
+    probe_successes: usize,
+}
+
+/// Circuit breaker of a server, see [`CircuitBreakerConfig`].
+pub(crate) struct CircuitBreaker {
+    server: String,
+    config: CircuitBreakerConfig,
+    state: Mutex<BreakerState>,
+}
+
+/// A request let through by a [`CircuitBreaker`], whose outcome is recorded
+/// with [`BreakerPermit::record`]. A probe dropped unrecorded frees its slot.
+pub(crate) struct BreakerPermit<'a> {
+    breaker: &'a CircuitBreaker,
+    probe: bool,
+    recorded: bool,
+}
+
+impl<'a> BreakerPermit<'a> {
+    pub fn record<T>(mut self, result: &Result<T>) {
+        self.recorded = true;
+        let failed = matches!(result, Err(e) if is_server_failure(e));
+        self.breaker.on_result(self.probe, failed);
+    }
+}
+
+impl<'a> Drop for BreakerPermit<'a> {
+    fn drop(&mut self) {
+        if self.probe && !self.recorded {
+            let mut state = self.breaker.state.lock().unwrap();
+            if state.state == CircuitState::HalfOpen {
+                state.probes -= 1;
+            }
+        }
+    }
+}
+
+impl CircuitBreaker {
+    pub fn new(server: String, config: CircuitBreakerConfig) -> Self {
+        OBKV_BREAKER_METRICS
+            .get_state()
+            .get_or_create(&ServerLabels {
+                server: server.clone(),
+            })
+            .set(CircuitState::Closed.metric_value());
+        CircuitBreaker {
+            server,
+            config,
+            state: Mutex::new(BreakerState {
+                state: CircuitState::Closed,
+                outcomes: VecDeque::new(),
+                failures: 0,
+                consecutive_failures: 0,
+                opened_at: Instant::now(),
+                probes: 0,
+                probe_successes: 0,
+            }),
+        }
+    }
+
+    /// The current state, an open breaker due for probing being half-open.
+    pub fn state(&self) -> CircuitState {
+        let state = self.state.lock().unwrap();
+        match state.state {
+            CircuitState::Open if state.opened_at.elapsed() >= self.config.open_duration => {
+                CircuitState::HalfOpen
+            }
+            s => s,
+        }
+    }
+
+    /// Whether a request would be let through now.
+    pub fn is_available(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        match state.state {
+            CircuitState::Closed => true,
+            CircuitState::Open => state.opened_at.elapsed() >= self.config.open_duration,
+            CircuitState::HalfOpen => state.probes < self.config.half_open_probes,
+        }
+    }
+
+    /// Let a request through, or fail fast if the breaker is open.
+    pub fn acquire(&self) -> Result<BreakerPermit<'_>> {
+        let mut state = self.state.lock().unwrap();
+        if state.state == CircuitState::Open
+            && state.opened_at.elapsed() >= self.config.open_duration
+        {
+            self.transit(&mut state, CircuitState::HalfOpen);
+        }
+        let probe = match state.state {
+            CircuitState::Closed => false,
+            CircuitState::HalfOpen if state.probes < self.config.half_open_probes => {
+                state.probes += 1;
+                true
+            }
+            _ => {
+                drop(state);
+                OBKV_BREAKER_METRICS.inc_rejected(&self.server);
+                return Err(CommonErr(
+                    CommonErrCode::CircuitBreakerOpen,
+                    format!("circuit breaker of server {} is open", self.server),
+                ));
+            }
+        };
+        Ok(BreakerPermit {
+            breaker: self,
+            probe,
+            recorded: false,
+        })
+    }
+
+    fn on_result(&self, probe: bool, failed: bool) {
+        let mut state = self.state.lock().unwrap();
+        match state.state {
+            CircuitState::Closed if !probe => {
+                state.outcomes.push_back(failed);
+                if failed {
+                    state.failures += 1;
+                    state.consecutive_failures += 1;
+                } else {
+                    state.consecutive_failures = 0;
+                }
+                if state.outcomes.len() > self.config.window_size
+                    && state.outcomes.pop_front() == Some(true)
+                {
+                    state.failures -= 1;
+                }
+
+                let requests = state.outcomes.len();
+                if state.consecutive_failures >= self.config.consecutive_failures
+                    || (requests >= self.config.min_requests
+                        && state.failures * 100 >= self.config.failure_rate_percent * requests)
+                {
+                    warn!(
+                        "CircuitBreaker::on_result open the breaker of server {}, \
+                         failures:{}/{}, consecutive_failures:{}",
+                        self.server, state.failures, requests, state.consecutive_failures
+                    );
+                    self.transit(&mut state, CircuitState::Open);
+                }
+            }
+            CircuitState::HalfOpen if probe => {
+                if failed {
+                    warn!(
+                        "CircuitBreaker::on_result probe failed, reopen the breaker of server {}",
+                        self.server
+                    );
+                    self.transit(&mut state, CircuitState::Open);
+                } else {
+                    state.probe_successes += 1;
+                    if state.probe_successes >= self.config.half_open_probes {
+                        info!(
+                            "CircuitBreaker::on_result probes succeeded, close the breaker of server {}",
+                            self.server
+                        );
+                        self.transit(&mut state, CircuitState::Closed);
+                    }
+                }
+            }
+            // the requests sent before the state changed
+            _ => (),
+        }
+    }
+
+    fn transit(&self, state: &mut BreakerState, to: CircuitState) {
+        state.state = to;
+        state.outcomes.clear();
+        state.failures = 0;
+        state.consecutive_failures = 0;
+        state.probes = 0;
+        state.probe_successes = 0;
+        if to == CircuitState::Open {
+            state.opened_at = Instant::now();
+        }
+        OBKV_BREAKER_METRICS.set_state(&self.server, to.as_str(), to.metric_value());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn breaker(open_duration: Duration) -> CircuitBreaker {
+        CircuitBreaker::new(
+            "test_breaker".to_owned(),
+            CircuitBreakerConfig {
+                window_size: 4,
+                min_requests: 4,
+                failure_rate_percent: 50,
+                consecutive_failures: 3,
+                open_duration,
+                half_open_probes: 2,
+            },
+        )
+    }
+
+    fn ok() -> Result<()> {
+        Ok(())
+    }
+
+    fn timeout() -> Result<()> {
+        Err(CommonErr(CommonErrCode::Rpc, "timeout".to_owned()))
+    }
+
+    fn record(breaker: &CircuitBreaker, result: Result<()>) {
+        breaker.acquire().unwrap().record(&result);
+    }
+
+    #[test]
+    fn test_is_server_failure() {
+        let ob_err = |code| CommonErr(CommonErrCode::ObException(code), "test".to_owned());
+        assert!(is_server_failure(&ob_err(
+            ResultCodes::OB_RPC_CONNECT_ERROR
+        )));
+        assert!(!is_server_failure(&ob_err(ResultCodes::OB_TIMEOUT)));
+        assert!(!is_server_failure(&ob_err(
+            ResultCodes::OB_TRY_LOCK_ROW_CONFLICT
+        )));
+        assert!(is_server_failure(&timeout().unwrap_err()));
+    }
+
+    #[test]
+    fn test_open_on_failure_rate() {
+        let breaker = breaker(Duration::from_secs(60));
+        for result in [ok(), timeout(), ok()] {
+            record(&breaker, result);
+        }
+        // the errors of the operations don't count
+        let conflict = Err(CommonErr(
+            CommonErrCode::ObException(ResultCodes::OB_TRY_LOCK_ROW_CONFLICT),
+            "conflict".to_owned(),
+        ));
+        record(&breaker, conflict);
+        assert_eq!(CircuitState::Closed, breaker.state());
+
+        record(&breaker, timeout());
+        assert_eq!(CircuitState::Open, breaker.state());
+        assert!(!breaker.is_available());
+        let err = breaker.acquire().err().unwrap();
+        assert_eq!(
+            Some(CommonErrCode::CircuitBreakerOpen),
+            err.common_err_code()
+        );
+    }
+
+    #[test]
+    fn test_open_on_consecutive_failures() {
+        let breaker = breaker(Duration::from_secs(60));
+        record(&breaker, timeout());
+        record(&breaker, timeout());
+        assert_eq!(CircuitState::Closed, breaker.state());
+        record(&breaker, timeout());
+        assert_eq!(CircuitState::Open, breaker.state());
+    }
+
+    #[test]
+    fn test_half_open_probes() {
+        let breaker = breaker(Duration::ZERO);
+        for _ in 0..3 {
+            record(&breaker, timeout());
+        }
+        assert_eq!(CircuitState::HalfOpen, breaker.state());
+
+        // a failed probe opens the breaker again
+        record(&breaker, timeout());
+        assert_eq!(CircuitState::HalfOpen, breaker.state());
+
+        let first = breaker.acquire().unwrap();
+        let second = breaker.acquire().unwrap();
+        assert!(breaker.acquire().is_err());
+        assert!(!breaker.is_available());
+        // a probe dropped unrecorded frees its slot
+        drop(second);
+        assert!(breaker.is_available());
+        first.record(&ok());
+        assert_eq!(CircuitState::HalfOpen, breaker.state());
+        record(&breaker, ok());
+        assert_eq!(CircuitState::Closed, breaker.state());
+        assert!(breaker.is_available());
+    }
+}
@@ -0,0 +1,289 @@
+/*-
+ * #%L
+ * OBKV Table Client Framework
+ * %%
+ * Copyright (C) 2021 OceanBase
+ * %%
+ * OBKV Table Client Framework is licensed under Mulan PSL v2.
+ * You can use this software according to the terms and conditions of the
+ * Mulan PSL v2. You may obtain a copy of Mulan PSL v2 at:
+ *          http://license.coscl.org.cn/MulanPSL2
+ * THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ * KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ * NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ * See the Mulan PSL v2 for more details.
+ * #L%
+ */
+
+//! Hedged reads.
+
+use std::{
+    collections::VecDeque,
+    future::Future,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use tokio::time::sleep;
+
+use crate::{
+    client::table_client::OBKV_CLIENT_METRICS, error::Result,
+    monitors::client_metrics::ObClientHedgedReadType,
+};
+
+/// Settings of the hedged reads, see [`crate::ClientConfig::hedged_reads`].
+///
+/// An eventual read not answered by its replica within the delay is sent
+/// to a second replica as well, the first answer wins and the other read is
+/// cancelled. The delay is the percentile `percentile_permille` of the
+/// latencies of the latest reads of the same kind, kept within `min_delay`
+/// and `max_delay`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HedgeConfig {
+    /// Percentile of the latencies the reads are hedged after, in per mille:
+    /// 950 for the 95th percentile.
+    pub percentile_permille: u32,
+    /// Lower bound of the delay.
+    pub min_delay: Duration,
+    /// Upper bound of the delay, also the delay until `min_samples` reads
+    /// are recorded.
+    pub max_delay: Duration,
+    /// Latest reads the percentile is computed over.
+    pub window_size: usize,
+    /// Reads recorded before the percentile is used.
+    pub min_samples: usize,
+    /// Queries limited to that many rows at most are hedged, the others and
+    /// the streamed ones never are.
+    pub max_query_limit: i32,
+}
+
+impl Default for HedgeConfig {
+    fn default() -> Self {
+        HedgeConfig {
+            percentile_permille: 950,
+            min_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(100),
+            window_size: 1000,
+            min_samples: 100,
+            max_query_limit: 100,
+        }
+    }
+}
+
+/// Kind of the hedged reads, whose latencies are tracked apart.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ReadKind {
+    Get,
+    MultiGet,
+    Query,
+}
+
+impl ReadKind {
+    fn as_str(&self) -> &str {
+        match self {
+            ReadKind::Get => "get",
+            ReadKind::MultiGet => "multi_get",
+            ReadKind::Query => "query",
+        }
+    }
+}
+
+#[derive(Default)]
+struct LatencyWindow {
+    latencies: VecDeque<Duration>,
+    // recorded since the delay was computed
+    recorded: usize,
+    delay: Option<Duration>,
+}
+
+/// Hedges the reads, see [`HedgeConfig`].
+pub(crate) struct Hedger {
+    config: HedgeConfig,
+    // indexed by `ReadKind`
+    windows: [Mutex<LatencyWindow>; 3],
+}
+
+impl Hedger {
+    pub fn new(config: HedgeConfig) -> Self {
+        Hedger {
+            config,
+            windows: Default::default(),
+        }
+    }
+
+    pub fn config(&self) -> &HedgeConfig {
+        &self.config
+    }
+
+    /// The delay after which a read of `kind` is hedged.
+    pub fn delay(&self, kind: ReadKind) -> Duration {
+        self.windows[kind as usize]
+            .lock()
+            .unwrap()
+            .delay
+            .unwrap_or(self.config.max_delay)
+    }
+
+    /// Record the latency of a read of `kind`, the delay being computed again
+    /// every tenth of the window.
+    pub fn record(&self, kind: ReadKind, latency: Duration) {
+        let mut window = self.windows[kind as usize].lock().unwrap();
+        window.latencies.push_back(latency);
+        if window.latencies.len() > self.config.window_size {
+            window.latencies.pop_front();
+        }
+        window.recorded += 1;
+
+        let len = window.latencies.len();
+        if len < self.config.min_samples.max(1)
+            || (window.delay.is_some() && window.recorded < (self.config.window_size / 10).max(1))
+        {
+            return;
+        }
+        let mut latencies: Vec<_> = window.latencies.iter().copied().collect();
+        latencies.sort_unstable();
+        let rank = (len * self.config.percentile_permille as usize).div_ceil(1000);
+        let percentile = latencies[rank.clamp(1, len) - 1];
+        window.delay = Some(percentile.clamp(self.config.min_delay, self.config.max_delay));
+        window.recorded = 0;
+    }
+
+    /// Run the read `primary`, then `hedge` too if `primary` hasn't completed
+    /// within the delay. The first success wins and the other read is
+    /// dropped, the error of `primary` is returned if both fail.
+    pub async fn run<T, P, H>(&self, kind: ReadKind, primary: P, hedge: Option<H>) -> Result<T>
+    where
+        P: Future<Output = Result<T>>,
+        H: Future<Output = Result<T>>,
+    {
+        let start = Instant::now();
+        let res = match hedge {
+            Some(hedge) => self.race(kind, primary, hedge).await,
+            None => primary.await,
+        };
+        if res.is_ok() {
+            self.record(kind, start.elapsed());
+        }
+        res
+    }
+
+    async fn race<T, P, H>(&self, kind: ReadKind, primary: P, hedge: H) -> Result<T>
+    where
+        P: Future<Output = Result<T>>,
+        H: Future<Output = Result<T>>,
+    {
+        tokio::pin!(primary);
+        tokio::select! {
+            res = &mut primary => return res,
+            _ = sleep(self.delay(kind)) => (),
+        }
+
+        OBKV_CLIENT_METRICS.inc_hedged_read(ObClientHedgedReadType::Sent, kind.as_str());
+        tokio::pin!(hedge);
+        tokio::select! {
+            res = &mut primary => match res {
+                Ok(v) => Ok(v),
+                Err(e) => hedge.await.map_err(|_| e),
+            },
+            res = &mut hedge => match res {
+                Ok(v) => {
+                    OBKV_CLIENT_METRICS.inc_hedged_read(ObClientHedgedReadType::Won, kind.as_str());
+                    Ok(v)
+                }
+                Err(_) => primary.await,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::{CommonErrCode, Error::Common as CommonErr};
+
+    fn hedger() -> Hedger {
+        Hedger::new(HedgeConfig {
+            percentile_permille: 900,
+            min_delay: Duration::from_millis(5),
+            max_delay: Duration::from_millis(50),
+            window_size: 10,
+            min_samples: 5,
+            max_query_limit: 10,
+        })
+    }
+
+    async fn read(delay: Duration, res: Result<&'static str>) -> Result<&'static str> {
+        sleep(delay).await;
+        res
+    }
+
+    fn failed() -> Result<&'static str> {
+        Err(CommonErr(CommonErrCode::Rpc, "failed".to_owned()))
+    }
+
+    #[test]
+    fn test_delay_percentile() {
+        let hedger = hedger();
+        assert_eq!(Duration::from_millis(50), hedger.delay(ReadKind::Get));
+        for ms in 1..=4 {
+            hedger.record(ReadKind::Get, Duration::from_millis(ms * 10));
+        }
+        assert_eq!(Duration::from_millis(50), hedger.delay(ReadKind::Get));
+        hedger.record(ReadKind::Get, Duration::from_millis(20));
+        // the 90th percentile of [10, 20, 20, 30, 40]
+        assert_eq!(Duration::from_millis(40), hedger.delay(ReadKind::Get));
+        assert_eq!(Duration::from_millis(50), hedger.delay(ReadKind::Query));
+
+        // the window slides
+        for _ in 0..10 {
+            hedger.record(ReadKind::Get, Duration::from_millis(1));
+        }
+        assert_eq!(Duration::from_millis(5), hedger.delay(ReadKind::Get));
+    }
+
+    #[tokio::test]
+    async fn test_race() {
+        let hedger = hedger();
+        let fast = Duration::from_millis(1);
+        let slow = Duration::from_secs(10);
+
+        // the hedge isn't sent if the primary is fast enough
+        let res = hedger
+            .run(
+                ReadKind::Get,
+                read(fast, Ok("primary")),
+                Some(read(fast, failed())),
+            )
+            .await;
+        assert_eq!("primary", res.unwrap());
+
+        let start = Instant::now();
+        let res = hedger
+            .run(
+                ReadKind::Get,
+                read(slow, Ok("primary")),
+                Some(read(fast, Ok("hedge"))),
+            )
+            .await;
+        assert_eq!("hedge", res.unwrap());
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        // a failure waits for the other read
+        let res = hedger
+            .run(
+                ReadKind::Get,
+                read(Duration::from_millis(100), Ok("primary")),
+                Some(read(fast, failed())),
+            )
+            .await;
+        assert_eq!("primary", res.unwrap());
+        let res = hedger
+            .run(
+                ReadKind::Get,
+                read(Duration::from_millis(100), failed()),
+                Some(read(fast, failed())),
+            )
+            .await;
+        assert!(res.is_err());
+    }
+}
@@ -17,10 +17,22 @@
 
 use std::{collections::HashMap, time::Duration};
 
-use crate::{rpc::protocol::DEFAULT_FLAG, serde_obkv::value::Value};
-
-mod ocp;
+use chrono::FixedOffset;
+
+use crate::{
+    rpc::{
+        protocol::{ObCompressType, DEFAULT_FLAG},
+        tls::TlsConfig,
+    },
+    serde_obkv::value::Value,
+};
+
+pub mod blocking;
+pub mod breaker;
+pub mod hedge;
+pub(crate) mod ocp;
 pub mod query;
+pub mod retry;
 pub mod table;
 pub mod table_client;
 use self::table::ObTable;
@@ -41,6 +53,20 @@ pub struct ClientConfig {
     pub metadata_mysql_conn_pool_max_size: usize,
     pub metadata_refresh_interval: Duration,
     pub ocp_model_cache_file: String,
+    /// File persisting the servers and the table routing across restarts,
+    /// e.g. next to `ocp_model_cache_file`. Cached routing lets the client
+    /// start while the sys tenant is unreachable and is refreshed in the
+    /// background, nothing is persisted if absent. The file is written in
+    /// the background and when the client is shut down, and may be shared by
+    /// several clients.
+    pub table_entry_cache_file: Option<String>,
+    /// Cached routing older than this is ignored at startup.
+    pub table_entry_cache_max_age: Duration,
+    /// Time zone the server renders timestamps in, which the bounds of the
+    /// partitions on timestamp columns are parsed in. Loaded from the
+    /// `time_zone` variable of the metadata sessions if absent, UTC when
+    /// it's a named time zone.
+    pub server_time_zone: Option<FixedOffset>,
 
     pub rslist_acquire_timeout: Duration,
     pub rslist_acquire_try_times: usize,
@@ -62,8 +88,48 @@ pub struct ClientConfig {
     pub rpc_read_timeout: Duration,
     pub rpc_operation_timeout: Duration,
     pub rpc_login_timeout: Duration,
+    /// Attempts at most of an operation with the default retry policy, see
+    /// [`retry::ExponentialBackoff`].
     pub rpc_retry_limit: usize,
+    /// Delay after the first failed attempt with the default retry policy,
+    /// doubled after every other one.
     pub rpc_retry_interval: Duration,
+    /// Ceiling of the delays between the attempts with the default retry
+    /// policy.
+    pub rpc_retry_max_interval: Duration,
+    /// Total time of a single or batch operation, covering its retries, the
+    /// routing refreshes and the connections made, none for no limit. Every
+    /// attempt is given the time left at most, the operation fails with
+    /// [`crate::error::Error::DeadlineExceeded`] once none is left, wrapped
+    /// in [`crate::error::Error::AmbiguousOutcome`] if a request not
+    /// idempotent was sent without an answer. Queries
+    /// and aggregations are given the deadline to execute, then again for
+    /// every fetch of the next rows of a stream. See
+    /// [`table_client::ObTableClient::with_deadline`] for a deadline per call.
+    pub operation_deadline: Option<Duration>,
+    /// Whether to retry the operations not idempotent, such as increments,
+    /// appends and the batches including them, once they may have reached
+    /// the servers. They fail with
+    /// [`crate::error::Error::AmbiguousOutcome`] by default, since they could
+    /// be applied twice.
+    pub retry_ambiguous_operations: bool,
+    /// Hedging of the eventual gets, multi-gets and small queries, none to
+    /// disable it. See [`hedge::HedgeConfig`].
+    pub hedged_reads: Option<hedge::HedgeConfig>,
+    /// Circuit breakers of the servers, failing the requests fast while the
+    /// servers fail, none to disable them as by default. See
+    /// [`breaker::CircuitBreakerConfig`].
+    pub circuit_breaker: Option<breaker::CircuitBreakerConfig>,
+    /// Whether to verify the checksums of responses, a connection is closed
+    /// once a response fails the verification.
+    pub rpc_verify_checksum: bool,
+    /// Compression of the requests, `ObCompressType::None` disables it.
+    pub rpc_compress_type: ObCompressType,
+    /// Requests whose payloads are smaller than this are never compressed.
+    pub rpc_compress_min_size: usize,
+    /// TLS of the connections to the observers, connections are in cleartext
+    /// if absent.
+    pub rpc_tls: Option<TlsConfig>,
 
     pub refresh_workers_num: usize,
 
@@ -78,6 +144,10 @@ pub struct ClientConfig {
     pub max_inflight_reqs_per_conn: usize,
 
     pub log_level_flag: u16,
+
+    /// Deadline of [`crate::Builder::build_async`], covering the loading of
+    /// the servers and the warm-up of the connections.
+    pub init_timeout: Duration,
 }
 
 impl Default for ClientConfig {
@@ -89,6 +159,9 @@ impl Default for ClientConfig {
             metadata_mysql_conn_pool_max_size: 3,
             metadata_refresh_interval: Duration::from_secs(3),
             ocp_model_cache_file: "/tmp/ocp_model_cache.json".to_owned(),
+            table_entry_cache_file: None,
+            table_entry_cache_max_age: Duration::from_secs(24 * 3600),
+            server_time_zone: None,
 
             rslist_acquire_timeout: Duration::from_secs(10),
             rslist_acquire_try_times: 3,
@@ -111,6 +184,15 @@ impl Default for ClientConfig {
             rpc_operation_timeout: Duration::from_secs(3),
             rpc_retry_limit: 3,
             rpc_retry_interval: Duration::from_millis(500),
+            rpc_retry_max_interval: Duration::from_secs(5),
+            operation_deadline: None,
+            retry_ambiguous_operations: false,
+            hedged_reads: None,
+            circuit_breaker: None,
+            rpc_verify_checksum: true,
+            rpc_compress_type: ObCompressType::None,
+            rpc_compress_min_size: 4096,
+            rpc_tls: None,
 
             refresh_workers_num: 5,
 
@@ -125,6 +207,8 @@ impl Default for ClientConfig {
             max_inflight_reqs_per_conn: 100,
 
             log_level_flag: DEFAULT_FLAG,
+
+            init_timeout: Duration::from_secs(30),
         }
     }
 }
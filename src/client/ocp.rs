@@ -174,6 +174,7 @@ impl Default for OcpResponse {
 
 pub struct ObOcpModelManager {
     client: Client,
+    http_timeout: time::Duration,
     cache_file: OcpModelCacheFile,
 }
 
@@ -181,12 +182,24 @@ impl ObOcpModelManager {
     pub fn new(http_timeout: time::Duration, cache_path: &str) -> Result<ObOcpModelManager> {
         Ok(Self {
             client: Client::builder().timeout(http_timeout).build()?,
+            http_timeout,
             cache_file: OcpModelCacheFile::new(cache_path),
         })
     }
 
-    fn load_ocp_model_once(&self, param_url: &str, datasource_name: &str) -> Result<OcpModel> {
-        match self.client.get(param_url).send()?.text() {
+    fn load_ocp_model_once(
+        &self,
+        param_url: &str,
+        datasource_name: &str,
+        http_timeout: time::Duration,
+    ) -> Result<OcpModel> {
+        match self
+            .client
+            .get(param_url)
+            .timeout(http_timeout)
+            .send()?
+            .text()
+        {
             Ok(text) => {
                 let response: std::result::Result<OcpResponse, _> = serde_json::from_str(&text);
                 if let Ok(response) = response {
@@ -256,6 +269,8 @@ impl ObOcpModelManager {
         }
     }
 
+    /// Loads the ocp model, retrying `retry_times` times. The requests and
+    /// the retries stop at `deadline` if any.
     pub fn load_ocp_model(
         &self,
         param_url: &str,
@@ -263,13 +278,21 @@ impl ObOcpModelManager {
         retry_times: usize,
         retry_interval: time::Duration,
         from_cache_when_fail: bool,
+        deadline: Option<time::Instant>,
     ) -> Result<OcpModel> {
+        let remaining =
+            || deadline.map(|deadline| deadline.saturating_duration_since(time::Instant::now()));
         for _ in 0..retry_times {
-            let ret = self.load_ocp_model_once(param_url, datasource_name);
+            let http_timeout = match remaining() {
+                Some(remaining) if remaining.is_zero() => break,
+                Some(remaining) => self.http_timeout.min(remaining),
+                None => self.http_timeout,
+            };
+            let ret = self.load_ocp_model_once(param_url, datasource_name, http_timeout);
             if ret.is_ok() {
                 return ret;
             } else {
-                thread::sleep(retry_interval);
+                thread::sleep(remaining().map_or(retry_interval, |r| retry_interval.min(r)));
             }
         }
 
@@ -304,13 +327,30 @@ mod test {
         let manager = ObOcpModelManager::new(Duration::from_secs(10), "/tmp/test")
             .expect("fail to create ocp manager.");
         let model = manager
-            .load_ocp_model(TEST_URL, "", 3, Duration::from_secs(1), false)
+            .load_ocp_model(TEST_URL, "", 3, Duration::from_secs(1), false, None)
             .expect("Fail to load ocp model");
 
         assert_eq!(model.cluster_id, 1774840318);
         assert_eq!(3, model.observer_addrs.len());
     }
 
+    #[test]
+    fn test_load_ocp_model_until_deadline() {
+        // the config server accepts the connection but never answers
+        let server = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/services", server.local_addr().unwrap());
+        let dir = Builder::new().prefix("ocp_model").tempfile().unwrap();
+        let manager = ObOcpModelManager::new(Duration::from_secs(10), dir.path().to_str().unwrap())
+            .expect("fail to create ocp manager.");
+
+        let start = time::Instant::now();
+        let deadline = start + Duration::from_millis(200);
+        let res =
+            manager.load_ocp_model(&url, "", 3, Duration::from_secs(1), false, Some(deadline));
+        assert!(res.is_err());
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
     #[test]
     fn test_cache_ocp_model() {
         let dir = Builder::new().prefix("ocp_model").tempfile().unwrap();
@@ -25,10 +25,10 @@ use std::{
 /// Query API for ob table
 use super::ObTable;
 use crate::{
-    client::table_client::{PartInfo, StreamQuerier, OBKV_CLIENT_METRICS},
+    client::table_client::{OpDeadline, PartInfo, StreamQuerier, OBKV_CLIENT_METRICS},
     error::{CommonErrCode, Error::Common as CommonErr, Result},
     rpc::protocol::{
-        payloads::ObTableEntityType,
+        payloads::{ObTableConsistencyLevel, ObTableEntityType},
         query::{ObTableQuery, ObTableQueryRequest, ObTableQueryResult, ObTableStreamRequest},
         DEFAULT_FLAG,
     },
@@ -49,12 +49,19 @@ pub struct QueryStreamResult {
     row_index: i32,
     table_query: ObTableQuery,
     operation_timeout: Option<Duration>,
+    // deadline of every fetch of rows from the servers
+    deadline: Option<Duration>,
     table_name: String,
     entity_type: ObTableEntityType,
     expectant: HashMap<i64, (PartInfo, Arc<ObTable>)>,
+    // part id -> table of the replica the query of the partition is hedged to
+    hedges: HashMap<i64, Arc<ObTable>>,
+    consistency_level: ObTableConsistencyLevel,
     cache_properties: Vec<String>,
     cache_rows: VecDeque<Vec<Value>>,
     partition_last_result: PartitionQueryResultDeque,
+    // the stream session whose next rows are being fetched
+    inflight_session: Option<(Arc<ObTable>, u64)>,
     flag: u16,
 }
 
@@ -77,20 +84,21 @@ impl QueryStreamResult {
             row_index: 0,
             table_query,
             operation_timeout: None,
+            deadline: None,
             table_name: "".to_owned(),
             entity_type: ObTableEntityType::Dynamic,
             expectant: HashMap::new(),
+            hedges: HashMap::new(),
+            consistency_level: ObTableConsistencyLevel::Strong,
             cache_properties: vec![],
             cache_rows: VecDeque::new(),
             partition_last_result: VecDeque::new(),
+            inflight_session: None,
             flag: DEFAULT_FLAG,
         }
     }
 
-    async fn refer_to_new_partition(
-        &mut self,
-        (part_info, ob_table): (PartInfo, Arc<ObTable>),
-    ) -> Result<i64> {
+    fn new_query_request(&self, part_info: &PartInfo, ob_table: &ObTable) -> ObTableQueryRequest {
         let mut req = ObTableQueryRequest::new(
             &self.table_name,
             part_info.table_id,
@@ -101,11 +109,24 @@ impl QueryStreamResult {
                 .unwrap_or_else(|| ob_table.operation_timeout()),
             self.flag,
         );
+        req.set_consistency_level(self.consistency_level);
+        req
+    }
+
+    async fn refer_to_new_partition(
+        &mut self,
+        (part_info, ob_table): (PartInfo, Arc<ObTable>),
+    ) -> Result<i64> {
+        let mut req = self.new_query_request(&part_info, &ob_table);
+        let hedge = self.hedges.remove(&part_info.part_id).map(|hedge_table| {
+            let hedge_req = self.new_query_request(&part_info, &hedge_table);
+            (hedge_table, hedge_req)
+        });
 
         let result = self
             .querier
             .clone()
-            .execute_query(self, (part_info.part_id, ob_table), &mut req)
+            .execute_query(self, (part_info.part_id, ob_table), &mut req, hedge)
             .await;
 
         if result.is_err() {
@@ -127,11 +148,13 @@ impl QueryStreamResult {
             self.flag,
         );
         req.set_stream_next();
+        self.inflight_session = Some((ob_table.clone(), last_result.session_id()));
         let result = self
             .querier
             .clone()
             .execute_stream(self, (part_id, ob_table), &mut req)
             .await;
+        self.inflight_session = None;
 
         if result.is_err() {
             self.close_eagerly("err").await;
@@ -156,10 +179,23 @@ impl QueryStreamResult {
         self.expectant = expectant;
     }
 
+    pub fn set_hedges(&mut self, hedges: HashMap<i64, Arc<ObTable>>) {
+        self.hedges = hedges;
+    }
+
+    pub fn set_consistency_level(&mut self, consistency_level: ObTableConsistencyLevel) {
+        self.consistency_level = consistency_level;
+    }
+
     pub fn set_operation_timeout(&mut self, timeout: Option<Duration>) {
         self.operation_timeout = timeout;
     }
 
+    /// Give every fetch of rows from the servers `deadline` in total.
+    pub fn set_deadline(&mut self, deadline: Option<Duration>) {
+        self.deadline = deadline;
+    }
+
     pub fn set_flag(&mut self, flag: u16) {
         self.flag = flag;
     }
@@ -326,6 +362,33 @@ impl QueryStreamResult {
             return self.pop_next_row_from_cache();
         }
 
+        let deadline = self.deadline.map(OpDeadline::new);
+        match OpDeadline::run(deadline.as_ref(), 1, self.fetch_next_rows()).await {
+            Err(e) if e.is_deadline_exceeded() => {
+                self.abandon();
+                Err(e)
+            }
+            res => res,
+        }
+    }
+
+    // Closes in the background the stream sessions of a fetch given up, the
+    // result can't be read any further.
+    fn abandon(&mut self) {
+        self.closed = true;
+        let sessions: Vec<_> = self
+            .partition_last_result
+            .drain(..)
+            .filter(|(_, result)| result.is_stream() && result.is_stream_next())
+            .map(|((_, ob_table), result)| (ob_table, result.session_id()))
+            .chain(self.inflight_session.take())
+            .collect();
+        if !sessions.is_empty() {
+            self.querier.close_abandoned_sessions(sessions);
+        }
+    }
+
+    async fn fetch_next_rows(&mut self) -> Result<Option<Vec<Value>>> {
         // 2. Get from the last stream request result
         loop {
             let last_part_result = self.partition_last_result.pop_front();
@@ -377,7 +440,8 @@ impl QueryStreamResult {
 impl Drop for QueryStreamResult {
     fn drop(&mut self) {
         if !self.closed {
-            error!("QueryStreamResult::drop stream is not closed when drop")
+            error!("QueryStreamResult::drop stream is not closed when drop");
+            self.abandon();
         }
     }
 }
@@ -417,7 +481,7 @@ impl QueryResultSet {
         match self {
             QueryResultSet::None => Ok(()),
             QueryResultSet::Some(stream_result) => {
-                // TODO: async close
+                // the stream sessions are closed in the background on drop
                 if stream_result.closed {
                     Ok(())
                 } else {
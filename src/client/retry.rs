@@ -0,0 +1,265 @@
+/*-
+ * #%L
+ * OBKV Table Client Framework
+ * %%
+ * Copyright (C) 2021 OceanBase
+ * %%
+ * OBKV Table Client Framework is licensed under Mulan PSL v2.
+ * You can use this software according to the terms and conditions of the
+ * Mulan PSL v2. You may obtain a copy of Mulan PSL v2 at:
+ *          http://license.coscl.org.cn/MulanPSL2
+ * THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ * KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ * NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ * See the Mulan PSL v2 for more details.
+ * #L%
+ */
+
+//! Retries of the failed operations.
+
+use std::{cmp, fmt, sync::Arc, time::Duration};
+
+use rand::{thread_rng, Rng};
+
+use super::ClientConfig;
+use crate::{error::Error, rpc::protocol::payloads::ObTableOperationType};
+
+/// Operation retried by a [`RetryPolicy`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RetryOp {
+    /// A single operation, such as a get or an insert.
+    Execute(ObTableOperationType),
+    /// A batch of operations.
+    ExecuteBatch,
+    /// A query or the next rows of a stream query. Only the refresh of the
+    /// decision applies, the rows already returned can't be taken back so
+    /// the query isn't resent.
+    Query,
+    /// A request resent on another connection to the same server after its
+    /// connection broke. The connection serves every table, so the table
+    /// name of the context is empty.
+    Connection,
+}
+
+/// The failed attempt of an operation.
+#[derive(Clone, Copy, Debug)]
+pub struct RetryContext<'a> {
+    pub table_name: &'a str,
+    pub op: RetryOp,
+    /// Attempts made so far, the failed one included.
+    pub attempt: usize,
+    /// Time left before the deadline of the operation, none without a
+    /// deadline.
+    pub remaining: Option<Duration>,
+}
+
+/// What to do after a failed attempt.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryDecision {
+    /// Delay before the next attempt, none to fail with the error.
+    pub retry_after: Option<Duration>,
+    /// Whether to refresh the routing of the table.
+    pub refresh: bool,
+}
+
+/// Decides the retries of the single and batch operations failed, see
+/// [`crate::Builder::retry_policy`]. [`ExponentialBackoff`] is used by
+/// default.
+pub trait RetryPolicy: Send + Sync {
+    fn decide(&self, ctx: &RetryContext<'_>, error: &Error) -> RetryDecision;
+}
+
+/// Shared [`RetryPolicy`], policies are only equal to themselves.
+#[derive(Clone)]
+pub(crate) struct RetryPolicyRef(pub Arc<dyn RetryPolicy>);
+
+impl fmt::Debug for RetryPolicyRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RetryPolicy")
+    }
+}
+
+impl PartialEq for RetryPolicyRef {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for RetryPolicyRef {}
+
+/// The default policy: the retryable errors (see [`Error::need_retry`]) are
+/// retried after a delay doubled on every attempt, the routing is refreshed
+/// on the errors requiring it (see [`Error::need_refresh_table`]). The
+/// requests of a broken connection are resent on another connection at once.
+///
+/// The delays are jittered, drawn between the half and the whole of the
+/// backoff, so that the clients failed together don't retry together. The
+/// client fails an operation with [`Error::DeadlineExceeded`] rather than
+/// waiting past its deadline.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExponentialBackoff {
+    max_attempts: usize,
+    base_delay: Duration,
+    max_delay: Duration,
+    jitter: bool,
+}
+
+impl ExponentialBackoff {
+    /// Makes `max_attempts` attempts at most, waiting `base_delay` after the
+    /// first one, then twice as long after every other one up to `max_delay`.
+    pub fn new(max_attempts: usize, base_delay: Duration, max_delay: Duration) -> Self {
+        ExponentialBackoff {
+            max_attempts,
+            base_delay,
+            max_delay,
+            jitter: true,
+        }
+    }
+
+    /// The policy of the retry settings of `config`.
+    pub fn from_config(config: &ClientConfig) -> Self {
+        Self::new(
+            config.rpc_retry_limit,
+            config.rpc_retry_interval,
+            config.rpc_retry_max_interval,
+        )
+    }
+
+    /// Whether to jitter the delays, true by default.
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// The delay after the failed attempt `attempt`, before jitter.
+    fn backoff(&self, attempt: usize) -> Duration {
+        let exp = cmp::min(attempt.saturating_sub(1), 31) as u32;
+        cmp::min(self.base_delay.saturating_mul(1u32 << exp), self.max_delay)
+    }
+}
+
+impl RetryPolicy for ExponentialBackoff {
+    fn decide(&self, ctx: &RetryContext<'_>, error: &Error) -> RetryDecision {
+        let refresh = error.need_refresh_table();
+        let retryable = match ctx.op {
+            RetryOp::Connection => true,
+            RetryOp::Query => false,
+            _ => error.need_retry(),
+        };
+        if ctx.attempt >= self.max_attempts || !retryable {
+            return RetryDecision {
+                retry_after: None,
+                refresh,
+            };
+        }
+
+        if ctx.op == RetryOp::Connection {
+            return RetryDecision {
+                retry_after: Some(Duration::ZERO),
+                refresh,
+            };
+        }
+        let mut delay = self.backoff(ctx.attempt);
+        if self.jitter && !delay.is_zero() {
+            delay = thread_rng().gen_range(delay / 2..=delay);
+        }
+        RetryDecision {
+            retry_after: Some(delay),
+            refresh,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        error::{CommonErrCode, Error::Common as CommonErr},
+        ResultCodes,
+    };
+
+    fn ctx(attempt: usize) -> RetryContext<'static> {
+        RetryContext {
+            table_name: "test",
+            op: RetryOp::Execute(ObTableOperationType::Get),
+            attempt,
+            remaining: None,
+        }
+    }
+
+    fn ob_err(code: ResultCodes) -> Error {
+        CommonErr(CommonErrCode::ObException(code), "test".to_owned())
+    }
+
+    #[test]
+    fn test_exponential_backoff() {
+        let policy =
+            ExponentialBackoff::new(5, Duration::from_millis(100), Duration::from_millis(300))
+                .jitter(false);
+        let err = ob_err(ResultCodes::OB_TRY_LOCK_ROW_CONFLICT);
+        let delays: Vec<_> = (1..=5)
+            .map(|attempt| policy.decide(&ctx(attempt), &err).retry_after)
+            .collect();
+        assert_eq!(
+            vec![
+                Some(Duration::from_millis(100)),
+                Some(Duration::from_millis(200)),
+                Some(Duration::from_millis(300)),
+                Some(Duration::from_millis(300)),
+                None,
+            ],
+            delays
+        );
+        assert!(!policy.decide(&ctx(1), &err).refresh);
+
+        // refreshed but not retried
+        let decision = policy.decide(&ctx(5), &ob_err(ResultCodes::OB_NOT_MASTER));
+        assert_eq!(None, decision.retry_after);
+        assert!(decision.refresh);
+
+        let err = CommonErr(CommonErrCode::InvalidParam, "test".to_owned());
+        assert_eq!(None, policy.decide(&ctx(1), &err).retry_after);
+    }
+
+    #[test]
+    fn test_connection_and_query_retries() {
+        let policy =
+            ExponentialBackoff::new(3, Duration::from_millis(100), Duration::from_millis(300));
+        let conn_ctx = |attempt| RetryContext {
+            table_name: "",
+            op: RetryOp::Connection,
+            attempt,
+            remaining: None,
+        };
+        let err = Error::Common(CommonErrCode::BrokenPipe, "test".to_owned());
+        assert_eq!(
+            Some(Duration::ZERO),
+            policy.decide(&conn_ctx(1), &err).retry_after
+        );
+        assert_eq!(None, policy.decide(&conn_ctx(3), &err).retry_after);
+
+        let query_ctx = RetryContext {
+            op: RetryOp::Query,
+            ..ctx(1)
+        };
+        let decision = policy.decide(&query_ctx, &ob_err(ResultCodes::OB_NOT_MASTER));
+        assert_eq!(None, decision.retry_after);
+        assert!(decision.refresh);
+    }
+
+    #[test]
+    fn test_backoff_jitter() {
+        let policy =
+            ExponentialBackoff::new(10, Duration::from_millis(100), Duration::from_secs(1));
+        let err = ob_err(ResultCodes::OB_NOT_MASTER);
+        for _ in 0..100 {
+            let delay = policy.decide(&ctx(3), &err).retry_after.unwrap();
+            assert!(delay >= Duration::from_millis(200) && delay <= Duration::from_millis(400));
+        }
+        let policy = ExponentialBackoff::new(10, Duration::ZERO, Duration::ZERO);
+        assert_eq!(
+            Some(Duration::ZERO),
+            policy.decide(&ctx(3), &err).retry_after
+        );
+    }
+}
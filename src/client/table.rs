@@ -15,9 +15,12 @@
  * #L%
  */
 
-use std::{fmt::Formatter, time::Duration};
+use std::{fmt::Formatter, sync::Arc, time::Duration};
 
-use super::{ClientConfig, TableOpResult};
+use super::{
+    breaker::{CircuitBreaker, CircuitState},
+    ClientConfig, TableOpResult,
+};
 use crate::{
     error::{CommonErrCode, Error::Common as CommonErr, Result},
     rpc::{
@@ -36,6 +39,7 @@ pub struct ObTable {
     user_name: String,
     database: String,
     rpc_proxy: Proxy,
+    circuit_breaker: Option<Arc<CircuitBreaker>>,
 }
 
 impl std::fmt::Debug for ObTable {
@@ -57,8 +61,29 @@ impl ObTable {
         payload: &mut T,
         result: &mut R,
     ) -> Result<()> {
-        self.rpc_proxy.execute(payload, result).await?;
-        Ok(())
+        // the requests not waiting for a reply, such as closing a stream, are
+        // always sent
+        let permit = match &self.circuit_breaker {
+            Some(breaker) if payload.timeout_millis() > 0 => Some(breaker.acquire()?),
+            _ => None,
+        };
+        let res = self.rpc_proxy.execute(payload, result).await;
+        if let Some(permit) = permit {
+            permit.record(&res);
+        }
+        res
+    }
+
+    /// Whether the circuit breaker of the server lets requests through.
+    pub fn is_available(&self) -> bool {
+        self.circuit_breaker
+            .as_ref()
+            .map_or(true, |breaker| breaker.is_available())
+    }
+
+    /// State of the circuit breaker of the server, none if disabled.
+    pub fn circuit_state(&self) -> Option<CircuitState> {
+        self.circuit_breaker.as_ref().map(|breaker| breaker.state())
     }
 
     pub fn operation_timeout(&self) -> Duration {
@@ -68,17 +93,50 @@ impl ObTable {
     /// Execute batch operation
     pub async fn execute_batch(
         &self,
-        _table_name: &str,
+        table_name: &str,
+        batch_op: ObTableBatchOperation,
+    ) -> Result<Vec<TableOpResult>> {
+        self.execute_batch_with_timeout(table_name, batch_op, self.config.rpc_operation_timeout)
+            .await
+    }
+
+    /// Execute batch operation, waiting `timeout` for the server at most.
+    pub async fn execute_batch_with_timeout(
+        &self,
+        table_name: &str,
         batch_op: ObTableBatchOperation,
+        timeout: Duration,
     ) -> Result<Vec<TableOpResult>> {
-        let mut payload = ObTableBatchOperationRequest::new(
+        self.execute_batch_with_consistency(
+            table_name,
             batch_op,
-            self.config.rpc_operation_timeout,
-            self.config.log_level_flag,
-        );
+            timeout,
+            ObTableConsistencyLevel::Strong,
+        )
+        .await
+    }
+
+    /// Execute batch operation, waiting `timeout` for the server at most. The
+    /// read only batches may be read with eventual `consistency`.
+    pub async fn execute_batch_with_consistency(
+        &self,
+        _table_name: &str,
+        batch_op: ObTableBatchOperation,
+        timeout: Duration,
+        consistency: ObTableConsistencyLevel,
+    ) -> Result<Vec<TableOpResult>> {
+        let read_only = batch_op.is_read_only();
+        let mut payload =
+            ObTableBatchOperationRequest::new(batch_op, timeout, self.config.log_level_flag);
+        if read_only {
+            payload.set_consistency_level(consistency);
+        }
+        if self.config.retry_ambiguous_operations {
+            payload.set_idempotent(true);
+        }
         let mut result = ObTableBatchOperationResult::new();
 
-        self.rpc_proxy.execute(&mut payload, &mut result).await?;
+        self.execute_payload(&mut payload, &mut result).await?;
 
         result.into()
     }
@@ -99,6 +157,7 @@ pub struct Builder {
     password: String,
     database: String,
     rpc_proxy: Option<Proxy>,
+    circuit_breaker: Option<Arc<CircuitBreaker>>,
 }
 
 impl Builder {
@@ -112,6 +171,7 @@ impl Builder {
             password: "".to_owned(),
             database: "".to_owned(),
             rpc_proxy: None,
+            circuit_breaker: None,
         }
     }
 
@@ -145,6 +205,11 @@ impl Builder {
         self
     }
 
+    pub(crate) fn circuit_breaker(mut self, circuit_breaker: Option<Arc<CircuitBreaker>>) -> Self {
+        self.circuit_breaker = circuit_breaker;
+        self
+    }
+
     pub fn build(self) -> ObTable {
         assert!(self.rpc_proxy.is_some(), "missing necessary rpc proxy");
         ObTable {
@@ -155,6 +220,7 @@ impl Builder {
             user_name: self.user_name,
             database: self.database,
             rpc_proxy: self.rpc_proxy.unwrap(),
+            circuit_breaker: self.circuit_breaker,
         }
     }
 }
@@ -17,6 +17,7 @@
 
 use std::{
     borrow::Borrow,
+    cmp,
     collections::HashMap,
     isize,
     sync::{
@@ -28,20 +29,32 @@ use std::{
 };
 
 use rand::{seq::SliceRandom, thread_rng};
-use scheduled_thread_pool::ScheduledThreadPool;
-use tokio::time::sleep;
+use scheduled_thread_pool::{JobHandle, ScheduledThreadPool};
+use tokio::{
+    runtime::{Handle, RuntimeFlavor},
+    sync::{oneshot, Notify, OnceCell},
+    task,
+    time::{sleep, timeout, timeout_at},
+};
 
 use super::{
-    ocp::{ObOcpModelManager, OcpModel},
+    breaker::{CircuitBreaker, CircuitState},
+    hedge::{Hedger, ReadKind},
     query::{QueryResultSet, QueryStreamResult},
+    retry::{ExponentialBackoff, RetryContext, RetryOp, RetryPolicy, RetryPolicyRef},
     table::{self, ObTable},
     ClientConfig, TableOpResult,
 };
 use crate::{
-    error::{self, CommonErrCode, Error::Common as CommonErr, Result},
+    error::{self, CommonErrCode, Error, Error::Common as CommonErr, InitStage, Result},
     location::{
-        ob_part_constants::generate_phy_part_id, ObPartitionLevel, ObServerAddr, ObTableLocation,
-        ReplicaLocation, TableEntry, TableEntryKey,
+        cache::TableEntryCacheFile,
+        ob_part_constants::generate_phy_part_id,
+        provider::{
+            LocationProvider, LocationProviderRef, OdpLocationProvider, SqlLocationProvider,
+        },
+        ObPartitionLevel, ObPartitionLocation, ObServerAddr, ObTableLocation, ReplicaLocation,
+        TableEntry, TableEntryKey, OB_INVALID_ID,
     },
     monitors::{
         client_metrics::{ClientMetrics, ObClientOpRecordType, ObClientOpRetryType},
@@ -50,18 +63,21 @@ use crate::{
     query::ObTableAggregationType,
     rpc::{
         conn_pool::{Builder as ConnPoolBuilder, ConnPool},
+        inherit_pending_requests,
         protocol::{
             payloads::{
-                ObTableBatchOperation, ObTableEntityType, ObTableOperationRequest,
-                ObTableOperationResult, ObTableOperationType,
+                ObTableBatchOperation, ObTableConsistencyLevel, ObTableEntityType,
+                ObTableOperationRequest, ObTableOperationResult, ObTableOperationType,
             },
             query::{
                 ObHTableFilter, ObNewRange, ObScanOrder, ObTableQuery, ObTableQueryRequest,
                 ObTableQueryResult, ObTableStreamRequest,
             },
+            ObPayload,
         },
         proxy::Proxy,
-        Builder as ConnBuilder,
+        tls::TlsConnector,
+        track_pending_requests, Builder as ConnBuilder,
     },
     runtime,
     runtime::RuntimeRef,
@@ -98,6 +114,36 @@ impl PartInfo {
     }
 }
 
+/// Partition a row key is routed to, see [`ObTableClient::partition_of`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PartitionId {
+    pub table_id: i64,
+    // phy id computed from the partition descs, which is the partition id in
+    // 3.x and encodes the partition / sub partition index in 4.x.
+    pub partition_id: i64,
+    // tablet id of the partition in 4.x, none in 3.x and for non-partitioned
+    // tables.
+    pub tablet_id: Option<i64>,
+}
+
+/// Partition with its leader, see [`ObTableClient::partitions_for_range`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartitionInfo {
+    pub id: PartitionId,
+    pub leader: ObServerAddr,
+}
+
+/// Row key range of a table served by a single partition, see
+/// [`ObTableClient::scan_splits`].
+#[derive(Debug, Clone)]
+pub struct ScanSplit {
+    pub start: Vec<Value>,
+    pub start_inclusive: bool,
+    pub end: Vec<Value>,
+    pub end_inclusive: bool,
+    pub partition: PartitionInfo,
+}
+
 pub struct ServerRoster {
     max_priority: AtomicIsize,
     roster: RwLock<Arc<Vec<ObServerAddr>>>,
@@ -190,10 +236,109 @@ pub enum RunningMode {
 
 type Lock = Mutex<u8>;
 
+// Result of a table entry refresh shared by the concurrent callers, errors are
+// shared as their code and message.
+type TableRefresh = OnceCell<std::result::Result<Arc<TableEntry>, Arc<Error>>>;
+
+// Steps of a shutdown after the in-flight operations are drained are given at
+// least this long.
+const MIN_SHUTDOWN_STEP_TIMEOUT: Duration = Duration::from_millis(100);
+
+// The server is given at least this long by the last attempt of an operation.
+const MIN_ATTEMPT_TIMEOUT: Duration = Duration::from_millis(1);
+
+// Options of the single and batch operations of an `ObTableClient`.
+#[derive(Clone, Copy, Debug)]
+struct OpOptions {
+    // deadline overriding the one of the config
+    deadline: Option<Duration>,
+    // consistency of the gets, eventual ones may be served by followers
+    read_consistency: ObTableConsistencyLevel,
+}
+
+impl Default for OpOptions {
+    fn default() -> Self {
+        OpOptions {
+            deadline: None,
+            read_consistency: ObTableConsistencyLevel::Strong,
+        }
+    }
+}
+
+// Total deadline of an operation, see `ClientConfig::operation_deadline`.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct OpDeadline {
+    timeout: Duration,
+    at: Instant,
+}
+
+impl OpDeadline {
+    pub(crate) fn new(timeout: Duration) -> Self {
+        OpDeadline {
+            timeout,
+            at: Instant::now() + timeout,
+        }
+    }
+
+    #[inline]
+    fn remaining(&self) -> Duration {
+        self.at.saturating_duration_since(Instant::now())
+    }
+
+    #[inline]
+    fn exceeded(&self, attempts: usize) -> Error {
+        Error::DeadlineExceeded(self.timeout, attempts)
+    }
+
+    // The deadline exceeded after the last attempt failed with `err`, the
+    // operation may have been applied if that attempt may have been.
+    fn exceeded_after(&self, attempts: usize, err: &Error) -> Error {
+        if err.is_ambiguous_outcome() {
+            Error::AmbiguousOutcome(Box::new(self.exceeded(attempts)))
+        } else {
+            self.exceeded(attempts)
+        }
+    }
+
+    // Run the attempt `attempt` of the operation until the deadline, an
+    // attempt abandoned while waiting for the outcome of a request not
+    // idempotent may have been applied.
+    pub(crate) async fn run<T, F>(deadline: Option<&Self>, attempt: usize, f: F) -> Result<T>
+    where
+        F: std::future::Future<Output = Result<T>>,
+    {
+        match deadline {
+            None => f.await,
+            Some(deadline) => {
+                let pending = Arc::new(AtomicUsize::new(0));
+                let f = track_pending_requests(pending.clone(), f);
+                match timeout_at(deadline.at.into(), f).await {
+                    Ok(res) => res,
+                    Err(_) if pending.load(Ordering::Acquire) > 0 => Err(Error::AmbiguousOutcome(
+                        Box::new(deadline.exceeded(attempt)),
+                    )),
+                    Err(_) => Err(deadline.exceeded(attempt)),
+                }
+            }
+        }
+    }
+}
+
+// Counts an operation in flight until dropped, see `ObTableClient::shutdown`.
+struct InflightOp<'a>(&'a ObTableClientInner);
+
+impl<'a> Drop for InflightOp<'a> {
+    fn drop(&mut self) {
+        if self.0.inflight_ops.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.inflight_drained.notify_waiters();
+        }
+    }
+}
+
 // ObTableClient inner implemetation.
 struct ObTableClientInner {
-    location: ObTableLocation,
-    ocp_manager: ObOcpModelManager,
+    location: Arc<ObTableLocation>,
+    location_provider: Arc<dyn LocationProvider>,
     config: ClientConfig,
     table_entry_refresh_continuous_failure_count: AtomicUsize,
     datasource_name: String,
@@ -204,24 +349,42 @@ struct ObTableClientInner {
     cluster_name: String,
     password: String,
     database: String,
-    ocp_model: RwLock<OcpModel>,
     initialized: AtomicBool,
     closed: AtomicBool,
+    shutting_down: AtomicBool,
     status_mutex: Lock,
+    // operations in flight, drained on shutdown
+    inflight_ops: AtomicUsize,
+    inflight_drained: Notify,
+    // (server, session id) -> stream sessions opened and not closed yet
+    stream_sessions: Mutex<HashMap<(String, u64), Arc<ObTable>>>,
 
     // Client Runtimes
     runtimes: RuntimesRef,
+    // false if the runtimes are provided by the caller
+    owns_runtimes: bool,
 
     // ServerAddr(all) -> ObTableConnection
     table_roster: RwLock<HashMap<ObServerAddr, Arc<ObTable>>>,
-    server_roster: ServerRoster,
+    server_roster: Arc<ServerRoster>,
     running_mode: RunningMode,
+    // the single endpoint routing every request in odp mode
+    odp_addr: Option<ObServerAddr>,
     // TableName -> TableEntry
     table_locations: RwLock<HashMap<String, Arc<TableEntry>>>,
+    // routing persisted across restarts
+    table_entry_cache: Option<TableEntryCacheFile>,
     table_mutexs: RwLock<HashMap<String, Arc<Lock>>>,
+    // (TableName, refresh) -> load in flight, awaited by the async operations,
+    // a refresh never joins a load which may return the cached entry
+    table_refreshes: Mutex<HashMap<(String, bool), Arc<TableRefresh>>>,
     // TableName -> rowKey element
     table_row_key_element: RwLock<HashMap<String, HashMap<String, i32>>>,
     connection_pools: RwLock<HashMap<ObServerAddr, Arc<ConnPool>>>,
+    // ServerAddr -> lock held while building the pool of the server
+    conn_pool_builds: Mutex<HashMap<ObServerAddr, Arc<Mutex<()>>>>,
+    // ServerAddr -> circuit breaker, kept while the server leaves the roster
+    circuit_breakers: Mutex<HashMap<ObServerAddr, Arc<CircuitBreaker>>>,
 
     _retry_on_change_master: bool,
     // TableName -> failure counter
@@ -231,9 +394,15 @@ struct ObTableClientInner {
     last_refresh_metadata_ts: AtomicUsize,
 
     refresh_sender: std::sync::mpsc::SyncSender<String>,
+    // decides the retries of the failed operations
+    retry_policy: Arc<dyn RetryPolicy>,
 
     // query concurrency control
     query_permits: Option<Permits>,
+    // hedges the eventual reads, none if disabled
+    hedger: Option<Hedger>,
+
+    tls_connector: Option<Arc<TlsConnector>>,
 }
 
 impl ObTableClientInner {
@@ -250,14 +419,49 @@ impl ObTableClientInner {
         config: ClientConfig,
         refresh_sender: std::sync::mpsc::SyncSender<String>,
         runtimes: Arc<ObClientRuntimes>,
+        owns_runtimes: bool,
+        location_provider: Option<LocationProviderRef>,
+        retry_policy: Option<RetryPolicyRef>,
+        odp_addr: Option<ObServerAddr>,
     ) -> Result<Self> {
-        let ocp_manager =
-            ObOcpModelManager::new(config.rslist_acquire_timeout, &config.ocp_model_cache_file)?;
+        let location = Arc::new(ObTableLocation::new(config.clone()));
+        let server_roster = Arc::new(ServerRoster::new());
+        let location_provider: Arc<dyn LocationProvider> = match (&odp_addr, location_provider) {
+            (Some(addr), _) => Arc::new(OdpLocationProvider::new(addr.clone())),
+            (None, Some(provider)) => provider.0,
+            (None, None) => Arc::new(SqlLocationProvider::new(
+                location.clone(),
+                server_roster.clone(),
+                &param_url,
+                &cluster_name,
+                &tenant_name,
+                config.clone(),
+            )?),
+        };
 
+        let retry_policy: Arc<dyn RetryPolicy> = match retry_policy {
+            Some(policy) => policy.0,
+            None => Arc::new(ExponentialBackoff::from_config(&config)),
+        };
         let query_permits = config.query_concurrency_limit.map(Permits::new);
+        let hedger = config.hedged_reads.clone().map(Hedger::new);
+
+        let table_entry_cache = match (&odp_addr, &config.table_entry_cache_file) {
+            (None, Some(path)) => Some(TableEntryCacheFile::new(
+                path,
+                config.table_entry_cache_max_age,
+            )),
+            _ => None,
+        };
+
+        let tls_connector = match &config.rpc_tls {
+            Some(tls_config) => Some(Arc::new(TlsConnector::new(tls_config)?)),
+            None => None,
+        };
 
         Ok(Self {
-            ocp_manager,
+            location,
+            location_provider,
             full_user_name,
             param_url,
             password,
@@ -267,20 +471,28 @@ impl ObTableClientInner {
             database,
             datasource_name: "".to_owned(),
             running_mode,
+            odp_addr,
             config: config.clone(),
             runtimes,
+            owns_runtimes,
 
-            location: ObTableLocation::new(config),
             initialized: AtomicBool::new(false),
             closed: AtomicBool::new(false),
+            shutting_down: AtomicBool::new(false),
             status_mutex: Mutex::new(0),
+            inflight_ops: AtomicUsize::new(0),
+            inflight_drained: Notify::new(),
+            stream_sessions: Mutex::new(HashMap::new()),
             table_entry_refresh_continuous_failure_count: AtomicUsize::new(0),
-            ocp_model: RwLock::new(OcpModel::new()),
             table_roster: RwLock::new(HashMap::new()),
-            server_roster: ServerRoster::new(),
+            server_roster,
             table_locations: RwLock::new(HashMap::new()),
+            table_entry_cache,
             connection_pools: RwLock::new(HashMap::new()),
+            conn_pool_builds: Mutex::new(HashMap::new()),
+            circuit_breakers: Mutex::new(HashMap::new()),
             table_mutexs: RwLock::new(HashMap::new()),
+            table_refreshes: Mutex::new(HashMap::new()),
             table_row_key_element: RwLock::new(HashMap::new()),
             table_continuous_failures: RwLock::new(HashMap::new()),
             _retry_on_change_master: true, //TODO it's useless right now.
@@ -288,7 +500,10 @@ impl ObTableClientInner {
             last_refresh_metadata_ts: AtomicUsize::new(0),
 
             refresh_sender,
+            retry_policy,
             query_permits,
+            hedger,
+            tls_connector,
         })
     }
 
@@ -309,8 +524,42 @@ impl ObTableClientInner {
         self.table_locations.rl().get(table_name).cloned()
     }
 
-    fn on_table_op_failure(&self, table_name: &str, error: &error::Error) -> Result<()> {
-        if error.need_refresh_table() {
+    // The deadline of an operation started now, `deadline` overriding the one
+    // of the config.
+    fn op_deadline(&self, deadline: Option<Duration>) -> Option<OpDeadline> {
+        deadline
+            .or(self.config.operation_deadline)
+            .map(OpDeadline::new)
+    }
+
+    // Server timeout of an attempt, shortened to the time left before the
+    // deadline of the operation. The time left is rounded up to whole
+    // milliseconds so that an attempt doesn't time out before the deadline.
+    fn attempt_timeout(&self, deadline: Option<&OpDeadline>) -> Duration {
+        match deadline {
+            Some(deadline) => {
+                let remaining = deadline.remaining().as_nanos().div_ceil(1_000_000);
+                cmp::min(
+                    self.config.rpc_operation_timeout,
+                    cmp::max(Duration::from_millis(remaining as u64), MIN_ATTEMPT_TIMEOUT),
+                )
+            }
+            None => self.config.rpc_operation_timeout,
+        }
+    }
+
+    fn on_table_op_failure(
+        &self,
+        table_name: &str,
+        error: &error::Error,
+        refresh: bool,
+    ) -> Result<()> {
+        // the endpoint routes the requests in odp mode, nothing to refresh
+        if self.odp_addr.is_some() {
+            return Ok(());
+        }
+
+        if refresh {
             debug!(
                 "ObTableClientInner::on_table_op_failure: found error requiring refresh, \
                  table_name:{}, err:{}",
@@ -388,53 +637,39 @@ impl ObTableClientInner {
             table_name,
         );
 
-        let server_roster = &self.server_roster;
-        let connect_timeout = self.config.table_entry_acquire_connect_timeout;
-        let read_timeout = self.config.table_entry_acquire_read_timeout;
-        let priority_timeout = self.config.server_address_priority_timeout;
-
-        let result = if let Some(table_entry) = table_entry {
-            self.location.load_table_location_with_priority(
-                server_roster,
-                &table_entry_key,
-                table_entry.borrow(),
-                connect_timeout,
-                read_timeout,
-                priority_timeout,
-            )
-        } else {
-            let mut table_entry: TableEntry = self.location.load_table_entry_with_priority(
-                server_roster,
-                &table_entry_key,
-                connect_timeout,
-                read_timeout,
-                priority_timeout,
-            )?;
-
-            if table_entry.is_partition_table() {
-                match self.running_mode {
-                    RunningMode::Normal => match self.table_row_key_element.rl().get(table_name) {
-                        Some(v) => table_entry.set_row_key_element(v.clone()),
-                        None => {
-                            return Err(CommonErr(
-                                CommonErrCode::NotFound,
-                                format!("Partition table must has row key element, table_key={table_entry_key:?}"),
-                            ));
-                        }
-                    },
-                    RunningMode::HBase => {
-                        let mut hbase_row_key_element: HashMap<String, i32> = HashMap::new();
-                        hbase_row_key_element.insert("K".to_owned(), 0);
-                        hbase_row_key_element.insert("Q".to_owned(), 1);
-                        hbase_row_key_element.insert("T".to_owned(), 2);
-                        table_entry.set_row_key_element(hbase_row_key_element);
+        let mut result = match table_entry {
+            Some(table_entry) => self
+                .location_provider
+                .load_table_location(&table_entry_key, table_entry.borrow())?,
+            None => self.location_provider.load_table_entry(&table_entry_key)?,
+        };
+
+        // the row key of tables loaded from the system tables is declared by users
+        if result.is_partition_table() && result.row_key_element().is_empty() {
+            match self.running_mode {
+                RunningMode::Normal => match self.table_row_key_element.rl().get(table_name) {
+                    Some(v) => result.set_row_key_element(v.clone()),
+                    None => {
+                        return Err(CommonErr(
+                            CommonErrCode::NotFound,
+                            format!("Partition table must has row key element, table_key={table_entry_key:?}"),
+                        ));
                     }
+                },
+                RunningMode::HBase => {
+                    let mut hbase_row_key_element: HashMap<String, i32> = HashMap::new();
+                    hbase_row_key_element.insert("K".to_owned(), 0);
+                    hbase_row_key_element.insert("Q".to_owned(), 1);
+                    hbase_row_key_element.insert("T".to_owned(), 2);
+                    result.set_row_key_element(hbase_row_key_element);
                 }
-                table_entry.prepare()?;
             }
-            OBKV_CLIENT_METRICS.observe_sys_operation_rt("refresh_table", start.elapsed());
-            Ok(table_entry)
-        }?;
+            result.prepare()?;
+        }
+
+        if let Some(cache) = &self.table_entry_cache {
+            cache.save_table_entry(&result);
+        }
 
         self.table_entry_refresh_continuous_failure_count
             .store(0, Ordering::SeqCst);
@@ -442,20 +677,155 @@ impl ObTableClientInner {
         Ok(Arc::new(result))
     }
 
-    fn get_table(
-        &self,
+    async fn get_table_async(
+        self: &Arc<Self>,
         table_name: &str,
         row_key: &[Value],
         refresh: bool,
     ) -> Result<(PartInfo, Arc<ObTable>)> {
-        let table_entry = self.get_or_refresh_table_entry(table_name, refresh)?;
+        if let Some(addr) = &self.odp_addr {
+            return self.get_odp_table(addr).await;
+        }
+
+        let table_entry = self
+            .get_or_refresh_table_entry_async(table_name, refresh)
+            .await?;
         // actually phy id here
         let phy_id = self.get_partition(&table_entry, row_key)?;
         self.get_or_create_table(table_name, &table_entry, phy_id)
+            .await
     }
 
-    fn get_tables(
-        &self,
+    // Like `get_table_async`, but returns the tables of the replicas serving
+    // an eventual read, see `get_read_replica_tables`.
+    async fn get_read_tables_async(
+        self: &Arc<Self>,
+        table_name: &str,
+        row_key: &[Value],
+    ) -> Result<(PartInfo, Vec<Arc<ObTable>>)> {
+        if let Some(addr) = &self.odp_addr {
+            let (part_info, table) = self.get_odp_table(addr).await?;
+            return Ok((part_info, vec![table]));
+        }
+
+        let table_entry = self
+            .get_or_refresh_table_entry_async(table_name, false)
+            .await?;
+        // actually phy id here
+        let phy_id = self.get_partition(&table_entry, row_key)?;
+        self.get_read_part_tables(table_name, &table_entry, phy_id)
+            .await
+    }
+
+    // Like `get_or_create_table`, but returns the tables of the replicas
+    // serving an eventual read, see `get_read_replica_tables`.
+    async fn get_read_part_tables(
+        self: &Arc<Self>,
+        table_name: &str,
+        table_entry: &Arc<TableEntry>,
+        phy_id: i64,
+    ) -> Result<(PartInfo, Vec<Arc<ObTable>>)> {
+        if let Some((part_id, Some(location))) = self.get_partition_location(table_entry, phy_id) {
+            if let Some(tables) = self.get_read_replica_tables(location).await? {
+                return Ok((PartInfo::new(table_entry.table_id(), part_id), tables));
+            }
+        }
+        // no replica is available, the leader fails fast
+        let (part_info, table) = self
+            .get_or_create_table(table_name, table_entry, phy_id)
+            .await?;
+        Ok((part_info, vec![table]))
+    }
+
+    // Like `get_tables`, but returns the tables of the replicas serving an
+    // eventual read of every partition, see `get_read_replica_tables`.
+    async fn get_read_range_tables(
+        self: &Arc<Self>,
+        table_name: &str,
+        start: &[Value],
+        start_inclusive: bool,
+        end: &[Value],
+        end_inclusive: bool,
+    ) -> Result<Vec<(PartInfo, Vec<Arc<ObTable>>)>> {
+        if let Some(addr) = &self.odp_addr {
+            let (part_info, table) = self.get_odp_table(addr).await?;
+            return Ok(vec![(part_info, vec![table])]);
+        }
+
+        let table_entry = self
+            .get_or_refresh_table_entry_async(table_name, false)
+            .await?;
+        let mut result = Vec::new();
+        for phy_id in
+            self.get_partition_phy_ids(&table_entry, start, start_inclusive, end, end_inclusive)?
+        {
+            let tables = match table_entry.get_partition_location_with_phy_id(phy_id) {
+                Some(location) => self.get_read_replica_tables(location).await?,
+                None => None,
+            };
+            match tables {
+                Some(tables) => result.push((self.range_part_info(&table_entry, phy_id), tables)),
+                // no replica is available, the leader fails fast
+                None => {
+                    let pairs = self
+                        .get_tables(
+                            table_name,
+                            start,
+                            start_inclusive,
+                            end,
+                            end_inclusive,
+                            false,
+                        )
+                        .await?;
+                    return Ok(pairs.into_iter().map(|(p, t)| (p, vec![t])).collect());
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    // The tables of the replicas serving an eventual read of the partition:
+    // the leader unless its circuit breaker is open, then the available
+    // followers in random order. Two of them are returned when the reads are
+    // hedged, one otherwise, none if no replica is available.
+    async fn get_read_replica_tables(
+        self: &Arc<Self>,
+        location: &ObPartitionLocation,
+    ) -> Result<Option<Vec<Arc<ObTable>>>> {
+        let mut followers: Vec<_> = location.followers().iter().collect();
+        followers.shuffle(&mut thread_rng());
+        let replicas = location
+            .leader()
+            .iter()
+            .chain(followers)
+            .filter(|r| r.is_valid() && self.is_server_available(r.addr()));
+
+        let wanted = if self.hedger.is_some() { 2 } else { 1 };
+        let mut tables = Vec::with_capacity(wanted);
+        let mut error = None;
+        for replica in replicas {
+            let addr = replica.addr();
+            let table = self.add_ob_table_async(addr).await;
+            match table {
+                Ok(table) => tables.push(table),
+                Err(e) => {
+                    warn!("ObTableClientInner::get_read_replica_tables fail to add table of replica {addr:?}, err:{e}");
+                    error.get_or_insert(e);
+                }
+            }
+            if tables.len() == wanted {
+                break;
+            }
+        }
+        match (tables.is_empty(), error) {
+            (true, Some(e)) => Err(e),
+            (true, None) => Ok(None),
+            (false, _) => Ok(Some(tables)),
+        }
+    }
+
+    async fn get_tables(
+        self: &Arc<Self>,
         table_name: &str,
         start: &[Value],
         start_inclusive: bool,
@@ -463,8 +833,14 @@ impl ObTableClientInner {
         end_inclusive: bool,
         refresh: bool,
     ) -> Result<Vec<(PartInfo, Arc<ObTable>)>> {
+        if let Some(addr) = &self.odp_addr {
+            return Ok(vec![self.get_odp_table(addr).await?]);
+        }
+
         // 1. get table entry info
-        let table_entry = self.get_or_refresh_table_entry(table_name, refresh)?;
+        let table_entry = self
+            .get_or_refresh_table_entry_async(table_name, refresh)
+            .await?;
 
         // 2. get replica location
         let part_info_with_replicas: Vec<(PartInfo, ReplicaLocation)> =
@@ -500,6 +876,29 @@ impl ObTableClientInner {
         Ok(result)
     }
 
+    /// The table of the odp endpoint, with the partition left to the endpoint
+    /// to calculate.
+    async fn get_odp_table(
+        self: &Arc<Self>,
+        addr: &ObServerAddr,
+    ) -> Result<(PartInfo, Arc<ObTable>)> {
+        let part_info = PartInfo::new(OB_INVALID_ID, 0);
+        Ok((part_info, self.add_ob_table_async(addr).await?))
+    }
+
+    // The part info the range queries address the partition of `phy_id` with.
+    fn range_part_info(&self, table_entry: &TableEntry, phy_id: i64) -> PartInfo {
+        if ob_vsn_major() >= 4 {
+            let part_id = table_entry
+                .part_tablet_id_map()
+                .and_then(|m| m.get(&phy_id).copied())
+                .unwrap_or(0);
+            PartInfo::new(table_entry.table_id(), part_id)
+        } else {
+            PartInfo::new(table_entry.table_id(), phy_id)
+        }
+    }
+
     /// fill_partition_location_with_phy_id will return real partId/tabletId
     /// and corresponding executable table in [`PartInfo`]
     fn fill_partition_location_with_phy_id(
@@ -507,20 +906,11 @@ impl ObTableClientInner {
         result: &mut Vec<(PartInfo, ReplicaLocation)>,
         table_entry: &TableEntry,
         phy_id: i64,
-        table_id: i64,
     ) -> Result<()> {
         match table_entry.get_partition_location_with_phy_id(phy_id) {
             Some(location) => match location.leader() {
                 Some(leader) => {
-                    if ob_vsn_major() >= 4 {
-                        let part_id = table_entry
-                            .part_tablet_id_map()
-                            .and_then(|m| m.get(&phy_id).copied())
-                            .unwrap_or(0);
-                        result.push((PartInfo::new(table_id, part_id), leader.clone()));
-                    } else {
-                        result.push((PartInfo::new(table_id, phy_id), leader.clone()));
-                    }
+                    result.push((self.range_part_info(table_entry, phy_id), leader.clone()));
                 }
                 None => {
                     return Err(CommonErr(
@@ -558,7 +948,24 @@ impl ObTableClientInner {
         end_inclusive: bool,
     ) -> Result<Vec<(PartInfo, ReplicaLocation)>> {
         let mut result: Vec<(PartInfo, ReplicaLocation)> = vec![];
+        for phy_id in
+            self.get_partition_phy_ids(table_entry, start, start_inclusive, end, end_inclusive)?
+        {
+            self.fill_partition_location_with_phy_id(&mut result, table_entry, phy_id)?;
+        }
+        Ok(result)
+    }
 
+    /// get_partition_phy_ids will return phy part ids of the partitions the
+    /// range [start, end] is routed to
+    fn get_partition_phy_ids(
+        &self,
+        table_entry: &TableEntry,
+        start: &[Value],
+        start_inclusive: bool,
+        end: &[Value],
+        end_inclusive: bool,
+    ) -> Result<Vec<i64>> {
         if !table_entry.is_partition_table()
             || table_entry.is_partition_level(ObPartitionLevel::Zero)
         {
@@ -566,40 +973,24 @@ impl ObTableClientInner {
             // check empty row keys
             if start.is_empty() || end.is_empty() {
                 error!(
-                    "ObTableClientInner::get_partition_leaders invalid start keys :{:?} or end keys :{:?}",
+                    "ObTableClientInner::get_partition_phy_ids invalid start keys :{:?} or end keys :{:?}",
                     start,
                     end,
                 );
                 return Err(CommonErr(
                     CommonErrCode::InvalidParam,
-                    "ObTableClientInner::get_partition_leaders start or end key is empty"
+                    "ObTableClientInner::get_partition_phy_ids start or end key is empty"
                         .to_owned(),
                 ));
             }
-            self.fill_partition_location_with_phy_id(
-                &mut result,
-                table_entry,
-                0,
-                table_entry.table_id(),
-            )?;
-            Ok(result)
+            Ok(vec![0])
         } else if table_entry.is_partition_level(ObPartitionLevel::One) {
             //Level one
             match table_entry.partition_info() {
                 Some(info) => match info.first_part_desc() {
+                    // actually phy id here
                     Some(part_desc) => {
-                        // actually phy id here
-                        let part_ids =
-                            part_desc.get_part_ids(start, start_inclusive, end, end_inclusive)?;
-                        for part_id in part_ids {
-                            self.fill_partition_location_with_phy_id(
-                                &mut result,
-                                table_entry,
-                                part_id,
-                                table_entry.table_id(),
-                            )?;
-                        }
-                        Ok(result)
+                        part_desc.get_part_ids(start, start_inclusive, end, end_inclusive)
                     }
                     None => Err(CommonErr(
                         CommonErrCode::NotFound,
@@ -626,46 +1017,315 @@ impl ObTableClientInner {
         }
     }
 
+    fn partition_id(&self, table_entry: &TableEntry, phy_id: i64) -> PartitionId {
+        let tablet_id = if ob_vsn_major() >= 4 {
+            table_entry
+                .part_tablet_id_map()
+                .and_then(|m| m.get(&phy_id).copied())
+        } else {
+            None
+        };
+        PartitionId {
+            table_id: table_entry.table_id(),
+            partition_id: phy_id,
+            tablet_id,
+        }
+    }
+
+    async fn partition_of(
+        self: &Arc<Self>,
+        table_name: &str,
+        row_key: &[Value],
+    ) -> Result<PartitionId> {
+        let table_entry = self
+            .get_or_refresh_table_entry_async(table_name, false)
+            .await?;
+        let phy_id = self.get_partition(&table_entry, row_key)?;
+        Ok(self.partition_id(&table_entry, phy_id))
+    }
+
+    fn partition_info(&self, table_entry: &TableEntry, phy_id: i64) -> Result<PartitionInfo> {
+        let mut leaders = Vec::with_capacity(1);
+        self.fill_partition_location_with_phy_id(&mut leaders, table_entry, phy_id)?;
+        match leaders.pop() {
+            Some((_, replica)) => Ok(PartitionInfo {
+                id: self.partition_id(table_entry, phy_id),
+                leader: replica.addr().clone(),
+            }),
+            None => Err(CommonErr(
+                CommonErrCode::NotFound,
+                format!(
+                    "Leader not found part_id={} for table {:?}",
+                    phy_id,
+                    table_entry.to_owned(),
+                ),
+            )),
+        }
+    }
+
+    async fn partitions_for_range(
+        self: &Arc<Self>,
+        table_name: &str,
+        start: &[Value],
+        end: &[Value],
+    ) -> Result<Vec<PartitionInfo>> {
+        let table_entry = self
+            .get_or_refresh_table_entry_async(table_name, false)
+            .await?;
+        self.get_partition_phy_ids(&table_entry, start, true, end, true)?
+            .into_iter()
+            .map(|phy_id| self.partition_info(&table_entry, phy_id))
+            .collect()
+    }
+
+    async fn scan_splits(
+        self: &Arc<Self>,
+        table_name: &str,
+        target_splits: usize,
+    ) -> Result<Vec<ScanSplit>> {
+        if target_splits == 0 {
+            return Err(CommonErr(
+                CommonErrCode::InvalidParam,
+                format!("Target splits of scan must be positive, table_name={table_name}"),
+            ));
+        }
+        let table_entry = self
+            .get_or_refresh_table_entry_async(table_name, false)
+            .await?;
+        let row_key_element = match self.running_mode {
+            RunningMode::Normal => self.table_row_key_element.rl().get(table_name).cloned(),
+            RunningMode::HBase => None,
+        };
+        let row_key_len = match self.running_mode {
+            RunningMode::Normal => row_key_element.as_ref().map(|element| element.len()),
+            RunningMode::HBase => Some(3),
+        }
+        .ok_or_else(|| {
+            CommonErr(
+                CommonErrCode::NotFound,
+                format!("Scan splits of table must has row key element, table_name={table_name}"),
+            )
+        })?;
+        let whole_range = |phy_id| {
+            (
+                vec![Value::get_min(); row_key_len],
+                vec![Value::get_max(); row_key_len],
+                phy_id,
+            )
+        };
+
+        let part_ranges = if !table_entry.is_partition_table()
+            || table_entry.is_partition_level(ObPartitionLevel::Zero)
+        {
+            vec![whole_range(0)]
+        } else if table_entry.is_partition_level(ObPartitionLevel::One) {
+            match table_entry
+                .partition_info()
+                .as_ref()
+                .and_then(|info| info.first_part_desc().as_ref())
+            {
+                // the partitions of the other tables are not row key ranges,
+                // every one of them is scanned whole
+                Some(part_desc) => match part_desc.get_part_ranges() {
+                    Ok(part_ranges) => part_ranges,
+                    Err(_) => {
+                        let (min, max, _) = whole_range(0);
+                        self.get_partition_phy_ids(&table_entry, &min, true, &max, true)?
+                            .into_iter()
+                            .map(whole_range)
+                            .collect()
+                    }
+                },
+                None => {
+                    return Err(CommonErr(
+                        CommonErrCode::NotFound,
+                        format!(
+                            "First part desc not found for table {:?}",
+                            table_entry.to_owned(),
+                        ),
+                    ))
+                }
+            }
+        } else {
+            return Err(CommonErr(
+                CommonErrCode::PartitionError,
+                format!("Unsupported partition level two right now, table={table_entry:?}"),
+            ));
+        };
+        // a table without any partition has nothing to scan
+        if part_ranges.is_empty() {
+            return Ok(vec![]);
+        }
+        let leading_column = row_key_element.and_then(|element| {
+            element
+                .into_iter()
+                .find(|(_, idx)| *idx == 0)
+                .map(|(name, _)| name)
+        });
+
+        let splits_per_part = (target_splits + part_ranges.len() - 1) / part_ranges.len();
+        let mut splits = Vec::with_capacity(part_ranges.len().max(target_splits));
+        for (start, end, phy_id) in part_ranges {
+            let partition = self.partition_info(&table_entry, phy_id)?;
+            // split between the leading values of the first and the last
+            // rows, or between the bounds of the range if it's empty
+            let probed = match &leading_column {
+                Some(column) if splits_per_part > 1 => {
+                    let first = self
+                        .probe_leading_value(table_name, column, &start, &end, &partition.id, true)
+                        .await?;
+                    let last = self
+                        .probe_leading_value(table_name, column, &start, &end, &partition.id, false)
+                        .await?;
+                    first.zip(last)
+                }
+                _ => None,
+            };
+            let leading_bounds = match probed {
+                Some((first, last)) => int_value(&first)
+                    .zip(int_value(&last))
+                    .map(|(lo, hi)| (lo, hi + 1, first)),
+                None => int_value(&start[0])
+                    .zip(int_value(&end[0]))
+                    .map(|(lo, hi)| (lo, hi, start[0].clone())),
+            };
+            let ranges = match leading_bounds {
+                Some((lo, hi, template)) => {
+                    split_row_key_range(start, end, lo, hi, &template, splits_per_part)
+                }
+                None => vec![(start, end)],
+            };
+            for (start, end) in ranges {
+                splits.push(ScanSplit {
+                    start,
+                    start_inclusive: true,
+                    end,
+                    end_inclusive: false,
+                    partition: partition.clone(),
+                });
+            }
+        }
+        Ok(splits)
+    }
+
+    // The leading row key value of the first or the last row in the range
+    // [start, end) of the partition `id`.
+    async fn probe_leading_value(
+        self: &Arc<Self>,
+        table_name: &str,
+        column: &str,
+        start: &[Value],
+        end: &[Value],
+        id: &PartitionId,
+        forward: bool,
+    ) -> Result<Option<Value>> {
+        let mut result_set = ObTableClientQueryImpl::new(table_name, self.clone())
+            .select(vec![column.to_owned()])
+            .primary_index()
+            .add_scan_range(start.to_vec(), true, end.to_vec(), false)
+            .scan_order(forward)
+            .limit(None, 1)
+            .partition(*id)
+            .execute()
+            .await?;
+        let row = result_set.next().await.transpose();
+        result_set.close().await?;
+        Ok(row?.and_then(|mut row| row.remove(column)))
+    }
+
     fn get_or_create_conn_pool(&self, addr: &ObServerAddr) -> Result<Arc<ConnPool>> {
         if let Some(pool) = self.connection_pools.rl().get(addr) {
             return Ok(pool.clone());
         }
-        let mut pools = self.connection_pools.wl();
-        if let Some(pool) = pools.get(addr) {
-            Ok(pool.clone())
-        } else {
-            let conn_builder = ConnBuilder::new()
-                .connect_timeout(self.config.rpc_connect_timeout)
-                .read_timeout(self.config.rpc_read_timeout)
-                .login_timeout(self.config.rpc_login_timeout)
-                .operation_timeout(self.config.rpc_operation_timeout)
-                .ip(addr.ip())
-                .port(addr.svr_port() as u16)
-                .tenant_name(&self.tenant_name)
-                .user_name(&self.user_name)
-                .database_name(&self.database)
-                .password(&self.password)
-                .runtimes(self.runtimes.clone())
-                .sender_channel_size(self.config.max_inflight_reqs_per_conn);
-
-            let pool = Arc::new(
-                ConnPoolBuilder::new()
-                    .max_conn_num(self.config.max_conns_per_server)
-                    .min_conn_num(self.config.min_idle_conns_per_server)
-                    .conn_builder(conn_builder)
-                    .build()?,
-            );
+        let closed = || {
+            CommonErr(
+                CommonErrCode::AlreadyClosed,
+                format!("ObTableClientInner::get_or_create_conn_pool is closed, addr: {addr:?}"),
+            )
+        };
+        if self.is_closed() {
+            return Err(closed());
+        }
 
-            pools.insert(addr.to_owned(), pool.clone());
+        // build a single pool per server, without locking the pools of the
+        // other servers while waiting for the connections
+        let build_lock = self
+            .conn_pool_builds
+            .lock()
+            .unwrap()
+            .entry(addr.to_owned())
+            .or_default()
+            .clone();
+        let _building = build_lock.lock().unwrap();
+        if let Some(pool) = self.connection_pools.rl().get(addr) {
+            return Ok(pool.clone());
+        }
+
+        let conn_builder = ConnBuilder::new()
+            .connect_timeout(self.config.rpc_connect_timeout)
+            .read_timeout(self.config.rpc_read_timeout)
+            .login_timeout(self.config.rpc_login_timeout)
+            .operation_timeout(self.config.rpc_operation_timeout)
+            .ip(addr.ip())
+            .port(addr.svr_port() as u16)
+            .tenant_name(&self.tenant_name)
+            .user_name(&self.user_name)
+            .database_name(&self.database)
+            .password(&self.password)
+            .runtimes(self.runtimes.clone())
+            .sender_channel_size(self.config.max_inflight_reqs_per_conn)
+            .verify_checksum(self.config.rpc_verify_checksum)
+            .compress_type(self.config.rpc_compress_type.clone())
+            .compress_min_size(self.config.rpc_compress_min_size)
+            .tls_connector(self.tls_connector.clone());
+
+        let pool = Arc::new(
+            ConnPoolBuilder::new()
+                .max_conn_num(self.config.max_conns_per_server)
+                .min_conn_num(self.config.min_idle_conns_per_server)
+                .conn_builder(conn_builder)
+                .build()?,
+        );
+
+        let mut pools = self.connection_pools.wl();
+        if self.is_closed() {
+            pool.close();
+            return Err(closed());
+        }
+        pools.insert(addr.to_owned(), pool.clone());
+        Ok(pool)
+    }
 
-            Ok(pool)
+    // Like `add_ob_table`, but connects on a blocking thread so that the
+    // operation waiting for the connections can give up at its deadline, the
+    // connections are then set up in the background.
+    async fn add_ob_table_async(self: &Arc<Self>, addr: &ObServerAddr) -> Result<Arc<ObTable>> {
+        if let Some(table) = self.table_roster.rl().get(addr) {
+            return Ok(table.clone());
         }
+        let inner = self.clone();
+        let addr = addr.clone();
+        self.runtimes
+            .bg_runtime
+            .spawn_blocking(move || inner.add_ob_table(&addr))
+            .await
+            .map_err(Error::from)
+            .and_then(|r| r)
     }
 
     fn add_ob_table(&self, addr: &ObServerAddr) -> Result<Arc<ObTable>> {
         let start = Instant::now();
-        let mut table_roster = self.table_roster.wl();
-        let result = self.add_ob_table_to_roster(addr, &mut table_roster);
+        if let Some(table) = self.table_roster.rl().get(addr) {
+            return Ok(table.clone());
+        }
+        // connect without locking the roster, not to block the other servers
+        let result = self.create_ob_table(addr).map(|table| {
+            self.table_roster
+                .wl()
+                .entry(addr.clone())
+                .or_insert(table)
+                .clone()
+        });
         OBKV_CLIENT_METRICS.observe_sys_operation_rt("add_table", start.elapsed());
         result
     }
@@ -682,9 +1342,53 @@ impl ObTableClientInner {
             return Ok(table.clone());
         }
 
-        let rpc_proxy = Proxy::new(self.get_or_create_conn_pool(addr)?);
+        let ob_table = self.create_ob_table(addr)?;
+        table_roster.insert(addr.clone(), ob_table.clone());
+        OBKV_CLIENT_METRICS.observe_sys_operation_rt("add_ob_table_to_roster", start.elapsed());
+        Ok(ob_table)
+    }
+
+    // The circuit breaker of the server, none if disabled.
+    fn circuit_breaker(&self, addr: &ObServerAddr) -> Option<Arc<CircuitBreaker>> {
+        let config = self.config.circuit_breaker.as_ref()?;
+        let breaker = self
+            .circuit_breakers
+            .lock()
+            .unwrap()
+            .entry(addr.clone())
+            .or_insert_with(|| {
+                Arc::new(CircuitBreaker::new(
+                    format!("{}:{}", addr.ip(), addr.svr_port()),
+                    config.clone(),
+                ))
+            })
+            .clone();
+        Some(breaker)
+    }
+
+    // Whether the circuit breaker of the server lets requests through.
+    fn is_server_available(&self, addr: &ObServerAddr) -> bool {
+        self.circuit_breakers
+            .lock()
+            .unwrap()
+            .get(addr)
+            .map_or(true, |breaker| breaker.is_available())
+    }
+
+    fn create_ob_table(&self, addr: &ObServerAddr) -> Result<Arc<ObTable>> {
+        let circuit_breaker = self.circuit_breaker(addr);
+        let conn_pool = match &circuit_breaker {
+            Some(breaker) => {
+                let permit = breaker.acquire()?;
+                let conn_pool = self.get_or_create_conn_pool(addr);
+                permit.record(&conn_pool);
+                conn_pool?
+            }
+            None => self.get_or_create_conn_pool(addr)?,
+        };
+        let rpc_proxy = Proxy::new(conn_pool, self.retry_policy.clone());
 
-        let ob_table = Arc::new(
+        Ok(Arc::new(
             table::Builder::new(addr.ip(), addr.svr_port())
                 .config(&self.config)
                 .tenant_name(&self.tenant_name)
@@ -692,15 +1396,13 @@ impl ObTableClientInner {
                 .password(&self.password)
                 .database(&self.database)
                 .rpc_proxy(rpc_proxy)
+                .circuit_breaker(circuit_breaker)
                 .build(),
-        );
-        table_roster.insert(addr.clone(), ob_table.clone());
-        OBKV_CLIENT_METRICS.observe_sys_operation_rt("add_ob_table_to_roster", start.elapsed());
-        Ok(ob_table)
+        ))
     }
 
-    fn get_or_create_table(
-        &self,
+    async fn get_or_create_table(
+        self: &Arc<Self>,
         table_name: &str,
         table_entry: &Arc<TableEntry>,
         phy_id: i64,
@@ -716,7 +1418,7 @@ impl ObTableClientInner {
                         ));
                     }
 
-                    let ob_table = self.add_ob_table(addr)?;
+                    let ob_table = self.add_ob_table_async(addr).await?;
                     Ok((PartInfo::new(table_entry.table_id(), part_id), ob_table))
                 }
 
@@ -738,41 +1440,51 @@ impl ObTableClientInner {
         table_entry: &Arc<TableEntry>,
         phy_id: i64,
     ) -> Option<(i64, Option<ReplicaLocation>)> {
+        self.get_partition_location(table_entry, phy_id)
+            .map(|(part_id, location)| (part_id, location.and_then(|l| l.leader().to_owned())))
+    }
+
+    /// get_partition_location will return gt part id (partition id or tablet
+    /// id) and the replicas of the partition
+    fn get_partition_location<'a>(
+        &self,
+        table_entry: &'a Arc<TableEntry>,
+        phy_id: i64,
+    ) -> Option<(i64, Option<&'a ObPartitionLocation>)> {
         let partition_info = match table_entry.partition_info() {
             Some(partition_info) => partition_info,
             None => {
                 if phy_id == 0 {
                     // partition_info not exist -> not partition table
                     // only have one tablet/partition, then return 0/loc of p0
-                    return table_entry.partition_entry().as_ref().map(|entry| {
-                        (
-                            phy_id,
-                            entry
-                                .get_partition_location_with_part_id(phy_id)
-                                .and_then(|location| location.leader().to_owned()),
-                        )
-                    });
+                    return table_entry
+                        .partition_entry()
+                        .as_ref()
+                        .map(|entry| (phy_id, entry.get_partition_location_with_part_id(phy_id)));
                 }
-                warn!("get_partition_leader can not get partition_info");
+                warn!("get_partition_location can not get partition_info");
                 return Some((phy_id, None));
             }
         };
         let part_id = partition_info.get_partid_from_phyid(phy_id);
 
-        let leader_location = table_entry.partition_entry().as_ref().and_then(|entry| {
-            let partition_location = match partition_info.level() {
-                ObPartitionLevel::Two => entry.get_sub_partition_location_with_phy_id(
-                    phy_id,
-                    partition_info.sub_part_desc().as_ref()?.get_part_num(),
-                    table_entry.part_tablet_id_map(),
-                ),
-                _ => entry
-                    .get_partition_location_with_phy_id(phy_id, table_entry.part_tablet_id_map()),
-            };
-            partition_location.and_then(|location| location.leader().to_owned())
-        });
+        let location =
+            table_entry
+                .partition_entry()
+                .as_ref()
+                .and_then(|entry| match partition_info.level() {
+                    ObPartitionLevel::Two => entry.get_sub_partition_location_with_phy_id(
+                        phy_id,
+                        partition_info.sub_part_desc().as_ref()?.get_part_num(),
+                        table_entry.part_tablet_id_map(),
+                    ),
+                    _ => entry.get_partition_location_with_phy_id(
+                        phy_id,
+                        table_entry.part_tablet_id_map(),
+                    ),
+                });
 
-        Some((part_id, leader_location))
+        Some((part_id, location))
     }
 
     /// get_partition will return phy part id. phy id is part id in 3.x, not
@@ -841,8 +1553,9 @@ impl ObTableClientInner {
                     return Err(CommonErr(CommonErrCode::PartitionError, error_msg));
                 }
 
-                let first_part_id = first_part_desc.get_part_id(&row_key[..first_part_len])?;
-                let sub_part_id = sub_part_desc.get_part_id(&row_key[first_part_len..])?;
+                // part descs locate their columns by the row key indexes
+                let first_part_id = first_part_desc.get_part_id(row_key)?;
+                let sub_part_id = sub_part_desc.get_part_id(row_key)?;
 
                 if first_part_id < 0 || sub_part_id < 0 {
                     let error_msg = format!(
@@ -906,6 +1619,59 @@ impl ObTableClientInner {
         self.get_or_refresh_table_entry_with_blocking(table_name, refresh, true)
     }
 
+    /// Get the table entry without blocking the runtime: the entry is loaded
+    /// on a blocking thread, and the concurrent callers missing or refreshing
+    /// the same table wait for a single load.
+    async fn get_or_refresh_table_entry_async(
+        self: &Arc<Self>,
+        table_name: &str,
+        refresh: bool,
+    ) -> Result<Arc<TableEntry>> {
+        if let Some(table_entry) = self.get_table_entry_from_cache(table_name) {
+            if !refresh || !self.need_refresh_table_entry(&table_entry, false) {
+                return Ok(table_entry);
+            }
+        }
+
+        let flight = self
+            .table_refreshes
+            .lock()
+            .unwrap()
+            .entry((table_name.to_owned(), refresh))
+            .or_default()
+            .clone();
+
+        let result = flight
+            .get_or_init(|| async move {
+                let inner = self.clone();
+                let name = table_name.to_owned();
+                self.runtimes
+                    .bg_runtime
+                    .spawn_blocking(move || inner.get_or_refresh_table_entry(&name, refresh))
+                    .await
+                    .and_then(|r| r)
+                    .map_err(Arc::new)
+            })
+            .await
+            .clone();
+
+        {
+            let mut table_refreshes = self.table_refreshes.lock().unwrap();
+            let key = (table_name.to_owned(), refresh);
+            if table_refreshes
+                .get(&key)
+                .map_or(false, |f| Arc::ptr_eq(f, &flight))
+            {
+                table_refreshes.remove(&key);
+            }
+        }
+        drop(flight);
+
+        // the error keeps its kind, and is returned as is to the last caller
+        // holding it
+        result.map_err(|e| Arc::try_unwrap(e).unwrap_or_else(Error::Shared))
+    }
+
     fn get_or_refresh_table_entry_non_blocking(
         &self,
         table_name: &str,
@@ -1187,7 +1953,7 @@ impl ObTableClientInner {
         self.init_metadata()
     }
 
-    fn close(&mut self) -> Result<()> {
+    fn close(&self) -> Result<()> {
         if self.is_closed() {
             warn!("ObTableClientInner::close already closed.");
             return Ok(());
@@ -1203,6 +1969,9 @@ impl ObTableClientInner {
         for (_addr, table) in self.table_roster.wl().drain() {
             drop(table);
         }
+        if let Some(cache) = &self.table_entry_cache {
+            cache.close();
+        }
 
         Ok(())
     }
@@ -1230,50 +1999,16 @@ impl ObTableClientInner {
             return Ok(());
         }
 
-        let new_ocp_model = self.ocp_manager.load_ocp_model(
-            &self.param_url,
-            &self.datasource_name,
-            self.config.rslist_acquire_try_times,
-            self.config.rslist_acquire_retry_interval,
-            false,
-        )?;
-
-        self.location
-            .invalidate_mysql_pools(&new_ocp_model.observer_addrs);
-
-        *self.ocp_model.wl() = new_ocp_model;
-
-        let root_server_key =
-            TableEntryKey::new_root_server_key(&self.cluster_name, &self.tenant_name);
-
-        let table_entry = {
-            self.location.load_table_entry_randomly(
-                &self.ocp_model.rl().observer_addrs,
-                &root_server_key,
-                self.config.table_entry_acquire_connect_timeout,
-                self.config.table_entry_acquire_read_timeout,
-            )?
-        };
-
-        let mut servers: Vec<ObServerAddr> = vec![];
+        let servers = self.location_provider.load_servers()?;
+        if let Some(cache) = &self.table_entry_cache {
+            cache.save_servers(&self.cluster_name, &self.tenant_name, &servers);
+        }
 
         {
             // update table roster
             let mut table_roster = self.table_roster.wl();
 
-            for replica_location in table_entry.table_location().replica_locations() {
-                let info = replica_location.info();
-
-                if !info.is_active() {
-                    warn!("ObTableClientInner::sync_refresh_metadata will not refresh location {:?}, because it's status is {:?} and stop time is {}",
-                          replica_location.addr(), info.status(), info.stop_time());
-                    continue;
-                }
-
-                let addr = replica_location.addr();
-
-                servers.push(addr.clone());
-
+            for addr in &servers {
                 if table_roster.contains_key(addr) {
                     continue;
                 }
@@ -1304,48 +2039,113 @@ impl ObTableClientInner {
 
     fn init_metadata(&self) -> Result<()> {
         let _lock = self.refresh_metadata_mutex.lock();
-        *self.ocp_model.wl() = self.ocp_manager.load_ocp_model(
-            &self.param_url,
-            &self.datasource_name,
-            self.config.rslist_acquire_try_times,
-            self.config.rslist_acquire_retry_interval,
-            true,
-        )?;
-
-        let root_server_key =
-            TableEntryKey::new_root_server_key(&self.cluster_name, &self.tenant_name);
-
-        let table_entry = {
-            self.location.load_table_entry_randomly(
-                &self.ocp_model.rl().observer_addrs,
-                &root_server_key,
-                self.config.table_entry_acquire_connect_timeout,
-                self.config.table_entry_acquire_read_timeout,
-            )?
-        };
-
-        let mut servers: Vec<ObServerAddr> = vec![];
+        let servers = self.load_init_servers(None)?;
 
         let mut conn_count = 0;
-        for replica_location in table_entry.table_location().replica_locations().iter() {
-            let info = replica_location.info();
-
-            if !info.is_active() {
-                warn!("ObTableClientInner::init_metadata will not refresh location {:?}, because it's status is {:?} and stop time is {}",
-                      replica_location.addr(), info.status(), info.stop_time());
-                continue;
+        for addr in &servers {
+            match self.add_ob_table(addr) {
+                Ok(_) => conn_count += 1,
+                Err(e) => warn!("ObTableClientInner::init_metadata add ob table fail with location:{:?}, err:{:?}",
+                      addr, e)
             }
+        }
+        self.reset_init_servers(servers, conn_count)
+    }
+
+    /// Initializes the client as `init` does, running the blocking loading of
+    /// the servers and the connections to the servers on the background
+    /// runtime until `deadline`. The timeouts of the config server and the
+    /// sys tenant are shortened to the deadline, a blocking task still
+    /// running at the deadline completes in the background.
+    async fn init_async(self: &Arc<Self>, deadline: Instant) -> Result<()> {
+        if self.initialized.swap(true, Ordering::AcqRel) {
+            warn!("ObTableClientInner::init_async already initialized.");
+            return Ok(());
+        }
+        let timed_out = |stage| Error::InitTimeout(stage, self.config.init_timeout);
 
-            let addr = replica_location.addr();
+        let inner = self.clone();
+        let servers = self.runtimes.bg_runtime.spawn_blocking(move || {
+            let _lock = inner.refresh_metadata_mutex.lock();
+            inner.load_init_servers(Some(deadline))
+        });
+        let servers = timeout_at(deadline.into(), servers)
+            .await
+            .map_err(|_| timed_out(InitStage::LoadServers))?
+            .and_then(|r| r)
+            .map_err(|e| {
+                let stage = match e {
+                    Error::Http(_) | CommonErr(CommonErrCode::OcpError, _) => {
+                        InitStage::ConfigServer
+                    }
+                    _ => InitStage::LoadServers,
+                };
+                Error::Init(stage, Box::new(e))
+            })?;
+
+        // connect to the servers concurrently
+        let handles: Vec<_> = servers
+            .iter()
+            .map(|addr| {
+                let inner = self.clone();
+                let addr = addr.clone();
+                self.runtimes
+                    .bg_runtime
+                    .spawn_blocking(move || inner.add_ob_table(&addr).map_err(|e| (addr, e)))
+            })
+            .collect();
+        let conn_count = timeout_at(deadline.into(), async move {
+            let mut conn_count = 0;
+            for handle in handles {
+                match handle.await {
+                    Ok(Ok(_)) => conn_count += 1,
+                    Ok(Err((addr, e))) => warn!("ObTableClientInner::init_async add ob table fail with location:{:?}, err:{:?}",
+                          addr, e),
+                    Err(e) => warn!("ObTableClientInner::init_async fail to join the warm-up task, err:{}", e),
+                }
+            }
+            conn_count
+        })
+        .await
+        .map_err(|_| timed_out(InitStage::WarmUpConnections))?;
 
-            servers.push(addr.clone());
+        self.reset_init_servers(servers, conn_count)
+            .map_err(|e| Error::Init(InitStage::WarmUpConnections, Box::new(e)))
+    }
 
-            match self.add_ob_table(addr){
-                Ok(_) => conn_count += 1,
-                Err(e) => warn!("ObTableClientInner::init_metadata add ob table fail with location:{:?}, err:{:?}",
-                      replica_location.addr(), e)
+    /// Loads the servers to start with from the location provider, or from
+    /// the cache file if the provider fails. The provider is asked to give up
+    /// at `deadline` if any.
+    fn load_init_servers(&self, deadline: Option<Instant>) -> Result<Vec<ObServerAddr>> {
+        let loaded = match deadline {
+            Some(deadline) => self.location_provider.load_servers_until(deadline),
+            None => self.location_provider.load_servers(),
+        };
+        let servers = match loaded {
+            Ok(servers) => {
+                if let Some(cache) = &self.table_entry_cache {
+                    cache.save_servers(&self.cluster_name, &self.tenant_name, &servers);
+                }
+                servers
             }
-        }
+            Err(e) => match self
+                .table_entry_cache
+                .as_ref()
+                .and_then(|cache| cache.load_servers(&self.cluster_name, &self.tenant_name))
+            {
+                Some(servers) => {
+                    warn!("ObTableClientInner::load_init_servers fail to load servers, use the cached ones: {:?}, err: {}",
+                          servers, e);
+                    servers
+                }
+                None => return Err(e),
+            },
+        };
+        Ok(servers)
+    }
+
+    /// Starts with the servers once `conn_count` of them are connected.
+    fn reset_init_servers(&self, servers: Vec<ObServerAddr>, conn_count: usize) -> Result<()> {
         if conn_count == 0 {
             return Err(CommonErr(
                 CommonErrCode::InvalidServerAddr,
@@ -1360,6 +2160,29 @@ impl ObTableClientInner {
         Ok(())
     }
 
+    /// Loads the cached routing of the tables not loaded yet, returns their
+    /// names.
+    fn load_cached_table_entries(&self) -> Vec<String> {
+        let cache = match &self.table_entry_cache {
+            Some(cache) => cache,
+            None => return Vec::new(),
+        };
+        let entries =
+            cache.load_table_entries(&self.cluster_name, &self.tenant_name, &self.database);
+
+        let mut table_locations = self.table_locations.wl();
+        let mut loaded = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let table_name = entry.table_entry_key().table_name().to_owned();
+            if table_locations.contains_key(&table_name) {
+                continue;
+            }
+            table_locations.insert(table_name.clone(), Arc::new(entry));
+            loaded.push(table_name);
+        }
+        loaded
+    }
+
     fn check_status(&self) -> Result<()> {
         if !self.is_initialized() {
             return Err(CommonErr(
@@ -1378,20 +2201,119 @@ impl ObTableClientInner {
                 ),
             ));
         }
-        Ok(())
+        Ok(())
+    }
+
+    /// Counts an operation in flight, new operations are refused once the
+    /// client is shutting down.
+    fn begin_op(&self) -> Result<InflightOp<'_>> {
+        self.inflight_ops.fetch_add(1, Ordering::SeqCst);
+        let op = InflightOp(self);
+        if self.shutting_down.load(Ordering::SeqCst) {
+            return Err(CommonErr(
+                CommonErrCode::AlreadyClosed,
+                format!(
+                    "ObTableClientInner::begin_op is shutting down, param url is {}, full username is {}",
+                    self.param_url, self.full_user_name
+                ),
+            ));
+        }
+        self.check_status()?;
+        Ok(op)
+    }
+
+    fn register_stream_session(&self, ob_table: &Arc<ObTable>, session_id: u64) {
+        self.stream_sessions
+            .lock()
+            .unwrap()
+            .insert((ob_table.addr(), session_id), ob_table.clone());
+    }
+
+    fn unregister_stream_session(&self, ob_table: &ObTable, session_id: u64) {
+        self.stream_sessions
+            .lock()
+            .unwrap()
+            .remove(&(ob_table.addr(), session_id));
+    }
+
+    /// Waits until the operations in flight complete, returns false if they
+    /// don't before the deadline.
+    async fn drain_inflight_ops(&self, deadline: Instant) -> bool {
+        loop {
+            let drained = self.inflight_drained.notified();
+            if self.inflight_ops.load(Ordering::SeqCst) == 0 {
+                return true;
+            }
+            if timeout_at(deadline.into(), drained).await.is_err() {
+                return false;
+            }
+        }
+    }
+
+    /// Closes the stream sessions left open by the queries on the servers.
+    async fn close_stream_sessions(&self) {
+        let sessions: Vec<_> = self.stream_sessions.lock().unwrap().drain().collect();
+        for ((_, session_id), ob_table) in sessions {
+            self.close_stream_session(&ob_table, session_id).await;
+        }
+    }
+
+    async fn close_stream_session(&self, ob_table: &ObTable, session_id: u64) {
+        // no-wait request as `QueryStreamResult::close` does
+        let mut req =
+            ObTableStreamRequest::new(session_id, Duration::ZERO, self.config.log_level_flag);
+        req.set_stream_last();
+        if let Err(e) = ob_table
+            .execute_payload(&mut req, &mut ObTableQueryResult::new())
+            .await
+        {
+            warn!("ObTableClientInner::close_stream_session fail to close stream session, addr: {}, session_id: {}, err: {}",
+                  ob_table.addr(), session_id, e);
+        }
+    }
+
+    /// Closes every connection to the servers.
+    async fn close_connections(&self, step_timeout: Duration) {
+        let pools: Vec<_> = self.connection_pools.wl().drain().collect();
+        for (addr, pool) in pools {
+            for conn in pool.close() {
+                match timeout(step_timeout, conn.close()).await {
+                    Ok(Ok(())) => (),
+                    Ok(Err(e)) => warn!(
+                        "ObTableClientInner::close_connections fail to close connection, addr: {:?}, err: {}",
+                        addr, e
+                    ),
+                    Err(_) => warn!(
+                        "ObTableClientInner::close_connections close connection timeout, addr: {:?}",
+                        addr
+                    ),
+                }
+            }
+        }
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn execute_once(
-        &self,
+        self: &Arc<Self>,
         table_name: &str,
         operation_type: ObTableOperationType,
         row_keys: Vec<Value>,
         columns: Option<Vec<String>>,
         properties: Option<Vec<Value>>,
+        read_consistency: ObTableConsistencyLevel,
+        deadline: Option<&OpDeadline>,
     ) -> Result<ObTableOperationResult> {
         self.check_status()?;
 
-        let (part_info, table) = self.get_table(table_name, &row_keys, false)?;
+        if operation_type == ObTableOperationType::Get
+            && read_consistency == ObTableConsistencyLevel::Eventual
+        {
+            return self
+                .execute_read_once(table_name, row_keys, columns, read_consistency, deadline)
+                .await;
+        }
+
+        let (part_info, table) = self.get_table_async(table_name, &row_keys, false).await?;
 
         let start = Instant::now();
 
@@ -1401,11 +2323,14 @@ impl ObTableClientInner {
             row_keys,
             columns,
             properties,
-            self.config.rpc_operation_timeout,
+            self.attempt_timeout(deadline),
             self.config.log_level_flag,
         );
         payload.set_table_id(part_info.table_id);
         payload.set_partition_id(part_info.part_id);
+        if self.config.retry_ambiguous_operations {
+            payload.set_idempotent(true);
+        }
         let mut result = ObTableOperationResult::new();
         table.execute_payload(&mut payload, &mut result).await?;
 
@@ -1414,27 +2339,110 @@ impl ObTableClientInner {
         Ok(result)
     }
 
-    async fn execute(
+    // An eventual get, hedged if enabled.
+    async fn execute_read_once(
+        self: &Arc<Self>,
+        table_name: &str,
+        row_keys: Vec<Value>,
+        columns: Option<Vec<String>>,
+        read_consistency: ObTableConsistencyLevel,
+        deadline: Option<&OpDeadline>,
+    ) -> Result<ObTableOperationResult> {
+        let (part_info, tables) = self.get_read_tables_async(table_name, &row_keys).await?;
+
+        let start = Instant::now();
+
+        let timeout = self.attempt_timeout(deadline);
+        let read = |table: Arc<ObTable>| {
+            let mut payload = ObTableOperationRequest::new(
+                table_name,
+                ObTableOperationType::Get,
+                row_keys.clone(),
+                columns.clone(),
+                None,
+                timeout,
+                self.config.log_level_flag,
+            );
+            payload.set_table_id(part_info.table_id);
+            payload.set_partition_id(part_info.part_id);
+            payload.set_consistency_level(read_consistency);
+            async move {
+                let mut result = ObTableOperationResult::new();
+                table.execute_payload(&mut payload, &mut result).await?;
+                Ok(result)
+            }
+        };
+        let mut tables = tables.into_iter();
+        let primary = read(tables.next().expect("no table to read"));
+        let result = match &self.hedger {
+            Some(hedger) => {
+                hedger
+                    .run(ReadKind::Get, primary, tables.next().map(read))
+                    .await?
+            }
+            None => primary.await?,
+        };
+
+        OBKV_CLIENT_METRICS.observe_operation_opt_rt(ObTableOperationType::Get, start.elapsed());
+
+        Ok(result)
+    }
+
+    // Execute the batch of a partition on the first of `tables`, hedged to the
+    // second one if any.
+    async fn execute_part_batch(
         &self,
         table_name: &str,
+        tables: Vec<Arc<ObTable>>,
+        batch_op: ObTableBatchOperation,
+        timeout: Duration,
+        read_consistency: ObTableConsistencyLevel,
+    ) -> Result<Vec<TableOpResult>> {
+        let read = |table: Arc<ObTable>, batch_op| async move {
+            table
+                .execute_batch_with_consistency(table_name, batch_op, timeout, read_consistency)
+                .await
+        };
+        let mut tables = tables.into_iter();
+        let primary = tables.next().expect("no table to execute the batch");
+        match (&self.hedger, tables.next()) {
+            (Some(hedger), Some(hedge)) => {
+                hedger
+                    .run(
+                        ReadKind::MultiGet,
+                        read(primary, batch_op.clone()),
+                        Some(read(hedge, batch_op)),
+                    )
+                    .await
+            }
+            _ => read(primary, batch_op).await,
+        }
+    }
+
+    async fn execute(
+        self: &Arc<Self>,
+        table_name: &str,
         operation_type: ObTableOperationType,
         row_keys: Vec<Value>,
         columns: Option<Vec<String>>,
         properties: Option<Vec<Value>>,
+        options: OpOptions,
     ) -> Result<ObTableOperationResult> {
+        let _op = self.begin_op()?;
+        let deadline = self.op_deadline(options.deadline);
         let mut retry_num = 0;
         loop {
             retry_num += 1;
-            return match self
-                .execute_once(
-                    table_name,
-                    operation_type,
-                    row_keys.clone(),
-                    columns.clone(),
-                    properties.clone(),
-                )
-                .await
-            {
+            let attempt = self.execute_once(
+                table_name,
+                operation_type,
+                row_keys.clone(),
+                columns.clone(),
+                properties.clone(),
+                options.read_consistency,
+                deadline.as_ref(),
+            );
+            return match OpDeadline::run(deadline.as_ref(), retry_num, attempt).await {
                 Ok(result) => {
                     let error_no = result.header().errorno();
                     let result_code = ResultCodes::from_i32(error_no);
@@ -1459,7 +2467,19 @@ impl ObTableClientInner {
                          op_type:{:?}, retry_num:{}, err:{}",
                         table_name, operation_type, retry_num, e
                     );
-                    if let Err(fail_err) = self.on_table_op_failure(table_name, &e) {
+                    let remaining = deadline.as_ref().map(OpDeadline::remaining);
+                    let decision = self.retry_policy.decide(
+                        &RetryContext {
+                            table_name,
+                            op: RetryOp::Execute(operation_type),
+                            attempt: retry_num,
+                            remaining,
+                        },
+                        &e,
+                    );
+                    if let Err(fail_err) =
+                        self.on_table_op_failure(table_name, &e, decision.refresh)
+                    {
                         error!(
                             "ObTableClientInner::execute on_table_op_failure, table_name:{}, \
                              op_type:{:?}, retry_num:{}, err:{}",
@@ -1467,14 +2487,21 @@ impl ObTableClientInner {
                         );
                         return Err(e);
                     }
-                    if retry_num < self.config.rpc_retry_limit && e.need_retry() {
+                    if let (Some(deadline), Some(remaining)) = (&deadline, remaining) {
+                        if remaining.is_zero() || decision.retry_after >= Some(remaining) {
+                            warn!(
+                                "ObTableClientInner::execute deadline exceeded, table_name:{}, \
+                                 op_type:{:?}, retry_num:{}, err:{}",
+                                table_name, operation_type, retry_num, e
+                            );
+                            return Err(deadline.exceeded_after(retry_num, &e));
+                        }
+                    }
+                    if let Some(delay) = decision.retry_after {
                         OBKV_CLIENT_METRICS.inc_retry_times(ObClientOpRetryType::Execute);
 
-                        if self.config.rpc_retry_interval.as_secs() > 0 {
-                            sleep(Duration::from_millis(
-                                self.config.rpc_retry_interval.as_millis() as u64,
-                            ))
-                            .await;
+                        if !delay.is_zero() {
+                            sleep(delay).await;
                         }
                         continue;
                     }
@@ -1502,6 +2529,9 @@ impl Drop for ObTableClientInner {
 pub type RuntimesRef = Arc<ObClientRuntimes>;
 
 /// OBKV Table Runtime
+///
+/// The runtimes can be shared by several clients, see
+/// [`Builder::runtimes`].
 #[derive(Clone, Debug)]
 pub struct ObClientRuntimes {
     /// Runtime for connection to read data
@@ -1513,6 +2543,32 @@ pub struct ObClientRuntimes {
 }
 
 impl ObClientRuntimes {
+    /// Build the dedicated runtimes sized by `config`.
+    pub fn new(config: &ClientConfig) -> ObClientRuntimes {
+        build_obkv_runtimes(config)
+    }
+
+    /// Run every task on the runtime of `handle`, which is never shut down by
+    /// the clients. The runtime must be multi-threaded: the clients block
+    /// its worker threads shortly, waiting for new connections.
+    pub fn from_handle(handle: Handle) -> Result<ObClientRuntimes> {
+        if handle.runtime_flavor() != RuntimeFlavor::MultiThread {
+            return Err(CommonErr(
+                CommonErrCode::InvalidParam,
+                format!(
+                    "ObClientRuntimes::from_handle the runtime must be multi-threaded, flavor: {:?}",
+                    handle.runtime_flavor()
+                ),
+            ));
+        }
+        let runtime = Arc::new(runtime::Runtime::from_handle("ob-host", handle));
+        Ok(ObClientRuntimes {
+            tcp_send_runtime: runtime.clone(),
+            tcp_recv_runtime: runtime.clone(),
+            bg_runtime: runtime,
+        })
+    }
+
     pub fn test_default() -> ObClientRuntimes {
         ObClientRuntimes {
             tcp_recv_runtime: Arc::new(build_runtime("ob-tcp-reviever", 1)),
@@ -1520,6 +2576,92 @@ impl ObClientRuntimes {
             bg_runtime: Arc::new(build_runtime("ob-default", 1)),
         }
     }
+
+    /// Shut the owned runtimes down, waiting up to `timeout` for each of
+    /// them, see [`runtime::Runtime::shutdown_timeout`].
+    ///
+    /// It blocks the current thread, so it must not be called in an
+    /// asynchronous context.
+    pub fn shutdown_timeout(&self, timeout: Duration) {
+        for runtime in [
+            &self.bg_runtime,
+            &self.tcp_send_runtime,
+            &self.tcp_recv_runtime,
+        ] {
+            runtime.shutdown_timeout(timeout);
+        }
+    }
+}
+
+impl PartialEq for ObClientRuntimes {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.tcp_send_runtime, &other.tcp_send_runtime)
+            && Arc::ptr_eq(&self.tcp_recv_runtime, &other.tcp_recv_runtime)
+            && Arc::ptr_eq(&self.bg_runtime, &other.bg_runtime)
+    }
+}
+
+impl Eq for ObClientRuntimes {}
+
+/// Split the row key range [start, end) into at most `splits` ranges at even
+/// points of its leading column in [lo, hi), which lie inside the range and
+/// take the integer type of `template`.
+fn split_row_key_range(
+    start: Vec<Value>,
+    end: Vec<Value>,
+    lo: i128,
+    hi: i128,
+    template: &Value,
+    splits: usize,
+) -> Vec<(Vec<Value>, Vec<Value>)> {
+    let mut points: Vec<i128> = Vec::with_capacity(splits.saturating_sub(1));
+    for i in 1..splits {
+        let point = lo + (hi - lo) * i as i128 / splits as i128;
+        if point > lo && points.last().map_or(true, |last| point > *last) {
+            points.push(point);
+        }
+    }
+
+    let mut ranges = Vec::with_capacity(points.len() + 1);
+    let mut range_start = start;
+    for point in points {
+        let mut split = vec![Value::get_min(); range_start.len()];
+        split[0] = with_int_value(template, point);
+        ranges.push((range_start, split.clone()));
+        range_start = split;
+    }
+    ranges.push((range_start, end));
+    ranges
+}
+
+// The value of an integer row key column, none for the other types and the
+// min / max values.
+fn int_value(value: &Value) -> Option<i128> {
+    if value.is_extend() {
+        return None;
+    }
+    match value {
+        Value::Int8(v, _) => Some(*v as i128),
+        Value::UInt8(v, _) => Some(*v as i128),
+        Value::Int32(v, _) => Some(*v as i128),
+        Value::UInt32(v, _) => Some(*v as i128),
+        Value::Int64(v, _) => Some(*v as i128),
+        Value::UInt64(v, _) => Some(*v as i128),
+        _ => None,
+    }
+}
+
+// The value `v` of the integer type of `template`, which `v` fits in.
+fn with_int_value(template: &Value, v: i128) -> Value {
+    match template {
+        Value::Int8(_, meta) => Value::Int8(v as i8, meta.clone()),
+        Value::UInt8(_, meta) => Value::UInt8(v as u8, meta.clone()),
+        Value::Int32(_, meta) => Value::Int32(v as i32, meta.clone()),
+        Value::UInt32(_, meta) => Value::UInt32(v as u32, meta.clone()),
+        Value::UInt64(_, meta) => Value::UInt64(v as u64, meta.clone()),
+        Value::Int64(_, meta) => Value::Int64(v as i64, meta.clone()),
+        _ => unreachable!("not an integer value: {template:?}"),
+    }
 }
 
 fn build_runtime(name: &str, threads_num: usize) -> runtime::Runtime {
@@ -1545,6 +2687,12 @@ fn build_obkv_runtimes(config: &ClientConfig) -> ObClientRuntimes {
 pub struct ObTableClient {
     inner: Arc<ObTableClientInner>,
     refresh_thread_pool: Arc<ScheduledThreadPool>,
+    // the periodic refresh of the table entries, cancelled on shutdown
+    refresh_job: Arc<Mutex<Option<JobHandle>>>,
+    // the thread refreshing the table entries on demand, joined on shutdown
+    refresh_worker: Arc<Mutex<Option<thread::JoinHandle<()>>>>,
+    // options of the single and batch operations
+    options: OpOptions,
 }
 
 impl ObTableClient {
@@ -1553,6 +2701,45 @@ impl ObTableClient {
         ob_vsn_major()
     }
 
+    /// A client sharing this one, whose single and batch operations, queries
+    /// and aggregations are given `deadline` in total, see
+    /// [`ClientConfig::operation_deadline`].
+    pub fn with_deadline(&self, deadline: Duration) -> ObTableClient {
+        ObTableClient {
+            options: OpOptions {
+                deadline: Some(deadline),
+                ..self.options
+            },
+            ..self.clone()
+        }
+    }
+
+    /// A client sharing this one, whose gets, multi-gets and queries are read
+    /// with `consistency`. Eventual reads are served by a follower while the
+    /// circuit breaker of the leader is open, see
+    /// [`ClientConfig::circuit_breaker`], and hedged if enabled, see
+    /// [`ClientConfig::hedged_reads`].
+    pub fn with_read_consistency(&self, consistency: ObTableConsistencyLevel) -> ObTableClient {
+        ObTableClient {
+            options: OpOptions {
+                read_consistency: consistency,
+                ..self.options
+            },
+            ..self.clone()
+        }
+    }
+
+    /// The state of the circuit breaker of the server, none if the client
+    /// hasn't talked to it yet or the circuit breakers are disabled.
+    pub fn circuit_state(&self, addr: &ObServerAddr) -> Option<CircuitState> {
+        self.inner
+            .circuit_breakers
+            .lock()
+            .unwrap()
+            .get(addr)
+            .map(|breaker| breaker.state())
+    }
+
     /// Add row key element for table.
     pub fn add_row_key_element(&self, table_name: &str, columns: Vec<String>) {
         self.inner.add_row_key_element(table_name, columns);
@@ -1565,12 +2752,17 @@ impl ObTableClient {
 
     /// Create a TableQuery instance for table.
     pub fn query(&self, table_name: &str) -> ObTableClientQueryImpl {
-        ObTableClientQueryImpl::new(table_name, self.inner.clone())
+        let mut query = ObTableClientQueryImpl::new(table_name, self.inner.clone());
+        query.read_consistency = self.options.read_consistency;
+        query.deadline = self.options.deadline;
+        query
     }
 
     /// Create a TableAggregation instacne for table.
     pub fn aggregate(&self, table_name: &str) -> ObTableAggregation {
-        ObTableAggregation::new(table_name, self.inner.clone())
+        let mut aggregation = ObTableAggregation::new(table_name, self.inner.clone());
+        aggregation.table_query.deadline = self.options.deadline;
+        aggregation
     }
 
     pub fn truncate_table(&self, table_name: &str) -> Result<()> {
@@ -1594,6 +2786,10 @@ impl ObTableClient {
         self.inner.is_closed()
     }
 
+    pub(crate) fn runtimes(&self) -> &RuntimesRef {
+        &self.inner.runtimes
+    }
+
     /// Returns true when the client is initialized.
     pub fn is_initialized(&self) -> bool {
         self.inner.is_initialized()
@@ -1602,40 +2798,204 @@ impl ObTableClient {
     /// Intialize the ob table client instance.
     pub fn init(&self) -> Result<()> {
         self.inner.init()?;
+        self.start_refreshing();
+        Ok(())
+    }
+
+    /// Starts refreshing the table entries, the cached ones soon.
+    fn start_refreshing(&self) {
+        let cached_tables = self.inner.load_cached_table_entries();
+        if !cached_tables.is_empty() {
+            info!(
+                "ObTableClient::init load cached table entries: {:?}",
+                cached_tables
+            );
+            // the cached routing may be out of date, refresh it soon
+            let inner = self.inner.clone();
+            self.refresh_thread_pool.execute(move || {
+                for table_name in cached_tables {
+                    if let Err(e) = inner.get_or_refresh_table_entry_non_blocking(&table_name, true)
+                    {
+                        warn!("ObTableClient::init fail to refresh cached table entry, table_name: {}, err: {}",
+                              table_name, e);
+                    }
+                }
+            });
+        }
+
         let inner = self.inner.clone();
-        self.refresh_thread_pool.execute_with_fixed_delay(
+        let refresh_job = self.refresh_thread_pool.execute_with_fixed_delay(
             inner.config.table_entry_refresh_interval_base,
             inner.config.table_entry_refresh_interval_ceiling,
             move || {
                 inner.refresh_all_table_entries();
             },
         );
+        *self.refresh_job.lock().unwrap() = Some(refresh_job);
+    }
+
+    /// Shut the client down gracefully: new operations are refused at once
+    /// and the ones in flight are given up to `timeout` to complete. Then
+    /// the stream sessions left open are closed on the servers, every
+    /// connection is closed, and the background workers and the runtimes of
+    /// the client are stopped.
+    ///
+    /// Operations still in flight after the timeout fail. The client can't be
+    /// used anymore once shut down, shutting it down again does nothing.
+    pub async fn shutdown(&self, timeout: Duration) -> Result<()> {
+        let inner = &self.inner;
+        if inner.shutting_down.swap(true, Ordering::SeqCst) {
+            warn!("ObTableClient::shutdown already shut down.");
+            return Ok(());
+        }
+        let start = Instant::now();
+        let deadline = start + timeout;
+        let step_timeout = || {
+            cmp::max(
+                deadline.saturating_duration_since(Instant::now()),
+                MIN_SHUTDOWN_STEP_TIMEOUT,
+            )
+        };
+
+        if !inner.drain_inflight_ops(deadline).await {
+            warn!(
+                "ObTableClient::shutdown {} operations are still in flight after {:?}",
+                inner.inflight_ops.load(Ordering::SeqCst),
+                timeout
+            );
+        }
+
+        inner.close_stream_sessions().await;
+        inner.close()?;
+        inner.close_connections(step_timeout()).await;
 
+        // stop refreshing the table entries
+        if let Some(refresh_job) = self.refresh_job.lock().unwrap().take() {
+            refresh_job.cancel();
+        }
+        let refresh_worker = self.refresh_worker.lock().unwrap().take();
+        if let Some(refresh_worker) = refresh_worker {
+            // wake the worker up to find the client closed
+            let _ = inner.refresh_sender.try_send(String::new());
+            let joined = task::spawn_blocking(move || refresh_worker.join());
+            if tokio::time::timeout(step_timeout(), joined).await.is_err() {
+                warn!("ObTableClient::shutdown refresh worker is still running");
+            }
+        }
+
+        // the runtimes provided by the caller are left running
+        if inner.owns_runtimes {
+            // on a thread of its own, as the current one may belong to the runtimes
+            let runtimes = inner.runtimes.clone();
+            let runtime_timeout = step_timeout();
+            let (sender, receiver) = oneshot::channel();
+            thread::Builder::new()
+                .name("ShutdownRuntimesThread".to_string())
+                .spawn(move || {
+                    runtimes.shutdown_timeout(runtime_timeout);
+                    let _ = sender.send(());
+                })?;
+            receiver.await.map_err(|e| {
+                CommonErr(
+                    CommonErrCode::Rpc,
+                    format!("ObTableClient::shutdown fail to shut the runtimes down, err: {e}"),
+                )
+            })?;
+        }
+
+        info!("ObTableClient::shutdown cost {:?}", start.elapsed());
         Ok(())
     }
 
-    pub fn get_table(
+    pub async fn get_table(
         &self,
         table_name: &str,
         row_key: &[Value],
         refresh: bool,
     ) -> Result<(PartInfo, Arc<ObTable>)> {
-        self.inner.get_table(table_name, row_key, refresh)
+        self.inner
+            .get_table_async(table_name, row_key, refresh)
+            .await
+    }
+
+    /// Returns the partition the row key is routed to.
+    pub async fn partition_of(&self, table_name: &str, row_key: &[Value]) -> Result<PartitionId> {
+        self.inner.partition_of(table_name, row_key).await
+    }
+
+    /// Returns the partitions and their leaders a scan over the range
+    /// [start, end] is routed to.
+    pub async fn partitions_for_range(
+        &self,
+        table_name: &str,
+        start: &[Value],
+        end: &[Value],
+    ) -> Result<Vec<PartitionInfo>> {
+        self.inner
+            .partitions_for_range(table_name, start, end)
+            .await
+    }
+
+    /// Returns row key ranges which together cover the table, each served by
+    /// a single partition and usable as a scan range with an inclusive start
+    /// and an exclusive end, restricted to its partition by
+    /// [`ObTableClientQueryImpl::partition`].
+    ///
+    /// Tables are split along the bounds of their range partitions. The
+    /// partitions of hash, key and list partitioned tables are not key
+    /// ranges, each of them is a split over all the row keys. Partitions are
+    /// further split evenly between the first and the last rows on an
+    /// integer leading row key column to approach `target_splits`, which
+    /// must be positive. A table without any partition has no split.
+    pub async fn scan_splits(
+        &self,
+        table_name: &str,
+        target_splits: usize,
+    ) -> Result<Vec<ScanSplit>> {
+        self.inner.scan_splits(table_name, target_splits).await
     }
 
     async fn execute_batch_once(
         &self,
         table_name: &str,
         batch_op: ObTableBatchOperation,
+        deadline: Option<&OpDeadline>,
     ) -> Result<Vec<TableOpResult>> {
         self.inner.check_status()?;
 
         assert!(batch_op.is_raw());
         let mut batch_op = batch_op;
+        let idempotent = batch_op.is_idempotent() || self.inner.config.retry_ambiguous_operations;
 
         OBKV_CLIENT_METRICS.observe_misc("batch_ops", batch_op.get_raw_ops().len() as f64);
 
-        let table_entry = self.inner.get_or_refresh_table_entry(table_name, false)?;
+        if let Some(addr) = &self.inner.odp_addr {
+            if batch_op.get_raw_ops().is_empty() {
+                return Ok(Vec::new());
+            }
+            let (part_info, table) = self.inner.get_odp_table(addr).await?;
+            let is_atomic_op = batch_op.is_atomic_op();
+            let mut odp_batch_op = ObTableBatchOperation::new();
+            for op in batch_op.take_raw_ops() {
+                odp_batch_op.add_op(op);
+            }
+            odp_batch_op.set_table_id(part_info.table_id);
+            odp_batch_op.set_table_name(table_name.to_owned());
+            odp_batch_op.set_partition_id(part_info.part_id);
+            odp_batch_op.set_atomic_op(is_atomic_op);
+            return table
+                .execute_batch_with_timeout(
+                    table_name,
+                    odp_batch_op,
+                    self.inner.attempt_timeout(deadline),
+                )
+                .await;
+        }
+
+        let table_entry = self
+            .inner
+            .get_or_refresh_table_entry_async(table_name, false)
+            .await?;
 
         let mut part_batch_ops = HashMap::with_capacity(1);
         for op in batch_op.take_raw_ops() {
@@ -1651,17 +3011,43 @@ impl ObTableClient {
 
         OBKV_CLIENT_METRICS.observe_misc("partitioned_batch_ops", part_batch_ops.len() as f64);
 
+        // the multi-gets read with eventual consistency may be served by the
+        // followers and hedged
+        let read_consistency = self.options.read_consistency;
+        let eventual_read = read_consistency == ObTableConsistencyLevel::Eventual
+            && part_batch_ops.values().all(|op| op.is_read_only());
+        let table_entry = &table_entry;
+        let get_tables = |phy_id| async move {
+            if eventual_read {
+                self.inner
+                    .get_read_part_tables(table_name, table_entry, phy_id)
+                    .await
+            } else {
+                self.inner
+                    .get_or_create_table(table_name, table_entry, phy_id)
+                    .await
+                    .map(|(part_info, table)| (part_info, vec![table]))
+            }
+        };
+
         // fast path: to process batch operations involving only one partition
         if part_batch_ops.len() == 1 {
             let (phy, mut part_batch_op) = part_batch_ops.into_iter().next().unwrap();
-            let (part_info, table) =
-                self.inner
-                    .get_or_create_table(table_name, &table_entry, phy)?;
+            let (part_info, tables) = get_tables(phy).await?;
             part_batch_op.set_table_id(part_info.table_id);
             part_batch_op.set_table_name(table_name.to_owned());
             part_batch_op.set_partition_id(part_info.part_id);
             part_batch_op.set_atomic_op(batch_op.is_atomic_op());
-            return table.execute_batch(table_name, part_batch_op).await;
+            return self
+                .inner
+                .execute_part_batch(
+                    table_name,
+                    tables,
+                    part_batch_op,
+                    self.inner.attempt_timeout(deadline),
+                    read_consistency,
+                )
+                .await;
         }
 
         // atomic now only support single partition
@@ -1676,28 +3062,57 @@ impl ObTableClient {
 
         // slow path: have to process operations involving multiple partitions
         // concurrent send the batch ops by partition
-        let mut all_results = Vec::new();
-        let mut handles = Vec::with_capacity(part_batch_ops.len());
+        let mut part_batches = Vec::with_capacity(part_batch_ops.len());
+        for (phy_id, batch_op) in part_batch_ops {
+            part_batches.push((get_tables(phy_id).await?, batch_op));
+        }
 
-        for (phy_id, mut batch_op) in part_batch_ops {
-            let (part_info, table) =
-                self.inner
-                    .get_or_create_table(table_name, &table_entry, phy_id)?;
+        let mut handles = Vec::with_capacity(part_batches.len());
+        for ((part_info, tables), mut batch_op) in part_batches {
+            let inner = self.inner.clone();
             let table_name = table_name.to_owned();
-            handles.push(self.inner.runtimes.bg_runtime.spawn(async move {
+            let timeout = self.inner.attempt_timeout(deadline);
+            let part_batch = async move {
                 batch_op.set_table_id(part_info.table_id);
                 batch_op.set_table_name(table_name.clone());
                 batch_op.set_partition_id(part_info.part_id);
-                table.execute_batch(&table_name, batch_op).await
-            }));
+                inner
+                    .execute_part_batch(&table_name, tables, batch_op, timeout, read_consistency)
+                    .await
+            };
+            handles.push(
+                self.inner
+                    .runtimes
+                    .bg_runtime
+                    .spawn(inherit_pending_requests(part_batch)),
+            );
         }
 
+        // wait for every partition, so that none is still running when the
+        // batch is retried
+        let mut all_results = Vec::new();
+        let mut applied = false;
+        let mut first_err = None;
         for handle in handles {
-            let results = handle.await??;
-            all_results.extend(results);
+            match handle.await.map_err(Error::from).and_then(|res| res) {
+                Ok(results) => {
+                    applied = true;
+                    all_results.extend(results);
+                }
+                Err(e) => {
+                    first_err.get_or_insert(e);
+                }
+            }
         }
 
-        Ok(all_results)
+        match first_err {
+            None => Ok(all_results),
+            // retrying the whole batch would apply the partitions succeeded twice
+            Some(e) if applied && !idempotent && !e.is_ambiguous_outcome() => {
+                Err(Error::AmbiguousOutcome(Box::new(e)))
+            }
+            Some(e) => Err(e),
+        }
     }
 
     #[inline]
@@ -1716,6 +3131,7 @@ impl ObTableClient {
                 row_keys,
                 Some(columns),
                 Some(properties),
+                self.options,
             )
             .await?
             .affected_rows())
@@ -1737,6 +3153,7 @@ impl ObTableClient {
                 row_keys,
                 Some(columns),
                 Some(properties),
+                self.options,
             )
             .await?
             .affected_rows())
@@ -1758,6 +3175,7 @@ impl ObTableClient {
                 row_keys,
                 Some(columns),
                 Some(properties),
+                self.options,
             )
             .await?
             .affected_rows())
@@ -1779,6 +3197,7 @@ impl ObTableClient {
                 row_keys,
                 Some(columns),
                 Some(properties),
+                self.options,
             )
             .await?
             .affected_rows())
@@ -1800,6 +3219,7 @@ impl ObTableClient {
                 row_keys,
                 Some(columns),
                 Some(properties),
+                self.options,
             )
             .await?
             .affected_rows())
@@ -1821,6 +3241,7 @@ impl ObTableClient {
                 row_keys,
                 Some(columns),
                 Some(properties),
+                self.options,
             )
             .await?
             .affected_rows())
@@ -1830,7 +3251,14 @@ impl ObTableClient {
     pub async fn delete(&self, table_name: &str, row_keys: Vec<Value>) -> Result<i64> {
         Ok(self
             .inner
-            .execute(table_name, ObTableOperationType::Del, row_keys, None, None)
+            .execute(
+                table_name,
+                ObTableOperationType::Del,
+                row_keys,
+                None,
+                None,
+                self.options,
+            )
             .await?
             .affected_rows())
     }
@@ -1850,6 +3278,7 @@ impl ObTableClient {
                 row_keys,
                 Some(columns),
                 None,
+                self.options,
             )
             .await?
             .take_entity()
@@ -1866,10 +3295,13 @@ impl ObTableClient {
         table_name: &str,
         batch_op: ObTableBatchOperation,
     ) -> Result<Vec<TableOpResult>> {
+        let _op = self.inner.begin_op()?;
+        let deadline = self.inner.op_deadline(self.options.deadline);
         let mut retry_num = 0;
         loop {
             retry_num += 1;
-            match self.execute_batch_once(table_name, batch_op.clone()).await {
+            let attempt = self.execute_batch_once(table_name, batch_op.clone(), deadline.as_ref());
+            match OpDeadline::run(deadline.as_ref(), retry_num, attempt).await {
                 Ok(res) => {
                     self.inner.reset_table_failure(table_name);
                     return Ok(res);
@@ -1880,7 +3312,20 @@ impl ObTableClient {
                          table_name:{}, retry_num:{}, err:{}",
                         table_name, retry_num, e
                     );
-                    if let Err(fail_err) = self.inner.on_table_op_failure(table_name, &e) {
+                    let remaining = deadline.as_ref().map(OpDeadline::remaining);
+                    let decision = self.inner.retry_policy.decide(
+                        &RetryContext {
+                            table_name,
+                            op: RetryOp::ExecuteBatch,
+                            attempt: retry_num,
+                            remaining,
+                        },
+                        &e,
+                    );
+                    if let Err(fail_err) =
+                        self.inner
+                            .on_table_op_failure(table_name, &e, decision.refresh)
+                    {
                         error!(
                             "ObTableClient::execute_batch fail to call on_table_op_failure, \
                              table_name:{}, err:{}",
@@ -1888,15 +3333,22 @@ impl ObTableClient {
                         );
                         return Err(e);
                     };
-                    if retry_num < self.inner.config.rpc_retry_limit && e.need_retry() {
+                    if let (Some(deadline), Some(remaining)) = (&deadline, remaining) {
+                        if remaining.is_zero() || decision.retry_after >= Some(remaining) {
+                            warn!(
+                                "ObTableClient::execute_batch deadline exceeded, table_name:{}, \
+                                 retry_num:{}, err:{}",
+                                table_name, retry_num, e
+                            );
+                            return Err(deadline.exceeded_after(retry_num, &e));
+                        }
+                    }
+                    if let Some(delay) = decision.retry_after {
                         // TODO: add error type as label
                         OBKV_CLIENT_METRICS.inc_retry_times(ObClientOpRetryType::ExecuteBatch);
 
-                        if self.inner.config.rpc_retry_interval.as_secs() > 0 {
-                            sleep(Duration::from_millis(
-                                self.inner.config.rpc_retry_interval.as_millis() as u64,
-                            ))
-                            .await;
+                        if !delay.is_zero() {
+                            sleep(delay).await;
                         }
                         continue;
                     }
@@ -1946,17 +3398,36 @@ impl StreamQuerier {
         stream_result: &mut QueryStreamResult,
         (part_id, ob_table): (i64, Arc<ObTable>),
         payload: &mut ObTableQueryRequest,
+        hedge: Option<(Arc<ObTable>, ObTableQueryRequest)>,
     ) -> Result<i64> {
+        let _op = self.client.begin_op()?;
         self.client.acquire_query_permit()?;
 
         self.start_execute_ts
             .store(current_time_millis(), Ordering::Relaxed);
 
-        let mut result = ObTableQueryResult::new();
-        match ob_table.execute_payload(payload, &mut result).await {
-            Ok(()) => self.client.reset_table_failure(&self.table_name),
+        let executed = match (&self.client.hedger, hedge) {
+            (Some(hedger), Some((hedge_table, mut hedge_payload))) => {
+                hedger
+                    .run(
+                        ReadKind::Query,
+                        Self::query_partition(ob_table, payload),
+                        Some(Self::query_partition(hedge_table, &mut hedge_payload)),
+                    )
+                    .await
+            }
+            _ => Self::query_partition(ob_table, payload).await,
+        };
+        let (ob_table, result) = match executed {
+            Ok(executed) => {
+                self.client.reset_table_failure(&self.table_name);
+                executed
+            }
             Err(e) => {
-                if let Err(e) = self.client.on_table_op_failure(&self.table_name, &e) {
+                if let Err(e) =
+                    self.client
+                        .on_table_op_failure(&self.table_name, &e, self.need_refresh(&e))
+                {
                     error!(
                         "StreamQuerier::execute_query on_table_op_failure err: {}.",
                         e
@@ -1964,26 +3435,73 @@ impl StreamQuerier {
                 }
                 return Err(e);
             }
-        }
+        };
         let row_count = result.row_count();
         OBKV_CLIENT_METRICS.observe_misc("query_rows", row_count as f64);
+        if result.is_stream() && result.is_stream_next() {
+            self.client
+                .register_stream_session(&ob_table, result.session_id());
+        }
         stream_result.cache_stream_next((part_id, ob_table), result);
         Ok(row_count)
     }
 
+    // Whether to refresh the routing of the table after a failed query, as
+    // the retry policy decides.
+    fn need_refresh(&self, e: &Error) -> bool {
+        let ctx = RetryContext {
+            table_name: &self.table_name,
+            op: RetryOp::Query,
+            attempt: 1,
+            remaining: None,
+        };
+        self.client.retry_policy.decide(&ctx, e).refresh
+    }
+
+    /// Closes in the background the stream sessions of a result dropped
+    /// before it's closed.
+    pub fn close_abandoned_sessions(&self, sessions: Vec<(Arc<ObTable>, u64)>) {
+        let client = self.client.clone();
+        self.client.runtimes.bg_runtime.spawn(async move {
+            for (ob_table, session_id) in sessions {
+                client.unregister_stream_session(&ob_table, session_id);
+                client.close_stream_session(&ob_table, session_id).await;
+            }
+        });
+    }
+
+    async fn query_partition(
+        ob_table: Arc<ObTable>,
+        payload: &mut ObTableQueryRequest,
+    ) -> Result<(Arc<ObTable>, ObTableQueryResult)> {
+        let mut result = ObTableQueryResult::new();
+        ob_table.execute_payload(payload, &mut result).await?;
+        Ok((ob_table, result))
+    }
+
     pub async fn execute_stream(
         &self,
         stream_result: &mut QueryStreamResult,
         (part_id, ob_table): (i64, Arc<ObTable>),
         payload: &mut ObTableStreamRequest,
     ) -> Result<i64> {
+        let _op = self.client.begin_op()?;
         let is_stream_next = payload.is_stream_next();
 
         let mut result = ObTableQueryResult::new();
-        match ob_table.execute_payload(payload, &mut result).await {
+        let executed = ob_table.execute_payload(payload, &mut result).await;
+        // the session is closed unless more rows are coming
+        if !(executed.is_ok() && is_stream_next && result.is_stream() && result.is_stream_next()) {
+            self.client
+                .unregister_stream_session(&ob_table, payload.session_id());
+        }
+        match executed {
             Ok(()) => self.client.reset_table_failure(&self.table_name),
             Err(e) => {
-                if let Err(e) = self.client.on_table_op_failure(&self.table_name, &e) {
+                if let Err(e) =
+                    self.client
+                        .on_table_op_failure(&self.table_name, &e, self.need_refresh(&e))
+                {
                     error!(
                         "StreamQuerier::execute_query on_table_op_failure err: {}.",
                         e
@@ -2006,20 +3524,27 @@ pub const PRIMARY_INDEX_NAME: &str = "PRIMARY";
 
 pub struct ObTableClientQueryImpl {
     operation_timeout: Option<Duration>,
+    // deadline overriding the one of the config
+    deadline: Option<Duration>,
     entity_type: ObTableEntityType,
     table_name: String,
     client: Arc<ObTableClientInner>,
     table_query: ObTableQuery,
+    read_consistency: ObTableConsistencyLevel,
+    partition: Option<PartitionId>,
 }
 
 impl ObTableClientQueryImpl {
     fn new(table_name: &str, client: Arc<ObTableClientInner>) -> Self {
         Self {
             operation_timeout: None,
+            deadline: None,
             entity_type: ObTableEntityType::Dynamic,
             table_name: table_name.to_owned(),
             client,
             table_query: ObTableQuery::new(),
+            read_consistency: ObTableConsistencyLevel::Strong,
+            partition: None,
         }
     }
 
@@ -2038,30 +3563,90 @@ impl ObTableClientQueryImpl {
         self.table_query.is_aggregation()
     }
 
+    /// Whether the query is small enough to be hedged: not streamed and
+    /// limited to a few rows, see
+    /// [`crate::client::hedge::HedgeConfig::max_query_limit`].
+    fn is_hedged(&self) -> bool {
+        let limit = self.table_query.get_limit();
+        match &self.client.hedger {
+            Some(hedger) => {
+                self.table_query.batch_size() == -1
+                    && limit > 0
+                    && limit <= hedger.config().max_query_limit
+            }
+            None => false,
+        }
+    }
+
+    /// Executes the query, within the deadline of the query if any, see
+    /// [`ObTableClientQueryImpl::deadline`].
     pub async fn execute(&self) -> Result<QueryResultSet> {
+        let _op = self.client.begin_op()?;
+        let deadline = self.deadline.or(self.client.config.operation_deadline);
+        let op_deadline = deadline.map(OpDeadline::new);
+        OpDeadline::run(op_deadline.as_ref(), 1, self.execute_inner(deadline)).await
+    }
+
+    async fn execute_inner(&self, deadline: Option<Duration>) -> Result<QueryResultSet> {
         let mut partition_table: HashMap<i64, (PartInfo, Arc<ObTable>)> = HashMap::new();
 
         self.table_query.verify()?;
 
+        let eventual_read = self.read_consistency == ObTableConsistencyLevel::Eventual;
+        let mut hedges: HashMap<i64, Arc<ObTable>> = HashMap::new();
         for range in self.table_query.get_key_ranges() {
             let border_flag = range.get_border_flag();
-            let pairs = self.client.get_tables(
-                &self.table_name,
-                range.get_start_key().keys(),
+            let (start, end) = (range.get_start_key().keys(), range.get_end_key().keys());
+            let (start_inclusive, end_inclusive) = (
                 border_flag.is_inclusive_start(),
-                range.get_end_key().keys(),
                 border_flag.is_inclusive_end(),
-                false,
-            )?;
+            );
+            let pairs = if eventual_read {
+                self.client
+                    .get_read_range_tables(
+                        &self.table_name,
+                        start,
+                        start_inclusive,
+                        end,
+                        end_inclusive,
+                    )
+                    .await?
+            } else {
+                self.client
+                    .get_tables(
+                        &self.table_name,
+                        start,
+                        start_inclusive,
+                        end,
+                        end_inclusive,
+                        false,
+                    )
+                    .await?
+                    .into_iter()
+                    .map(|(part_info, ob_table)| (part_info, vec![ob_table]))
+                    .collect()
+            };
 
-            for (part_info, ob_table) in pairs {
+            for (part_info, ob_tables) in pairs {
                 if partition_table.contains_key(&part_info.part_id) {
                     continue;
                 }
+                let mut ob_tables = ob_tables.into_iter();
+                let ob_table = ob_tables.next().expect("no table to query");
+                if let Some(hedge_table) = ob_tables.next().filter(|_| self.is_hedged()) {
+                    hedges.insert(part_info.part_id, hedge_table);
+                }
                 partition_table.insert(part_info.part_id, (part_info, ob_table));
             }
         }
 
+        // the odp endpoint routes the ranges itself
+        if let (Some(id), None) = (&self.partition, &self.client.odp_addr) {
+            let part_id = id.tablet_id.unwrap_or(id.partition_id);
+            partition_table.retain(|k, _| *k == part_id);
+            hedges.retain(|k, _| *k == part_id);
+        }
+
         // defense for multiple partition aggreagtion
         // partition table len > 1, should check aggregation
         if partition_table.len() > 1 && self.aggregation_check() {
@@ -2082,7 +3667,10 @@ impl ObTableClientQueryImpl {
         stream_result.set_entity_type(self.entity_type());
         stream_result.set_table_name(&self.table_name);
         stream_result.set_expectant(partition_table);
+        stream_result.set_hedges(hedges);
+        stream_result.set_consistency_level(self.read_consistency);
         stream_result.set_operation_timeout(self.operation_timeout);
+        stream_result.set_deadline(deadline);
         stream_result.set_flag(self.client.config.log_level_flag);
         stream_result.init().await?;
 
@@ -2210,6 +3798,17 @@ impl ObTableClientQueryImpl {
         self
     }
 
+    /// Only scan the ranges in the partition `id`, such as the partition of a
+    /// [`ScanSplit`].
+    #[inline]
+    pub fn partition(mut self, id: PartitionId) -> Self
+    where
+        Self: Sized,
+    {
+        self.partition = Some(id);
+        self
+    }
+
     #[inline]
     pub fn index_name(mut self, index_name: &str) -> Self
     where
@@ -2263,6 +3862,19 @@ impl ObTableClientQueryImpl {
         self
     }
 
+    /// Give the execution of the query `deadline` in total, the routing and
+    /// the connections made included, then again every fetch of the next
+    /// rows of the stream. A stream whose fetch is given up is closed.
+    /// Overrides [`ClientConfig::operation_deadline`].
+    #[inline]
+    pub fn deadline(mut self, deadline: Duration) -> Self
+    where
+        Self: Sized,
+    {
+        self.deadline = Some(deadline);
+        self
+    }
+
     #[inline]
     pub fn clear(&mut self) {
         self.reset();
@@ -2281,6 +3893,10 @@ pub struct Builder {
     database: String,
     running_mode: RunningMode,
     config: ClientConfig,
+    location_provider: Option<LocationProviderRef>,
+    retry_policy: Option<RetryPolicyRef>,
+    odp_addr: Option<ObServerAddr>,
+    runtimes: Option<RuntimesRef>,
 }
 
 const USER_NAME_SEPERATORS: &[&str] = &[":", "-", "."];
@@ -2298,6 +3914,10 @@ impl Builder {
             database: "".to_owned(),
             running_mode: RunningMode::Normal,
             config: ClientConfig::default(),
+            location_provider: None,
+            retry_policy: None,
+            odp_addr: None,
+            runtimes: None,
         }
     }
 
@@ -2427,10 +4047,107 @@ impl Builder {
         self
     }
 
+    /// Load the servers and the table locations from `provider` instead of
+    /// the config server and the sys tenant.
+    pub fn location_provider(mut self, provider: Arc<dyn LocationProvider>) -> Self {
+        self.location_provider = Some(LocationProviderRef(provider));
+        self
+    }
+
+    /// Decide the retries of the failed operations with `policy` instead of
+    /// the [`ExponentialBackoff`] of the retry settings of the config.
+    pub fn retry_policy(mut self, policy: Arc<dyn RetryPolicy>) -> Self {
+        self.retry_policy = Some(RetryPolicyRef(policy));
+        self
+    }
+
+    /// Send every request to the ODP (or the single observer) at `ip:port`,
+    /// which routes the requests to the partitions itself. No metadata is
+    /// loaded from the config server or the sys tenant, so no param url is
+    /// needed and the database is set with [`Builder::database`]. Routing
+    /// errors are retried on the endpoint without refreshing any location.
+    pub fn odp_addr(mut self, ip: &str, port: i32) -> Self {
+        assert_not_empty(ip, "Blank odp ip");
+        let mut addr = ObServerAddr::new();
+        addr.address(ip.to_owned());
+        addr.set_svr_port(port);
+        addr.set_sql_port(port);
+        self.odp_addr = Some(addr);
+        self
+    }
+
+    pub fn database(mut self, database: &str) -> Self {
+        assert_not_empty(database, "blank database.");
+        self.database = database.to_owned();
+        self
+    }
+
+    /// Run the connections and the background tasks on `runtimes` instead of
+    /// building dedicated runtimes, so that several clients can share them.
+    /// The runtimes provided are not shut down with the client, and the
+    /// thread numbers of the config are ignored.
+    pub fn runtimes(mut self, runtimes: RuntimesRef) -> Self {
+        self.runtimes = Some(runtimes);
+        self
+    }
+
+    /// Run the connections and the background tasks on the multi-threaded
+    /// runtime of `handle`, see [`ObClientRuntimes::from_handle`].
+    pub fn runtime_handle(self, handle: Handle) -> Result<Self> {
+        Ok(self.runtimes(Arc::new(ObClientRuntimes::from_handle(handle)?)))
+    }
+
+    /// Build and initialize the client asynchronously, as [`Builder::build`]
+    /// and [`ObTableClient::init`] do. The blocking loading of the servers
+    /// from the config server and the sys tenant runs on the background
+    /// runtime, then the connections to the servers are warmed up
+    /// concurrently, all within `init_timeout` of the config.
+    ///
+    /// The loading runs on blocking threads: the HTTP and MySQL timeouts are
+    /// shortened to the time left before `init_timeout`, and a connection
+    /// still being set up when it expires is completed in the background
+    /// before being dropped.
+    ///
+    /// The errors tell the stage that failed, see
+    /// [`crate::error::InitStage`], and the client is shut down in the
+    /// background.
+    pub async fn build_async(self) -> Result<ObTableClient> {
+        let start = Instant::now();
+        let deadline = start + self.config.init_timeout;
+        // the http client of the config server blocks on creation
+        let client = task::spawn_blocking(move || self.build())
+            .await
+            .map_err(Error::from)
+            .and_then(|r| r)
+            .map_err(|e| Error::Init(InitStage::Build, Box::new(e)))?;
+        if let Err(e) = client.inner.init_async(deadline).await {
+            // the blocking loads left may take a while to give up
+            task::spawn(async move {
+                if let Err(e) = client.shutdown(Duration::ZERO).await {
+                    warn!(
+                        "Builder::build_async fail to shut the client down, err:{}",
+                        e
+                    );
+                }
+            });
+            return Err(e);
+        }
+        client.start_refreshing();
+
+        info!("Builder::build_async cost {:?}", start.elapsed());
+        Ok(client)
+    }
+
     pub fn build(self) -> Result<ObTableClient> {
-        assert_not_empty(&self.param_url, "Blank param url");
+        if self.odp_addr.is_none() {
+            assert_not_empty(&self.param_url, "Blank param url");
+        }
+        assert_not_empty(&self.database, "blank database.");
         assert_not_empty(&self.full_user_name, "Blank full user name");
-        let runtimes = Arc::new(build_obkv_runtimes(&self.config));
+        let owns_runtimes = self.runtimes.is_none();
+        let runtimes = self
+            .runtimes
+            .unwrap_or_else(|| Arc::new(build_obkv_runtimes(&self.config)));
         let (sender, receiver) = std::sync::mpsc::sync_channel::<String>(1);
         let inner_client = Arc::new(ObTableClientInner::internal_new(
             self.param_url,
@@ -2444,11 +4161,15 @@ impl Builder {
             self.config,
             sender,
             runtimes,
+            owns_runtimes,
+            self.location_provider,
+            self.retry_policy,
+            self.odp_addr,
         )?);
 
         // refresh schema in ActiveRefreshSchemaThread
         let inner = inner_client.clone();
-        let _handle = thread::Builder::new()
+        let refresh_worker = thread::Builder::new()
             .name("ActiveRefreshMetaThread".to_string())
             .spawn(move || {
                 loop {
@@ -2458,6 +4179,9 @@ impl Builder {
                             Err(_) => None,
                         }
                     };
+                    if inner.is_closed() {
+                        break;
+                    }
 
                     if let Some(message) = message {
                         if let Err(e) = inner.get_or_refresh_table_entry_non_blocking(&message, true) {
@@ -2478,6 +4202,9 @@ impl Builder {
                     .thread_name_pattern("RefreshMetaThread")
                     .build(),
             ),
+            refresh_job: Arc::new(Mutex::new(None)),
+            refresh_worker: Arc::new(Mutex::new(refresh_worker.ok())),
+            options: OpOptions::default(),
         })
     }
 }
@@ -2550,6 +4277,13 @@ impl ObTableAggregation {
         self
     }
 
+    /// Give the aggregation `deadline` in total, overriding
+    /// [`ClientConfig::operation_deadline`].
+    pub fn deadline(mut self, deadline: Duration) -> Self {
+        self.table_query = self.table_query.deadline(deadline);
+        self
+    }
+
     pub async fn execute(mut self) -> Result<HashMap<String, Value>> {
         // In order to get cache size.
         self.table_query = self.table_query.select(self.aggregation_operations);
@@ -2649,3 +4383,47 @@ impl ObTableAggregation {
         self
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_split_row_key_range() {
+        let key = |v: i64| vec![Value::from(v), Value::get_min()];
+        let template = Value::from(0i64);
+        assert_eq!(
+            vec![(key(0), key(10)), (key(10), key(20)), (key(20), key(30))],
+            split_row_key_range(key(0), key(30), 0, 30, &template, 3)
+        );
+        // no more splits than distinct leading values
+        assert_eq!(
+            vec![(key(0), key(1)), (key(1), key(2))],
+            split_row_key_range(key(0), key(2), 0, 2, &template, 8)
+        );
+        // unbounded ranges are split between the rows in them
+        let min = vec![Value::get_min(), Value::get_min()];
+        let max = vec![Value::get_max(), Value::get_max()];
+        assert_eq!(
+            vec![
+                (min.clone(), key(-5)),
+                (key(-5), key(5)),
+                (key(5), max.clone())
+            ],
+            split_row_key_range(min.clone(), max.clone(), -15, 15, &template, 3)
+        );
+        assert_eq!(
+            vec![(key(0), key(30))],
+            split_row_key_range(key(0), key(30), 0, 30, &template, 1)
+        );
+        // the split points take the type of the leading column
+        let template = Value::from(0u32);
+        assert_eq!(
+            vec![
+                (min.clone(), vec![Value::from(2u32), Value::get_min()]),
+                (vec![Value::from(2u32), Value::get_min()], max.clone())
+            ],
+            split_row_key_range(min, max, 0, 4, &template, 2)
+        );
+    }
+}
@@ -20,6 +20,8 @@ use std::{
     num::ParseIntError,
     str::Utf8Error,
     string::{FromUtf8Error, ParseError},
+    sync::Arc,
+    time::Duration,
 };
 
 use futures::Canceled as FutureCanceled;
@@ -103,9 +105,47 @@ quick_error! {
             display("Tokio join error, err:{}", e)
             cause(e)
         }
+        Init(stage: InitStage, e: Box<Error>) {
+            description("Client initialization error")
+            display("Fail to initialize client, stage:{:?}, err:{}", stage, e)
+            cause(&**e)
+        }
+        InitTimeout(stage: InitStage, timeout: Duration) {
+            description("Client initialization timeout")
+            display("Client initialization timeout, stage:{:?}, timeout:{:?}", stage, timeout)
+        }
+        AmbiguousOutcome(e: Box<Error>) {
+            description("Operation outcome unknown")
+            display("Operation may have been applied, err:{}", e)
+            cause(&**e)
+        }
+        DeadlineExceeded(deadline: Duration, attempts: usize) {
+            description("Operation deadline exceeded")
+            display("Operation deadline exceeded, deadline:{:?}, attempts:{}", deadline, attempts)
+        }
+        Shared(e: Arc<Error>) {
+            description("Shared error")
+            display("{}", e)
+            cause(&**e)
+        }
     }
 }
 
+/// Stage of the asynchronous initialization of a client.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InitStage {
+    /// Building the runtimes, the workers and the client.
+    Build,
+    /// Loading the root servers from the config server (OCP).
+    ConfigServer,
+    /// Loading the servers of the cluster from the location provider, the
+    /// sys tenant by default. Timeouts while querying the config server are
+    /// reported at this stage too.
+    LoadServers,
+    /// Connecting and logging in to the servers.
+    WarmUpConnections,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum CommonErrCode {
     InvalidParam,
@@ -123,22 +163,62 @@ pub enum CommonErrCode {
     Lock,
     PermitDenied,
     ConvertFailed,
+    ChecksumMismatch,
+    BlockingInRuntime,
+    CircuitBreakerOpen,
 }
 
 impl Error {
+    // The error shared by the concurrent callers of an operation run once,
+    // such as the load of a table entry, classified as the error itself.
+    fn unshared(&self) -> &Error {
+        match self {
+            Error::Shared(e) => e.unshared(),
+            e => e,
+        }
+    }
+
     /// Returns true when the error is an ob exception.
     pub fn is_ob_exception(&self) -> bool {
-        matches!(self, Error::Common(CommonErrCode::ObException(_), _))
+        matches!(
+            self.unshared(),
+            Error::Common(CommonErrCode::ObException(_), _)
+        )
+    }
+
+    /// Returns the stage of the initialization that failed, return none if
+    /// it's not an initialization error.
+    pub fn init_stage(&self) -> Option<InitStage> {
+        match self.unshared() {
+            Error::Init(stage, _) | Error::InitTimeout(stage, _) => Some(*stage),
+            _ => None,
+        }
+    }
+
+    /// Returns true when the operation failed after it was sent and may have
+    /// been applied, see [`crate::ClientConfig::retry_ambiguous_operations`].
+    pub fn is_ambiguous_outcome(&self) -> bool {
+        matches!(self.unshared(), Error::AmbiguousOutcome(_))
+    }
+
+    /// Returns true when the operation failed to complete before its
+    /// deadline, whether it may have been applied or not.
+    pub fn is_deadline_exceeded(&self) -> bool {
+        match self.unshared() {
+            Error::DeadlineExceeded(_, _) => true,
+            Error::AmbiguousOutcome(e) => e.is_deadline_exceeded(),
+            _ => false,
+        }
     }
 
     // Returns true when the error is common error
     pub fn is_common_err(&self) -> bool {
-        matches!(self, Error::Common(_, _))
+        matches!(self.unshared(), Error::Common(_, _))
     }
 
     // Return the common error code if it's a common error, otherwise return None.
     pub fn common_err_code(&self) -> Option<CommonErrCode> {
-        if let Error::Common(code, _) = self {
+        if let Error::Common(code, _) = self.unshared() {
             Some(*code)
         } else {
             None
@@ -148,7 +228,7 @@ impl Error {
     /// Returns the result code of ob exception, return none if it's not an ob
     /// exception.
     pub fn ob_result_code(&self) -> Option<ResultCodes> {
-        if let Error::Common(CommonErrCode::ObException(code), _desc) = self {
+        if let Error::Common(CommonErrCode::ObException(code), _desc) = self.unshared() {
             Some(*code)
         } else {
             None
@@ -158,7 +238,7 @@ impl Error {
     /// Returns the result msg of ob exception, return none if it's not an ob
     /// exception.
     pub fn ob_result_msg(&self) -> Option<&String> {
-        if let Error::Common(CommonErrCode::ObException(_code), desc) = self {
+        if let Error::Common(CommonErrCode::ObException(_code), desc) = self.unshared() {
             Some(desc)
         } else {
             None
@@ -166,7 +246,7 @@ impl Error {
     }
 
     pub fn need_retry(&self) -> bool {
-        if let Error::Common(CommonErrCode::ObException(code), _) = self {
+        if let Error::Common(CommonErrCode::ObException(code), _) = self.unshared() {
             code.need_retry()
         } else {
             false
@@ -174,9 +254,12 @@ impl Error {
     }
 
     pub fn need_refresh_table(&self) -> bool {
-        if let Error::Common(CommonErrCode::ObException(code), _) = self {
+        if let Error::AmbiguousOutcome(e) = self.unshared() {
+            // the routing may be stale all the same
+            e.need_refresh_table()
+        } else if let Error::Common(CommonErrCode::ObException(code), _) = self.unshared() {
             code.need_refresh_table()
-        } else if let Error::Common(CommonErrCode::ConnPool, message) = self {
+        } else if let Error::Common(CommonErrCode::ConnPool, message) = self.unshared() {
             // conn_pool will produced this error if all connection to a server is shutdown
             // which means we need refresh
             return message.ends_with("are all removed");
@@ -186,7 +269,7 @@ impl Error {
     }
 
     pub fn need_invalidate_table(&self) -> bool {
-        if let Error::Common(CommonErrCode::PartitionError, message) = self {
+        if let Error::Common(CommonErrCode::PartitionError, message) = self.unshared() {
             // Location::get_table_location_from_remote will produce this error if the table
             // is dropped
             message.starts_with("Location::get_table_location_from_remote: Table maybe dropped.")
@@ -48,6 +48,8 @@ pub mod client;
 mod constant;
 pub mod error;
 mod location;
+#[cfg(any(test, feature = "mock"))]
+pub mod mock;
 pub mod monitors;
 mod rpc;
 pub mod runtime;
@@ -55,15 +57,27 @@ pub mod serde_obkv;
 mod util;
 pub use self::{
     client::{
+        blocking,
+        breaker::{CircuitBreakerConfig, CircuitState},
+        hedge::HedgeConfig,
         query::QueryResultSet,
+        retry::{ExponentialBackoff, RetryContext, RetryDecision, RetryOp, RetryPolicy},
         table::ObTable,
-        table_client::{Builder, ObTableClient, RunningMode},
+        table_client::{
+            Builder, ObClientRuntimes, ObTableClient, PartitionId, PartitionInfo, RunningMode,
+            ScanSplit,
+        },
         ClientConfig, TableOpResult,
     },
+    location::{
+        provider::{LocationProvider, StaticLocationProvider, StaticTable},
+        ObServerAddr, PartitionScheme, TableEntry, TableEntryKey,
+    },
     monitors::prometheus::dump_metrics,
     rpc::{
-        protocol::{codes::ResultCodes, payloads, query},
+        protocol::{codes::ResultCodes, payloads, query, ObCompressType},
         proxy,
+        tls::TlsConfig,
     },
     serde_obkv::value::{ObjType, Value},
 };
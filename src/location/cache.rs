@@ -0,0 +1,868 @@
+/*-
+ * #%L
+ * OBKV Table Client Framework
+ * %%
+ * Copyright (C) 2021 OceanBase
+ * %%
+ * OBKV Table Client Framework is licensed under Mulan PSL v2.
+ * You can use this software according to the terms and conditions of the
+ * Mulan PSL v2. You may obtain a copy of Mulan PSL v2 at:
+ *          http://license.coscl.org.cn/MulanPSL2
+ * THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ * KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ * NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ * See the Mulan PSL v2 for more details.
+ * #L%
+ */
+
+//! Routing persisted across restarts, see
+//! [`ClientConfig::table_entry_cache_file`](crate::client::ClientConfig).
+//!
+//! The routing holds trait objects and is persisted as plain records instead,
+//! rebuilt into [`TableEntry`] when loaded.
+
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use bytes::BytesMut;
+
+use super::{
+    ob_part_desc::ObPartDesc, util::LocationUtil, ObPartitionEntry, ObPartitionInfo,
+    ObPartitionLevel, ObPartitionLocation, ObReplicaType, ObServerAddr, ObServerInfo, ObServerRole,
+    ObServerStatus, ReplicaLocation, TableEntry, TableEntryKey, TableLocation,
+};
+use crate::{
+    error::{CommonErrCode, Error::Common as CommonErr, Result},
+    rpc::protocol::partition::ob_column::{ObColumn, ObSimpleColumn},
+    serde_obkv::value::{CollationType, ObjType, Value},
+    util::{current_time_millis, duration_to_millis, obversion::ob_vsn_major},
+};
+
+/// Bumped whenever the records change incompatibly, files of other versions
+/// are ignored.
+const CACHE_FILE_VERSION: u32 = 3;
+
+const SAVE_SLOW_THRESHOLD_MS: i64 = 100;
+
+/// The saves within this interval are written to the file at once.
+const WRITE_INTERVAL: Duration = Duration::from_secs(1);
+
+// distinguishes the temporary files of the cache files of a process
+static NEXT_CACHE_FILE_ID: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheFileRecord {
+    version: u32,
+    // "{cluster}:{tenant}" -> servers
+    servers: HashMap<String, ServersRecord>,
+    // "{cluster}:{tenant}:{database}" -> table name -> table
+    tables: HashMap<String, HashMap<String, TableRecord>>,
+}
+
+impl CacheFileRecord {
+    // keeps the most recently refreshed record of each key
+    fn merge(&mut self, other: CacheFileRecord) {
+        for (key, record) in other.servers {
+            let newer = self.servers.get(&key).map_or(true, |old| {
+                old.refresh_time_mills < record.refresh_time_mills
+            });
+            if newer {
+                self.servers.insert(key, record);
+            }
+        }
+        for (key, tables) in other.tables {
+            let ours = self.tables.entry(key).or_default();
+            for (table_name, record) in tables {
+                let newer = ours.get(&table_name).map_or(true, |old| {
+                    old.refresh_time_mills < record.refresh_time_mills
+                });
+                if newer {
+                    ours.insert(table_name, record);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct ServersRecord {
+    refresh_time_mills: i64,
+    servers: Vec<ObServerAddr>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct TableRecord {
+    ob_version: i32,
+    refresh_time_mills: i64,
+    table_name: String,
+    table_id: i64,
+    partition_num: i64,
+    replica_num: i64,
+    replicas: Vec<ReplicaRecord>,
+    partition_info: Option<PartitionInfoRecord>,
+    partitions: Option<HashMap<i64, PartitionLocationRecord>>,
+    row_key_element: HashMap<String, i32>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct ReplicaRecord {
+    addr: ObServerAddr,
+    role: i32,
+    replica_type: i32,
+    status: String,
+    stop_time: i64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct PartitionLocationRecord {
+    leader: Option<ReplicaRecord>,
+    followers: Vec<ReplicaRecord>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct PartitionInfoRecord {
+    level: i32,
+    first_part_desc: Option<PartDescRecord>,
+    sub_part_desc: Option<PartDescRecord>,
+    part_columns: Vec<ColumnRecord>,
+    part_name_id_map: HashMap<String, i64>,
+    part_tablet_id_map: HashMap<i64, i64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct ColumnRecord {
+    name: String,
+    index: i32,
+    obj_type: u8,
+    collation_type: u8,
+}
+
+/// Persisted [`ObPartDesc`], fields not used by a kind of partitioning are
+/// left empty.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct PartDescRecord {
+    pub part_func_type: i32,
+    pub part_expr: String,
+    pub ordered_part_column_names: Vec<String>,
+    pub part_name_id_map: HashMap<String, i64>,
+    pub part_num: i32,
+    pub part_space: i32,
+    pub complete_works: Vec<i64>,
+    pub ordered_compare_column_types: Vec<u8>,
+    pub bounds: Vec<(Vec<ComparableRecord>, i64)>,
+    pub sets: Vec<(Vec<Vec<u8>>, i64)>,
+    pub default_part_id: Option<i64>,
+    // seconds east of UTC
+    pub time_zone: i32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) enum ComparableRecord {
+    MaxValue,
+    MinValue,
+    Value(Vec<u8>),
+}
+
+/// Encodes a partition value the way it is sent to the observers, values
+/// which can't be decoded back as themselves are refused.
+pub(crate) fn encode_value(value: &Value) -> Result<Vec<u8>> {
+    let mut buf = BytesMut::new();
+    value.encode(&mut buf)?;
+    let bytes = buf.to_vec();
+    if decode_value(&bytes).ok().as_ref() != Some(value) {
+        return Err(CommonErr(
+            CommonErrCode::ConvertFailed,
+            format!("encode_value value can't be persisted: {value:?}"),
+        ));
+    }
+    Ok(bytes)
+}
+
+pub(crate) fn decode_value(bytes: &[u8]) -> Result<Value> {
+    let obj_type = match bytes.first() {
+        Some(v) => ObjType::from_u8(*v)?,
+        None => {
+            return Err(CommonErr(
+                CommonErrCode::ConvertFailed,
+                "decode_value empty value".to_owned(),
+            ));
+        }
+    };
+    Ok(Value::decode(&mut BytesMut::from(bytes), obj_type)?)
+}
+
+fn servers_key(cluster_name: &str, tenant_name: &str) -> String {
+    format!("{cluster_name}:{tenant_name}")
+}
+
+fn database_key(cluster_name: &str, tenant_name: &str, database_name: &str) -> String {
+    format!("{cluster_name}:{tenant_name}:{database_name}")
+}
+
+impl ReplicaRecord {
+    fn new(replica: &ReplicaLocation) -> ReplicaRecord {
+        ReplicaRecord {
+            addr: replica.addr.clone(),
+            role: replica.role.clone() as i32,
+            replica_type: replica.replica_type.clone() as i32,
+            status: format!("{:?}", replica.info.status),
+            stop_time: replica.info.stop_time,
+        }
+    }
+
+    fn to_replica(&self) -> Result<ReplicaLocation> {
+        let status = match self.status.to_lowercase().as_str() {
+            "active" | "inactive" | "deleting" => ObServerStatus::from_string(self.status.clone()),
+            _ => {
+                return Err(CommonErr(
+                    CommonErrCode::ConvertFailed,
+                    format!("ReplicaRecord::to_replica invalid status: {}", self.status),
+                ));
+            }
+        };
+        if !(0..=2).contains(&self.role) {
+            return Err(CommonErr(
+                CommonErrCode::ConvertFailed,
+                format!("ReplicaRecord::to_replica invalid role: {}", self.role),
+            ));
+        }
+        Ok(ReplicaLocation {
+            addr: self.addr.clone(),
+            info: ObServerInfo {
+                stop_time: self.stop_time,
+                status,
+            },
+            role: ObServerRole::from_int(self.role),
+            replica_type: ObReplicaType::from_int(self.replica_type),
+        })
+    }
+}
+
+impl PartitionInfoRecord {
+    fn new(info: &ObPartitionInfo) -> Result<PartitionInfoRecord> {
+        let mut part_columns = Vec::with_capacity(info.part_columns.len());
+        for column in &info.part_columns {
+            // generated columns are evaluated from expressions which aren't kept
+            if column.get_ref_column_names() != [column.get_column_name()] {
+                return Err(CommonErr(
+                    CommonErrCode::ConvertFailed,
+                    format!(
+                        "PartitionInfoRecord::new generated column can't be persisted: {}",
+                        column.get_column_name()
+                    ),
+                ));
+            }
+            part_columns.push(ColumnRecord {
+                name: column.get_column_name(),
+                index: column.get_index(),
+                obj_type: column.get_ob_obj_type() as u8,
+                collation_type: column.get_ob_collation_type().clone() as u8,
+            });
+        }
+        Ok(PartitionInfoRecord {
+            level: info.level.get_index(),
+            first_part_desc: info
+                .first_part_desc
+                .as_ref()
+                .map(|v| v.to_record())
+                .transpose()?,
+            sub_part_desc: info
+                .sub_part_desc
+                .as_ref()
+                .map(|v| v.to_record())
+                .transpose()?,
+            part_columns,
+            part_name_id_map: info.part_name_id_map.clone(),
+            part_tablet_id_map: info.part_tablet_id_map.clone(),
+        })
+    }
+
+    fn to_partition_info(&self) -> Result<ObPartitionInfo> {
+        let mut part_columns: Vec<Box<dyn ObColumn>> = Vec::with_capacity(self.part_columns.len());
+        for column in &self.part_columns {
+            part_columns.push(Box::new(ObSimpleColumn::new(
+                column.name.clone(),
+                column.index,
+                ObjType::from_u8(column.obj_type)?,
+                CollationType::from_u8(column.collation_type)?,
+            )));
+        }
+
+        let mut info = ObPartitionInfo::new();
+        info.level = ObPartitionLevel::from_int(self.level);
+        info.first_part_desc = self
+            .first_part_desc
+            .as_ref()
+            .map(ObPartDesc::from_record)
+            .transpose()?;
+        info.sub_part_desc = self
+            .sub_part_desc
+            .as_ref()
+            .map(ObPartDesc::from_record)
+            .transpose()?;
+        for part_desc in [&mut info.first_part_desc, &mut info.sub_part_desc] {
+            let ordered_part_columns = match part_desc {
+                Some(v) if v.is_list_part() || v.is_range_part() => {
+                    LocationUtil::get_ordered_part_columns(&part_columns, v)
+                }
+                _ => Vec::new(),
+            };
+            LocationUtil::set_part_desc_property(part_desc, &part_columns, &ordered_part_columns)?;
+        }
+        info.part_columns = part_columns;
+        info.part_name_id_map = self.part_name_id_map.clone();
+        info.part_tablet_id_map = self.part_tablet_id_map.clone();
+        Ok(info)
+    }
+}
+
+impl TableRecord {
+    fn new(entry: &TableEntry) -> Result<TableRecord> {
+        let partitions = entry.partition_entry.as_ref().map(|v| {
+            v.parititon_location
+                .iter()
+                .map(|(part_id, location)| {
+                    let record = PartitionLocationRecord {
+                        leader: location.leader.as_ref().map(ReplicaRecord::new),
+                        followers: location.followers.iter().map(ReplicaRecord::new).collect(),
+                    };
+                    (*part_id, record)
+                })
+                .collect()
+        });
+        Ok(TableRecord {
+            ob_version: ob_vsn_major(),
+            refresh_time_mills: entry.refresh_time_mills(),
+            table_name: entry.table_entry_key.table_name.clone(),
+            table_id: entry.table_id,
+            partition_num: entry.partition_num,
+            replica_num: entry.replica_num,
+            replicas: entry
+                .table_location
+                .replica_locations
+                .iter()
+                .map(ReplicaRecord::new)
+                .collect(),
+            partition_info: entry
+                .partition_info
+                .as_ref()
+                .map(PartitionInfoRecord::new)
+                .transpose()?,
+            partitions,
+            row_key_element: entry.row_key_element.clone(),
+        })
+    }
+
+    fn to_table_entry(&self, key: TableEntryKey) -> Result<TableEntry> {
+        let partition_entry = match &self.partitions {
+            Some(partitions) => {
+                let mut parititon_location = HashMap::with_capacity(partitions.len());
+                for (part_id, location) in partitions {
+                    let location = ObPartitionLocation {
+                        leader: location
+                            .leader
+                            .as_ref()
+                            .map(ReplicaRecord::to_replica)
+                            .transpose()?,
+                        followers: location
+                            .followers
+                            .iter()
+                            .map(ReplicaRecord::to_replica)
+                            .collect::<Result<_>>()?,
+                    };
+                    parititon_location.insert(*part_id, location);
+                }
+                Some(ObPartitionEntry { parititon_location })
+            }
+            None => None,
+        };
+        let mut entry = TableEntry {
+            table_id: self.table_id,
+            partition_num: self.partition_num,
+            replica_num: self.replica_num,
+            table_entry_key: key,
+            refresh_time_mills: Arc::new(AtomicUsize::new(self.refresh_time_mills as usize)),
+            partition_info: self
+                .partition_info
+                .as_ref()
+                .map(PartitionInfoRecord::to_partition_info)
+                .transpose()?,
+            table_location: TableLocation {
+                replica_locations: self
+                    .replicas
+                    .iter()
+                    .map(ReplicaRecord::to_replica)
+                    .collect::<Result<_>>()?,
+            },
+            partition_entry,
+            row_key_element: HashMap::new(),
+        };
+        entry.set_row_key_element(self.row_key_element.clone());
+        if entry.is_partition_table() {
+            entry.prepare()?;
+        }
+        Ok(entry)
+    }
+}
+
+/// File persisting the servers and the table entries of a client. The saves
+/// only update the cache in memory, a background thread rewrites the whole
+/// file through a temporary file at most once per [`WRITE_INTERVAL`].
+///
+/// Clients may share the file, each write first merges the routing found in
+/// the file and keeps the most recently refreshed record of every tenant and
+/// table. Clients writing at the same time may still lose the records the
+/// other one has just added, which are written again by its next write.
+pub(crate) struct TableEntryCacheFile {
+    max_age: Duration,
+    state: Arc<CacheState>,
+    writer: Mutex<Option<thread::JoinHandle<()>>>,
+}
+
+struct CacheState {
+    path: String,
+    tmp_path: String,
+    cache: Mutex<CacheFileRecord>,
+    write_state: Mutex<WriteState>,
+    write_cond: Condvar,
+}
+
+#[derive(Default)]
+struct WriteState {
+    // the cache has changed since it was last written
+    dirty: bool,
+    closed: bool,
+}
+
+impl TableEntryCacheFile {
+    pub fn new(path: &str, max_age: Duration) -> TableEntryCacheFile {
+        let cache = match Self::load(path) {
+            Ok(cache) => cache,
+            Err(e) => {
+                debug!(
+                    "TableEntryCacheFile::new fail to load cached table entries, path: {}, err: {}",
+                    path, e
+                );
+                CacheFileRecord::default()
+            }
+        };
+        let tmp_path = format!(
+            "{}.tmp.{}.{}",
+            path,
+            std::process::id(),
+            NEXT_CACHE_FILE_ID.fetch_add(1, Ordering::Relaxed)
+        );
+        let state = Arc::new(CacheState {
+            path: path.to_owned(),
+            tmp_path,
+            cache: Mutex::new(cache),
+            write_state: Mutex::new(WriteState::default()),
+            write_cond: Condvar::new(),
+        });
+        let writer_state = state.clone();
+        let writer = thread::Builder::new()
+            .name("TableEntryCacheWriteThread".to_string())
+            .spawn(move || writer_state.run_writer());
+        let writer = match writer {
+            Ok(writer) => Some(writer),
+            Err(e) => {
+                warn!(
+                    "TableEntryCacheFile::new fail to start writer, path: {}, err: {}",
+                    path, e
+                );
+                None
+            }
+        };
+        TableEntryCacheFile {
+            max_age,
+            state,
+            writer: Mutex::new(writer),
+        }
+    }
+
+    fn load(path: &str) -> Result<CacheFileRecord> {
+        let file = File::open(path)?;
+        let reader = io::BufReader::new(file);
+        let cache: CacheFileRecord = serde_json::from_reader(reader)?;
+        if cache.version != CACHE_FILE_VERSION {
+            return Err(CommonErr(
+                CommonErrCode::InvalidParam,
+                format!(
+                    "TableEntryCacheFile::load unsupported version: {}, expected: {}",
+                    cache.version, CACHE_FILE_VERSION
+                ),
+            ));
+        }
+        Ok(cache)
+    }
+
+    fn is_fresh(&self, refresh_time_mills: i64) -> bool {
+        current_time_millis() - refresh_time_mills <= duration_to_millis(&self.max_age)
+    }
+
+    // unchanged routing is only rewritten to keep it fresh
+    fn need_rewrite(&self, persisted_time_mills: i64, refresh_time_mills: i64) -> bool {
+        refresh_time_mills - persisted_time_mills > duration_to_millis(&self.max_age) / 2
+    }
+
+    /// Returns the cached servers of the tenant if they are fresh enough.
+    pub fn load_servers(&self, cluster_name: &str, tenant_name: &str) -> Option<Vec<ObServerAddr>> {
+        let cache = self.state.cache.lock().unwrap();
+        cache
+            .servers
+            .get(&servers_key(cluster_name, tenant_name))
+            .filter(|v| self.is_fresh(v.refresh_time_mills) && !v.servers.is_empty())
+            .map(|v| v.servers.clone())
+    }
+
+    pub fn save_servers(&self, cluster_name: &str, tenant_name: &str, servers: &[ObServerAddr]) {
+        let key = servers_key(cluster_name, tenant_name);
+        let mut cache = self.state.cache.lock().unwrap();
+        let now = current_time_millis();
+        if let Some(old) = cache.servers.get(&key) {
+            if old.servers == servers && !self.need_rewrite(old.refresh_time_mills, now) {
+                return;
+            }
+        }
+        let record = ServersRecord {
+            refresh_time_mills: now,
+            servers: servers.to_vec(),
+        };
+        cache.servers.insert(key, record);
+        drop(cache);
+        self.state.mark_dirty();
+    }
+
+    /// Returns the cached table entries of the database which are fresh
+    /// enough and were loaded from the same major version of observers.
+    pub fn load_table_entries(
+        &self,
+        cluster_name: &str,
+        tenant_name: &str,
+        database_name: &str,
+    ) -> Vec<TableEntry> {
+        let cache = self.state.cache.lock().unwrap();
+        let tables = match cache
+            .tables
+            .get(&database_key(cluster_name, tenant_name, database_name))
+        {
+            Some(tables) => tables,
+            None => return Vec::new(),
+        };
+        let mut entries = Vec::new();
+        for record in tables.values() {
+            if !self.is_fresh(record.refresh_time_mills)
+                || (ob_vsn_major() != 0 && record.ob_version != ob_vsn_major())
+            {
+                continue;
+            }
+            let key =
+                TableEntryKey::new(cluster_name, tenant_name, database_name, &record.table_name);
+            match record.to_table_entry(key) {
+                Ok(entry) => entries.push(entry),
+                Err(e) => warn!(
+                    "TableEntryCacheFile::load_table_entries fail to rebuild table entry, table: {}, err: {}",
+                    record.table_name, e
+                ),
+            }
+        }
+        entries
+    }
+
+    pub fn save_table_entry(&self, entry: &TableEntry) {
+        let record = match TableRecord::new(entry) {
+            Ok(record) => record,
+            Err(e) => {
+                debug!(
+                    "TableEntryCacheFile::save_table_entry table entry can't be persisted, key: {:?}, err: {}",
+                    entry.table_entry_key, e
+                );
+                return;
+            }
+        };
+        let key = &entry.table_entry_key;
+        let mut cache = self.state.cache.lock().unwrap();
+        let tables = cache
+            .tables
+            .entry(database_key(
+                &key.cluster_name,
+                &key.tenant_name,
+                &key.database_name,
+            ))
+            .or_default();
+        if let Some(old) = tables.get(&key.table_name) {
+            let unchanged = TableRecord {
+                refresh_time_mills: old.refresh_time_mills,
+                ..record.clone()
+            };
+            if &unchanged == old
+                && !self.need_rewrite(old.refresh_time_mills, record.refresh_time_mills)
+            {
+                return;
+            }
+        }
+        tables.insert(key.table_name.clone(), record);
+        drop(cache);
+        self.state.mark_dirty();
+    }
+
+    /// Writes the changes not written yet and stops the background writer,
+    /// the later saves are not written anymore.
+    pub fn close(&self) {
+        let writer = self.writer.lock().unwrap().take();
+        if let Some(writer) = writer {
+            self.state.write_state.lock().unwrap().closed = true;
+            self.state.write_cond.notify_all();
+            if writer.join().is_err() {
+                warn!(
+                    "TableEntryCacheFile::close writer panicked, path: {}",
+                    self.state.path
+                );
+            }
+        }
+    }
+}
+
+impl Drop for TableEntryCacheFile {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+impl CacheState {
+    fn mark_dirty(&self) {
+        self.write_state.lock().unwrap().dirty = true;
+        self.write_cond.notify_all();
+    }
+
+    // writes the changes once per interval until closed
+    fn run_writer(&self) {
+        loop {
+            {
+                let mut write_state = self.write_state.lock().unwrap();
+                while !write_state.dirty && !write_state.closed {
+                    write_state = self.write_cond.wait(write_state).unwrap();
+                }
+                if !write_state.dirty {
+                    return;
+                }
+                // gather the saves of the interval, unless closed meanwhile
+                write_state = self
+                    .write_cond
+                    .wait_timeout_while(write_state, WRITE_INTERVAL, |s| !s.closed)
+                    .unwrap()
+                    .0;
+                write_state.dirty = false;
+            }
+            self.persist();
+        }
+    }
+
+    fn persist(&self) {
+        let start = current_time_millis();
+        // keep the routing other clients have written meanwhile
+        let on_disk = TableEntryCacheFile::load(&self.path).ok();
+        let data = {
+            let mut cache = self.cache.lock().unwrap();
+            if let Some(on_disk) = on_disk {
+                cache.merge(on_disk);
+            }
+            cache.version = CACHE_FILE_VERSION;
+            serde_json::to_vec(&*cache)
+        };
+        if let Err(e) = data.map_err(Into::into).and_then(|data| self.write(&data)) {
+            warn!(
+                "TableEntryCacheFile::persist fail to write cache file, path: {}, err: {}",
+                self.path, e
+            );
+            return;
+        }
+
+        let cost = current_time_millis() - start;
+        if cost > SAVE_SLOW_THRESHOLD_MS {
+            warn!("TableEntryCacheFile::persist cost {} ms.", cost);
+        }
+    }
+
+    fn write(&self, data: &[u8]) -> Result<()> {
+        // readers never see a partially written file, even after a crash
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&self.tmp_path)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+        fs::rename(&self.tmp_path, &self.path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::location::PartitionScheme;
+
+    fn addr(port: i32) -> ObServerAddr {
+        let mut addr = ObServerAddr::new();
+        addr.address(format!("127.0.0.1:{port}"));
+        addr.set_sql_port(port + 1);
+        addr
+    }
+
+    #[test]
+    fn test_persist_table_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("table_entry_cache.json");
+        let path = path.to_str().unwrap();
+
+        let row_key = vec![
+            ("c1".to_owned(), ObjType::Varchar),
+            ("c2".to_owned(), ObjType::Int64),
+        ];
+        let partitions: Vec<_> = (0..3).map(|i| (i + 200, vec![addr(i as i32)])).collect();
+        let range = PartitionScheme::Range {
+            column: "c2".to_owned(),
+            bounds: vec![Value::from(10i64), Value::from(20i64), Value::get_max()],
+        };
+        let key = PartitionScheme::Key {
+            columns: vec!["c1".to_owned()],
+            part_num: 3,
+        };
+        let mut expected = Vec::new();
+        for (name, scheme) in [
+            ("t_range", Some(&range)),
+            ("t_key", Some(&key)),
+            ("t", None),
+        ] {
+            let key = TableEntryKey::new("c", "t", "db", name);
+            let parts = if scheme.is_some() {
+                &partitions[..]
+            } else {
+                &partitions[..1]
+            };
+            let entry = TableEntry::build(&key, 100, &row_key, scheme, parts).unwrap();
+            expected.push(entry);
+        }
+
+        let file = TableEntryCacheFile::new(path, Duration::from_secs(60));
+        assert!(file.load_servers("c", "t").is_none());
+        file.save_servers("c", "t", &[addr(1), addr(2)]);
+        for entry in &expected {
+            file.save_table_entry(entry);
+        }
+        file.close();
+
+        let file = TableEntryCacheFile::new(path, Duration::from_secs(60));
+        assert_eq!(Some(vec![addr(1), addr(2)]), file.load_servers("c", "t"));
+        assert!(file.load_servers("c", "other").is_none());
+        assert!(file.load_table_entries("c", "t", "other").is_empty());
+
+        let entries = file.load_table_entries("c", "t", "db");
+        assert_eq!(expected.len(), entries.len());
+        for expected in &expected {
+            let entry = entries
+                .iter()
+                .find(|e| e.table_entry_key == expected.table_entry_key)
+                .unwrap();
+            assert_eq!(expected.table_id(), entry.table_id());
+            assert_eq!(expected.refresh_time_mills(), entry.refresh_time_mills());
+            assert_eq!(expected.partition_entry(), entry.partition_entry());
+            assert_eq!(expected.table_location(), entry.table_location());
+            assert_eq!(expected.row_key_element(), entry.row_key_element());
+            if let Some(info) = expected.partition_info() {
+                let part_desc = info.first_part_desc().as_ref().unwrap();
+                let loaded = entry.partition_info().as_ref().unwrap();
+                let loaded = loaded.first_part_desc().as_ref().unwrap();
+                for c2 in [0i64, 10, 15, 25, 100] {
+                    let row = vec![Value::from(format!("k{c2}")), Value::from(c2)];
+                    assert_eq!(
+                        part_desc.get_part_id(&row).unwrap(),
+                        loaded.get_part_id(&row).unwrap()
+                    );
+                }
+            }
+        }
+
+        // stale routing is ignored
+        let file = TableEntryCacheFile::new(path, Duration::ZERO);
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(file.load_servers("c", "t").is_none());
+        assert!(file.load_table_entries("c", "t", "db").is_empty());
+    }
+
+    #[test]
+    fn test_write_in_background() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("table_entry_cache.json");
+        let path = path.to_str().unwrap();
+
+        let file = TableEntryCacheFile::new(path, Duration::from_secs(60));
+        for port in 0..100 {
+            file.save_servers("c", "t", &[addr(port)]);
+        }
+        // not written by the saves themselves
+        assert!(!std::path::Path::new(path).exists());
+        let start = std::time::Instant::now();
+        while !std::path::Path::new(path).exists() && start.elapsed() < 5 * WRITE_INTERVAL {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        // the saves of the interval are written at once
+        let loaded = TableEntryCacheFile::new(path, Duration::from_secs(60));
+        assert_eq!(Some(vec![addr(99)]), loaded.load_servers("c", "t"));
+
+        // the pending saves are written when closed
+        file.save_servers("c", "t", &[addr(100)]);
+        drop(file);
+        let loaded = TableEntryCacheFile::new(path, Duration::from_secs(60));
+        assert_eq!(Some(vec![addr(100)]), loaded.load_servers("c", "t"));
+    }
+
+    #[test]
+    fn test_shared_by_clients() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("table_entry_cache.json");
+        let path = path.to_str().unwrap();
+
+        let file_1 = TableEntryCacheFile::new(path, Duration::from_secs(60));
+        let file_2 = TableEntryCacheFile::new(path, Duration::from_secs(60));
+        assert_ne!(file_1.state.tmp_path, file_2.state.tmp_path);
+        file_1.save_servers("c", "t1", &[addr(1)]);
+        file_1.save_servers("c", "t", &[addr(1)]);
+        file_1.close();
+        std::thread::sleep(Duration::from_millis(5));
+        file_2.save_servers("c", "t2", &[addr(2)]);
+        file_2.save_servers("c", "t", &[addr(2)]);
+        file_2.close();
+
+        // the routing of both is kept, the latest refreshed wins
+        let loaded = TableEntryCacheFile::new(path, Duration::from_secs(60));
+        assert_eq!(Some(vec![addr(1)]), loaded.load_servers("c", "t1"));
+        assert_eq!(Some(vec![addr(2)]), loaded.load_servers("c", "t2"));
+        assert_eq!(Some(vec![addr(2)]), loaded.load_servers("c", "t"));
+        assert_eq!(1, fs::read_dir(dir.path()).unwrap().count());
+    }
+
+    #[test]
+    fn test_ignore_other_versions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("table_entry_cache.json");
+        fs::write(
+            &path,
+            r#"{"version":0,"servers":{"c:t":{"refresh_time_mills":0,"servers":[]}},"tables":{}}"#,
+        )
+        .unwrap();
+        let file = TableEntryCacheFile::new(path.to_str().unwrap(), Duration::from_secs(60));
+        assert!(file.load_servers("c", "t").is_none());
+        assert!(file.state.cache.lock().unwrap().servers.is_empty());
+    }
+}
@@ -21,16 +21,19 @@ use std::{
     str::FromStr,
     sync::{
         atomic::{AtomicIsize, AtomicUsize, Ordering},
-        Arc, RwLock,
+        Arc, Mutex, RwLock,
     },
-    time::Duration,
+    time::{Duration, Instant},
 };
 
+use chrono::FixedOffset;
 use mysql as my;
 use mysql::{prelude::Queryable, PoolConstraints, PoolOpts, Row};
 use rand::{seq::SliceRandom, thread_rng};
 
-use self::ob_part_desc::{ObHashPartDesc, ObKeyPartDesc, ObPartDesc, ObRangePartDesc};
+use self::ob_part_desc::{
+    ObHashPartDesc, ObKeyPartDesc, ObListPartDesc, ObPartDesc, ObRangePartDesc,
+};
 use crate::{
     client::{table_client::ServerRoster, ClientConfig},
     constant::*,
@@ -41,13 +44,16 @@ use crate::{
         ObServerRole::InvalidRole,
     },
     rpc::protocol::partition::ob_column::ObColumn,
+    serde_obkv::value::{ObjType, Value},
     util as u,
     util::{obversion::ob_vsn_major, HandyRwLock},
 };
 
+pub(crate) mod cache;
 pub mod ob_part_constants;
 mod ob_part_desc;
 mod part_func_type;
+pub mod provider;
 mod util;
 
 pub const OB_INVALID_ID: i64 = -1;
@@ -200,6 +206,30 @@ pub enum ObReplicaType {
     Invalid = i32::MAX as isize,
 }
 
+/// Partitioning of a table declared by hand instead of being read from the
+/// system tables, see [`TableEntry::build`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum PartitionScheme {
+    /// `PARTITION BY HASH(column) PARTITIONS part_num`
+    Hash { column: String, part_num: i32 },
+    /// `PARTITION BY KEY(columns) PARTITIONS part_num`
+    Key { columns: Vec<String>, part_num: i32 },
+    /// `PARTITION BY RANGE COLUMNS(column)`, one partition per exclusive upper
+    /// bound in ascending order, [`Value::get_max`] stands for `MAXVALUE`.
+    Range { column: String, bounds: Vec<Value> },
+}
+
+impl PartitionScheme {
+    pub fn part_num(&self) -> i32 {
+        match self {
+            PartitionScheme::Hash { part_num, .. } | PartitionScheme::Key { part_num, .. } => {
+                *part_num
+            }
+            PartitionScheme::Range { bounds, .. } => bounds.len() as i32,
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct ObServerInfo {
     stop_time: i64,
@@ -266,6 +296,10 @@ impl TableEntryKey {
             ALL_DUMMY_TABLE,
         )
     }
+
+    pub fn table_name(&self) -> &str {
+        &self.table_name
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -427,6 +461,10 @@ impl ObPartitionLocation {
     pub fn leader(&self) -> &Option<ReplicaLocation> {
         &self.leader
     }
+
+    pub fn followers(&self) -> &[ReplicaLocation] {
+        &self.followers
+    }
 }
 
 impl ObPartitionEntry {
@@ -483,6 +521,19 @@ pub struct ReplicaLocation {
 }
 
 impl ReplicaLocation {
+    /// An active full replica on `addr`.
+    pub fn new(addr: ObServerAddr, role: ObServerRole) -> Self {
+        ReplicaLocation {
+            addr,
+            info: ObServerInfo {
+                stop_time: 0,
+                status: ObServerStatus::Active,
+            },
+            role,
+            replica_type: ObReplicaType::Full,
+        }
+    }
+
     pub fn addr(&self) -> &ObServerAddr {
         &self.addr
     }
@@ -532,6 +583,19 @@ impl ObServerStatus {
 }
 
 impl TableEntry {
+    /// Build a table entry from a declared partitioning instead of the system
+    /// tables, see [`PartitionScheme`]. `partitions` lists the tablet id and
+    /// the servers of every partition, the first server being the leader.
+    pub fn build(
+        key: &TableEntryKey,
+        table_id: i64,
+        row_key: &[(String, ObjType)],
+        scheme: Option<&PartitionScheme>,
+        partitions: &[(i64, Vec<ObServerAddr>)],
+    ) -> Result<TableEntry> {
+        LocationUtil::build_table_entry(key, table_id, row_key, scheme, partitions)
+    }
+
     pub fn set_row_key_element(&mut self, row_key_element: HashMap<String, i32>) {
         self.row_key_element = row_key_element.clone();
         if let Some(ref mut partition_info) = self.partition_info {
@@ -547,6 +611,10 @@ impl TableEntry {
         self.table_id
     }
 
+    pub fn table_entry_key(&self) -> &TableEntryKey {
+        &self.table_entry_key
+    }
+
     pub fn partition_entry(&self) -> &Option<ObPartitionEntry> {
         &self.partition_entry
     }
@@ -555,6 +623,10 @@ impl TableEntry {
         &self.partition_info
     }
 
+    pub fn row_key_element(&self) -> &HashMap<String, i32> {
+        &self.row_key_element
+    }
+
     pub fn table_location(&self) -> &TableLocation {
         &self.table_location
     }
@@ -616,6 +688,8 @@ pub struct ObTableLocation {
     config: ClientConfig,
     /// {ServerAddr} -> {User/DB} -> {Pool}
     mysql_pools: RwLock<HashMap<ObServerAddr, HashMap<String, Arc<my::Pool>>>>,
+    /// Time zone of the servers, configured or loaded once from them.
+    time_zone: Mutex<Option<FixedOffset>>,
 }
 
 impl Default for ObTableLocation {
@@ -627,6 +701,7 @@ impl Default for ObTableLocation {
 impl ObTableLocation {
     pub fn new(config: ClientConfig) -> ObTableLocation {
         ObTableLocation {
+            time_zone: Mutex::new(config.server_time_zone),
             config,
             mysql_pools: RwLock::new(HashMap::new()),
         }
@@ -675,28 +750,14 @@ impl ObTableLocation {
             if let Some(pool) = self.get_pool_from_cache(&pools, server_addr, &cache_key) {
                 return Ok(pool);
             }
-            let mut builder = my::OptsBuilder::new();
-            builder = builder
-                .ip_or_hostname(Some(server_addr.ip.to_owned()))
-                .tcp_port(server_addr.sql_port as u16)
-                .user(Some(username))
-                .pass(Some(password))
-                .db_name(Some(db_name))
-                .tcp_connect_timeout(connect_timeout)
-                .read_timeout(sock_timeout);
-            let constraints = PoolConstraints::new(
-                self.config.metadata_mysql_conn_pool_min_size,
-                self.config.metadata_mysql_conn_pool_max_size,
-            );
-            builder = builder.pool_opts(constraints.map(|v| PoolOpts::new().with_constraints(v)));
-
-            info!(
-                "ObTableLocation::get_or_create_mysql_pool create mysql pool \
-                 server {} port {} db_name {}.",
-                server_addr.ip, server_addr.sql_port, db_name
-            );
-
-            let pool = Arc::new(my::Pool::new(builder)?);
+            let pool = Arc::new(self.new_mysql_pool(
+                username,
+                password,
+                db_name,
+                server_addr,
+                connect_timeout,
+                sock_timeout,
+            )?);
 
             match pools.get_mut(server_addr) {
                 Some(map) => {
@@ -713,6 +774,39 @@ impl ObTableLocation {
         }
     }
 
+    fn new_mysql_pool(
+        &self,
+        username: &str,
+        password: &str,
+        db_name: &str,
+        server_addr: &ObServerAddr,
+        connect_timeout: Option<Duration>,
+        sock_timeout: Option<Duration>,
+    ) -> Result<my::Pool> {
+        let mut builder = my::OptsBuilder::new();
+        builder = builder
+            .ip_or_hostname(Some(server_addr.ip.to_owned()))
+            .tcp_port(server_addr.sql_port as u16)
+            .user(Some(username))
+            .pass(Some(password))
+            .db_name(Some(db_name))
+            .tcp_connect_timeout(connect_timeout)
+            .read_timeout(sock_timeout);
+        let constraints = PoolConstraints::new(
+            self.config.metadata_mysql_conn_pool_min_size,
+            self.config.metadata_mysql_conn_pool_max_size,
+        );
+        builder = builder.pool_opts(constraints.map(|v| PoolOpts::new().with_constraints(v)));
+
+        info!(
+            "ObTableLocation::new_mysql_pool create mysql pool \
+             server {} port {} db_name {}.",
+            server_addr.ip, server_addr.sql_port, db_name
+        );
+
+        Ok(my::Pool::new(builder)?)
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn execute_sql(
         &self,
@@ -777,6 +871,7 @@ impl ObTableLocation {
         key: &TableEntryKey,
         connect_timeout: Duration,
         sock_timeout: Duration,
+        deadline: Option<Instant>,
     ) -> Result<TableEntry> {
         if rs_list.is_empty() {
             return Err(CommonErr(
@@ -788,7 +883,28 @@ impl ObTableLocation {
         let mut rng = thread_rng();
         let random_server = rs_list.choose(&mut rng).unwrap();
 
-        self.get_table_entry_from_remote(random_server, key, connect_timeout, sock_timeout)
+        let remaining = deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+        match remaining {
+            Some(remaining) if remaining < connect_timeout.max(sock_timeout) => {
+                // a pool with the timeouts shortened to the deadline is not
+                // kept for the loads to come
+                let connect_timeout = connect_timeout.min(remaining);
+                let sock_timeout = sock_timeout.min(remaining);
+                let pool = self.new_mysql_pool(
+                    &self.config.sys_user_name,
+                    &self.config.sys_password,
+                    "oceanbase",
+                    random_server,
+                    Some(connect_timeout),
+                    Some(sock_timeout),
+                )?;
+                let mut conn = pool.try_get_conn(connect_timeout)?;
+                self.get_table_entry_with_conn(&mut conn, key)
+            }
+            _ => {
+                self.get_table_entry_from_remote(random_server, key, connect_timeout, sock_timeout)
+            }
+        }
     }
 
     /// refresh table entry with callback and priority
@@ -950,9 +1066,28 @@ impl ObTableLocation {
             Some(sock_timeout),
         )?;
         let mut conn = pool.try_get_conn(connect_timeout)?;
+        self.get_table_entry_with_conn(&mut conn, key)
+    }
 
+    fn get_time_zone(&self, conn: &mut my::PooledConn) -> Result<FixedOffset> {
+        let mut time_zone = self.time_zone.lock().unwrap();
+        match *time_zone {
+            Some(time_zone) => Ok(time_zone),
+            None => {
+                let loaded = LocationUtil::get_time_zone_from_server(conn)?;
+                *time_zone = Some(loaded);
+                Ok(loaded)
+            }
+        }
+    }
+
+    fn get_table_entry_with_conn(
+        &self,
+        conn: &mut my::PooledConn,
+        key: &TableEntryKey,
+    ) -> Result<TableEntry> {
         // get ob_version
-        match util::LocationUtil::get_ob_version_from_server(&mut conn) {
+        match util::LocationUtil::get_ob_version_from_server(conn) {
             Ok(_) => {}
             Err(e) => {
                 // return err
@@ -963,8 +1098,10 @@ impl ObTableLocation {
             }
         }
 
+        let time_zone = self.get_time_zone(conn)?;
+
         // majority of implementation is in LocationUtil
-        let table_entry = LocationUtil::get_table_entry_from_remote_inner(&mut conn, key)?;
+        let table_entry = LocationUtil::get_table_entry_from_remote_inner(conn, key, time_zone)?;
 
         Ok(table_entry)
     }
@@ -1044,6 +1181,7 @@ mod test {
                 &key,
                 Duration::from_secs(10),
                 Duration::from_secs(10),
+                None,
             )
             .expect("fail to load table entry");
     }
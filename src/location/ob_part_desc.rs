@@ -17,7 +17,13 @@
 
 use std::collections::HashMap;
 
-use super::{ob_part_constants, part_func_type::PartFuncType};
+use chrono::FixedOffset;
+
+use super::{
+    cache::{decode_value, encode_value, ComparableRecord, PartDescRecord},
+    ob_part_constants,
+    part_func_type::PartFuncType,
+};
 use crate::{
     error::{CommonErrCode, Error::Common as CommonErr, Result},
     location::part_func_type::PartFuncType::{KeyImplicitV2, KeyV3},
@@ -26,18 +32,21 @@ use crate::{
             ob_column::ObColumn,
             ob_partition_key::{Comparable, ObPartitionKey},
         },
-        util::hash::ob_hash_sort_utf8mb4::ObHashSortUtf8mb4,
+        util::hash::{ob_hash_sort_gbk::ObHashSortGbk, ob_hash_sort_utf8mb4::ObHashSortUtf8mb4},
     },
     serde_obkv::value::{CollationType, ObjType, Value},
-    util::obversion::ob_vsn_major,
+    util::{obversion::ob_vsn_major, timezone::utc},
 };
 
+/// Row key range `[start, end)` served by a partition, with its part id.
+pub type PartRange = (Vec<Value>, Vec<Value>, i64);
+
 #[derive(Clone, Debug)]
 pub enum ObPartDesc {
     Range(ObRangePartDesc),
     Hash(ObHashPartDesc),
     Key(ObKeyPartDesc),
-    // TODO: impl list part
+    List(ObListPartDesc),
 }
 
 impl ObPartDesc {
@@ -48,6 +57,7 @@ impl ObPartDesc {
             ObPartDesc::Hash(ref mut v) => v.ob_part_desc_obj.set_row_key_element(row_key_element),
 
             ObPartDesc::Key(ref mut v) => v.ob_part_desc_obj.set_row_key_element(row_key_element),
+            ObPartDesc::List(ref mut v) => v.ob_part_desc_obj.set_row_key_element(row_key_element),
         }
     }
 
@@ -56,6 +66,7 @@ impl ObPartDesc {
             ObPartDesc::Range(v) => v.ob_part_desc_obj.part_func_type.clone(),
             ObPartDesc::Hash(v) => v.ob_part_desc_obj.part_func_type.clone(),
             ObPartDesc::Key(v) => v.ob_part_desc_obj.part_func_type.clone(),
+            ObPartDesc::List(v) => v.ob_part_desc_obj.part_func_type.clone(),
         }
     }
 
@@ -64,6 +75,7 @@ impl ObPartDesc {
             ObPartDesc::Range(v) => &v.ob_part_desc_obj.ordered_part_column_names,
             ObPartDesc::Hash(v) => &v.ob_part_desc_obj.ordered_part_column_names,
             ObPartDesc::Key(v) => &v.ob_part_desc_obj.ordered_part_column_names,
+            ObPartDesc::List(v) => &v.ob_part_desc_obj.ordered_part_column_names,
         }
     }
 
@@ -72,6 +84,7 @@ impl ObPartDesc {
             ObPartDesc::Range(v) => &v.ob_part_desc_obj.part_name_id_map,
             ObPartDesc::Hash(v) => &v.ob_part_desc_obj.part_name_id_map,
             ObPartDesc::Key(v) => &v.ob_part_desc_obj.part_name_id_map,
+            ObPartDesc::List(v) => &v.ob_part_desc_obj.part_name_id_map,
         }
     }
 
@@ -80,6 +93,7 @@ impl ObPartDesc {
             ObPartDesc::Range(v) => v.get_part_id(row_key),
             ObPartDesc::Hash(v) => v.get_part_id(row_key),
             ObPartDesc::Key(v) => v.get_part_id(row_key),
+            ObPartDesc::List(v) => v.get_part_id(row_key),
         }
     }
 
@@ -94,6 +108,22 @@ impl ObPartDesc {
             ObPartDesc::Range(v) => v.get_part_ids(start, start_inclusive, end, end_inclusive),
             ObPartDesc::Hash(v) => v.get_part_ids(start, start_inclusive, end, end_inclusive),
             ObPartDesc::Key(v) => v.get_part_ids(start, start_inclusive, end, end_inclusive),
+            ObPartDesc::List(v) => v.get_part_ids(start, start_inclusive, end, end_inclusive),
+        }
+    }
+
+    /// Row key ranges served by each partition, see
+    /// [`ObRangePartDesc::get_part_ranges`].
+    pub fn get_part_ranges(&self) -> Result<Vec<PartRange>> {
+        match &self {
+            ObPartDesc::Range(v) => v.get_part_ranges(),
+            _ => Err(CommonErr(
+                CommonErrCode::PartitionError,
+                format!(
+                    "ObPartDesc::get_part_ranges partitions of {:?} are not row key ranges",
+                    self.get_part_func_type()
+                ),
+            )),
         }
     }
 
@@ -105,6 +135,7 @@ impl ObPartDesc {
             }
             ObPartDesc::Hash(v) => v.part_num,
             ObPartDesc::Key(v) => v.part_num,
+            ObPartDesc::List(v) => v.part_num,
         }
     }
 
@@ -113,6 +144,7 @@ impl ObPartDesc {
             ObPartDesc::Range(ref mut v) => v.ob_part_desc_obj.part_columns = part_columns,
             ObPartDesc::Hash(ref mut v) => v.ob_part_desc_obj.part_columns = part_columns,
             ObPartDesc::Key(ref mut v) => v.ob_part_desc_obj.part_columns = part_columns,
+            ObPartDesc::List(ref mut v) => v.ob_part_desc_obj.part_columns = part_columns,
         }
     }
 
@@ -121,6 +153,7 @@ impl ObPartDesc {
             ObPartDesc::Range(ref mut v) => v.ordered_compare_column = ordered_part_column,
             ObPartDesc::Hash(_) => (),
             ObPartDesc::Key(_) => (),
+            ObPartDesc::List(ref mut v) => v.ordered_compare_column = ordered_part_column,
         }
     }
 
@@ -131,6 +164,26 @@ impl ObPartDesc {
             ObPartDesc::Hash(ref mut v) => v.ob_part_desc_obj.part_name_id_map = part_name_id_map,
 
             ObPartDesc::Key(ref mut v) => v.ob_part_desc_obj.part_name_id_map = part_name_id_map,
+            ObPartDesc::List(ref mut v) => v.ob_part_desc_obj.part_name_id_map = part_name_id_map,
+        }
+    }
+
+    /// Time zone of the server the partition values are parsed in.
+    pub fn set_time_zone(&mut self, time_zone: FixedOffset) {
+        match self {
+            ObPartDesc::Range(ref mut v) => v.ob_part_desc_obj.time_zone = time_zone,
+            ObPartDesc::Hash(ref mut v) => v.ob_part_desc_obj.time_zone = time_zone,
+            ObPartDesc::Key(ref mut v) => v.ob_part_desc_obj.time_zone = time_zone,
+            ObPartDesc::List(ref mut v) => v.ob_part_desc_obj.time_zone = time_zone,
+        }
+    }
+
+    pub fn get_time_zone(&self) -> FixedOffset {
+        match self {
+            ObPartDesc::Range(v) => v.ob_part_desc_obj.time_zone,
+            ObPartDesc::Hash(v) => v.ob_part_desc_obj.time_zone,
+            ObPartDesc::Key(v) => v.ob_part_desc_obj.time_zone,
+            ObPartDesc::List(v) => v.ob_part_desc_obj.time_zone,
         }
     }
 
@@ -139,6 +192,7 @@ impl ObPartDesc {
             ObPartDesc::Range(v) => v.ob_part_desc_obj.part_func_type.is_list_part(),
             ObPartDesc::Hash(v) => v.ob_part_desc_obj.part_func_type.is_list_part(),
             ObPartDesc::Key(v) => v.ob_part_desc_obj.part_func_type.is_list_part(),
+            ObPartDesc::List(v) => v.ob_part_desc_obj.part_func_type.is_list_part(),
         }
     }
 
@@ -147,6 +201,7 @@ impl ObPartDesc {
             ObPartDesc::Range(v) => v.ob_part_desc_obj.part_func_type.is_key_part(),
             ObPartDesc::Hash(v) => v.ob_part_desc_obj.part_func_type.is_key_part(),
             ObPartDesc::Key(v) => v.ob_part_desc_obj.part_func_type.is_key_part(),
+            ObPartDesc::List(v) => v.ob_part_desc_obj.part_func_type.is_key_part(),
         }
     }
 
@@ -155,6 +210,7 @@ impl ObPartDesc {
             ObPartDesc::Range(v) => v.ob_part_desc_obj.part_func_type.is_range_part(),
             ObPartDesc::Hash(v) => v.ob_part_desc_obj.part_func_type.is_range_part(),
             ObPartDesc::Key(v) => v.ob_part_desc_obj.part_func_type.is_range_part(),
+            ObPartDesc::List(v) => v.ob_part_desc_obj.part_func_type.is_range_part(),
         }
     }
 
@@ -163,10 +219,151 @@ impl ObPartDesc {
             ObPartDesc::Range(v) => v.ob_part_desc_obj.prepare(),
             ObPartDesc::Hash(v) => v.ob_part_desc_obj.prepare(),
             ObPartDesc::Key(v) => v.ob_part_desc_obj.prepare(),
+            ObPartDesc::List(v) => v.ob_part_desc_obj.prepare(),
         }
     }
 }
 
+impl ObPartDesc {
+    /// Snapshot of the partitioning to be persisted, the columns are kept by
+    /// the partition info and the row key element by the table entry.
+    pub(crate) fn to_record(&self) -> Result<PartDescRecord> {
+        let obj = match self {
+            ObPartDesc::Range(v) => &v.ob_part_desc_obj,
+            ObPartDesc::Hash(v) => &v.ob_part_desc_obj,
+            ObPartDesc::Key(v) => &v.ob_part_desc_obj,
+            ObPartDesc::List(v) => &v.ob_part_desc_obj,
+        };
+        let mut record = PartDescRecord {
+            part_func_type: obj.part_func_type.clone() as i32,
+            part_expr: obj.part_expr.clone(),
+            ordered_part_column_names: obj.ordered_part_column_names.clone(),
+            part_name_id_map: obj.part_name_id_map.clone(),
+            part_num: self.get_part_num(),
+            time_zone: obj.time_zone.local_minus_utc(),
+            ..PartDescRecord::default()
+        };
+        match self {
+            ObPartDesc::Range(v) => {
+                record.ordered_compare_column_types = v
+                    .ordered_compare_column_types
+                    .iter()
+                    .map(|t| t.clone() as u8)
+                    .collect();
+                for (key, part_id) in &v.bounds {
+                    let mut elements = Vec::with_capacity(key.partition_elements().len());
+                    for element in key.partition_elements() {
+                        elements.push(match element {
+                            Comparable::MaxValue => ComparableRecord::MaxValue,
+                            Comparable::MinValue => ComparableRecord::MinValue,
+                            Comparable::Value(v) => ComparableRecord::Value(encode_value(v)?),
+                        });
+                    }
+                    record.bounds.push((elements, *part_id));
+                }
+            }
+            ObPartDesc::Hash(v) => {
+                record.complete_works = v.complete_works.clone();
+                record.part_space = v.part_space;
+            }
+            ObPartDesc::Key(v) => record.part_space = v.part_space,
+            ObPartDesc::List(v) => {
+                for (values, part_id) in &v.sets {
+                    let values = values.iter().map(encode_value).collect::<Result<_>>()?;
+                    record.sets.push((values, *part_id));
+                }
+                // keep the file stable across refreshes
+                record.sets.sort();
+                record.default_part_id = v.default_part_id;
+            }
+        }
+        Ok(record)
+    }
+
+    /// Rebuilds the partitioning persisted by [`ObPartDesc::to_record`].
+    pub(crate) fn from_record(record: &PartDescRecord) -> Result<ObPartDesc> {
+        let obj = ObPartDescObj {
+            part_func_type: PartFuncType::from_i32(record.part_func_type),
+            part_expr: record.part_expr.clone(),
+            ordered_part_column_names: record.ordered_part_column_names.clone(),
+            part_name_id_map: record.part_name_id_map.clone(),
+            time_zone: FixedOffset::east_opt(record.time_zone).ok_or_else(|| {
+                CommonErr(
+                    CommonErrCode::PartitionError,
+                    format!(
+                        "ObPartDesc::from_record invalid time zone: {}",
+                        record.time_zone
+                    ),
+                )
+            })?,
+            ..ObPartDescObj::new()
+        };
+        let part_desc = if obj.part_func_type.is_range_part() {
+            let mut bounds = Vec::with_capacity(record.bounds.len());
+            for (elements, part_id) in &record.bounds {
+                let mut key = Vec::with_capacity(elements.len());
+                for element in elements {
+                    key.push(match element {
+                        ComparableRecord::MaxValue => Comparable::MaxValue,
+                        ComparableRecord::MinValue => Comparable::MinValue,
+                        ComparableRecord::Value(v) => Comparable::Value(decode_value(v)?),
+                    });
+                }
+                bounds.push((ObPartitionKey::new(key), *part_id));
+            }
+            ObPartDesc::Range(ObRangePartDesc {
+                ob_part_desc_obj: obj,
+                ordered_compare_column: Vec::new(),
+                ordered_compare_column_types: record
+                    .ordered_compare_column_types
+                    .iter()
+                    .map(|t| Ok(ObjType::from_u8(*t)?))
+                    .collect::<Result<_>>()?,
+                bounds,
+                part_num: record.part_num,
+            })
+        } else if obj.part_func_type.is_list_part() {
+            let mut sets = HashMap::with_capacity(record.sets.len());
+            for (values, part_id) in &record.sets {
+                let values = values
+                    .iter()
+                    .map(|v| decode_value(v))
+                    .collect::<Result<_>>()?;
+                sets.insert(values, *part_id);
+            }
+            ObPartDesc::List(ObListPartDesc {
+                ob_part_desc_obj: obj,
+                ordered_compare_column: Vec::new(),
+                sets,
+                default_part_id: record.default_part_id,
+                part_num: record.part_num,
+            })
+        } else if obj.part_func_type.is_hash_part() {
+            ObPartDesc::Hash(ObHashPartDesc {
+                ob_part_desc_obj: obj,
+                complete_works: record.complete_works.clone(),
+                part_space: record.part_space,
+                part_num: record.part_num,
+            })
+        } else if obj.part_func_type.is_key_part() {
+            ObPartDesc::Key(ObKeyPartDesc {
+                ob_part_desc_obj: obj,
+                part_space: record.part_space,
+                part_num: record.part_num,
+            })
+        } else {
+            return Err(CommonErr(
+                CommonErrCode::PartitionError,
+                format!(
+                    "ObPartDesc::from_record unknown part func type: {}",
+                    record.part_func_type
+                ),
+            ));
+        };
+        Ok(part_desc)
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct ObPartDescObj {
     part_func_type: PartFuncType,
@@ -176,6 +373,7 @@ pub struct ObPartDescObj {
     part_columns: Vec<Box<dyn ObColumn>>,
     part_name_id_map: HashMap<String, i64>,
     row_key_element: HashMap<String, i32>,
+    time_zone: FixedOffset,
 }
 
 impl ObPartDescObj {
@@ -188,6 +386,7 @@ impl ObPartDescObj {
             part_columns: Vec::new(),
             part_name_id_map: HashMap::new(),
             row_key_element: HashMap::new(),
+            time_zone: utc(),
         }
     }
 
@@ -290,12 +489,53 @@ impl ObPartDescObj {
             eval_values.push(
                 ordered_part_ref_column_row_key_relation
                     .0
-                    .eval_value(&eval_params)?,
+                    .eval_value(&eval_params, self.time_zone)?,
             );
         }
         Ok(eval_values)
     }
 
+    /// Whether every row key column up to the last one referenced by the
+    /// partition columns holds the same value at both ends of the range, so
+    /// that all rows in the range share the partition values of `start`.
+    pub fn is_single_partition_range(&self, start: &[Value], end: &[Value]) -> bool {
+        let last_index = self
+            .ordered_part_ref_column_row_key_relations
+            .iter()
+            .flat_map(|(_, indexes)| indexes.iter())
+            .max();
+        match last_index {
+            Some(&last_index) if last_index >= 0 => {
+                let len = last_index as usize + 1;
+                start.len() >= len
+                    && end.len() >= len
+                    && start[..len]
+                        .iter()
+                        .zip(end[..len].iter())
+                        .all(|(s, e)| !s.is_min() && !s.is_max() && s == e)
+            }
+            _ => false,
+        }
+    }
+
+    // Whether the partition columns are the leading columns of the row key, so
+    // that the partition values of a row key range are bounded by the partition
+    // values of its start and end.
+    fn is_part_columns_prefix(&self) -> bool {
+        self.ordered_part_ref_column_row_key_relations
+            .iter()
+            .enumerate()
+            .all(|(i, (_, indexes))| indexes.len() == 1 && indexes[0] == i as i32)
+    }
+
+    // Row key index of the first column referenced by each partition column.
+    fn part_ref_row_key_index(&self, idx: usize) -> Option<usize> {
+        self.ordered_part_ref_column_row_key_relations
+            .get(idx)
+            .and_then(|(_, indexes)| indexes.first())
+            .map(|i| *i as usize)
+    }
+
     pub fn init_comparable_element_by_types(
         &self,
         row_key: &[Value],
@@ -384,6 +624,55 @@ impl ObRangePartDesc {
         Ok(self.bounds[idx as usize].1)
     }
 
+    /// Row key ranges served by each partition in bound order, as
+    /// `(start, end, part_id)` with an inclusive start and an exclusive end.
+    /// The ranges are only known when the partition columns are the leading
+    /// columns of the row key.
+    pub fn get_part_ranges(&self) -> Result<Vec<PartRange>> {
+        if self
+            .ob_part_desc_obj
+            .ordered_part_ref_column_row_key_relations
+            .is_empty()
+            || !self.ob_part_desc_obj.is_part_columns_prefix()
+        {
+            return Err(CommonErr(
+                CommonErrCode::PartitionError,
+                format!(
+                    "ObRangePartDesc::get_part_ranges partition columns {:?} are not the leading row key columns {:?}",
+                    self.ob_part_desc_obj.ordered_part_column_names,
+                    self.ob_part_desc_obj.row_key_element
+                ),
+            ));
+        }
+        let row_key_len = self.ob_part_desc_obj.row_key_element.len();
+        let mut start = vec![Value::get_min(); row_key_len];
+        let mut ranges = Vec::with_capacity(self.bounds.len());
+        for (bound, part_id) in &self.bounds {
+            let end = Self::bound_to_row_key(bound, row_key_len);
+            ranges.push((start, end.clone(), *part_id));
+            start = end;
+        }
+        Ok(ranges)
+    }
+
+    // Smallest row key whose partition values are not less than the bound.
+    fn bound_to_row_key(bound: &ObPartitionKey, row_key_len: usize) -> Vec<Value> {
+        let mut row_key = Vec::with_capacity(row_key_len);
+        let mut pad = Value::get_min();
+        for element in bound.partition_elements() {
+            row_key.push(match element {
+                Comparable::MaxValue => {
+                    pad = Value::get_max();
+                    Value::get_max()
+                }
+                Comparable::MinValue => Value::get_min(),
+                Comparable::Value(v) => v.clone(),
+            });
+        }
+        row_key.resize(row_key_len.max(row_key.len()), pad);
+        row_key
+    }
+
     pub fn get_ordered_compare_column(&self) -> &Vec<Box<dyn ObColumn>> {
         &self.ordered_compare_column
     }
@@ -431,6 +720,167 @@ impl ObRangePartDesc {
     }
 }
 
+#[derive(Clone, Debug)]
+pub struct ObListPartDesc {
+    ob_part_desc_obj: ObPartDescObj,
+    ordered_compare_column: Vec<Box<dyn ObColumn>>,
+    // partition values -> part id
+    sets: HashMap<Vec<Value>, i64>,
+    default_part_id: Option<i64>,
+    part_num: i32,
+}
+
+impl Default for ObListPartDesc {
+    fn default() -> ObListPartDesc {
+        ObListPartDesc::new()
+    }
+}
+
+impl ObListPartDesc {
+    pub fn new() -> Self {
+        Self {
+            ob_part_desc_obj: ObPartDescObj::new(),
+            ordered_compare_column: Vec::new(),
+            sets: HashMap::new(),
+            default_part_id: None,
+            part_num: 0,
+        }
+    }
+
+    pub fn get_ordered_compare_column(&self) -> &Vec<Box<dyn ObColumn>> {
+        &self.ordered_compare_column
+    }
+
+    /// Set the partition values of every partition, `values` are the
+    /// evaluated partition column values.
+    pub fn set_sets(&mut self, sets: Vec<(Vec<Value>, i64)>) {
+        self.sets = sets
+            .into_iter()
+            .map(|(values, part_id)| (self.normalize(values), part_id))
+            .collect();
+    }
+
+    pub fn set_default_part_id(&mut self, default_part_id: Option<i64>) {
+        self.default_part_id = default_part_id;
+    }
+
+    pub fn set_part_name_id_map(&mut self, part_name_id_map: HashMap<String, i64>) {
+        self.ob_part_desc_obj.part_name_id_map = part_name_id_map;
+    }
+
+    pub fn set_part_num(&mut self, part_num: i32) {
+        self.part_num = part_num;
+    }
+
+    pub fn get_part_num(&self) -> i32 {
+        self.part_num
+    }
+
+    pub fn set_part_func_type(&mut self, part_func_type: PartFuncType) {
+        self.ob_part_desc_obj.part_func_type = part_func_type;
+    }
+
+    pub fn set_part_expr(&mut self, part_expr: String) {
+        self.ob_part_desc_obj.part_expr = part_expr;
+    }
+
+    pub fn set_ordered_part_column_names(&mut self, ordered_part_column_names: Vec<String>) {
+        self.ob_part_desc_obj.ordered_part_column_names = ordered_part_column_names;
+    }
+
+    pub fn get_part_id(&self, row_key: &[Value]) -> Result<i64> {
+        if row_key.len() != self.ob_part_desc_obj.row_key_element.len() {
+            error!(
+                "ObListPartDesc::get_part_id row key is consist of :{:?}, but found: {:?}",
+                self.ob_part_desc_obj.row_key_element, row_key
+            );
+            return Err(CommonErr(
+                CommonErrCode::PartitionError,
+                format!(
+                    "ObListPartDesc::get_part_id row key is consist of :{:?}, but found: {:?}",
+                    self.ob_part_desc_obj.row_key_element, row_key
+                ),
+            ));
+        }
+        let values = self.normalize(self.ob_part_desc_obj.eval_row_key_values(row_key)?);
+        match self.sets.get(&values).or(self.default_part_id.as_ref()) {
+            Some(part_id) => Ok(*part_id),
+            None => {
+                error!(
+                    "ObListPartDesc::get_part_id no partition matches row key:{:?}",
+                    row_key
+                );
+                Err(CommonErr(
+                    CommonErrCode::PartitionError,
+                    format!("ObListPartDesc::get_part_id no partition matches row key:{row_key:?}"),
+                ))
+            }
+        }
+    }
+
+    /// Partitions owning any partition value in the range, a range which can
+    /// not be mapped onto the partition columns falls back to all partitions.
+    pub fn get_part_ids(
+        &self,
+        start: &[Value],
+        _start_inclusive: bool,
+        end: &[Value],
+        _end_inclusive: bool,
+    ) -> Result<Vec<i64>> {
+        let mut part_ids: Vec<i64> = if self.ob_part_desc_obj.is_part_columns_prefix() {
+            let start = self.to_partition_key(start)?;
+            let end = self.to_partition_key(end)?;
+            self.sets
+                .iter()
+                .filter(|(values, _)| {
+                    let key = ObPartitionKey::new(
+                        self.ob_part_desc_obj
+                            .init_comparable_element_by_types(values, &self.ordered_compare_column),
+                    );
+                    start <= key && key <= end
+                })
+                .map(|(_, part_id)| *part_id)
+                .collect()
+        } else {
+            self.sets.values().copied().collect()
+        };
+        if let Some(default_part_id) = self.default_part_id {
+            part_ids.push(default_part_id);
+        }
+        part_ids.sort_unstable();
+        part_ids.dedup();
+        Ok(part_ids)
+    }
+
+    fn to_partition_key(&self, row_key: &[Value]) -> Result<ObPartitionKey> {
+        let values = self.normalize(self.ob_part_desc_obj.eval_row_key_values(row_key)?);
+        Ok(ObPartitionKey::new(
+            self.ob_part_desc_obj
+                .init_comparable_element_by_types(&values, &self.ordered_compare_column),
+        ))
+    }
+
+    // strings equal in the column collation must be the same key: every
+    // collation but binary is PAD SPACE, and utf8mb4_general_ci is also case
+    // and accent insensitive
+    fn normalize(&self, values: Vec<Value>) -> Vec<Value> {
+        values
+            .into_iter()
+            .zip(self.ordered_compare_column.iter())
+            .map(|(value, column)| match value {
+                Value::String(v, meta) => match column.get_ob_collation_type() {
+                    CollationType::Binary => Value::String(v, meta),
+                    CollationType::UTF8MB4GeneralCi => {
+                        Value::String(ObHashSortUtf8mb4::ob_sort_key_utf8_mb4(&v), meta)
+                    }
+                    _ => Value::String(v.trim_end_matches(' ').to_owned(), meta),
+                },
+                other => other,
+            })
+            .collect()
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct ObHashPartDesc {
     ob_part_desc_obj: ObPartDescObj,
@@ -494,65 +944,69 @@ impl ObHashPartDesc {
         end: &[Value],
         end_inclusive: bool,
     ) -> Result<Vec<i64>> {
-        let start_value = &start[0];
-        let end_value = &end[0];
-        // TODO: impl parse_to_i64 in serde_obkv
-        let start_long_value: i64 = match start_value {
-            Value::String(v, _meta) => v.parse::<i64>()?,
-            Value::Int64(v, _meta) => *v,
-            Value::Int32(v, _meta) => *v as i64,
-            Value::Int8(v, _meta) => *v as i64,
-            // TODO: support value bytes
-            Value::Bytes(_v, _meta) => unimplemented!(),
-            _ => 0,
-        };
-        let end_long_value: i64 = match end_value {
-            Value::String(v, _meta) => v.parse::<i64>()?,
-            Value::Int64(v, _meta) => *v,
-            Value::Int32(v, _meta) => *v as i64,
-            Value::Int8(v, _meta) => *v as i64,
-            // TODO: support value bytes
-            Value::Bytes(_v, _meta) => unimplemented!(),
-            _ => 0,
-        };
+        if self.ob_part_desc_obj.is_single_partition_range(start, end) {
+            return Ok(vec![self.get_part_id(start)?]);
+        }
 
-        let start_hash_value = if start_inclusive {
-            start_long_value
+        // Only a range on the leading row key column bounds the values of the
+        // partition column, otherwise all partitions have to be scanned.
+        let (start_long_value, end_long_value) =
+            match self.ob_part_desc_obj.part_ref_row_key_index(0) {
+                Some(0) => match (
+                    Self::to_long_value(&start[0])?,
+                    Self::to_long_value(&end[0])?,
+                ) {
+                    (Some(start), Some(end)) => (start, end),
+                    _ => return Ok(self.complete_works.clone()),
+                },
+                _ => return Ok(self.complete_works.clone()),
+            };
+
+        // an exclusive bound only excludes the partition value if it is the last
+        // column of the bound, e.g. [(5, a), (5, b)) still holds rows of 5
+        let start_hash_value = if start_inclusive || start.len() > 1 {
+            Some(start_long_value)
         } else {
-            start_long_value + 1
+            start_long_value.checked_add(1)
         };
-        let end_hash_value = if end_inclusive {
-            end_long_value
+        let end_hash_value = if end_inclusive || end.len() > 1 {
+            Some(end_long_value)
         } else {
-            end_long_value - 1
+            end_long_value.checked_sub(1)
+        };
+        let (start_hash_value, end_hash_value) = match (start_hash_value, end_hash_value) {
+            (Some(start), Some(end)) if start <= end => (start, end),
+            _ => return Ok(Vec::new()),
         };
 
-        if end_hash_value - start_hash_value + 1 >= self.part_num as i64 {
+        if end_hash_value as i128 - start_hash_value as i128 + 1 >= self.part_num as i128 {
             Ok(self.complete_works.clone())
         } else {
-            let mut part_ids = Vec::with_capacity((end_hash_value - start_hash_value + 1) as usize);
-            for i in start_hash_value..=end_hash_value {
-                part_ids.push(self.inner_hash(i));
-            }
+            let mut part_ids: Vec<i64> = (start_hash_value..=end_hash_value)
+                .map(|i| self.inner_hash(i))
+                .collect();
+            part_ids.sort_unstable();
+            part_ids.dedup();
             Ok(part_ids)
         }
     }
 
     pub fn get_part_id(&self, row_key: &[Value]) -> Result<i64> {
-        if row_key.is_empty() {
+        let idx = self.ob_part_desc_obj.part_ref_row_key_index(0).unwrap_or(0);
+        if row_key.len() <= idx {
             error!(
                 "ObHashPartDesc::get_part_id invalid row keys :{:?}",
                 row_key
             );
             return Err(CommonErr(
                 CommonErrCode::PartitionError,
-                "ObHashPartDesc::get_part_id: row_key is empty".to_owned(),
+                format!("ObHashPartDesc::get_part_id: row_key has no partition column, row_key:{row_key:?}"),
             ));
         }
         // TODO: evalRowKeyValues
 
         // Note: There was a loop in java version
-        let value: i64 = match &row_key[0] {
+        let value: i64 = match &row_key[idx] {
             Value::String(v, _meta) => v.parse::<i64>()?,
             Value::Int64(v, _meta) => *v,
             Value::Int32(v, _meta) => *v as i64,
@@ -569,8 +1023,28 @@ impl ObHashPartDesc {
         Ok(current_part_id)
     }
 
+    // Integral value of a range bound, `None` for min / max and values which
+    // can not be enumerated.
+    fn to_long_value(value: &Value) -> Result<Option<i64>> {
+        if value.is_min() || value.is_max() {
+            return Ok(None);
+        }
+        // TODO: impl parse_to_i64 in serde_obkv
+        Ok(match value {
+            Value::String(v, _meta) => Some(v.parse::<i64>()?),
+            Value::Int64(v, _meta) => Some(*v),
+            Value::Int32(v, _meta) => Some(*v as i64),
+            Value::Int8(v, _meta) => Some(*v as i64),
+            Value::UInt64(v, _meta) => Some(*v as i64),
+            Value::UInt32(v, _meta) => Some(*v as i64),
+            Value::UInt8(v, _meta) => Some(*v as i64),
+            _ => None,
+        })
+    }
+
     fn inner_hash(&self, value: i64) -> i64 {
-        let hash_value = value.abs();
+        // as Math.abs of java, which keeps i64::MIN
+        let hash_value = value.wrapping_abs();
         ((self.part_space as i64) << ob_part_constants::OB_PART_IDS_BITNUM)
             | (hash_value % self.part_num as i64)
     }
@@ -633,47 +1107,16 @@ impl ObKeyPartDesc {
         end: &[Value],
         _end_inclusive: bool,
     ) -> Result<Vec<i64>> {
-        // if start=[min,min,min] end=[max,max,max], scan all partitions
-        let mut is_min_max = true;
-        for v in start {
-            if !is_min_max {
-                break;
-            }
-            is_min_max &= v.is_min();
-        }
-
-        for v in end {
-            if !is_min_max {
-                break;
-            }
-            is_min_max &= v.is_max();
+        if self.ob_part_desc_obj.is_single_partition_range(start, end) {
+            return Ok(vec![self.get_part_id(start)?]);
         }
 
-        // For partition key, the first part of the row key is the partition key
-        let part_ref_column_size = self
-            .ob_part_desc_obj
-            .ordered_part_ref_column_row_key_relations
-            .len();
         // Note: Java / ODP may not query all the partitions, and will return an error
         // instead
-        if is_min_max
-            || !self.is_equal_keys(
-                &start[0..part_ref_column_size],
-                &end[0..part_ref_column_size],
-            )
-        {
-            let mut part_ids: Vec<i64> = Vec::with_capacity(self.part_num as usize);
-            for i in 0..self.part_num as i64 {
-                part_ids.push(i);
-            }
-            return Ok(part_ids);
+        let mut part_ids: Vec<i64> = Vec::with_capacity(self.part_num as usize);
+        for i in 0..self.part_num as i64 {
+            part_ids.push(i);
         }
-
-        let mut row_keys: Vec<Value> = Vec::with_capacity(2);
-        row_keys.append(&mut start.to_vec());
-        row_keys.append(&mut end.to_vec());
-
-        let part_ids = vec![self.get_part_id(&row_keys)?];
         Ok(part_ids)
     }
 
@@ -686,20 +1129,37 @@ impl ObKeyPartDesc {
             ));
         }
         // TODO: evalRowKeyValues
-        let part_ref_column_size = self
+        let mut hash_value = 0u64;
+        for (idx, (column, _)) in self
             .ob_part_desc_obj
             .ordered_part_ref_column_row_key_relations
-            .len();
-
-        let mut hash_value = 0u64;
-        // FIXME: shall we do a check to ensure part_ref_column_size <= row_key.len()?
-        for (idx, row_key) in row_key.iter().enumerate().take(part_ref_column_size) {
+            .iter()
+            .enumerate()
+        {
+            let value = match self
+                .ob_part_desc_obj
+                .part_ref_row_key_index(idx)
+                .and_then(|i| row_key.get(i))
+            {
+                Some(value) => value,
+                None => {
+                    error!(
+                        "ObKeyPartDesc::get_part_id row key has no partition column {:?}, row_key:{:?}",
+                        column.get_column_name(),
+                        row_key
+                    );
+                    return Err(CommonErr(
+                        CommonErrCode::PartitionError,
+                        format!(
+                            "ObKeyPartDesc::get_part_id row key has no partition column {:?}, row_key:{row_key:?}",
+                            column.get_column_name()
+                        ),
+                    ));
+                }
+            };
             hash_value = ObKeyPartDesc::to_hashcode(
-                row_key,
-                &self
-                    .ob_part_desc_obj
-                    .ordered_part_ref_column_row_key_relations[idx]
-                    .0,
+                value,
+                column,
                 hash_value,
                 &self.ob_part_desc_obj.part_func_type,
             )?;
@@ -712,15 +1172,6 @@ impl ObKeyPartDesc {
         )
     }
 
-    pub fn is_equal_keys(&self, start: &[Value], end: &[Value]) -> bool {
-        for (start_value, end_value) in start.iter().zip(end.iter()) {
-            if start_value != end_value {
-                return false;
-            }
-        }
-        true
-    }
-
     #[allow(clippy::wrong_self_convention)]
     #[allow(clippy::borrowed_box)]
     pub fn to_hashcode(
@@ -750,29 +1201,33 @@ impl ObKeyPartDesc {
                 hash_code,
                 part_func_type.to_owned(),
             ),
-            // TODO: support value Time
-            Value::Time(_v, _meta) => unimplemented!(),
-            // TODO: support value Date
-            Value::Date(_v, _meta) => unimplemented!(),
-            _ => Ok(0),
+            // temporal values are hashed by their stored integer representation
+            Value::Time(v, _meta) => ObKeyPartDesc::long_hash(*v, hash_code),
+            Value::DateTime(v, _meta) => ObKeyPartDesc::long_hash(*v, hash_code),
+            Value::Timestamp(v, _meta) => ObKeyPartDesc::long_hash(*v, hash_code),
+            Value::Date(v, _meta) => Ok(murmur2::murmur64a(&v.to_le_bytes(), hash_code)),
+            Value::Year(v, _meta) => Ok(murmur2::murmur64a(&v.to_le_bytes(), hash_code)),
+            Value::Bit(v, _meta) => ObKeyPartDesc::long_hash(*v as i64, hash_code),
+            Value::Enum(v, _meta) => ObKeyPartDesc::long_hash(*v as i64, hash_code),
+            Value::Set(v, _meta) => ObKeyPartDesc::long_hash(*v as i64, hash_code),
+            _ => {
+                error!(
+                    "ObKeyPartDesc::to_hashcode key type not supported, object:{:?}",
+                    value
+                );
+                Err(CommonErr(
+                    CommonErrCode::PartitionError,
+                    format!("ObKeyPartDesc::to_hashcode key type not supported, object:{value:?}"),
+                ))
+            }
         }
     }
 
     // TODO: check if murmur2 hash value is correct(java sdk)
     pub fn long_hash(value: i64, hash_code: u64) -> Result<u64> {
-        Ok(murmur2::murmur64a(&value.to_ne_bytes(), hash_code))
+        Ok(murmur2::murmur64a(&value.to_le_bytes(), hash_code))
     }
 
-    // TODO: support value Time
-    //pub fn timestamp_hash() -> Result<i64> {
-    //    unimplemented!();
-    //}
-
-    // TODO: support value Date
-    //pub fn date_hash() -> Result<i64> {
-    //    unimplemented!();
-    //}
-
     pub fn varchar_hash(
         value: Value,
         collation_type: &CollationType,
@@ -781,7 +1236,7 @@ impl ObKeyPartDesc {
     ) -> Result<u64> {
         let seed: u64 = 0xc6a4_a793_5bd1_e995;
         let bytes = match value {
-            Value::String(v, _meta) => v.into_bytes(),
+            Value::String(v, _meta) => Self::encode_varchar(v, collation_type)?,
 
             Value::Bytes(v, _meta) => v,
             _ => {
@@ -832,6 +1287,26 @@ impl ObKeyPartDesc {
                     ))
                 }
             }
+            CollationType::GBKChineseCi => Ok(ObHashSortGbk::ob_hash_sort_gbk(
+                &bytes,
+                bytes.len() as i32,
+                hash_code,
+                seed,
+                ob_vsn_major() >= 4 || part_func_type == KeyV3 || part_func_type == KeyImplicitV2,
+            )),
+            CollationType::GBKBin | CollationType::GB18030Bin => {
+                if ob_vsn_major() >= 4 || part_func_type == KeyV3 || part_func_type == KeyImplicitV2
+                {
+                    Ok(murmur2::murmur64a(&bytes, hash_code))
+                } else {
+                    Ok(ObHashSortUtf8mb4::ob_hash_sort_mb_bin(
+                        &bytes,
+                        bytes.len() as i32,
+                        hash_code,
+                        seed,
+                    ))
+                }
+            }
             CollationType::Binary => {
                 if ob_vsn_major() >= 4 || part_func_type == KeyV3 || part_func_type == KeyImplicitV2
                 {
@@ -845,6 +1320,21 @@ impl ObKeyPartDesc {
                     ))
                 }
             }
+            // utf8mb4_unicode_ci and gb18030_chinese_ci hash the UCA 4.0.0 and the
+            // gb18030 pinyin weights of the characters, the weight tables of the server
+            // are not ported yet, so the rows are refused rather than misrouted.
+            CollationType::UTF8MB4UnicodeCi | CollationType::GB18030ChineseCi => {
+                error!(
+                    "ObKeyPartDesc::varchar_hash the weights of the collation are not supported yet, type:{:?}",
+                    collation_type
+                );
+                Err(CommonErr(
+                    CommonErrCode::PartitionError,
+                    format!(
+                        "ObKeyPartDesc::varchar_hash the weights of the collation are not supported yet, type:{collation_type:?}",
+                    ),
+                ))
+            }
             _ => {
                 error!(
                     "ObKeyPartDesc::varchar_hash not supported collation type, type:{:?}",
@@ -859,4 +1349,606 @@ impl ObKeyPartDesc {
             }
         }
     }
+
+    // Strings are hashed in the charset of the partition column.
+    fn encode_varchar(value: String, collation_type: &CollationType) -> Result<Vec<u8>> {
+        let encoding = match collation_type {
+            CollationType::GBKChineseCi | CollationType::GBKBin => encoding_rs::GBK,
+            CollationType::GB18030ChineseCi | CollationType::GB18030Bin => encoding_rs::GB18030,
+            _ => return Ok(value.into_bytes()),
+        };
+        let (bytes, _, had_errors) = encoding.encode(&value);
+        if had_errors {
+            error!(
+                "ObKeyPartDesc::encode_varchar can not encode {:?} in {}",
+                value,
+                encoding.name()
+            );
+            return Err(CommonErr(
+                CommonErrCode::PartitionError,
+                format!(
+                    "ObKeyPartDesc::encode_varchar can not encode {value:?} in {}",
+                    encoding.name()
+                ),
+            ));
+        }
+        Ok(bytes.into_owned())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{NaiveDate, NaiveDateTime};
+
+    use super::*;
+    use crate::rpc::protocol::partition::ob_column::ObSimpleColumn;
+
+    // range partitions on a timestamp column of a server in `time_zone`
+    fn timestamp_part_desc(time_zone: FixedOffset) -> ObPartDesc {
+        let column: Box<dyn ObColumn> = Box::new(ObSimpleColumn::new(
+            "c1".to_owned(),
+            0,
+            ObjType::Timestamp,
+            CollationType::Binary,
+        ));
+        let bound = column
+            .eval_value(&[Value::from("2023-01-01 00:00:00")], time_zone)
+            .unwrap();
+        let mut desc = ObRangePartDesc::new();
+        desc.set_part_func_type(PartFuncType::RangeColumns);
+        desc.set_ordered_part_column_names(vec!["c1".to_owned()]);
+        desc.set_bounds(vec![
+            (ObPartitionKey::new(vec![Comparable::Value(bound)]), 0),
+            (ObPartitionKey::new(vec![Comparable::MaxValue]), 1),
+        ]);
+        let mut part_desc = ObPartDesc::Range(desc);
+        part_desc.set_part_columns(vec![column.clone()]);
+        part_desc.set_ordered_compare_columns(vec![column]);
+        part_desc.set_row_key_element([("c1".to_owned(), 0)].into_iter().collect());
+        part_desc.set_time_zone(time_zone);
+        part_desc.prepare().unwrap();
+        part_desc
+    }
+
+    #[test]
+    fn test_timestamp_in_time_zone_of_each_table() {
+        let east_8 = timestamp_part_desc(FixedOffset::east_opt(8 * 3600).unwrap());
+        let utc = timestamp_part_desc(utc());
+        // the wall clocks of the row keys are in the time zone of each server
+        for part_desc in [&east_8, &utc] {
+            assert_eq!(
+                0,
+                part_desc
+                    .get_part_id(&[Value::from("2022-12-31 23:30:00")])
+                    .unwrap()
+            );
+            assert_eq!(
+                1,
+                part_desc
+                    .get_part_id(&[Value::from("2023-01-01 00:30:00")])
+                    .unwrap()
+            );
+        }
+
+        // and the time zone is persisted along the partitions
+        let loaded = ObPartDesc::from_record(&east_8.to_record().unwrap()).unwrap();
+        assert_eq!(east_8.get_time_zone(), loaded.get_time_zone());
+    }
+
+    #[test]
+    fn test_range_columns_on_datetime() {
+        let column: Box<dyn ObColumn> = Box::new(ObSimpleColumn::new(
+            "c1".to_owned(),
+            0,
+            ObjType::DateTime,
+            CollationType::Binary,
+        ));
+        let bounds = ["2023-01-01 00:00:00", "2023-02-01 00:00:00"]
+            .iter()
+            .enumerate()
+            .map(|(i, b)| {
+                (
+                    ObPartitionKey::new(vec![Comparable::Value(
+                        column.eval_value(&[Value::from(*b)], utc()).unwrap(),
+                    )]),
+                    i as i64,
+                )
+            })
+            .chain(std::iter::once((
+                ObPartitionKey::new(vec![Comparable::MaxValue]),
+                2,
+            )))
+            .collect();
+        let mut desc = ObRangePartDesc::new();
+        desc.set_part_func_type(PartFuncType::RangeColumns);
+        desc.set_ordered_part_column_names(vec!["c1".to_owned()]);
+        desc.set_bounds(bounds);
+        let mut part_desc = ObPartDesc::Range(desc);
+        part_desc.set_part_columns(vec![column.clone()]);
+        part_desc.set_ordered_compare_columns(vec![column.clone()]);
+        part_desc.set_row_key_element([("c1".to_owned(), 0)].into_iter().collect());
+        part_desc.prepare().unwrap();
+
+        let at = |s: &str| {
+            vec![Value::from(
+                NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S%.f").unwrap(),
+            )]
+        };
+        assert_eq!(
+            0,
+            part_desc
+                .get_part_id(&at("2022-12-31 23:59:59.999999"))
+                .unwrap()
+        );
+        assert_eq!(
+            1,
+            part_desc.get_part_id(&at("2023-01-01 00:00:00")).unwrap()
+        );
+        assert_eq!(
+            1,
+            part_desc.get_part_id(&at("2023-01-31 12:00:00")).unwrap()
+        );
+        assert_eq!(
+            2,
+            part_desc.get_part_id(&at("2023-02-01 00:00:00")).unwrap()
+        );
+        assert_eq!(
+            vec![0, 1, 2],
+            part_desc
+                .get_part_ids(
+                    &at("2022-12-01 00:00:00"),
+                    true,
+                    &at("2023-03-01 00:00:00"),
+                    true
+                )
+                .unwrap()
+        );
+    }
+
+    fn list_part_desc(
+        obj_type: ObjType,
+        collation_type: CollationType,
+        sets: Vec<(Vec<&str>, i64)>,
+        default_part_id: Option<i64>,
+    ) -> ObPartDesc {
+        let column: Box<dyn ObColumn> = Box::new(ObSimpleColumn::new(
+            "c1".to_owned(),
+            0,
+            obj_type,
+            collation_type,
+        ));
+        let mut desc = ObListPartDesc::new();
+        desc.set_part_func_type(PartFuncType::ListColumns);
+        desc.set_ordered_part_column_names(vec!["c1".to_owned()]);
+        let mut part_desc = ObPartDesc::List(desc);
+        part_desc.set_part_columns(vec![column.clone()]);
+        part_desc.set_ordered_compare_columns(vec![column.clone()]);
+        part_desc.set_row_key_element(
+            [("c1".to_owned(), 0), ("c2".to_owned(), 1)]
+                .into_iter()
+                .collect(),
+        );
+        part_desc.prepare().unwrap();
+        if let ObPartDesc::List(ref mut v) = part_desc {
+            v.set_sets(
+                sets.into_iter()
+                    .map(|(values, part_id)| {
+                        (
+                            values
+                                .into_iter()
+                                .map(|s| column.eval_value(&[Value::from(s)], utc()).unwrap())
+                                .collect(),
+                            part_id,
+                        )
+                    })
+                    .collect(),
+            );
+            v.set_default_part_id(default_part_id);
+        }
+        part_desc
+    }
+
+    #[test]
+    fn test_list_part() {
+        let part_desc = list_part_desc(
+            ObjType::Int64,
+            CollationType::Binary,
+            vec![(vec!["1"], 0), (vec!["2"], 0), (vec!["5"], 1)],
+            Some(2),
+        );
+        let row_key = |k: i64| vec![Value::from(k), Value::from("q")];
+        assert_eq!(0, part_desc.get_part_id(&row_key(1)).unwrap());
+        assert_eq!(0, part_desc.get_part_id(&row_key(2)).unwrap());
+        assert_eq!(1, part_desc.get_part_id(&row_key(5)).unwrap());
+        assert_eq!(2, part_desc.get_part_id(&row_key(3)).unwrap());
+
+        // range pruning
+        let ids = |start: Vec<Value>, end: Vec<Value>| {
+            part_desc.get_part_ids(&start, true, &end, true).unwrap()
+        };
+        assert_eq!(
+            vec![0, 2],
+            ids(
+                vec![Value::from(1i64), Value::get_min()],
+                vec![Value::from(4i64), Value::get_max()]
+            )
+        );
+        assert_eq!(
+            vec![0, 1, 2],
+            ids(
+                vec![Value::get_min(), Value::get_min()],
+                vec![Value::get_max(), Value::get_max()]
+            )
+        );
+        assert_eq!(
+            vec![1, 2],
+            ids(
+                vec![Value::from(5i64), Value::get_min()],
+                vec![Value::from(5i64), Value::get_max()]
+            )
+        );
+
+        // no default partition
+        let part_desc = list_part_desc(
+            ObjType::Int64,
+            CollationType::Binary,
+            vec![(vec!["1"], 0)],
+            None,
+        );
+        assert!(part_desc.get_part_id(&row_key(3)).is_err());
+    }
+
+    #[test]
+    fn test_list_part_varchar() {
+        let part_desc = list_part_desc(
+            ObjType::Varchar,
+            CollationType::UTF8MB4GeneralCi,
+            vec![
+                (vec!["hz"], 0),
+                (vec!["sh"], 0),
+                (vec!["bj"], 1),
+                (vec!["ae"], 1),
+            ],
+            None,
+        );
+        let row_key = |k: &str| vec![Value::from(k), Value::from(1i64)];
+        assert_eq!(0, part_desc.get_part_id(&row_key("HZ")).unwrap());
+        assert_eq!(0, part_desc.get_part_id(&row_key("sh")).unwrap());
+        assert_eq!(1, part_desc.get_part_id(&row_key("bj")).unwrap());
+        // PAD SPACE and accent insensitive
+        assert_eq!(1, part_desc.get_part_id(&row_key("bj  ")).unwrap());
+        assert_eq!(1, part_desc.get_part_id(&row_key("\u{e1}\u{c9}")).unwrap());
+        assert!(part_desc.get_part_id(&row_key(" bj")).is_err());
+        assert!(part_desc.get_part_id(&row_key("sz")).is_err());
+
+        let part_desc = list_part_desc(
+            ObjType::Varchar,
+            CollationType::UTF8MB4Bin,
+            vec![(vec!["hz"], 0), (vec!["bj"], 1)],
+            None,
+        );
+        assert_eq!(1, part_desc.get_part_id(&row_key("bj ")).unwrap());
+        assert!(part_desc.get_part_id(&row_key("BJ")).is_err());
+    }
+
+    fn hash_like_part_desc(
+        mut part_desc: ObPartDesc,
+        obj_type: ObjType,
+        part_column: &str,
+        row_key: &[&str],
+    ) -> ObPartDesc {
+        let column: Box<dyn ObColumn> = Box::new(ObSimpleColumn::new(
+            part_column.to_owned(),
+            0,
+            obj_type,
+            CollationType::UTF8MB4Bin,
+        ));
+        part_desc.set_part_columns(vec![column]);
+        part_desc.set_row_key_element(
+            row_key
+                .iter()
+                .enumerate()
+                .map(|(i, c)| (c.to_string(), i as i32))
+                .collect(),
+        );
+        part_desc.prepare().unwrap();
+        part_desc
+    }
+
+    fn key_part_desc(obj_type: ObjType, part_column: &str, row_key: &[&str]) -> ObPartDesc {
+        let mut desc = ObKeyPartDesc::new();
+        desc.set_part_func_type(PartFuncType::KeyV3);
+        desc.set_ordered_part_column_names(vec![part_column.to_owned()]);
+        desc.set_part_num(16);
+        hash_like_part_desc(ObPartDesc::Key(desc), obj_type, part_column, row_key)
+    }
+
+    #[test]
+    fn test_key_part_prefix_range() {
+        let part_desc = key_part_desc(ObjType::Int64, "k", &["k", "q"]);
+        let ids = |start: Vec<Value>, end: Vec<Value>| {
+            part_desc.get_part_ids(&start, true, &end, true).unwrap()
+        };
+        let part_id = part_desc
+            .get_part_id(&[Value::from(7i64), Value::from("q")])
+            .unwrap();
+        assert_eq!(
+            vec![part_id],
+            ids(
+                vec![Value::from(7i64), Value::get_min()],
+                vec![Value::from(7i64), Value::get_max()]
+            )
+        );
+        assert_eq!(
+            vec![part_id],
+            ids(
+                vec![Value::from(7i64), Value::from("a")],
+                vec![Value::from(7i64), Value::from("z")]
+            )
+        );
+        assert_eq!(
+            16,
+            ids(
+                vec![Value::from(7i64), Value::get_min()],
+                vec![Value::from(8i64), Value::get_max()]
+            )
+            .len()
+        );
+        assert_eq!(
+            16,
+            ids(
+                vec![Value::get_min(), Value::get_min()],
+                vec![Value::get_max(), Value::get_max()]
+            )
+            .len()
+        );
+
+        // the partition column is not the leading row key column
+        let part_desc = key_part_desc(ObjType::Int64, "k", &["a", "k"]);
+        let part_id = part_desc
+            .get_part_id(&[Value::from("a"), Value::from(7i64)])
+            .unwrap();
+        assert_eq!(
+            vec![part_id],
+            part_desc
+                .get_part_ids(
+                    &[Value::from("a"), Value::from(7i64)],
+                    true,
+                    &[Value::from("a"), Value::from(7i64)],
+                    true
+                )
+                .unwrap()
+        );
+        assert_eq!(
+            16,
+            part_desc
+                .get_part_ids(
+                    &[Value::from("a"), Value::get_min()],
+                    true,
+                    &[Value::from("a"), Value::get_max()],
+                    true
+                )
+                .unwrap()
+                .len()
+        );
+    }
+
+    #[test]
+    fn test_key_part_hbase_range() {
+        let part_desc = key_part_desc(ObjType::Varchar, "K", &["K", "Q", "T"]);
+        let row_key = |q: &str, t: i64| vec![Value::from("row1"), Value::from(q), Value::from(t)];
+        let part_id = part_desc.get_part_id(&row_key("q", 1)).unwrap();
+        assert_eq!(
+            part_id,
+            part_desc.get_part_id(&row_key("other", 2)).unwrap()
+        );
+        assert_eq!(
+            vec![part_id],
+            part_desc
+                .get_part_ids(
+                    &[Value::from("row1"), Value::get_min(), Value::get_min()],
+                    true,
+                    &[Value::from("row1"), Value::get_max(), Value::get_max()],
+                    true
+                )
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_hash_part_prefix_range() {
+        let mut desc = ObHashPartDesc::new();
+        desc.set_part_func_type(PartFuncType::Hash);
+        desc.set_ordered_part_column_names(vec!["k".to_owned()]);
+        desc.set_part_num(8);
+        desc.set_complete_works((0..8).collect());
+        let part_desc =
+            hash_like_part_desc(ObPartDesc::Hash(desc), ObjType::Int64, "k", &["k", "q"]);
+        let ids = |start: Vec<Value>, end: Vec<Value>| {
+            part_desc.get_part_ids(&start, true, &end, true).unwrap()
+        };
+        assert_eq!(
+            vec![5],
+            ids(
+                vec![Value::from(13i64), Value::get_min()],
+                vec![Value::from(13i64), Value::get_max()]
+            )
+        );
+        assert_eq!(
+            vec![1, 2],
+            ids(
+                vec![Value::from(1i64), Value::get_min()],
+                vec![Value::from(2i64), Value::get_max()]
+            )
+        );
+        assert_eq!(
+            (0..8).collect::<Vec<i64>>(),
+            ids(
+                vec![Value::get_min(), Value::get_min()],
+                vec![Value::get_max(), Value::get_max()]
+            )
+        );
+
+        let ids = |start: Vec<Value>, start_inclusive, end: Vec<Value>, end_inclusive| {
+            part_desc
+                .get_part_ids(&start, start_inclusive, &end, end_inclusive)
+                .unwrap()
+        };
+        let key = |k: i64| vec![Value::from(k)];
+        // empty ranges
+        assert!(ids(key(7), true, key(5), true).is_empty());
+        assert!(ids(key(5), false, key(6), false).is_empty());
+        assert!(ids(key(i64::MAX - 1), false, key(i64::MAX), false).is_empty());
+        assert_eq!(vec![0], ids(key(i64::MIN), true, key(i64::MIN + 1), false));
+        assert_eq!(vec![6], ids(key(5), false, key(7), false));
+        assert_eq!(
+            vec![6, 7],
+            ids(key(i64::MAX - 1), true, key(i64::MAX), true)
+        );
+        // the partition column isn't the last column of the bounds
+        assert_eq!(
+            vec![5],
+            ids(
+                vec![Value::from(5i64), Value::from("a")],
+                false,
+                vec![Value::from(5i64), Value::from("b")],
+                false
+            )
+        );
+        assert_eq!(
+            vec![4, 5],
+            ids(
+                vec![Value::from(4i64), Value::from("a")],
+                false,
+                vec![Value::from(5i64), Value::from("b")],
+                false
+            )
+        );
+    }
+
+    #[test]
+    fn test_range_part_ranges() {
+        let column: Box<dyn ObColumn> = Box::new(ObSimpleColumn::new(
+            "c1".to_owned(),
+            0,
+            ObjType::Int64,
+            CollationType::Binary,
+        ));
+        let bound = |v: i64| ObPartitionKey::new(vec![Comparable::Value(Value::from(v))]);
+        let mut desc = ObRangePartDesc::new();
+        desc.set_part_func_type(PartFuncType::RangeColumns);
+        desc.set_ordered_part_column_names(vec!["c1".to_owned()]);
+        desc.set_bounds(vec![
+            (bound(10), 0),
+            (bound(20), 1),
+            (ObPartitionKey::new(vec![Comparable::MaxValue]), 2),
+        ]);
+        let mut part_desc = ObPartDesc::Range(desc);
+        part_desc.set_part_columns(vec![column.clone()]);
+        part_desc.set_ordered_compare_columns(vec![column]);
+        part_desc.set_row_key_element(
+            [("c1".to_owned(), 0), ("c2".to_owned(), 1)]
+                .into_iter()
+                .collect(),
+        );
+        part_desc.prepare().unwrap();
+
+        let ranges = part_desc.get_part_ranges().unwrap();
+        assert_eq!(
+            vec![
+                (
+                    vec![Value::get_min(), Value::get_min()],
+                    vec![Value::from(10i64), Value::get_min()],
+                    0
+                ),
+                (
+                    vec![Value::from(10i64), Value::get_min()],
+                    vec![Value::from(20i64), Value::get_min()],
+                    1
+                ),
+                (
+                    vec![Value::from(20i64), Value::get_min()],
+                    vec![Value::get_max(), Value::get_max()],
+                    2
+                ),
+            ],
+            ranges
+        );
+        // every range is served by its own partition
+        for (start, _, part_id) in ranges {
+            assert_eq!(part_id, part_desc.get_part_id(&start).unwrap());
+        }
+
+        let part_desc = key_part_desc(ObjType::Int64, "k", &["k", "q"]);
+        assert!(part_desc.get_part_ranges().is_err());
+    }
+
+    // the server hashes the little endian bytes whatever the host is, the
+    // partitions it puts the keys in are checked against a real server by
+    // `test_key_partition_temporal`
+    #[test]
+    fn test_temporal_key_hash() {
+        let column: Box<dyn ObColumn> = Box::new(ObSimpleColumn::new(
+            "c1".to_owned(),
+            0,
+            ObjType::Date,
+            CollationType::Binary,
+        ));
+        let hash = |value: Value| {
+            ObKeyPartDesc::to_hashcode(&value, &column, 0, &PartFuncType::KeyV3).unwrap()
+        };
+        // 2023-09-01 is day 19601 (0x4c91) since 1970-01-01
+        let date = Value::from(NaiveDate::from_ymd_opt(2023, 9, 1).unwrap());
+        assert_eq!(murmur2::murmur64a(&[0x91, 0x4c, 0, 0], 0), hash(date));
+        // 2023 is stored as 123 years after 1900
+        let year = Value::year(2023).unwrap();
+        assert_eq!(murmur2::murmur64a(&[123], 0), hash(year));
+        let datetime = Value::from(
+            NaiveDate::from_ymd_opt(1970, 1, 1)
+                .unwrap()
+                .and_hms_micro_opt(0, 0, 1, 2)
+                .unwrap(),
+        );
+        assert_eq!(
+            murmur2::murmur64a(&[0x42, 0x42, 0x0f, 0, 0, 0, 0, 0], 0),
+            hash(datetime)
+        );
+        // routing the keys to partition 0 would misplace them
+        for value in [Value::from(1.5f64), Value::json("{}"), Value::from(true)] {
+            assert!(ObKeyPartDesc::to_hashcode(&value, &column, 0, &PartFuncType::KeyV3).is_err());
+        }
+    }
+
+    // the partitions the server puts the keys in are checked against a real
+    // server by `test_key_partition_collations`
+    #[test]
+    fn test_varchar_hash_collations() {
+        let hash = |s: &str, collation_type: CollationType| {
+            ObKeyPartDesc::varchar_hash(Value::from(s), &collation_type, 0, PartFuncType::KeyV3)
+        };
+        // gbk and gb18030 agree on the characters of gbk
+        assert_eq!(
+            hash("中文", CollationType::GBKBin).unwrap(),
+            hash("中文", CollationType::GB18030Bin).unwrap()
+        );
+        // but not with utf8mb4 on the bytes
+        assert_ne!(
+            hash("中文", CollationType::GBKBin).unwrap(),
+            hash("中文", CollationType::UTF8MB4Bin).unwrap()
+        );
+        assert_eq!(
+            hash("中文key", CollationType::GBKChineseCi).unwrap(),
+            hash("中文KEY ", CollationType::GBKChineseCi).unwrap()
+        );
+        assert_ne!(
+            hash("中文key", CollationType::GBKBin).unwrap(),
+            hash("中文KEY", CollationType::GBKBin).unwrap()
+        );
+        // not representable in gbk
+        assert!(hash("😀", CollationType::GBKBin).is_err());
+        assert!(hash("😀", CollationType::GB18030Bin).is_ok());
+        // refused rather than misrouted until the weights are ported
+        assert!(hash("key", CollationType::UTF8MB4UnicodeCi).is_err());
+        assert!(hash("中文", CollationType::GB18030ChineseCi).is_err());
+    }
 }
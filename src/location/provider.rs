@@ -0,0 +1,511 @@
+/*-
+ * #%L
+ * OBKV Table Client Framework
+ * %%
+ * Copyright (C) 2021 OceanBase
+ * %%
+ * OBKV Table Client Framework is licensed under Mulan PSL v2.
+ * You can use this software according to the terms and conditions of the
+ * Mulan PSL v2. You may obtain a copy of Mulan PSL v2 at:
+ *          http://license.coscl.org.cn/MulanPSL2
+ * THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ * KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ * NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ * See the Mulan PSL v2 for more details.
+ * #L%
+ */
+
+//! Providers of the routing metadata: the servers of the cluster, the
+//! partitioning of the tables and the leaders of their partitions.
+
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    time::Instant,
+};
+
+use super::{ObTableLocation, PartitionScheme, TableEntry, TableEntryKey};
+use crate::{
+    client::{
+        ocp::{ObOcpModelManager, OcpModel},
+        table_client::ServerRoster,
+        ClientConfig,
+    },
+    error::{CommonErrCode, Error::Common as CommonErr, Result},
+    location::ObServerAddr,
+    serde_obkv::value::{ObjType, Value},
+    util::HandyRwLock,
+};
+
+/// Source of the routing metadata of a client.
+///
+/// The client reads the sys tenant of the cluster by default, see
+/// [`Builder::location_provider`] for replacing it, e.g. with a
+/// [`StaticLocationProvider`].
+///
+/// [`Builder::location_provider`]: crate::Builder::location_provider
+pub trait LocationProvider: Send + Sync {
+    /// Load the active servers of the cluster, on init and on every refresh
+    /// of the metadata.
+    fn load_servers(&self) -> Result<Vec<ObServerAddr>>;
+
+    /// Load the active servers of the cluster on an init bounded by
+    /// `deadline`. Implementations doing IO should shorten their timeouts to
+    /// the deadline, the servers are loaded as on a refresh by default.
+    fn load_servers_until(&self, _deadline: Instant) -> Result<Vec<ObServerAddr>> {
+        self.load_servers()
+    }
+
+    /// Load the entry of a table: its partition info and the replicas of its
+    /// partitions, the leader first. See [`TableEntry::build`] for building
+    /// one.
+    fn load_table_entry(&self, key: &TableEntryKey) -> Result<TableEntry>;
+
+    /// Reload the leaders of the partitions of a table whose partition info
+    /// is known, the whole entry is reloaded by default.
+    fn load_table_location(
+        &self,
+        key: &TableEntryKey,
+        _table_entry: &TableEntry,
+    ) -> Result<TableEntry> {
+        self.load_table_entry(key)
+    }
+}
+
+/// Shared [`LocationProvider`], providers are only equal to themselves.
+#[derive(Clone)]
+pub(crate) struct LocationProviderRef(pub Arc<dyn LocationProvider>);
+
+impl fmt::Debug for LocationProviderRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("LocationProvider")
+    }
+}
+
+impl PartialEq for LocationProviderRef {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for LocationProviderRef {}
+
+/// The default provider: the server list comes from the config server and the
+/// table entries from the sys tenant over MySQL.
+pub(crate) struct SqlLocationProvider {
+    location: Arc<ObTableLocation>,
+    server_roster: Arc<ServerRoster>,
+    ocp_manager: ObOcpModelManager,
+    ocp_model: RwLock<OcpModel>,
+    param_url: String,
+    datasource_name: String,
+    root_server_key: TableEntryKey,
+    config: ClientConfig,
+    loaded: AtomicBool,
+}
+
+impl SqlLocationProvider {
+    pub fn new(
+        location: Arc<ObTableLocation>,
+        server_roster: Arc<ServerRoster>,
+        param_url: &str,
+        cluster_name: &str,
+        tenant_name: &str,
+        config: ClientConfig,
+    ) -> Result<Self> {
+        let ocp_manager =
+            ObOcpModelManager::new(config.rslist_acquire_timeout, &config.ocp_model_cache_file)?;
+        Ok(SqlLocationProvider {
+            location,
+            server_roster,
+            ocp_manager,
+            ocp_model: RwLock::new(OcpModel::new()),
+            param_url: param_url.to_owned(),
+            datasource_name: "".to_owned(),
+            root_server_key: TableEntryKey::new_root_server_key(cluster_name, tenant_name),
+            config,
+            loaded: AtomicBool::new(false),
+        })
+    }
+
+    // Loads the servers, the config server and the sys tenant are given up
+    // at `deadline` if any.
+    fn load_servers_before(&self, deadline: Option<Instant>) -> Result<Vec<ObServerAddr>> {
+        let init = !self.loaded.load(Ordering::Acquire);
+        let new_ocp_model = self.ocp_manager.load_ocp_model(
+            &self.param_url,
+            &self.datasource_name,
+            self.config.rslist_acquire_try_times,
+            self.config.rslist_acquire_retry_interval,
+            init,
+            deadline,
+        )?;
+
+        if !init {
+            self.location
+                .invalidate_mysql_pools(&new_ocp_model.observer_addrs);
+        }
+
+        *self.ocp_model.wl() = new_ocp_model;
+
+        let root_server_entry = self.location.load_table_entry_randomly(
+            &self.ocp_model.rl().observer_addrs,
+            &self.root_server_key,
+            self.config.table_entry_acquire_connect_timeout,
+            self.config.table_entry_acquire_read_timeout,
+            deadline,
+        )?;
+        self.loaded.store(true, Ordering::Release);
+
+        let mut servers = vec![];
+        for replica_location in root_server_entry.table_location().replica_locations() {
+            let info = replica_location.info();
+            if !info.is_active() {
+                warn!("SqlLocationProvider::load_servers skip location {:?}, because it's status is {:?} and stop time is {}",
+                      replica_location.addr(), info.status(), info.stop_time());
+                continue;
+            }
+            servers.push(replica_location.addr().clone());
+        }
+        Ok(servers)
+    }
+}
+
+impl LocationProvider for SqlLocationProvider {
+    fn load_servers(&self) -> Result<Vec<ObServerAddr>> {
+        self.load_servers_before(None)
+    }
+
+    fn load_servers_until(&self, deadline: Instant) -> Result<Vec<ObServerAddr>> {
+        self.load_servers_before(Some(deadline))
+    }
+
+    fn load_table_entry(&self, key: &TableEntryKey) -> Result<TableEntry> {
+        self.location.load_table_entry_with_priority(
+            &self.server_roster,
+            key,
+            self.config.table_entry_acquire_connect_timeout,
+            self.config.table_entry_acquire_read_timeout,
+            self.config.server_address_priority_timeout,
+        )
+    }
+
+    fn load_table_location(
+        &self,
+        key: &TableEntryKey,
+        table_entry: &TableEntry,
+    ) -> Result<TableEntry> {
+        self.location.load_table_location_with_priority(
+            &self.server_roster,
+            key,
+            table_entry,
+            self.config.table_entry_acquire_connect_timeout,
+            self.config.table_entry_acquire_read_timeout,
+            self.config.server_address_priority_timeout,
+        )
+    }
+}
+
+/// Provider of a client sending every request to a single endpoint, an ODP or
+/// an observer, which routes the requests itself. Table locations are unknown
+/// to the client in this mode.
+pub(crate) struct OdpLocationProvider {
+    addr: ObServerAddr,
+}
+
+impl OdpLocationProvider {
+    pub fn new(addr: ObServerAddr) -> Self {
+        OdpLocationProvider { addr }
+    }
+}
+
+impl LocationProvider for OdpLocationProvider {
+    fn load_servers(&self) -> Result<Vec<ObServerAddr>> {
+        Ok(vec![self.addr.clone()])
+    }
+
+    fn load_table_entry(&self, key: &TableEntryKey) -> Result<TableEntry> {
+        Err(CommonErr(
+            CommonErrCode::InvalidParam,
+            format!(
+                "OdpLocationProvider::load_table_entry table locations are not available in odp mode, table:{}",
+                key.table_name()
+            ),
+        ))
+    }
+}
+
+/// A table of a [`StaticLocationProvider`].
+#[derive(Clone, Debug)]
+pub struct StaticTable {
+    table_id: i64,
+    row_key: Vec<(String, ObjType)>,
+    scheme: Option<PartitionScheme>,
+    partitions: Vec<(i64, Vec<ObServerAddr>)>,
+}
+
+impl StaticTable {
+    /// A non-partitioned table with the row key columns `row_key`.
+    pub fn new(table_id: i64, row_key: &[(&str, ObjType)]) -> Self {
+        StaticTable {
+            table_id,
+            row_key: row_key
+                .iter()
+                .map(|(column, obj_type)| (column.to_string(), obj_type.clone()))
+                .collect(),
+            scheme: None,
+            partitions: vec![],
+        }
+    }
+
+    /// Partition the table by `scheme`.
+    pub fn partition(mut self, scheme: PartitionScheme) -> Self {
+        self.scheme = Some(scheme);
+        self
+    }
+
+    /// Place the next partition on `servers`, the first one being its leader.
+    /// `tablet_id` identifies the partition on 4.x servers and is ignored
+    /// otherwise.
+    pub fn replicas(mut self, tablet_id: i64, servers: Vec<ObServerAddr>) -> Self {
+        self.partitions.push((tablet_id, servers));
+        self
+    }
+}
+
+/// A [`LocationProvider`] of a fixed topology, declared in code or loaded
+/// from a JSON file.
+///
+/// The file holds `servers`, a list of `"ip:rpc_port"`, and `tables`, each
+/// with a `name`, a `table_id` and the `row_key` columns as `{"name", "type"}`
+/// objects, the types being named after [`ObjType`]. A partitioned table adds
+/// a `partition` object, `{"type": "hash", "column", "part_num"}`,
+/// `{"type": "key", "columns", "part_num"}` or `{"type": "range", "column",
+/// "bounds"}` with numbers, strings or `"MAXVALUE"` as bounds, and lists its
+/// `partitions` in order as `{"tablet_id", "servers"}`, the leader first.
+#[derive(Clone, Debug, Default)]
+pub struct StaticLocationProvider {
+    servers: Vec<ObServerAddr>,
+    tables: HashMap<String, StaticTable>,
+}
+
+impl StaticLocationProvider {
+    pub fn new(servers: Vec<ObServerAddr>) -> Self {
+        StaticLocationProvider {
+            servers,
+            tables: HashMap::new(),
+        }
+    }
+
+    pub fn table(mut self, name: &str, table: StaticTable) -> Self {
+        self.tables.insert(name.to_owned(), table);
+        self
+    }
+
+    pub fn from_file(path: &str) -> Result<Self> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        let file: LocationFile = serde_json::from_str(json)?;
+        let mut provider = StaticLocationProvider::new(parse_addrs(&file.servers));
+        for table in file.tables {
+            let row_key = table
+                .row_key
+                .iter()
+                .map(|c| Ok((c.name.as_str(), parse_obj_type(&c.obj_type)?)))
+                .collect::<Result<Vec<_>>>()?;
+            let mut static_table = StaticTable::new(table.table_id, &row_key);
+            if let Some(partition) = table.partition {
+                static_table = static_table.partition(partition.into_scheme()?);
+            }
+            for partition in table.partitions {
+                static_table =
+                    static_table.replicas(partition.tablet_id, parse_addrs(&partition.servers));
+            }
+            provider = provider.table(&table.name, static_table);
+        }
+        Ok(provider)
+    }
+}
+
+impl LocationProvider for StaticLocationProvider {
+    fn load_servers(&self) -> Result<Vec<ObServerAddr>> {
+        Ok(self.servers.clone())
+    }
+
+    fn load_table_entry(&self, key: &TableEntryKey) -> Result<TableEntry> {
+        let table = self.tables.get(key.table_name()).ok_or_else(|| {
+            CommonErr(
+                CommonErrCode::NotFound,
+                format!(
+                    "StaticLocationProvider::load_table_entry unknown table {}",
+                    key.table_name()
+                ),
+            )
+        })?;
+        let partitions = if table.partitions.is_empty() {
+            // a non-partitioned table led by the first server by default
+            vec![(0, self.servers.clone())]
+        } else {
+            table.partitions.clone()
+        };
+        TableEntry::build(
+            key,
+            table.table_id,
+            &table.row_key,
+            table.scheme.as_ref(),
+            &partitions,
+        )
+    }
+}
+
+#[derive(Deserialize)]
+struct LocationFile {
+    servers: Vec<String>,
+    #[serde(default)]
+    tables: Vec<TableFile>,
+}
+
+#[derive(Deserialize)]
+struct TableFile {
+    name: String,
+    table_id: i64,
+    row_key: Vec<ColumnFile>,
+    #[serde(default)]
+    partition: Option<PartitionFile>,
+    #[serde(default)]
+    partitions: Vec<ReplicasFile>,
+}
+
+#[derive(Deserialize)]
+struct ColumnFile {
+    name: String,
+    #[serde(rename = "type")]
+    obj_type: String,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum PartitionFile {
+    Hash {
+        column: String,
+        part_num: i32,
+    },
+    Key {
+        columns: Vec<String>,
+        part_num: i32,
+    },
+    Range {
+        column: String,
+        bounds: Vec<serde_json::Value>,
+    },
+}
+
+impl PartitionFile {
+    fn into_scheme(self) -> Result<PartitionScheme> {
+        Ok(match self {
+            PartitionFile::Hash { column, part_num } => PartitionScheme::Hash { column, part_num },
+            PartitionFile::Key { columns, part_num } => PartitionScheme::Key { columns, part_num },
+            PartitionFile::Range { column, bounds } => PartitionScheme::Range {
+                column,
+                bounds: bounds.iter().map(parse_bound).collect::<Result<Vec<_>>>()?,
+            },
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct ReplicasFile {
+    #[serde(default)]
+    tablet_id: i64,
+    servers: Vec<String>,
+}
+
+fn parse_addrs(addrs: &[String]) -> Vec<ObServerAddr> {
+    addrs
+        .iter()
+        .map(|addr| {
+            let mut server = ObServerAddr::new();
+            server.address(addr.to_owned());
+            server
+        })
+        .collect()
+}
+
+fn parse_obj_type(name: &str) -> Result<ObjType> {
+    (0..=u8::MAX)
+        .filter_map(|v| ObjType::from_u8(v).ok())
+        .find(|t| format!("{t:?}").eq_ignore_ascii_case(name))
+        .ok_or_else(|| {
+            CommonErr(
+                CommonErrCode::InvalidParam,
+                format!("StaticLocationProvider unknown column type {name}"),
+            )
+        })
+}
+
+fn parse_bound(bound: &serde_json::Value) -> Result<Value> {
+    match bound {
+        serde_json::Value::String(s) if s.eq_ignore_ascii_case("MAXVALUE") => Ok(Value::get_max()),
+        serde_json::Value::String(s) => Ok(Value::from(s.to_owned())),
+        serde_json::Value::Number(n) if n.is_i64() => Ok(Value::from(n.as_i64().unwrap())),
+        _ => Err(CommonErr(
+            CommonErrCode::InvalidParam,
+            format!("StaticLocationProvider invalid range bound {bound}"),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_static_provider_from_json() {
+        let provider = StaticLocationProvider::from_json(
+            r#"{
+                "servers": ["127.0.0.1:2882", "127.0.0.2:2882"],
+                "tables": [
+                    {"name": "t1", "table_id": 500001,
+                     "row_key": [{"name": "c1", "type": "varchar"}]},
+                    {"name": "t2", "table_id": 500002,
+                     "row_key": [{"name": "c1", "type": "Int64"}],
+                     "partition": {"type": "range", "column": "c1", "bounds": [10, "MAXVALUE"]},
+                     "partitions": [
+                        {"tablet_id": 1, "servers": ["127.0.0.2:2882"]},
+                        {"tablet_id": 2, "servers": ["127.0.0.1:2882"]}
+                     ]}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let servers = provider.load_servers().unwrap();
+        assert_eq!(2, servers.len());
+        assert_eq!("127.0.0.2", servers[1].ip());
+        assert_eq!(2882, servers[1].svr_port());
+
+        let key = TableEntryKey::new("cluster", "tenant", "test", "t1");
+        let entry = provider.load_table_entry(&key).unwrap();
+        assert_eq!(500001, entry.table_id());
+        assert!(!entry.is_partition_table());
+
+        let key = TableEntryKey::new("cluster", "tenant", "test", "t2");
+        let entry = provider.load_table_entry(&key).unwrap();
+        assert!(entry.is_partition_table());
+        assert!(entry.partition_info().is_some());
+
+        let key = TableEntryKey::new("cluster", "tenant", "test", "t3");
+        assert!(provider.load_table_entry(&key).is_err());
+        assert!(StaticLocationProvider::from_json(
+            r#"{"servers": [], "tables": [{"name": "t", "table_id": 1,
+                "row_key": [{"name": "c1", "type": "nope"}]}]}"#
+        )
+        .is_err());
+    }
+}
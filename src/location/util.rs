@@ -22,13 +22,13 @@ use std::{
     u8,
 };
 
-use chrono::Utc;
+use chrono::{FixedOffset, Utc};
 use mysql as my;
 use mysql::{prelude::Queryable, PooledConn, Row};
 
 use super::{
-    ob_part_constants, part_func_type::PartFuncType, ObHashPartDesc, ObKeyPartDesc, ObPartDesc,
-    ObPartitionInfo, ObPartitionLevel, ObRangePartDesc, TableEntry,
+    ob_part_constants, part_func_type::PartFuncType, ObHashPartDesc, ObKeyPartDesc, ObListPartDesc,
+    ObPartDesc, ObPartitionInfo, ObPartitionLevel, ObRangePartDesc, PartitionScheme, TableEntry,
 };
 use crate::{
     constant::ALL_DUMMY_TABLE,
@@ -42,7 +42,10 @@ use crate::{
         ob_partition_key::{Comparable, ObPartitionKey},
     },
     serde_obkv::value::{CollationLevel, CollationType, ObjMeta, ObjType, Value},
-    util::obversion::{ob_vsn_major, parse_ob_vsn_from_sql},
+    util::{
+        obversion::{ob_vsn_major, parse_ob_vsn_from_sql},
+        timezone::{parse_time_zone, utc},
+    },
     ResultCodes,
 };
 
@@ -71,6 +74,21 @@ impl LocationUtil {
         Ok(())
     }
 
+    /// Loads the time zone of the server from the `time_zone` variable of the
+    /// session, UTC for the named time zones.
+    pub fn get_time_zone_from_server(conn: &mut my::PooledConn) -> Result<FixedOffset> {
+        let time_zone: Option<String> = conn.query_first("SELECT @@time_zone")?;
+        let offset = time_zone.as_deref().and_then(parse_time_zone);
+        if offset.is_none() {
+            warn!(
+                "LocationUtil::get_time_zone_from_server timestamps are parsed in UTC, \
+                 time_zone:{:?}, set ClientConfig::server_time_zone instead",
+                time_zone
+            );
+        }
+        Ok(offset.unwrap_or_else(utc))
+    }
+
     pub fn generate_table_location_from_remote_sqlv3(
         key: &TableEntryKey,
         table_entry: &TableEntry,
@@ -328,9 +346,12 @@ impl LocationUtil {
         })
     }
 
+    /// Loads the table entry of `key`, the partition values of which are
+    /// parsed in `time_zone`.
     pub fn get_table_entry_from_remote_inner(
         conn: &mut PooledConn,
         key: &TableEntryKey,
+        time_zone: FixedOffset,
     ) -> Result<TableEntry> {
         let sql: String = if ob_vsn_major() >= 4 {
             // generate SQL for OB Server 4.x
@@ -386,7 +407,7 @@ impl LocationUtil {
         // fetch partition info
         if table_entry.is_partition_table() {
             // fetch partition info
-            match LocationUtil::fetch_partition_info(conn, &table_entry) {
+            match LocationUtil::fetch_partition_info(conn, &table_entry, time_zone) {
                 Ok(v) => table_entry.partition_info = Some(v),
                 Err(e) => {
                     table_entry.partition_info = None;
@@ -522,9 +543,204 @@ impl LocationUtil {
         })
     }
 
+    /// Build a table entry from a declared partitioning, `partitions` lists
+    /// the tablet id and the servers of every partition in partition order,
+    /// the first server being the leader. The single partition of a table
+    /// without `scheme` is keyed by 0.
+    pub fn build_table_entry(
+        key: &TableEntryKey,
+        table_id: i64,
+        row_key: &[(String, ObjType)],
+        scheme: Option<&PartitionScheme>,
+        partitions: &[(i64, Vec<ObServerAddr>)],
+    ) -> Result<TableEntry> {
+        let part_num = scheme.map_or(1, |scheme| scheme.part_num());
+        if part_num as usize != partitions.len() || partitions.iter().any(|(_, s)| s.is_empty()) {
+            return Err(CommonErr(
+                CommonErrCode::InvalidParam,
+                format!(
+                    "LocationUtil::build_table_entry expect {part_num} partitions with servers, table:{}",
+                    key.table_name
+                ),
+            ));
+        }
+
+        let row_key_element: HashMap<String, i32> = row_key
+            .iter()
+            .enumerate()
+            .map(|(i, (name, _))| (name.clone(), i as i32))
+            .collect();
+        let partition_info = match scheme {
+            None => None,
+            Some(scheme) => Some(LocationUtil::build_partition_info(
+                row_key, scheme, partitions,
+            )?),
+        };
+
+        let mut replica_locations = Vec::new();
+        let mut partition_location = HashMap::new();
+        for (idx, (tablet_id, servers)) in partitions.iter().enumerate() {
+            let part_id = match scheme {
+                None => 0,
+                Some(_) if ob_vsn_major() >= 4 => *tablet_id,
+                Some(_) => idx as i64,
+            };
+            let mut location = ObPartitionLocation {
+                leader: None,
+                followers: Vec::new(),
+            };
+            for (i, addr) in servers.iter().enumerate() {
+                let role = if i == 0 {
+                    ObServerRole::Leader
+                } else {
+                    ObServerRole::Follower
+                };
+                let replica = ReplicaLocation::new(addr.clone(), role);
+                if !replica_locations
+                    .iter()
+                    .any(|r: &ReplicaLocation| r.addr == replica.addr)
+                {
+                    replica_locations.push(replica.clone());
+                }
+                if i == 0 {
+                    location.leader = Some(replica);
+                } else {
+                    location.followers.push(replica);
+                }
+            }
+            partition_location.insert(part_id, location);
+        }
+
+        let mut table_entry = TableEntry {
+            table_id,
+            partition_num: part_num as i64,
+            replica_num: partitions[0].1.len() as i64,
+            table_entry_key: key.clone(),
+            refresh_time_mills: Arc::new(AtomicUsize::new(Utc::now().timestamp_millis() as usize)),
+            partition_info,
+            table_location: TableLocation { replica_locations },
+            partition_entry: Some(ObPartitionEntry {
+                parititon_location: partition_location,
+            }),
+            row_key_element: HashMap::new(),
+        };
+        table_entry.set_row_key_element(row_key_element);
+        table_entry.prepare()?;
+        Ok(table_entry)
+    }
+
+    fn build_partition_info(
+        row_key: &[(String, ObjType)],
+        scheme: &PartitionScheme,
+        partitions: &[(i64, Vec<ObServerAddr>)],
+    ) -> Result<ObPartitionInfo> {
+        let column_names = match scheme {
+            PartitionScheme::Hash { column, .. } | PartitionScheme::Range { column, .. } => {
+                vec![column.clone()]
+            }
+            PartitionScheme::Key { columns, .. } => columns.clone(),
+        };
+        let mut part_columns: Vec<Box<dyn ObColumn>> = Vec::with_capacity(column_names.len());
+        for (idx, name) in column_names.iter().enumerate() {
+            let obj_type = match row_key.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)) {
+                Some((_, obj_type)) => obj_type.clone(),
+                None => {
+                    return Err(CommonErr(
+                        CommonErrCode::InvalidParam,
+                        format!("LocationUtil::build_partition_info partition column {name} is not a row key column"),
+                    ));
+                }
+            };
+            let collation_type = match obj_type {
+                ObjType::Varchar | ObjType::Char => CollationType::UTF8MB4GeneralCi,
+                _ => CollationType::Binary,
+            };
+            part_columns.push(Box::new(ObSimpleColumn::new(
+                name.clone(),
+                idx as i32,
+                obj_type,
+                collation_type,
+            )));
+        }
+
+        let part_desc = match scheme {
+            PartitionScheme::Hash { column, part_num } => {
+                let mut hash_desc = ObHashPartDesc::new();
+                hash_desc.set_part_func_type(PartFuncType::Hash);
+                hash_desc.set_part_expr(column.clone());
+                hash_desc.set_ordered_part_column_names(column_names.clone());
+                hash_desc.set_part_num(*part_num);
+                hash_desc.set_complete_works((0..*part_num as i64).collect());
+                hash_desc.set_part_space(0);
+                ObPartDesc::Hash(hash_desc)
+            }
+            PartitionScheme::Key { columns, part_num } => {
+                let mut key_part_desc = ObKeyPartDesc::new();
+                key_part_desc.set_part_func_type(PartFuncType::KeyV3);
+                key_part_desc.set_part_expr(columns.join(","));
+                key_part_desc.set_ordered_part_column_names(column_names.clone());
+                key_part_desc.set_part_num(*part_num);
+                key_part_desc.set_part_space(0);
+                ObPartDesc::Key(key_part_desc)
+            }
+            PartitionScheme::Range { column, bounds } => {
+                let mut range_bounds = Vec::with_capacity(bounds.len());
+                for (idx, bound) in bounds.iter().enumerate() {
+                    let element = if bound.is_max() {
+                        Comparable::MaxValue
+                    } else {
+                        Comparable::Value(part_columns[0].eval_value(&[bound.clone()], utc())?)
+                    };
+                    range_bounds.push((ObPartitionKey::new(vec![element]), idx as i64));
+                }
+                let mut range_desc = ObRangePartDesc::new();
+                range_desc.set_part_func_type(PartFuncType::RangeColumns);
+                range_desc.set_part_expr(column.clone());
+                range_desc.set_ordered_part_column_names(column_names.clone());
+                range_desc
+                    .set_ordered_compare_column_types(vec![part_columns[0].get_ob_obj_type()]);
+                range_desc.set_bounds(range_bounds);
+                ObPartDesc::Range(range_desc)
+            }
+        };
+
+        let mut info = ObPartitionInfo::new();
+        info.level = ObPartitionLevel::One;
+        info.first_part_desc = Some(part_desc);
+        let ordered_part_columns = if matches!(scheme, PartitionScheme::Range { .. }) {
+            part_columns.clone()
+        } else {
+            Vec::new()
+        };
+        LocationUtil::set_part_desc_property(
+            &mut info.first_part_desc,
+            &part_columns,
+            &ordered_part_columns,
+        )?;
+        info.part_columns = part_columns;
+        if ob_vsn_major() >= 4 {
+            info.set_tablet_id_map(
+                partitions
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, (tablet_id, _))| (idx as i64, *tablet_id))
+                    .collect(),
+            );
+        } else {
+            let part_name_id_map =
+                LocationUtil::build_default_part_name_id_map(partitions.len() as i32);
+            if let Some(ref mut desc) = info.first_part_desc {
+                desc.set_part_name_id_map(part_name_id_map.clone());
+            }
+            info.part_name_id_map = part_name_id_map;
+        }
+        Ok(info)
+    }
+
     pub fn fetch_partition_info(
         conn: &mut PooledConn,
         table_entry: &TableEntry,
+        time_zone: FixedOffset,
     ) -> Result<ObPartitionInfo> {
         let sql: String = if ob_vsn_major() >= 4 {
             format!("SELECT /*+READ_CONSISTENCY(WEAK)*/ part_level, part_num, part_type, part_space, part_expr,
@@ -630,6 +846,21 @@ impl LocationUtil {
             }
         }
 
+        LocationUtil::set_ordered_part_columns(&mut info)?;
+        for part_desc in [&mut info.first_part_desc, &mut info.sub_part_desc]
+            .into_iter()
+            .flatten()
+        {
+            part_desc.set_time_zone(time_zone);
+        }
+
+        Ok(info)
+    }
+
+    /// Set the part columns of both levels, and the ordered compare columns
+    /// of the list and range levels. The sub part has its own compare columns
+    /// whatever the first part is, e.g. hash first part with range sub part.
+    fn set_ordered_part_columns(info: &mut ObPartitionInfo) -> Result<()> {
         // get list partition column types here
         let mut first_ordered_parted_columns = Vec::new();
         match &info.first_part_desc {
@@ -642,16 +873,12 @@ impl LocationUtil {
             }
         }
         let mut sub_ordered_parted_columns = Vec::new();
-        if let Some(first_part_desc) = &info.first_part_desc {
-            match &info.sub_part_desc {
-                None => (),
-                Some(sub_part_desc) => {
-                    if sub_part_desc.is_list_part() || first_part_desc.is_range_part() {
-                        sub_ordered_parted_columns = LocationUtil::get_ordered_part_columns(
-                            &info.part_columns,
-                            sub_part_desc,
-                        );
-                    }
+        match &info.sub_part_desc {
+            None => (),
+            Some(sub_part_desc) => {
+                if sub_part_desc.is_list_part() || sub_part_desc.is_range_part() {
+                    sub_ordered_parted_columns =
+                        LocationUtil::get_ordered_part_columns(&info.part_columns, sub_part_desc);
                 }
             }
         }
@@ -667,8 +894,7 @@ impl LocationUtil {
             &info.part_columns,
             &sub_ordered_parted_columns,
         )?;
-
-        Ok(info)
+        Ok(())
     }
 
     fn build_part_desc(level: ObPartitionLevel, mut row: my::Row) -> Result<Option<ObPartDesc>> {
@@ -698,6 +924,15 @@ impl LocationUtil {
             }
             range_desc.set_ordered_compare_column_types(types);
             return Ok(Some(ObPartDesc::Range(range_desc)));
+        } else if part_type.is_list_part() {
+            let mut list_desc = ObListPartDesc::new();
+            list_desc.set_part_func_type(part_type);
+            list_desc.set_part_expr(part_expr.to_string());
+            list_desc.set_ordered_part_column_names(
+                part_expr.split(',').map(|s| s.to_string()).collect(),
+            );
+            list_desc.set_part_num(row.take(&*format!("{part_level_prefix}part_num")).unwrap());
+            return Ok(Some(ObPartDesc::List(list_desc)));
         } else if part_type.is_hash_part() {
             let mut hash_desc = ObHashPartDesc::new();
             hash_desc.set_part_func_type(part_type);
@@ -754,7 +989,7 @@ impl LocationUtil {
         Ok(None)
     }
 
-    fn get_ordered_part_columns(
+    pub fn get_ordered_part_columns(
         partition_key_columns: &[Box<dyn ObColumn>],
         part_desc: &ObPartDesc,
     ) -> Vec<Box<dyn ObColumn>> {
@@ -769,7 +1004,7 @@ impl LocationUtil {
         columns
     }
 
-    fn set_part_desc_property(
+    pub fn set_part_desc_property(
         part_desc: &mut Option<ObPartDesc>,
         part_columns: &[Box<dyn ObColumn>],
         list_part_columns: &[Box<dyn ObColumn>],
@@ -784,11 +1019,7 @@ impl LocationUtil {
                         "LocationUtil::set_part_desc_property key part desc is empty".to_owned(),
                     ));
                 }
-            } else if v.is_list_part() {
-                // TODO: impl list part
-                unimplemented!();
-            // v.set_ordered_compare_columns((*part_columns).clone());
-            } else if v.is_range_part() {
+            } else if v.is_list_part() || v.is_range_part() {
                 v.set_ordered_compare_columns(list_part_columns.to_vec());
             }
         }
@@ -826,7 +1057,6 @@ impl LocationUtil {
         part_name_map
     }
 
-    // TODO: impl list part
     pub fn fetch_first_part(
         conn: &mut my::PooledConn,
         table_entry: &mut TableEntry,
@@ -865,14 +1095,8 @@ impl LocationUtil {
                 },
             }
         } else if ob_part_func_type.is_list_part() {
-            // TODO: impl list part
-            unimplemented!();
-            //            conn.query(sql).map(|result| {
-            //                result
-            //                    .map(|x| x.unwrap())
-            //                    .map(|mut row| {})
-            //                    .collect()
-            //            })?;
+            // in java sdk is parseFirstPartList
+            LocationUtil::parse_list_part(conn, sql, table_entry, false)?;
         } else if ob_vsn_major() >= 4
             && (ob_part_func_type.is_hash_part() || ob_part_func_type.is_key_part())
         {
@@ -910,7 +1134,6 @@ impl LocationUtil {
         Ok(())
     }
 
-    // TODO: impl list part
     pub fn fetch_sub_part(
         conn: &mut my::PooledConn,
         table_entry: &mut TableEntry,
@@ -952,14 +1175,8 @@ impl LocationUtil {
                 },
             }
         } else if sub_part_func_type.is_list_part() {
-            // TODO: impl list part
-            unimplemented!();
-            //            conn.query(sql).map(|result| {
-            //                result
-            //                    .map(|x| x.unwrap())
-            //                    .map(|mut row| {})
-            //                    .collect()
-            //            })?;
+            // in java sdk is parseSubPartList
+            LocationUtil::parse_list_part(conn, sql, table_entry, true)?;
         } else if ob_vsn_major() >= 4
             && (sub_part_func_type.is_hash_part() || sub_part_func_type.is_key_part())
         {
@@ -1069,16 +1286,17 @@ impl LocationUtil {
         let mut part_tablet_id_map: HashMap<i64, i64> = HashMap::new();
         let mut idx: i64 = 0;
 
-        if is_sub_part {
-            match &info.sub_part_desc {
-                Some(ObPartDesc::Range(v)) => order_part_columns = v.get_ordered_compare_column(),
-                _ => error!("LocationUtil::parse_range_part never be here"),
-            }
+        let part_desc = if is_sub_part {
+            &info.sub_part_desc
         } else {
-            match &info.first_part_desc {
-                Some(ObPartDesc::Range(v)) => order_part_columns = v.get_ordered_compare_column(),
-                _ => error!("LocationUtil::parse_range_part never be here"),
-            }
+            &info.first_part_desc
+        };
+        let time_zone = part_desc
+            .as_ref()
+            .map_or_else(utc, ObPartDesc::get_time_zone);
+        match part_desc {
+            Some(ObPartDesc::Range(v)) => order_part_columns = v.get_ordered_compare_column(),
+            _ => error!("LocationUtil::parse_range_part never be here"),
         }
 
         for mut row in conn.query::<Row, String>(sql)? {
@@ -1107,15 +1325,18 @@ impl LocationUtil {
                 } else {
                     part_elements.push(Comparable::Value(
                         order_part_columns[i]
-                            .eval_value(&[Value::String(
-                                element_str,
-                                ObjMeta::new(
-                                    order_part_columns[i].get_ob_obj_type().clone(),
-                                    CollationLevel::Numeric,
-                                    order_part_columns[i].get_ob_collation_type().clone(),
-                                    10,
-                                ),
-                            )])
+                            .eval_value(
+                                &[Value::String(
+                                    element_str,
+                                    ObjMeta::new(
+                                        order_part_columns[i].get_ob_obj_type().clone(),
+                                        CollationLevel::Numeric,
+                                        order_part_columns[i].get_ob_collation_type().clone(),
+                                        10,
+                                    ),
+                                )],
+                                time_zone,
+                            )
                             .unwrap(),
                     ));
                 }
@@ -1183,6 +1404,191 @@ impl LocationUtil {
         Ok(bounds)
     }
 
+    /*
+     * parse_list_part parse the values of every list partition from sql result
+     * and set them into the list part desc
+     */
+    fn parse_list_part(
+        conn: &mut my::PooledConn,
+        sql: String,
+        table_entry: &mut TableEntry,
+        is_sub_part: bool,
+    ) -> Result<()> {
+        let part_id_column_name = if is_sub_part {
+            "sub_part_id"
+        } else {
+            "part_id"
+        };
+
+        // get partition info
+        let info = match table_entry.partition_info {
+            None => {
+                error!("LocationUtil::parse_list_part partition_info is None");
+                return Err(CommonErr(
+                    CommonErrCode::PartitionError,
+                    "LocationUtil::parse_list_part partition_info is None".to_owned(),
+                ));
+            }
+            Some(ref mut info) => info,
+        };
+
+        let part_desc = if is_sub_part {
+            &mut info.sub_part_desc
+        } else {
+            &mut info.first_part_desc
+        };
+        let time_zone = part_desc
+            .as_ref()
+            .map_or_else(utc, ObPartDesc::get_time_zone);
+        let list_desc = match part_desc {
+            Some(ObPartDesc::List(v)) => v,
+            _ => {
+                error!("LocationUtil::parse_list_part part desc is not list part");
+                return Err(CommonErr(
+                    CommonErrCode::PartitionError,
+                    "LocationUtil::parse_list_part part desc is not list part".to_owned(),
+                ));
+            }
+        };
+        let order_part_columns = list_desc.get_ordered_compare_column().clone();
+
+        let mut sets: Vec<(Vec<Value>, i64)> = Vec::new();
+        let mut default_part_id = None;
+        let mut part_name_id_map: HashMap<String, i64> = HashMap::new();
+        let mut part_tablet_id_map: HashMap<i64, i64> = HashMap::new();
+        let mut idx: i64 = 0;
+
+        for mut row in conn.query::<Row, String>(sql)? {
+            let part_id = if ob_vsn_major() >= 4 {
+                let tablet_id: i64 = row.take("tablet_id").unwrap();
+                part_tablet_id_map.insert(idx, tablet_id);
+                idx += 1;
+                idx - 1
+            } else {
+                let part_id: i64 = row.take(part_id_column_name).unwrap();
+                let part_name: String = row.take("part_name").unwrap();
+                part_name_id_map.insert(part_name.to_lowercase(), part_id);
+                part_id
+            };
+
+            let high_bound_val: String = row.take("high_bound_val").unwrap();
+            if high_bound_val.trim().eq_ignore_ascii_case("DEFAULT") {
+                default_part_id = Some(part_id);
+                continue;
+            }
+            for set in LocationUtil::split_list_values(&high_bound_val)? {
+                if set.len() != order_part_columns.len() {
+                    error!(
+                        "LocationUtil::parse_list_part list value {:?} mismatch partition columns {:?}",
+                        set, order_part_columns
+                    );
+                    return Err(CommonErr(
+                        CommonErrCode::PartitionError,
+                        format!(
+                            "LocationUtil::parse_list_part list value {set:?} mismatch partition columns {order_part_columns:?}",
+                        ),
+                    ));
+                }
+                let mut values = Vec::with_capacity(set.len());
+                for (element, column) in set.iter().zip(order_part_columns.iter()) {
+                    values.push(column.eval_value(
+                        &[Value::String(
+                            LocationUtil::get_plain_string(element),
+                            ObjMeta::new(
+                                column.get_ob_obj_type(),
+                                CollationLevel::Numeric,
+                                column.get_ob_collation_type().clone(),
+                                10,
+                            ),
+                        )],
+                        time_zone,
+                    )?);
+                }
+                sets.push((values, part_id));
+            }
+        }
+
+        list_desc.set_sets(sets);
+        list_desc.set_default_part_id(default_part_id);
+        if ob_vsn_major() >= 4 {
+            if list_desc.get_part_num() == 0 {
+                list_desc.set_part_num(idx as i32);
+            }
+            info.set_tablet_id_map(part_tablet_id_map);
+        } else {
+            list_desc.set_part_num(part_name_id_map.len() as i32);
+            list_desc.set_part_name_id_map(part_name_id_map);
+        }
+        Ok(())
+    }
+
+    /// Split list partition values into sets of column values, the values are
+    /// like `1,2,3`, `'a','b'` or `(1,'a'),(2,'b')` for list columns.
+    fn split_list_values(s: &str) -> Result<Vec<Vec<String>>> {
+        let mut sets = Vec::new();
+        let mut set = Vec::new();
+        let mut element = String::new();
+        let mut in_quote = false;
+        let mut escaped = false;
+        let mut in_paren = false;
+        for c in s.chars() {
+            if in_quote {
+                element.push(c);
+                if escaped {
+                    escaped = false;
+                } else if c == '\\' {
+                    escaped = true;
+                } else if c == '\'' {
+                    in_quote = false;
+                }
+                continue;
+            }
+            match c {
+                '\'' => {
+                    in_quote = true;
+                    element.push(c);
+                }
+                '(' if !in_paren => in_paren = true,
+                ')' if in_paren => {
+                    in_paren = false;
+                    set.push(element.trim().to_owned());
+                    element.clear();
+                }
+                ',' if in_paren => {
+                    set.push(element.trim().to_owned());
+                    element.clear();
+                }
+                ',' => {
+                    if !element.trim().is_empty() {
+                        set.push(element.trim().to_owned());
+                    }
+                    element.clear();
+                    if set.is_empty() {
+                        return Err(CommonErr(
+                            CommonErrCode::PartitionError,
+                            format!("LocationUtil::split_list_values invalid list values:{s}"),
+                        ));
+                    }
+                    sets.push(std::mem::take(&mut set));
+                }
+                _ => element.push(c),
+            }
+        }
+        if in_quote || in_paren {
+            return Err(CommonErr(
+                CommonErrCode::PartitionError,
+                format!("LocationUtil::split_list_values invalid list values:{s}"),
+            ));
+        }
+        if !element.trim().is_empty() {
+            set.push(element.trim().to_owned());
+        }
+        if !set.is_empty() {
+            sets.push(set);
+        }
+        Ok(sets)
+    }
+
     fn get_plain_string(s: &str) -> String {
         let start = if !s.is_empty() && s[..1] == '\''.to_string() {
             1
@@ -1212,3 +1618,60 @@ impl ObPartIdCalculator {
             + ob_part_constants::extract_subpart_idx(part_id)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_split_list_values() {
+        assert_eq!(
+            vec![vec!["1"], vec!["2"], vec!["3"]],
+            LocationUtil::split_list_values("1,2,3").unwrap()
+        );
+        assert_eq!(
+            vec![vec!["'a,b'"], vec!["'c'"]],
+            LocationUtil::split_list_values("'a,b', 'c'").unwrap()
+        );
+        assert_eq!(
+            vec![vec!["1", "'a'"], vec!["2", "'b)'"]],
+            LocationUtil::split_list_values("(1,'a'),(2,'b)')").unwrap()
+        );
+        assert!(LocationUtil::split_list_values("(1,'a'").is_err());
+        assert!(LocationUtil::split_list_values("1,,2").is_err());
+    }
+
+    #[test]
+    fn test_set_ordered_part_columns() {
+        let mut first = ObHashPartDesc::new();
+        first.set_part_func_type(PartFuncType::Hash);
+        first.set_ordered_part_column_names(vec!["c1".to_owned()]);
+        let mut sub = ObRangePartDesc::new();
+        sub.set_part_func_type(PartFuncType::RangeColumns);
+        sub.set_ordered_part_column_names(vec!["c2".to_owned()]);
+
+        let mut info = ObPartitionInfo::new();
+        info.level = ObPartitionLevel::Two;
+        info.first_part_desc = Some(ObPartDesc::Hash(first));
+        info.sub_part_desc = Some(ObPartDesc::Range(sub));
+        for (idx, name) in ["c1", "c2"].iter().enumerate() {
+            info.part_columns.push(Box::new(ObSimpleColumn::new(
+                name.to_string(),
+                idx as i32,
+                ObjType::Int64,
+                CollationType::Binary,
+            )));
+        }
+
+        LocationUtil::set_ordered_part_columns(&mut info).unwrap();
+        // the range sub part of a hash first part still gets its compare columns
+        match &info.sub_part_desc {
+            Some(ObPartDesc::Range(v)) => {
+                let columns = v.get_ordered_compare_column();
+                assert_eq!(1, columns.len());
+                assert_eq!("c2", columns[0].get_column_name());
+            }
+            _ => unreachable!(),
+        }
+    }
+}
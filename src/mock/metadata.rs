@@ -0,0 +1,80 @@
+/*-
+ * #%L
+ * OBKV Table Client Framework
+ * %%
+ * Copyright (C) 2021 OceanBase
+ * %%
+ * OBKV Table Client Framework is licensed under Mulan PSL v2.
+ * You can use this software according to the terms and conditions of the
+ * Mulan PSL v2. You may obtain a copy of Mulan PSL v2 at:
+ *          http://license.coscl.org.cn/MulanPSL2
+ * THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ * KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ * NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ * See the Mulan PSL v2 for more details.
+ * #L%
+ */
+
+//! Metadata of a mock cluster, served in place of the config server and the
+//! system tables.
+
+use std::sync::Arc;
+
+use super::MockState;
+use crate::{
+    error::{CommonErrCode, Error::Common as CommonErr, Result},
+    location::{provider::LocationProvider, ObServerAddr, TableEntry, TableEntryKey},
+    rpc::protocol::codes::ResultCodes,
+    util::HandyRwLock,
+};
+
+pub(crate) struct MockMetadata {
+    state: Arc<MockState>,
+}
+
+impl MockMetadata {
+    pub fn new(state: Arc<MockState>) -> Self {
+        MockMetadata { state }
+    }
+}
+
+impl LocationProvider for MockMetadata {
+    fn load_servers(&self) -> Result<Vec<ObServerAddr>> {
+        Ok(self.state.servers.clone())
+    }
+
+    fn load_table_entry(&self, key: &TableEntryKey) -> Result<TableEntry> {
+        let tables = self.state.tables.rl();
+        let table = tables.get(key.table_name()).ok_or_else(|| {
+            CommonErr(
+                CommonErrCode::ObException(ResultCodes::OB_ERR_UNKNOWN_TABLE),
+                format!(
+                    "MockMetadata::load_table_entry unknown table {}",
+                    key.table_name()
+                ),
+            )
+        })?;
+        let partitions: Vec<_> = (0..table.leaders.len())
+            .map(|idx| {
+                let leader = table.leaders[idx];
+                let mut servers = vec![self.state.servers[leader].clone()];
+                servers.extend(
+                    self.state
+                        .servers
+                        .iter()
+                        .enumerate()
+                        .filter(|(i, _)| *i != leader)
+                        .map(|(_, addr)| addr.clone()),
+                );
+                (table.partition_id(idx), servers)
+            })
+            .collect();
+        TableEntry::build(
+            key,
+            table.table_id,
+            &table.spec.row_key,
+            table.spec.scheme.as_ref(),
+            &partitions,
+        )
+    }
+}
@@ -0,0 +1,474 @@
+/*-
+ * #%L
+ * OBKV Table Client Framework
+ * %%
+ * Copyright (C) 2021 OceanBase
+ * %%
+ * OBKV Table Client Framework is licensed under Mulan PSL v2.
+ * You can use this software according to the terms and conditions of the
+ * Mulan PSL v2. You may obtain a copy of Mulan PSL v2 at:
+ *          http://license.coscl.org.cn/MulanPSL2
+ * THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ * KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ * NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ * See the Mulan PSL v2 for more details.
+ * #L%
+ */
+
+//! An in-process mock OBKV cluster, so that [`ObTableClient`] can be tested
+//! without an OceanBase deployment.
+//!
+//! [`MockCluster::start`] serves every observer of the cluster on a local TCP
+//! port and keeps the rows of its tables in memory. The builder returned by
+//! [`MockCluster::client_builder`] loads the server list and the table
+//! locations from the cluster instead of the config server and the system
+//! tables. Login, single and batch operations and (stream) queries are served,
+//! routing errors and timeouts can be injected with [`Fault`].
+//!
+//! Only built with the `mock` feature, which the tests of the crate enable.
+//!
+//! [`ObTableClient`]: crate::ObTableClient
+
+mod metadata;
+mod server;
+mod store;
+
+use std::{
+    collections::{HashMap, VecDeque},
+    net::TcpListener,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    time::Duration,
+};
+
+use tokio::runtime::Runtime;
+
+use self::{metadata::MockMetadata, store::TableData};
+use crate::{
+    client::table_client::Builder,
+    error::{CommonErrCode, Error::Common as CommonErr, Result},
+    location::{ObServerAddr, PartitionScheme},
+    rpc::protocol::codes::ResultCodes,
+    serde_obkv::value::{ObjType, Value},
+    util::HandyRwLock,
+};
+
+const FIRST_TABLE_ID: i64 = 500001;
+const TABLETS_PER_TABLE: i64 = 1000;
+
+/// Declaration of a table of a [`MockCluster`].
+#[derive(Clone, Debug)]
+pub struct MockTable {
+    name: String,
+    row_key: Vec<(String, ObjType)>,
+    scheme: Option<PartitionScheme>,
+    leaders: Option<Vec<usize>>,
+}
+
+impl MockTable {
+    /// A non-partitioned table with the row key columns `row_key`.
+    pub fn new(name: &str, row_key: &[(&str, ObjType)]) -> Self {
+        MockTable {
+            name: name.to_owned(),
+            row_key: row_key
+                .iter()
+                .map(|(column, obj_type)| (column.to_string(), obj_type.clone()))
+                .collect(),
+            scheme: None,
+            leaders: None,
+        }
+    }
+
+    /// Partition the table by `scheme`.
+    pub fn partition(mut self, scheme: PartitionScheme) -> Self {
+        self.scheme = Some(scheme);
+        self
+    }
+
+    /// The server index leading every partition, partitions are spread over
+    /// the servers by default.
+    pub fn leaders(mut self, leaders: Vec<usize>) -> Self {
+        self.leaders = Some(leaders);
+        self
+    }
+
+    fn part_num(&self) -> usize {
+        self.scheme.as_ref().map_or(1, |s| s.part_num() as usize)
+    }
+}
+
+/// Kind of the requests served by a mock observer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RequestKind {
+    Login,
+    Execute,
+    BatchExecute,
+    /// Queries, including the next and last requests of a stream.
+    ExecuteQuery,
+}
+
+/// What a mock observer does instead of serving a request.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FaultAction {
+    /// Reply the result code, such as `OB_NOT_MASTER`.
+    Error(ResultCodes),
+    /// Serve the request after the delay.
+    Delay(Duration),
+    /// Never reply, so that the request times out.
+    NoReply,
+}
+
+/// A fault injected into a [`MockCluster`], matching every request unless
+/// narrowed down.
+#[derive(Clone, Debug)]
+pub struct Fault {
+    action: FaultAction,
+    kind: Option<RequestKind>,
+    table: Option<String>,
+    server: Option<usize>,
+    times: Option<usize>,
+}
+
+impl Fault {
+    fn new(action: FaultAction) -> Self {
+        Fault {
+            action,
+            kind: None,
+            table: None,
+            server: None,
+            times: None,
+        }
+    }
+
+    pub fn error(code: ResultCodes) -> Self {
+        Fault::new(FaultAction::Error(code))
+    }
+
+    pub fn delay(delay: Duration) -> Self {
+        Fault::new(FaultAction::Delay(delay))
+    }
+
+    pub fn no_reply() -> Self {
+        Fault::new(FaultAction::NoReply)
+    }
+
+    /// Only fail the requests of `kind`.
+    pub fn on(mut self, kind: RequestKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+
+    /// Only fail the requests on `table`.
+    pub fn table(mut self, table: &str) -> Self {
+        self.table = Some(table.to_owned());
+        self
+    }
+
+    /// Only fail the requests served by the server of index `server`.
+    pub fn server(mut self, server: usize) -> Self {
+        self.server = Some(server);
+        self
+    }
+
+    /// Fail `times` requests, then stop failing.
+    pub fn times(mut self, times: usize) -> Self {
+        self.times = Some(times);
+        self
+    }
+
+    fn matches(&self, kind: RequestKind, table: Option<&str>, server: usize) -> bool {
+        self.kind.map_or(true, |k| k == kind)
+            && self.server.map_or(true, |s| s == server)
+            && self
+                .table
+                .as_deref()
+                .map_or(true, |t| table.map_or(false, |table| table == t))
+            && self.times != Some(0)
+    }
+}
+
+struct MockTableState {
+    spec: MockTable,
+    table_id: i64,
+    leaders: Vec<usize>,
+    data: TableData,
+}
+
+impl MockTableState {
+    /// Partition id of the partition `idx` as sent by the client, the tablet
+    /// id for partitioned tables.
+    fn partition_id(&self, idx: usize) -> i64 {
+        if self.spec.scheme.is_some() {
+            self.table_id * TABLETS_PER_TABLE + idx as i64
+        } else {
+            0
+        }
+    }
+
+    fn partition_idx(&self, partition_id: i64) -> Option<usize> {
+        (0..self.leaders.len()).find(|idx| self.partition_id(*idx) == partition_id)
+    }
+}
+
+/// Rows left to send of a stream query.
+pub(crate) struct StreamSession {
+    table_name: String,
+    names: Vec<String>,
+    rows: VecDeque<Vec<Value>>,
+    batch_size: usize,
+}
+
+/// State shared by the servers of a mock cluster.
+pub(crate) struct MockState {
+    servers: Vec<ObServerAddr>,
+    tables: RwLock<HashMap<String, MockTableState>>,
+    faults: Mutex<Vec<Fault>>,
+    sessions: Mutex<HashMap<u64, StreamSession>>,
+    next_session_id: AtomicU64,
+    served: Vec<AtomicUsize>,
+}
+
+impl MockState {
+    fn on_request(&self, server: usize) {
+        self.served[server].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Take the first fault matching the request.
+    fn take_fault(
+        &self,
+        kind: RequestKind,
+        table: Option<&str>,
+        server: usize,
+    ) -> Option<FaultAction> {
+        let mut faults = self.faults.lock().unwrap();
+        let fault = faults.iter_mut().find(|f| f.matches(kind, table, server))?;
+        if let Some(times) = fault.times.as_mut() {
+            *times -= 1;
+        }
+        Some(fault.action.clone())
+    }
+
+    /// Run `f` on the data of a table if the partition is served by `server`.
+    fn with_partition<R>(
+        &self,
+        table_name: &str,
+        partition_id: i64,
+        server: usize,
+        strong: bool,
+        f: impl FnOnce(&mut TableData) -> R,
+    ) -> std::result::Result<R, ResultCodes> {
+        let mut tables = self.tables.wl();
+        let table = tables
+            .get_mut(table_name)
+            .ok_or(ResultCodes::OB_ERR_UNKNOWN_TABLE)?;
+        let idx = table
+            .partition_idx(partition_id)
+            .ok_or(ResultCodes::OB_PARTITION_NOT_EXIST)?;
+        if strong && table.leaders[idx] != server {
+            return Err(ResultCodes::OB_NOT_MASTER);
+        }
+        Ok(f(&mut table.data))
+    }
+
+    fn open_session(&self) -> u64 {
+        self.next_session_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    fn keep_session(&self, session_id: u64, session: StreamSession) {
+        self.sessions.lock().unwrap().insert(session_id, session);
+    }
+
+    fn close_session(&self, session_id: u64) -> Option<StreamSession> {
+        self.sessions.lock().unwrap().remove(&session_id)
+    }
+
+    fn session_table(&self, session_id: u64) -> Option<String> {
+        self.sessions
+            .lock()
+            .unwrap()
+            .get(&session_id)
+            .map(|s| s.table_name.clone())
+    }
+}
+
+/// An in-process OBKV cluster serving its observers on local TCP ports.
+pub struct MockCluster {
+    state: Arc<MockState>,
+    runtime: Option<Runtime>,
+}
+
+impl MockCluster {
+    /// Start a cluster of `servers` observers serving `tables`.
+    pub fn start(servers: usize, tables: Vec<MockTable>) -> Result<MockCluster> {
+        if servers == 0 {
+            return Err(CommonErr(
+                CommonErrCode::InvalidParam,
+                "MockCluster::start no server".to_owned(),
+            ));
+        }
+
+        let mut listeners = Vec::with_capacity(servers);
+        let mut addrs = Vec::with_capacity(servers);
+        for _ in 0..servers {
+            let listener = TcpListener::bind("127.0.0.1:0")?;
+            listener.set_nonblocking(true)?;
+            let mut addr = ObServerAddr::new();
+            addr.address(listener.local_addr()?.to_string());
+            listeners.push(listener);
+            addrs.push(addr);
+        }
+
+        let mut states = HashMap::with_capacity(tables.len());
+        for (i, table) in tables.into_iter().enumerate() {
+            let leaders = match &table.leaders {
+                Some(leaders) => {
+                    if leaders.len() != table.part_num() || leaders.iter().any(|l| *l >= servers) {
+                        return Err(CommonErr(
+                            CommonErrCode::InvalidParam,
+                            format!("MockCluster::start invalid leaders of table {}", table.name),
+                        ));
+                    }
+                    leaders.clone()
+                }
+                None => (0..table.part_num()).map(|idx| idx % servers).collect(),
+            };
+            let row_key_names = table.row_key.iter().map(|(c, _)| c.clone()).collect();
+            states.insert(
+                table.name.clone(),
+                MockTableState {
+                    spec: table,
+                    table_id: FIRST_TABLE_ID + i as i64,
+                    leaders,
+                    data: TableData::new(row_key_names),
+                },
+            );
+        }
+
+        let state = Arc::new(MockState {
+            servers: addrs,
+            tables: RwLock::new(states),
+            faults: Mutex::new(vec![]),
+            sessions: Mutex::new(HashMap::new()),
+            next_session_id: AtomicU64::new(1),
+            served: (0..servers).map(|_| AtomicUsize::new(0)).collect(),
+        });
+
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .thread_name("mock-observer")
+            .enable_all()
+            .build()?;
+        for (server, listener) in listeners.into_iter().enumerate() {
+            runtime.spawn(server::serve(listener, server, state.clone()));
+        }
+
+        Ok(MockCluster {
+            state,
+            runtime: Some(runtime),
+        })
+    }
+
+    /// Addresses of the observers.
+    pub fn servers(&self) -> &[ObServerAddr] {
+        &self.state.servers
+    }
+
+    /// A client builder loading its metadata from this cluster.
+    pub fn client_builder(&self) -> Builder {
+        Builder::new()
+            .full_user_name("root@mock#mock_cluster")
+            .param_url("http://127.0.0.1/services?Action=ObRootServiceInfo&database=test")
+            .password("")
+            .location_provider(Arc::new(MockMetadata::new(self.state.clone())))
+    }
+
+    /// A client builder sending every request to the observer of index
+    /// `server` in odp mode. The observer serves the partitions it leads only,
+    /// so only non-partitioned tables are served in this mode.
+    pub fn odp_client_builder(&self, server: usize) -> Builder {
+        let addr = &self.state.servers[server];
+        Builder::new()
+            .full_user_name("root@mock#mock_cluster")
+            .database("test")
+            .password("")
+            .odp_addr(addr.ip(), addr.svr_port())
+    }
+
+    /// Move the leader of the partition `part_idx` of a table to `server`,
+    /// requests sent to the former leader fail with `OB_NOT_MASTER` until the
+    /// client refreshes the table location.
+    pub fn set_leader(&self, table: &str, part_idx: usize, server: usize) -> Result<()> {
+        let mut tables = self.state.tables.wl();
+        match tables.get_mut(table) {
+            Some(t) if part_idx < t.leaders.len() && server < self.state.servers.len() => {
+                t.leaders[part_idx] = server;
+                Ok(())
+            }
+            _ => Err(CommonErr(
+                CommonErrCode::InvalidParam,
+                format!("MockCluster::set_leader invalid partition {part_idx} of table {table} or server {server}"),
+            )),
+        }
+    }
+
+    /// The server index leading the partition `part_idx` of a table.
+    pub fn leader(&self, table: &str, part_idx: usize) -> Option<usize> {
+        self.state
+            .tables
+            .rl()
+            .get(table)
+            .and_then(|t| t.leaders.get(part_idx).copied())
+    }
+
+    pub fn inject(&self, fault: Fault) {
+        self.state.faults.lock().unwrap().push(fault);
+    }
+
+    pub fn clear_faults(&self) {
+        self.state.faults.lock().unwrap().clear();
+    }
+
+    /// Number of requests other than login served by the server of index
+    /// `server`.
+    pub fn served_requests(&self, server: usize) -> usize {
+        self.state.served[server].load(Ordering::Relaxed)
+    }
+
+    /// Number of stream query sessions left open on the cluster.
+    pub fn open_sessions(&self) -> usize {
+        self.state.sessions.lock().unwrap().len()
+    }
+
+    pub fn row_count(&self, table: &str) -> usize {
+        self.state
+            .tables
+            .rl()
+            .get(table)
+            .map_or(0, |t| t.data.row_count())
+    }
+
+    /// The row of a table stored under `row_key`, by column name.
+    pub fn get_row(&self, table: &str, row_key: &[Value]) -> Option<HashMap<String, Value>> {
+        self.state
+            .tables
+            .rl()
+            .get(table)
+            .and_then(|t| t.data.get_row(row_key))
+    }
+
+    pub fn truncate(&self, table: &str) {
+        if let Some(t) = self.state.tables.wl().get_mut(table) {
+            t.data.truncate();
+        }
+    }
+}
+
+impl Drop for MockCluster {
+    fn drop(&mut self) {
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
@@ -0,0 +1,391 @@
+/*-
+ * #%L
+ * OBKV Table Client Framework
+ * %%
+ * Copyright (C) 2021 OceanBase
+ * %%
+ * OBKV Table Client Framework is licensed under Mulan PSL v2.
+ * You can use this software according to the terms and conditions of the
+ * Mulan PSL v2. You may obtain a copy of Mulan PSL v2 at:
+ *          http://license.coscl.org.cn/MulanPSL2
+ * THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ * KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ * NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ * See the Mulan PSL v2 for more details.
+ * #L%
+ */
+
+//! RPC serving of a mock observer.
+
+use std::{collections::VecDeque, io, sync::Arc};
+
+use bytes::BytesMut;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::mpsc,
+    time::sleep,
+};
+use tokio_util::codec::{Decoder, Encoder};
+
+use super::{FaultAction, MockState, RequestKind, StreamSession};
+use crate::{
+    rpc::{
+        protocol::{
+            codes::ResultCodes,
+            payloads::{
+                ObRpcResultCode, ObTableBatchOperationRequest, ObTableBatchOperationResult,
+                ObTableConsistencyLevel, ObTableLoginRequest, ObTableLoginResult,
+                ObTableOperationRequest, ObTableOperationType,
+            },
+            query::{ObTableQueryRequest, ObTableQueryResult},
+            ObRpcPacket, ObRpcPacketHeader, ObTablePacket, ObTablePacketCodec, ProtoDecoder,
+            ProtoEncoder, PCODE_BATCH_EXECUTE, PCODE_EXECUTE, PCODE_EXECUTE_QUERY, PCODE_LOGIN,
+            RESP_FLAG, STREAM_FLAG, STREAM_LAST_FLAG,
+        },
+        util::checksum::ob_crc64::ObCrc64Sse42,
+    },
+    serde_obkv::value::Value,
+};
+
+/// Version replied on login, the client follows the 4.x protocol with it.
+pub(crate) const MOCK_SERVER_VERSION: &str = "OceanBase 4.2.1.0";
+const MOCK_TENANT_ID: u64 = 1002;
+const READ_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Reply of a request: the result code, the payload and the stream header.
+struct Reply {
+    rcode: ResultCodes,
+    payload: Option<Box<dyn ProtoEncoder + Send>>,
+    flag: u16,
+    session_id: u64,
+}
+
+impl Reply {
+    fn ok<P: ProtoEncoder + Send + 'static>(payload: P) -> Self {
+        Reply {
+            rcode: ResultCodes::OB_SUCCESS,
+            payload: Some(Box::new(payload)),
+            flag: 0,
+            session_id: 0,
+        }
+    }
+
+    fn error(rcode: ResultCodes) -> Self {
+        Reply {
+            rcode,
+            payload: None,
+            flag: 0,
+            session_id: 0,
+        }
+    }
+}
+
+/// Accept the connections of the observer `server` until the runtime stops.
+pub(crate) async fn serve(listener: std::net::TcpListener, server: usize, state: Arc<MockState>) {
+    let listener = match TcpListener::from_std(listener) {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!(
+                "MockServer::serve fail to listen, server:{}, err:{}",
+                server, e
+            );
+            return;
+        }
+    };
+    loop {
+        match listener.accept().await {
+            Ok((socket, _)) => {
+                tokio::spawn(serve_connection(socket, server, state.clone()));
+            }
+            Err(e) => {
+                warn!(
+                    "MockServer::serve fail to accept, server:{}, err:{}",
+                    server, e
+                );
+            }
+        }
+    }
+}
+
+async fn serve_connection(socket: TcpStream, server: usize, state: Arc<MockState>) {
+    let _ = socket.set_nodelay(true);
+    let (mut reader, mut writer) = socket.into_split();
+    let (sender, mut receiver) = mpsc::unbounded_channel::<ObTablePacket>();
+
+    tokio::spawn(async move {
+        let mut codec = ObTablePacketCodec::new();
+        let mut buf = BytesMut::new();
+        while let Some(packet) = receiver.recv().await {
+            buf.clear();
+            if let Err(e) = codec.encode(packet, &mut buf) {
+                error!("MockServer::serve_connection fail to encode, err:{}", e);
+                continue;
+            }
+            if writer.write_all(&buf).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut codec = ObTablePacketCodec::new();
+    let mut buf = BytesMut::with_capacity(READ_BUFFER_SIZE);
+    loop {
+        match reader.read_buf(&mut buf).await {
+            Ok(0) | Err(_) => return,
+            Ok(_) => (),
+        }
+        loop {
+            let packet = match codec.decode(&mut buf) {
+                Ok(Some(packet)) => packet,
+                Ok(None) => break,
+                Err(e) => {
+                    error!("MockServer::serve_connection fail to decode, err:{}", e);
+                    return;
+                }
+            };
+            if let ObTablePacket::ServerPacket {
+                id,
+                header,
+                content,
+                ..
+            } = packet
+            {
+                if let Some(header) = *header {
+                    let state = state.clone();
+                    let sender = sender.clone();
+                    tokio::spawn(async move {
+                        if let Some(packet) = handle(&state, server, id, header, content).await {
+                            let _ = sender.send(packet);
+                        }
+                    });
+                }
+            }
+        }
+    }
+}
+
+async fn handle(
+    state: &MockState,
+    server: usize,
+    id: i32,
+    header: ObRpcPacketHeader,
+    mut content: BytesMut,
+) -> Option<ObTablePacket> {
+    let reply = match dispatch(state, server, &header, &mut content).await {
+        Ok(Some(reply)) => reply,
+        Ok(None) => return None,
+        Err(e) => {
+            warn!("MockServer::handle fail to decode request, err:{}", e);
+            Reply::error(ResultCodes::OB_ERR_UNEXPECTED)
+        }
+    };
+    match encode_reply(&header, reply) {
+        Ok(content) => Some(ObTablePacket::ServerPacket {
+            id,
+            code: None,
+            content,
+            header: Box::new(None),
+        }),
+        Err(e) => {
+            error!("MockServer::handle fail to encode reply, err:{}", e);
+            None
+        }
+    }
+}
+
+fn encode_reply(request: &ObRpcPacketHeader, reply: Reply) -> io::Result<BytesMut> {
+    let mut payload = BytesMut::new();
+    let mut result_code = ObRpcResultCode::new();
+    result_code.set_rcode(reply.rcode);
+    if reply.rcode != ResultCodes::OB_SUCCESS {
+        result_code.set_message(&format!("mock server error {:?}", reply.rcode));
+    }
+    result_code.encode(&mut payload)?;
+    if let Some(p) = reply.payload {
+        p.encode(&mut payload)?;
+    }
+
+    let mut header = ObRpcPacketHeader::new();
+    header.set_pcode(request.pcode());
+    header.set_flag(RESP_FLAG | reply.flag);
+    header.set_session_id(reply.session_id);
+    header.set_trace_id(request.trace_id());
+    header.set_tenant_id(request.tenant_id());
+    header.set_checksum(ObCrc64Sse42::crc64(0, &payload));
+
+    let mut content = BytesMut::new();
+    ObRpcPacket::new(header, payload).encode(&mut content)?;
+    Ok(content)
+}
+
+/// Decode and execute a request, `None` for requests without reply.
+async fn dispatch(
+    state: &MockState,
+    server: usize,
+    header: &ObRpcPacketHeader,
+    content: &mut BytesMut,
+) -> io::Result<Option<Reply>> {
+    let pcode = header.pcode() as u16;
+    let (kind, request) = match pcode {
+        PCODE_LOGIN => {
+            let mut req = ObTableLoginRequest::default();
+            req.decode(content)?;
+            (RequestKind::Login, Request::Login)
+        }
+        PCODE_EXECUTE => {
+            let mut req = ObTableOperationRequest::default();
+            req.decode(content)?;
+            (RequestKind::Execute, Request::Execute(req))
+        }
+        PCODE_BATCH_EXECUTE => {
+            let mut req = ObTableBatchOperationRequest::default();
+            req.decode(content)?;
+            (RequestKind::BatchExecute, Request::BatchExecute(req))
+        }
+        PCODE_EXECUTE_QUERY if header.is_stream() => (
+            RequestKind::ExecuteQuery,
+            Request::Stream(header.session_id()),
+        ),
+        PCODE_EXECUTE_QUERY => {
+            let mut req = ObTableQueryRequest::default();
+            req.decode(content)?;
+            (RequestKind::ExecuteQuery, Request::Query(req))
+        }
+        _ => return Ok(Some(Reply::error(ResultCodes::OB_NOT_SUPPORTED))),
+    };
+
+    if kind != RequestKind::Login {
+        state.on_request(server);
+    }
+    let table_name = match &request {
+        Request::Login => None,
+        Request::Execute(req) => Some(req.table_name().to_owned()),
+        Request::BatchExecute(req) => Some(req.table_name().to_owned()),
+        Request::Query(req) => Some(req.table_name().to_owned()),
+        Request::Stream(session_id) => state.session_table(*session_id),
+    };
+    match state.take_fault(kind, table_name.as_deref(), server) {
+        Some(FaultAction::Error(rcode)) => return Ok(Some(Reply::error(rcode))),
+        Some(FaultAction::Delay(delay)) => sleep(delay).await,
+        Some(FaultAction::NoReply) => return Ok(None),
+        None => (),
+    }
+
+    let reply = match request {
+        Request::Login => {
+            let mut result = ObTableLoginResult::new();
+            result.set_server_version(MOCK_SERVER_VERSION);
+            result.set_login_credential(b"mock-credential".to_vec());
+            result.set_login_tenant_id(MOCK_TENANT_ID);
+            Reply::ok(result)
+        }
+        Request::Execute(req) => {
+            let strong = req.consistency_level() == ObTableConsistencyLevel::Strong
+                || req.table_operation().get_type() != ObTableOperationType::Get;
+            state
+                .with_partition(
+                    req.table_name(),
+                    req.partition_id(),
+                    server,
+                    strong,
+                    |data| Reply::ok(data.execute(req.partition_id(), req.table_operation())),
+                )
+                .unwrap_or_else(Reply::error)
+        }
+        Request::BatchExecute(req) => {
+            let batch = req.batch_operation();
+            let strong =
+                req.consistency_level() == ObTableConsistencyLevel::Strong || !batch.is_read_only();
+            state
+                .with_partition(
+                    req.table_name(),
+                    req.partition_id(),
+                    server,
+                    strong,
+                    |data| {
+                        let mut result = ObTableBatchOperationResult::new();
+                        for op in batch.get_ops() {
+                            result.add_op_result(data.execute(req.partition_id(), op));
+                        }
+                        Reply::ok(result)
+                    },
+                )
+                .unwrap_or_else(Reply::error)
+        }
+        Request::Query(req) => {
+            let strong = req.consistency_level() == ObTableConsistencyLevel::Strong;
+            let query = req.table_query();
+            match state.with_partition(
+                req.table_name(),
+                req.partition_id(),
+                server,
+                strong,
+                |data| data.query(req.partition_id(), query),
+            ) {
+                Ok(Ok((names, rows))) => {
+                    let batch_size = query.batch_size();
+                    if batch_size > 0 && rows.len() > batch_size as usize {
+                        let session = StreamSession {
+                            table_name: req.table_name().to_owned(),
+                            names,
+                            rows: rows.into(),
+                            batch_size: batch_size as usize,
+                        };
+                        let session_id = state.open_session();
+                        stream_reply(state, session_id, session)
+                    } else {
+                        Reply::ok(query_result(names, rows))
+                    }
+                }
+                Ok(Err(rcode)) | Err(rcode) => Reply::error(rcode),
+            }
+        }
+        Request::Stream(session_id) => match state.close_session(session_id) {
+            Some(session) if header.is_stream_next() => stream_reply(state, session_id, session),
+            Some(session) => {
+                let mut reply = Reply::ok(query_result(session.names, vec![]));
+                reply.flag = STREAM_FLAG | STREAM_LAST_FLAG;
+                reply.session_id = session_id;
+                reply
+            }
+            None => Reply::error(ResultCodes::OB_ERR_UNEXPECTED),
+        },
+    };
+    Ok(Some(reply))
+}
+
+enum Request {
+    Login,
+    Execute(ObTableOperationRequest),
+    BatchExecute(ObTableBatchOperationRequest),
+    Query(ObTableQueryRequest),
+    Stream(u64),
+}
+
+fn query_result(
+    names: Vec<String>,
+    rows: impl IntoIterator<Item = Vec<Value>>,
+) -> ObTableQueryResult {
+    let mut result = ObTableQueryResult::new();
+    result.set_properties_names(names);
+    for row in rows {
+        result.add_properties_row(row);
+    }
+    result
+}
+
+/// Reply the next batch of a stream, keeping the session while rows remain.
+fn stream_reply(state: &MockState, session_id: u64, mut session: StreamSession) -> Reply {
+    let n = session.batch_size.min(session.rows.len());
+    let rows: VecDeque<Vec<Value>> = session.rows.drain(..n).collect();
+    let mut reply = Reply::ok(query_result(session.names.clone(), rows));
+    reply.session_id = session_id;
+    reply.flag = if session.rows.is_empty() {
+        STREAM_FLAG | STREAM_LAST_FLAG
+    } else {
+        state.keep_session(session_id, session);
+        STREAM_FLAG
+    };
+    reply
+}
@@ -0,0 +1,458 @@
+/*-
+ * #%L
+ * OBKV Table Client Framework
+ * %%
+ * Copyright (C) 2021 OceanBase
+ * %%
+ * OBKV Table Client Framework is licensed under Mulan PSL v2.
+ * You can use this software according to the terms and conditions of the
+ * Mulan PSL v2. You may obtain a copy of Mulan PSL v2 at:
+ *          http://license.coscl.org.cn/MulanPSL2
+ * THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ * KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ * NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ * See the Mulan PSL v2 for more details.
+ * #L%
+ */
+
+//! In-memory rows of the mock cluster.
+
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap},
+    ops::Bound,
+};
+
+use crate::{
+    rpc::protocol::{
+        codes::ResultCodes,
+        payloads::{
+            ObTableEntity, ObTableOperation, ObTableOperationResult, ObTableOperationType,
+            ObTableResult,
+        },
+        query::{ObNewRange, ObScanOrder, ObTableAggregationType, ObTableQuery},
+    },
+    serde_obkv::value::{ObjMeta, ObjType, Value},
+};
+
+pub(crate) type Row = HashMap<String, Value>;
+
+/// Row key ordered by the values regardless of their metas, the extend
+/// min/max values sort before/after everything.
+#[derive(Clone, Debug)]
+pub(crate) struct RowKey(pub Vec<Value>);
+
+impl PartialEq for RowKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for RowKey {}
+
+impl PartialOrd for RowKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for RowKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        for (a, b) in self.0.iter().zip(other.0.iter()) {
+            let ord = compare_value(a, b);
+            if ord != Ordering::Equal {
+                return ord;
+            }
+        }
+        self.0.len().cmp(&other.0.len())
+    }
+}
+
+fn value_rank(v: &Value) -> u8 {
+    if v.is_min() {
+        0
+    } else if v.is_max() {
+        5
+    } else {
+        match v {
+            Value::Null(_) => 1,
+            Value::String(_, _) | Value::Bytes(_, _) | Value::Json(_, _) => 4,
+            Value::Float(_, _) | Value::Double(_, _) => 3,
+            _ => 2,
+        }
+    }
+}
+
+fn as_bytes(v: &Value) -> &[u8] {
+    match v {
+        Value::String(s, _) | Value::Json(s, _) => s.as_bytes(),
+        Value::Bytes(b, _) => b,
+        _ => &[],
+    }
+}
+
+fn as_i128(v: &Value) -> Option<i128> {
+    match v {
+        Value::Bool(b, _) => Some(*b as i128),
+        Value::Int8(i, _) => Some(*i as i128),
+        Value::UInt8(i, _) => Some(*i as i128),
+        Value::Int32(i, _) | Value::Date(i, _) => Some(*i as i128),
+        Value::UInt32(i, _) => Some(*i as i128),
+        Value::Int64(i, _) | Value::Time(i, _) | Value::DateTime(i, _) => Some(*i as i128),
+        Value::Timestamp(i, _) => Some(*i as i128),
+        Value::UInt64(i, _) | Value::Bit(i, _) | Value::Enum(i, _) | Value::Set(i, _) => {
+            Some(*i as i128)
+        }
+        Value::Year(i, _) => Some(*i as i128),
+        _ => None,
+    }
+}
+
+fn as_f64(v: &Value) -> Option<f64> {
+    match v {
+        Value::Float(f, _) => Some(*f as f64),
+        Value::Double(f, _) => Some(*f),
+        _ => as_i128(v).map(|i| i as f64),
+    }
+}
+
+pub(crate) fn compare_value(a: &Value, b: &Value) -> Ordering {
+    let (ra, rb) = (value_rank(a), value_rank(b));
+    if ra != rb {
+        // integers and floats compare numerically
+        if (ra == 2 || ra == 3) && (rb == 2 || rb == 3) {
+            return as_f64(a).partial_cmp(&as_f64(b)).unwrap_or(Ordering::Equal);
+        }
+        return ra.cmp(&rb);
+    }
+    match ra {
+        2 => as_i128(a).cmp(&as_i128(b)),
+        3 => as_f64(a).partial_cmp(&as_f64(b)).unwrap_or(Ordering::Equal),
+        4 => as_bytes(a).cmp(as_bytes(b)),
+        _ => Ordering::Equal,
+    }
+}
+
+/// Rows of a table, by partition id (tablet id) and row key.
+#[derive(Debug, Default)]
+pub(crate) struct TableData {
+    row_key_names: Vec<String>,
+    partitions: HashMap<i64, BTreeMap<RowKey, Row>>,
+}
+
+fn op_result(op_type: ObTableOperationType, errorno: ResultCodes) -> ObTableOperationResult {
+    let mut result = ObTableOperationResult::new();
+    result.set_operation_type(op_type);
+    if errorno != ResultCodes::OB_SUCCESS {
+        let mut header = ObTableResult::new();
+        header.set_errorno(errorno as i32);
+        header.set_message(&format!("{errorno:?}"));
+        result.set_result(header);
+    }
+    result
+}
+
+impl TableData {
+    pub fn new(row_key_names: Vec<String>) -> Self {
+        TableData {
+            row_key_names,
+            partitions: HashMap::new(),
+        }
+    }
+
+    pub fn row_count(&self) -> usize {
+        self.partitions.values().map(|p| p.len()).sum()
+    }
+
+    pub fn truncate(&mut self) {
+        self.partitions.clear();
+    }
+
+    pub fn get_row(&self, row_key: &[Value]) -> Option<Row> {
+        let key = RowKey(row_key.to_vec());
+        self.partitions.values().find_map(|p| p.get(&key)).cloned()
+    }
+
+    fn new_row(&self, row_key: &[Value]) -> Row {
+        self.row_key_names
+            .iter()
+            .cloned()
+            .zip(row_key.iter().cloned())
+            .collect()
+    }
+
+    /// Execute a single operation on the partition `part_id`.
+    pub fn execute(&mut self, part_id: i64, op: &ObTableOperation) -> ObTableOperationResult {
+        let op_type = op.get_type();
+        let row_key = op.get_row_key().keys().to_vec();
+        if row_key.len() != self.row_key_names.len() {
+            return op_result(op_type, ResultCodes::OB_INVALID_ARGUMENT);
+        }
+        let props = op.get_table_entity().properties();
+        let new_row = self.new_row(&row_key);
+        let key = RowKey(row_key);
+        let rows = self.partitions.entry(part_id).or_default();
+
+        let mut affected_rows = 1;
+        match op_type {
+            ObTableOperationType::Get => {
+                let mut result = op_result(op_type, ResultCodes::OB_SUCCESS);
+                let mut entity = ObTableEntity::new(vec![]);
+                if let Some(row) = rows.get(&key) {
+                    if props.is_empty() {
+                        for (name, value) in row {
+                            entity.add_attr(name, value.clone());
+                        }
+                    } else {
+                        for name in props.keys() {
+                            entity.add_attr(name, row.get(name).cloned().unwrap_or_default());
+                        }
+                    }
+                }
+                result.set_entity(entity);
+                result.set_affected_rows(0);
+                return result;
+            }
+            ObTableOperationType::Insert => {
+                if rows.contains_key(&key) {
+                    return op_result(op_type, ResultCodes::OB_ERR_PRIMARY_KEY_DUPLICATE);
+                }
+                let mut row = new_row;
+                row.extend(props.iter().map(|(k, v)| (k.clone(), v.clone())));
+                rows.insert(key, row);
+            }
+            ObTableOperationType::Del => {
+                if rows.remove(&key).is_none() {
+                    affected_rows = 0;
+                }
+            }
+            ObTableOperationType::Update => match rows.get_mut(&key) {
+                Some(row) => row.extend(props.iter().map(|(k, v)| (k.clone(), v.clone()))),
+                None => affected_rows = 0,
+            },
+            ObTableOperationType::InsertOrUpdate => {
+                rows.entry(key)
+                    .or_insert(new_row)
+                    .extend(props.iter().map(|(k, v)| (k.clone(), v.clone())));
+            }
+            ObTableOperationType::Replace => {
+                let mut row = new_row;
+                row.extend(props.iter().map(|(k, v)| (k.clone(), v.clone())));
+                if rows.insert(key, row).is_some() {
+                    affected_rows = 2;
+                }
+            }
+            ObTableOperationType::Increment | ObTableOperationType::Append => {
+                let row = rows.entry(key).or_insert(new_row);
+                for (name, delta) in props {
+                    let current = row.get(name).filter(|v| !v.is_none());
+                    let value = if op_type == ObTableOperationType::Increment {
+                        match (current.map(as_i128), as_i128(delta)) {
+                            (None, Some(_)) => delta.clone(),
+                            (Some(Some(a)), Some(b)) => Value::Int64(
+                                (a + b) as i64,
+                                ObjMeta::new_numeric_meta(ObjType::Int64),
+                            ),
+                            _ => return op_result(op_type, ResultCodes::OB_OBJ_TYPE_ERROR),
+                        }
+                    } else {
+                        match (current, delta) {
+                            (None, _) => delta.clone(),
+                            (Some(Value::String(a, meta)), _) => Value::String(
+                                format!("{a}{}", String::from_utf8_lossy(as_bytes(delta))),
+                                meta.clone(),
+                            ),
+                            (Some(Value::Bytes(a, meta)), _) => {
+                                let mut bytes = a.clone();
+                                bytes.extend_from_slice(as_bytes(delta));
+                                Value::Bytes(bytes, meta.clone())
+                            }
+                            _ => return op_result(op_type, ResultCodes::OB_OBJ_TYPE_ERROR),
+                        }
+                    };
+                    row.insert(name.clone(), value);
+                }
+            }
+        }
+
+        let mut result = op_result(op_type, ResultCodes::OB_SUCCESS);
+        result.set_affected_rows(affected_rows);
+        result
+    }
+
+    /// Rows of the partition `part_id` matched by `query`, as column names and
+    /// row values.
+    pub fn query(
+        &self,
+        part_id: i64,
+        query: &ObTableQuery,
+    ) -> std::result::Result<(Vec<String>, Vec<Vec<Value>>), ResultCodes> {
+        if !query.get_filter_string().is_empty() || query.is_hbase_query() {
+            return Err(ResultCodes::OB_NOT_SUPPORTED);
+        }
+        let index_name = query.get_index_name();
+        if !index_name.is_empty() && !index_name.eq_ignore_ascii_case("PRIMARY") {
+            return Err(ResultCodes::OB_NOT_SUPPORTED);
+        }
+
+        let empty = BTreeMap::new();
+        let rows = self.partitions.get(&part_id).unwrap_or(&empty);
+        let mut matched: Vec<&Row> = Vec::new();
+        for range in query.get_key_ranges() {
+            matched.extend(range_rows(rows, range));
+        }
+        if *query.get_scan_order() == ObScanOrder::Reverse {
+            matched.reverse();
+        }
+
+        if query.is_aggregation() {
+            return self.aggregate(query, &matched);
+        }
+
+        let offset = query.get_offset().max(0) as usize;
+        let limit = match query.get_limit() {
+            l if l < 0 => usize::MAX,
+            l => l as usize,
+        };
+        let matched: Vec<&Row> = matched.into_iter().skip(offset).take(limit).collect();
+
+        let names: Vec<String> = if query.get_select_columns().is_empty() {
+            let mut others: Vec<String> = matched
+                .iter()
+                .flat_map(|row| row.keys())
+                .filter(|name| !self.row_key_names.contains(name))
+                .cloned()
+                .collect();
+            others.sort();
+            others.dedup();
+            self.row_key_names.iter().cloned().chain(others).collect()
+        } else {
+            query.get_select_columns().to_vec()
+        };
+        let rows = matched
+            .into_iter()
+            .map(|row| {
+                names
+                    .iter()
+                    .map(|name| row.get(name).cloned().unwrap_or_default())
+                    .collect()
+            })
+            .collect();
+        Ok((names, rows))
+    }
+
+    fn aggregate(
+        &self,
+        query: &ObTableQuery,
+        rows: &[&Row],
+    ) -> std::result::Result<(Vec<String>, Vec<Vec<Value>>), ResultCodes> {
+        let aggregations = query.get_aggregations();
+        let select_columns = query.get_select_columns();
+        let mut names = Vec::with_capacity(aggregations.len());
+        let mut values = Vec::with_capacity(aggregations.len());
+        for (i, agg) in aggregations.iter().enumerate() {
+            let column = agg.agg_column();
+            let column_values: Vec<&Value> = rows
+                .iter()
+                .filter_map(|row| row.get(column))
+                .filter(|v| !v.is_none())
+                .collect();
+            let value = match agg.agg_type() {
+                ObTableAggregationType::MAX => column_values
+                    .iter()
+                    .max_by(|a, b| compare_value(a, b))
+                    .map(|v| (*v).clone())
+                    .unwrap_or_default(),
+                ObTableAggregationType::MIN => column_values
+                    .iter()
+                    .min_by(|a, b| compare_value(a, b))
+                    .map(|v| (*v).clone())
+                    .unwrap_or_default(),
+                ObTableAggregationType::COUNT => {
+                    let count = if column == "*" {
+                        rows.len()
+                    } else {
+                        column_values.len()
+                    };
+                    Value::from(count as i64)
+                }
+                ObTableAggregationType::SUM => {
+                    if column_values.iter().all(|v| as_i128(v).is_some()) {
+                        Value::from(
+                            column_values
+                                .iter()
+                                .filter_map(|v| as_i128(v))
+                                .sum::<i128>() as i64,
+                        )
+                    } else {
+                        Value::from(column_values.iter().filter_map(|v| as_f64(v)).sum::<f64>())
+                    }
+                }
+                ObTableAggregationType::AVG => {
+                    if column_values.is_empty() {
+                        Value::default()
+                    } else {
+                        let sum: f64 = column_values.iter().filter_map(|v| as_f64(v)).sum();
+                        Value::from(sum / column_values.len() as f64)
+                    }
+                }
+                ObTableAggregationType::INVAILD => return Err(ResultCodes::OB_NOT_SUPPORTED),
+            };
+            names.push(
+                select_columns
+                    .get(i)
+                    .cloned()
+                    .unwrap_or_else(|| column.to_owned()),
+            );
+            values.push(value);
+        }
+        Ok((names, vec![values]))
+    }
+}
+
+fn range_rows<'a>(
+    rows: &'a BTreeMap<RowKey, Row>,
+    range: &ObNewRange,
+) -> impl Iterator<Item = &'a Row> {
+    let start = RowKey(range.get_start_key().keys().to_vec());
+    let end = RowKey(range.get_end_key().keys().to_vec());
+    let inclusive_start = range.get_border_flag().is_inclusive_start();
+    let inclusive_end = range.get_border_flag().is_inclusive_end();
+    let valid = start.cmp(&end) != Ordering::Greater;
+    let lower = if inclusive_start {
+        Bound::Included(start)
+    } else {
+        Bound::Excluded(start)
+    };
+    let upper = if inclusive_end {
+        Bound::Included(end)
+    } else {
+        Bound::Excluded(end)
+    };
+    valid
+        .then(|| rows.range((lower, upper)))
+        .into_iter()
+        .flatten()
+        .map(|(_, row)| row)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_row_key_order() {
+        let key = |v: Vec<Value>| RowKey(v);
+        assert!(key(vec![Value::from(1i64)]) < key(vec![Value::from(2i64)]));
+        assert!(key(vec![Value::get_min()]) < key(vec![Value::from(i64::MIN)]));
+        assert!(key(vec![Value::get_max()]) > key(vec![Value::from("zzz")]));
+        assert!(
+            key(vec![Value::from("a"), Value::from(2i64)])
+                < key(vec![Value::from("b"), Value::from(1i64)])
+        );
+        assert_eq!(
+            key(vec![Value::from("a")]),
+            key(vec![Value::from(b"a".to_vec())])
+        );
+    }
+}
@@ -0,0 +1,91 @@
+/*-
+ * #%L
+ * OBKV Table Client Framework
+ * %%
+ * Copyright (C) 2021 OceanBase
+ * %%
+ * OBKV Table Client Framework is licensed under Mulan PSL v2.
+ * You can use this software according to the terms and conditions of the
+ * Mulan PSL v2. You may obtain a copy of Mulan PSL v2 at:
+ *          http://license.coscl.org.cn/MulanPSL2
+ * THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ * KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ * NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ * See the Mulan PSL v2 for more details.
+ * #L%
+ */
+
+use prometheus_client::{
+    encoding::EncodeLabelSet,
+    metrics::{counter, family::Family, gauge},
+    registry::Registry,
+};
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+pub struct ServerLabels {
+    pub server: String,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+pub struct ServerStateLabels {
+    pub server: String,
+    pub state: String,
+}
+
+#[derive(Default)]
+pub struct CircuitBreakerMetrics {
+    state: Family<ServerLabels, gauge::Gauge>,
+    transitions: Family<ServerStateLabels, counter::Counter>,
+    rejected: Family<ServerLabels, counter::Counter>,
+}
+
+impl CircuitBreakerMetrics {
+    pub fn register(&self, registry: &mut Registry) {
+        let sub_registry = registry.sub_registry_with_prefix("circuit_breaker");
+        sub_registry.register(
+            "state",
+            "Circuit breaker state of the servers, 0 closed, 1 open and 2 half-open.",
+            self.state.clone(),
+        );
+        sub_registry.register(
+            "transitions",
+            "Circuit breaker transitions of the servers by the state entered.",
+            self.transitions.clone(),
+        );
+        sub_registry.register(
+            "rejected",
+            "Requests failed fast by the open circuit breakers of the servers.",
+            self.rejected.clone(),
+        );
+    }
+
+    pub fn set_state(&self, server: &str, state: &str, value: i64) {
+        self.state
+            .get_or_create(&ServerLabels {
+                server: server.to_string(),
+            })
+            .set(value);
+        self.transitions
+            .get_or_create(&ServerStateLabels {
+                server: server.to_string(),
+                state: state.to_string(),
+            })
+            .inc();
+    }
+
+    pub fn inc_rejected(&self, server: &str) {
+        self.rejected
+            .get_or_create(&ServerLabels {
+                server: server.to_string(),
+            })
+            .inc();
+    }
+
+    pub fn get_state(&self) -> &Family<ServerLabels, gauge::Gauge> {
+        &self.state
+    }
+
+    pub fn get_rejected(&self) -> &Family<ServerLabels, counter::Counter> {
+        &self.rejected
+    }
+}
@@ -46,6 +46,12 @@ pub enum ObClientOpRetryType {
     ExecuteBatch = 1,
 }
 
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelValue)]
+pub enum ObClientHedgedReadType {
+    Sent = 0,
+    Won = 1,
+}
+
 impl From<ObTableOperationType> for ObClientOpRecordType {
     fn from(op: ObTableOperationType) -> Self {
         match op {
@@ -71,6 +77,12 @@ pub struct OperationRetryLabels {
     pub operation_retry_type: ObClientOpRetryType,
 }
 
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+pub struct HedgedReadLabels {
+    pub hedged_read_type: ObClientHedgedReadType,
+    pub read_kind: String,
+}
+
 #[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
 pub struct ClientStringLabels {
     pub string_type: String,
@@ -86,6 +98,7 @@ pub struct ClientMetrics {
     client_operation_rt: Family<OperationLabels, histogram::Histogram>,
     client_sys_op_rt: Family<ClientStringLabels, histogram::Histogram>,
     client_retry: Family<OperationRetryLabels, counter::Counter>,
+    client_hedged_read: Family<HedgedReadLabels, counter::Counter>,
     client_misc: Family<ClientStringLabels, histogram::Histogram>,
     client_stream_query_counter: Family<ClientStreamQueryLabels, counter::Counter>,
 }
@@ -102,6 +115,7 @@ impl Default for ClientMetrics {
                     histogram::Histogram::new(histogram::exponential_buckets(0.001, 2.0, 10))
                 }),
             client_retry: Default::default(),
+            client_hedged_read: Default::default(),
             client_misc: Family::<ClientStringLabels, histogram::Histogram>::new_with_constructor(
                 || histogram::Histogram::new(histogram::exponential_buckets(5.0, 2.0, 8)),
             ),
@@ -128,6 +142,11 @@ impl ClientMetrics {
             "Client operation retry times.",
             self.client_retry.clone(),
         );
+        sub_registry.register(
+            "hedged reads ",
+            "Client hedged reads sent and won by the hedge.",
+            self.client_hedged_read.clone(),
+        );
         sub_registry.register(
             "system miscellaneous counter histogram ",
             "Client system miscellaneous counter histogram.",
@@ -198,6 +217,19 @@ impl ClientMetrics {
         &self.client_retry
     }
 
+    pub fn inc_hedged_read(&self, hedged_read_type: ObClientHedgedReadType, read_kind: &str) {
+        self.client_hedged_read
+            .get_or_create(&HedgedReadLabels {
+                hedged_read_type,
+                read_kind: read_kind.to_string(),
+            })
+            .inc();
+    }
+
+    pub fn get_client_hedged_read(&self) -> &Family<HedgedReadLabels, counter::Counter> {
+        &self.client_hedged_read
+    }
+
     pub fn observe_misc(&self, misc_type: &str, times: f64) {
         self.client_misc
             .get_or_create(&ClientStringLabels {
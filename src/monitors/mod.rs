@@ -15,6 +15,7 @@
  * #L%
  */
 
+pub mod breaker_metrics;
 pub mod client_metrics;
 pub mod prometheus;
 pub mod proxy_metrics;
@@ -16,7 +16,7 @@
  */
 
 use std::{
-    cmp,
+    cmp, mem,
     sync::{Arc, Condvar, Mutex},
     time::{Duration, Instant},
     u32,
@@ -39,6 +39,7 @@ struct PoolInner {
     next_index: usize,
     max_conn_num: usize,
     pending_conn_num: usize,
+    closed: bool,
 }
 
 impl PoolInner {
@@ -48,6 +49,7 @@ impl PoolInner {
             next_index: 0,
             max_conn_num,
             pending_conn_num: 0,
+            closed: false,
         }
     }
 
@@ -82,7 +84,7 @@ impl PoolInner {
 
     #[inline]
     fn should_add_conn(&self) -> bool {
-        self.pending_conn_num + self.conns.len() < self.max_conn_num
+        !self.closed && self.pending_conn_num + self.conns.len() < self.max_conn_num
     }
 
     #[inline]
@@ -180,9 +182,23 @@ impl ConnPool {
                     };
                     match shared_pool.build_conn().await {
                         Ok(conn) => {
-                            let mut inner = shared_pool.inner.lock().unwrap();
-                            inner.add_conn(conn);
-                            shared_pool.cond.notify_all();
+                            // the connection is left over if the pool was closed meanwhile
+                            let left_over = {
+                                let mut inner = shared_pool.inner.lock().unwrap();
+                                if inner.closed {
+                                    inner.unpend_conn();
+                                    Some(conn)
+                                } else {
+                                    inner.add_conn(conn);
+                                    shared_pool.cond.notify_all();
+                                    None
+                                }
+                            };
+                            if let Some(conn) = left_over {
+                                if let Err(e) = conn.close().await {
+                                    warn!("ConnPool::add_connection_background::bg_add fail to close connection built after closed, err:{}", e);
+                                }
+                            }
                             break;
                         }
                         Err(e) => {
@@ -260,6 +276,15 @@ impl ConnPool {
 
         let mut inner = pool.inner.lock().unwrap();
         loop {
+            if inner.closed {
+                return Err(CommonErr(
+                    CommonErrCode::AlreadyClosed,
+                    format!(
+                        "ConnPool::get pool is closed, addr:{}, port:{}",
+                        pool.conn_builder.ip, pool.conn_builder.port
+                    ),
+                ));
+            }
             match inner.try_get() {
                 (Some(conn), removed) => {
                     if removed > 0 {
@@ -326,6 +351,18 @@ impl ConnPool {
     pub fn idle_conn_num(&self) -> usize {
         self.shared_pool.inner.lock().unwrap().idle_conn_num()
     }
+
+    /// Stops building connections and takes the pooled ones out, getting a
+    /// connection fails afterwards. The connections are left for the caller
+    /// to close.
+    pub fn close(&self) -> Vec<Arc<Connection>> {
+        let pool = &self.shared_pool;
+        let mut inner = pool.inner.lock().unwrap();
+        inner.closed = true;
+        let conns = mem::take(&mut inner.conns);
+        pool.cond.notify_all();
+        conns
+    }
 }
 
 struct SharedPool {
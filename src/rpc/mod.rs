@@ -18,11 +18,12 @@
 pub mod conn_pool;
 pub mod protocol;
 pub mod proxy;
+pub mod tls;
 pub mod util;
 
 use std::{
     collections::HashMap,
-    mem,
+    future::Future,
     net::{IpAddr, SocketAddr, ToSocketAddrs},
     ops::Drop,
     sync::{
@@ -35,26 +36,24 @@ use std::{
 use bytes::BytesMut;
 use socket2::{Domain, Protocol, Socket, TcpKeepalive, Type};
 use tokio::{
-    io::{AsyncReadExt, AsyncWriteExt},
-    net::{
-        tcp::{OwnedReadHalf, OwnedWriteHalf},
-        TcpSocket, TcpStream,
-    },
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::TcpSocket,
     sync::{mpsc, oneshot},
     time::Duration as TokioDuration,
 };
 use tokio_util::codec::{Decoder, Encoder};
 
 use self::protocol::{
+    compress,
     payloads::{ObRpcResultCode, ObTableLoginRequest, ObTableLoginResult},
-    ObPayload, ObRpcPacket, ObRpcPacketHeader, ObTablePacket, ObTablePacketCodec, ProtoDecoder,
-    ProtoEncoder, TransportCode, HEADER_SIZE,
+    ChecksumMismatch, ObCompressType, ObPayload, ObRpcPacket, ObRpcPacketHeader, ObTablePacket,
+    ObTablePacketCodec, ProtoDecoder, ProtoEncoder, TransportCode, HEADER_SIZE,
 };
 use crate::{
     client::table_client::RuntimesRef,
     error::{CommonErrCode, Error, Error::Common as CommonErr, Result},
     monitors::{prometheus::OBKV_CLIENT_REGISTRY, rpc_metrics::RpcMetrics},
-    rpc::{protocol::TraceId, util::checksum::ob_crc64::ObCrc64Sse42},
+    rpc::{protocol::TraceId, tls::TlsConnector, util::checksum::ob_crc64::ObCrc64Sse42},
     runtime::{JoinHandle, RuntimeRef},
     util::obversion::parse_ob_vsn_from_login,
 };
@@ -69,18 +68,83 @@ lazy_static! {
 
 type RequestsMap = Arc<Mutex<HashMap<i32, oneshot::Sender<Result<ObTablePacket>>>>>;
 
+// halves of a plain tcp stream or a tls stream
+type ReadStream = Box<dyn AsyncRead + Send + Unpin>;
+type WriteStream = Box<dyn AsyncWrite + Send + Unpin>;
+
 const CONN_CONTINUOUS_TIMEOUT_CEILING: usize = 10;
 
+// Whether a request sent may have been applied although it failed with
+// `error`, only the server errors not ambiguous tell it wasn't.
+fn is_ambiguous_failure(error: &Error) -> bool {
+    match error.ob_result_code() {
+        Some(code) => code.is_ambiguous(),
+        None => true,
+    }
+}
+
+tokio::task_local! {
+    // requests not idempotent sent by the operation and not answered yet
+    static PENDING_REQUESTS: Arc<AtomicUsize>;
+}
+
+/// Runs `f` counting in `pending` the requests not idempotent it has sent
+/// and not received the outcome of, so that a caller abandoning `f`, e.g. at
+/// its deadline, knows whether it may have been applied.
+pub(crate) async fn track_pending_requests<F: Future>(
+    pending: Arc<AtomicUsize>,
+    f: F,
+) -> F::Output {
+    PENDING_REQUESTS.scope(pending, f).await
+}
+
+/// Counts the requests of `f`, spawned in another task, along the requests
+/// of the operation calling this.
+pub(crate) fn inherit_pending_requests<F: Future>(f: F) -> impl Future<Output = F::Output> {
+    let pending = PENDING_REQUESTS.try_with(Arc::clone).ok();
+    async move {
+        match pending {
+            Some(pending) => PENDING_REQUESTS.scope(pending, f).await,
+            None => f.await,
+        }
+    }
+}
+
+// A request not idempotent counted as pending until its outcome is known, it
+// stays pending if dropped before.
+struct PendingRequest(Option<Arc<AtomicUsize>>);
+
+impl PendingRequest {
+    fn new(idempotent: bool) -> PendingRequest {
+        let pending = if idempotent {
+            None
+        } else {
+            PENDING_REQUESTS.try_with(Arc::clone).ok()
+        };
+        if let Some(pending) = &pending {
+            pending.fetch_add(1, Ordering::AcqRel);
+        }
+        PendingRequest(pending)
+    }
+
+    fn settle(self) {
+        if let Some(pending) = &self.0 {
+            pending.fetch_sub(1, Ordering::AcqRel);
+        }
+    }
+}
+
 ///Send component of OBKV connection.
 #[derive(Debug)]
 pub struct ConnectionSender {
     sender: mpsc::Sender<ObTablePacket>,
-    writer: Option<JoinHandle<Result<()>>>,
+    writer: Mutex<Option<JoinHandle<Result<()>>>>,
 }
 
 impl ConnectionSender {
     fn new(
-        write_stream: OwnedWriteHalf,
+        addr: SocketAddr,
+        write_stream: WriteStream,
         requests: RequestsMap,
         active: Arc<AtomicBool>,
         sender_runtime: RuntimeRef,
@@ -93,7 +157,6 @@ impl ConnectionSender {
         let writer = sender_runtime.spawn(async move {
                 let mut buf = BytesMut::with_capacity(1024);
                 let mut write_stream = write_stream;
-                let addr = write_stream.peer_addr()?;
                 loop {
                     let start = Instant::now();
                     match receiver.recv().await {
@@ -167,7 +230,7 @@ impl ConnectionSender {
 
         ConnectionSender {
             sender,
-            writer: Some(writer),
+            writer: Mutex::new(Some(writer)),
         }
     }
 
@@ -179,12 +242,12 @@ impl ConnectionSender {
         self.sender.send(message).await.map_err(Self::broken_pipe)
     }
 
-    #[allow(dead_code)]
     /// Close the connection
     /// Requests in requests map will be cancelled when the writer closed
-    async fn close(&mut self) -> Result<()> {
+    async fn close(&self) -> Result<()> {
         self.request(ObTablePacket::ClosePoison).await?;
-        if let Some(writer) = mem::take(&mut self.writer) {
+        let writer = self.writer.lock().unwrap().take();
+        if let Some(writer) = writer {
             writer.await??
         }
         Ok(())
@@ -193,7 +256,7 @@ impl ConnectionSender {
     /// Shutdown the sender without closing remote
     /// Requests in the requests map will be not be cancelled
     fn shutdown(&mut self) -> Result<()> {
-        if let Some(writer) = mem::take(&mut self.writer) {
+        if let Some(writer) = self.writer.get_mut().unwrap().take() {
             writer.abort()
         }
         Ok(())
@@ -212,8 +275,8 @@ impl ConnectionSender {
 pub struct Connection {
     // remote addr
     addr: SocketAddr,
-    reader: Option<JoinHandle<Result<()>>>,
-    reader_signal_sender: Option<oneshot::Sender<()>>,
+    reader: Mutex<Option<JoinHandle<Result<()>>>>,
+    reader_signal_sender: Mutex<Option<oneshot::Sender<()>>>,
     sender: ConnectionSender,
     requests: RequestsMap,
     continuous_timeout_failures: AtomicUsize,
@@ -224,6 +287,8 @@ pub struct Connection {
     id: u64,
     trace_id_counter: AtomicU64,
     load: AtomicUsize,
+    compress_type: ObCompressType,
+    compress_min_size: usize,
 }
 
 const OB_MYSQL_MAX_PACKET_LENGTH: usize = 1 << 24;
@@ -244,28 +309,46 @@ impl<'a> Drop for LoadCounter<'a> {
     }
 }
 
+// Removes the entry of a request from the requests map once the request
+// completes, times out or is dropped, so that no entry is left behind by
+// the abandoned requests.
+struct RequestGuard<'a> {
+    requests: &'a RequestsMap,
+    channel_id: i32,
+}
+
+impl<'a> Drop for RequestGuard<'a> {
+    fn drop(&mut self) {
+        self.requests.lock().unwrap().remove(&self.channel_id);
+    }
+}
+
 impl Connection {
     fn internal_new(
         id: u64,
         addr: SocketAddr,
-        stream: TcpStream,
+        read_stream: ReadStream,
+        write_stream: WriteStream,
         runtimes: RuntimesRef,
         channel_capacity: usize,
+        verify_checksum: bool,
     ) -> Result<Self> {
         let requests: RequestsMap = Arc::new(Mutex::new(HashMap::new()));
         let read_requests = requests.clone();
 
-        let (read_stream, write_stream) = stream.into_split();
-
         let active = Arc::new(AtomicBool::new(false));
         let read_active = active.clone();
         let (sender, receiver) = oneshot::channel();
 
         let join_handle = runtimes.tcp_recv_runtime.spawn(async move {
-            let addr = read_stream.peer_addr()?;
-
-            Connection::process_reading_data(receiver, read_stream, read_requests.clone(), &addr)
-                .await;
+            Connection::process_reading_data(
+                receiver,
+                read_stream,
+                read_requests.clone(),
+                &addr,
+                verify_checksum,
+            )
+            .await;
 
             read_active.store(false, Ordering::Release);
             Connection::cancel_requests(&read_requests);
@@ -276,8 +359,9 @@ impl Connection {
 
         Ok(Connection {
             addr,
-            reader: Some(join_handle),
+            reader: Mutex::new(Some(join_handle)),
             sender: ConnectionSender::new(
+                addr,
                 write_stream,
                 requests.clone(),
                 active.clone(),
@@ -287,27 +371,37 @@ impl Connection {
             requests,
             continuous_timeout_failures: AtomicUsize::new(0),
             continuous_timeout_failures_ceiling: CONN_CONTINUOUS_TIMEOUT_CEILING,
-            reader_signal_sender: Some(sender),
+            reader_signal_sender: Mutex::new(Some(sender)),
             credential: None,
             tenant_id: None,
             active,
             id,
             trace_id_counter: AtomicU64::new(0),
             load: AtomicUsize::new(0),
+            compress_type: ObCompressType::None,
+            compress_min_size: 0,
         })
     }
 
+    /// Compress the requests whose payloads are at least `min_size` bytes.
+    fn with_compression(mut self, compress_type: ObCompressType, min_size: usize) -> Self {
+        self.compress_type = compress_type;
+        self.compress_min_size = min_size;
+        self
+    }
+
     pub fn load(&self) -> usize {
         self.load.load(Ordering::Relaxed)
     }
 
     async fn process_reading_data(
         mut signal_receiver: oneshot::Receiver<()>,
-        mut read_stream: OwnedReadHalf,
+        mut read_stream: ReadStream,
         read_requests: RequestsMap,
         addr: &SocketAddr,
+        verify_checksum: bool,
     ) {
-        let mut codec = ObTablePacketCodec::new();
+        let mut codec = ObTablePacketCodec::new().verify_checksum(verify_checksum);
         let mut read_buf = [0; READ_BUF_SIZE];
         let mut buf = BytesMut::with_capacity(READ_BUF_SIZE);
         loop {
@@ -432,6 +526,24 @@ impl Connection {
                         "Connection::decode_packets fail to decode packet from connection {}, err: {}, exit reader.",
                         addr, e
                     );
+                    // the stream can not be trusted any more, fail the request whose
+                    // response is corrupted and close the connection
+                    if let Some(mismatch) = e
+                        .get_ref()
+                        .and_then(|e| e.downcast_ref::<ChecksumMismatch>())
+                    {
+                        Self::notify_sender(
+                            read_requests,
+                            mismatch.channel_id,
+                            ObTablePacket::TransportPacket {
+                                error: CommonErr(
+                                    CommonErrCode::ChecksumMismatch,
+                                    format!("{mismatch}, addr:{addr}"),
+                                ),
+                                code: TransportCode::ChecksumMismatch,
+                            },
+                        );
+                    }
                     return false;
                 }
             }
@@ -456,6 +568,23 @@ impl Connection {
 
         let mut header = ObRpcPacketHeader::new();
 
+        if compress::is_compressed(&self.compress_type)
+            && payload_content.len() >= self.compress_min_size
+        {
+            let compressed = compress::compress(&self.compress_type, &payload_content)?;
+            // send the payload as it is if compression does not pay off
+            if compressed.len() < payload_content.len() {
+                OBKV_RPC_METRICS.observe_rpc_misc(
+                    "compress_ratio",
+                    compressed.len() as f64 / payload_content.len() as f64,
+                );
+                header.set_compress_type(self.compress_type.clone());
+                header.set_original_len(payload_content.len() as i32);
+                payload_content.clear();
+                payload_content.extend_from_slice(&compressed);
+            }
+        }
+
         header.set_pcode(payload.pcode().value() as u32);
         header.set_timeout(payload.timeout_millis() * 1000); //us
         header.set_tenant_id(self.tenant_id.unwrap_or(1));
@@ -465,11 +594,12 @@ impl Connection {
         header.set_trace_id(trace_id);
 
         // compute checksum
-        header.set_checksum(ObCrc64Sse42::fast_crc64_sse42_manually(0, &payload_content));
+        header.set_checksum(ObCrc64Sse42::crc64(0, &payload_content));
 
+        let content_len = payload_content.len();
         let packet = ObRpcPacket::new(header, payload_content);
 
-        let mut content = BytesMut::with_capacity(HEADER_SIZE + payload_len);
+        let mut content = BytesMut::with_capacity(HEADER_SIZE + content_len);
 
         packet.encode(&mut content)?;
 
@@ -543,13 +673,50 @@ impl Connection {
             Some(id) => id,
         };
 
-        let rx = self.send(req, channel_id).await?;
+        // the entry is removed even if this future is dropped while sending
+        // or waiting for the response
+        let _request_guard = RequestGuard {
+            requests: &self.requests,
+            channel_id,
+        };
+        let pending = PendingRequest::new(payload.is_idempotent());
+        let rx = match self.send(req, channel_id).await {
+            Ok(rx) => rx,
+            Err(e) => {
+                pending.settle();
+                return Err(e);
+            }
+        };
 
         if payload.timeout_millis() == 0 {
             // no-wait request,return Ok directly
+            pending.settle();
             return Ok(());
         }
 
+        let res = self
+            .recv_response(rx, response, timeout, trace_id, start)
+            .await;
+        pending.settle();
+        res.map_err(|e| {
+            // the request is sent, it may have been applied whatever the error
+            if !payload.is_idempotent() && is_ambiguous_failure(&e) {
+                Error::AmbiguousOutcome(Box::new(e))
+            } else {
+                e
+            }
+        })
+    }
+
+    // Wait for the response of a request sent.
+    async fn recv_response<R: ObPayload>(
+        &self,
+        rx: oneshot::Receiver<Result<ObTablePacket>>,
+        response: &mut R,
+        timeout: TokioDuration,
+        trace_id: TraceId,
+        start: Instant,
+    ) -> Result<()> {
         // Get result from receiver
         let resp = match tokio::time::timeout(timeout, rx).await {
             Ok(resp) => {
@@ -611,6 +778,10 @@ impl Connection {
                 OBKV_RPC_METRICS.observe_rpc_duration("execute_payload", start.elapsed());
                 Ok(())
             }
+            Ok(ObTablePacket::TransportPacket {
+                error,
+                code: TransportCode::ChecksumMismatch,
+            }) => Err(error),
             Ok(ObTablePacket::TransportPacket { error, code }) => Err(CommonErr(
                 CommonErrCode::Rpc,
                 format!("transport code: [{code:?}], error: [{error}]"),
@@ -678,8 +849,9 @@ impl Connection {
                 trace!("Connection::notify_sender fail to notify, id={}", id);
             }
         } else {
-            warn!(
-                "Connection sender fail to found sender for request id={}",
+            // the request timed out, was dropped or needed no reply
+            debug!(
+                "Connection::notify_sender discard the late response of request id={}",
                 id
             );
         }
@@ -697,14 +869,15 @@ impl Connection {
         Builder::new().build().await
     }
 
-    #[allow(dead_code)]
     /// close the connection
     ///
-    /// client should use close() if client close the connection voluntarily
-    async fn close(&mut self) -> Result<()> {
-        if self.reader.is_none() {
-            return Ok(());
-        }
+    /// client should use close() if client close the connection voluntarily,
+    /// the requests sent before are written out before the writer stops
+    pub async fn close(&self) -> Result<()> {
+        let reader = match self.reader.lock().unwrap().take() {
+            Some(reader) => reader,
+            None => return Ok(()),
+        };
         self.set_active(false);
 
         // 1. close writer
@@ -713,7 +886,8 @@ impl Connection {
         }
 
         // 2. close reader
-        if let Some(sender) = self.reader_signal_sender.take() {
+        let signal_sender = self.reader_signal_sender.lock().unwrap().take();
+        if let Some(sender) = signal_sender {
             if let Err(e) = sender.send(()).map_err(ConnectionSender::broken_pipe) {
                 error!(
                     "Connection::close fail to send signal to reader, err: {}.",
@@ -721,7 +895,6 @@ impl Connection {
                 );
             }
         }
-        let reader = mem::take(&mut self.reader);
         Connection::cancel_requests(&self.requests);
         drop(reader);
 
@@ -732,9 +905,10 @@ impl Connection {
     ///
     /// shutdown the conection without closing the TCP connection
     fn shutdown(&mut self) -> Result<()> {
-        if self.reader.is_none() {
-            return Ok(());
-        }
+        let reader = match self.reader.get_mut().unwrap().take() {
+            Some(reader) => reader,
+            None => return Ok(()),
+        };
         self.set_active(false);
 
         // 1. shutdown writer
@@ -743,7 +917,7 @@ impl Connection {
         }
 
         // 2. close reader
-        if let Some(sender) = self.reader_signal_sender.take() {
+        if let Some(sender) = self.reader_signal_sender.get_mut().unwrap().take() {
             if let Err(e) = sender.send(()).map_err(ConnectionSender::broken_pipe) {
                 error!(
                     "Connection::shutdown fail to send signal to reader, err: {}.",
@@ -751,7 +925,6 @@ impl Connection {
                 );
             }
         }
-        let reader = mem::take(&mut self.reader);
         Connection::cancel_requests(&self.requests);
         drop(reader);
 
@@ -813,6 +986,10 @@ pub struct Builder {
     runtimes: Option<RuntimesRef>,
 
     sender_channel_size: usize,
+    verify_checksum: bool,
+    compress_type: ObCompressType,
+    compress_min_size: usize,
+    tls_connector: Option<Arc<TlsConnector>>,
 }
 
 const SOCKET_KEEP_ALIVE_SECS: u64 = 15 * 60;
@@ -832,6 +1009,10 @@ impl Builder {
             password: "".to_owned(),
             runtimes: None,
             sender_channel_size: 100,
+            verify_checksum: true,
+            compress_type: ObCompressType::None,
+            compress_min_size: 0,
+            tls_connector: None,
         }
     }
 
@@ -895,6 +1076,26 @@ impl Builder {
         self
     }
 
+    pub fn verify_checksum(mut self, verify_checksum: bool) -> Self {
+        self.verify_checksum = verify_checksum;
+        self
+    }
+
+    pub fn compress_type(mut self, compress_type: ObCompressType) -> Self {
+        self.compress_type = compress_type;
+        self
+    }
+
+    pub fn compress_min_size(mut self, min_size: usize) -> Self {
+        self.compress_min_size = min_size;
+        self
+    }
+
+    pub fn tls_connector(mut self, tls_connector: Option<Arc<TlsConnector>>) -> Self {
+        self.tls_connector = tls_connector;
+        self
+    }
+
     fn generate_uniqueid(addr: SocketAddr) -> u64 {
         /* uniqueId(64 bytes)
          * ip: 32
@@ -953,15 +1154,39 @@ impl Builder {
 
             let id = Self::generate_uniqueid(stream.local_addr().unwrap());
 
+            let (read_stream, write_stream): (ReadStream, WriteStream) = match &self.tls_connector {
+                Some(tls_connector) => {
+                    let stream = match tls_connector.connect(&addr, stream).await {
+                        Ok(stream) => stream,
+                        Err(e) => {
+                            error!(
+                                "Builder::build fail to handshake with {}, err: {}.",
+                                addr, e
+                            );
+                            return Err(e);
+                        }
+                    };
+                    let (read_stream, write_stream) = tokio::io::split(stream);
+                    (Box::new(read_stream), Box::new(write_stream))
+                }
+                None => {
+                    let (read_stream, write_stream) = stream.into_split();
+                    (Box::new(read_stream), Box::new(write_stream))
+                }
+            };
+
             debug!("Builder::build succeeds in connecting to {}.", addr);
 
             let result = Connection::internal_new(
                 id,
                 addr,
-                stream,
+                read_stream,
+                write_stream,
                 self.runtimes.unwrap(),
                 self.sender_channel_size,
-            );
+                self.verify_checksum,
+            )
+            .map(|conn| conn.with_compression(self.compress_type, self.compress_min_size));
 
             OBKV_RPC_METRICS.observe_rpc_duration("connect", start.elapsed());
 
@@ -982,6 +1207,18 @@ mod test {
     use bytes::{BufMut, BytesMut};
 
     use super::*;
+    use crate::{
+        client::table_client::ObClientRuntimes,
+        mock::{Fault, MockCluster, MockTable, RequestKind},
+        rpc::{
+            protocol::{
+                payloads::{ObTableOperationRequest, ObTableOperationResult, ObTableOperationType},
+                DEFAULT_FLAG,
+            },
+            tls::TlsConfig,
+        },
+        serde_obkv::value::{ObjType, Value},
+    };
 
     const TEST_SERVER_IP: &str = "127.0.0.1";
     const TEST_SERVER_PORT: u16 = 2882;
@@ -1014,7 +1251,7 @@ mod test {
         let mut builder = Builder::new();
         builder = builder.ip(TEST_SERVER_IP).port(TEST_SERVER_PORT);
 
-        let mut conn: Connection = builder.build().await.expect("Create OBKV Client");
+        let conn: Connection = builder.build().await.expect("Create OBKV Client");
 
         let channel_id = packet.channel_id().unwrap();
         let res = conn
@@ -1025,4 +1262,96 @@ mod test {
         assert!(res.is_ok());
         assert!(conn.close().await.is_ok());
     }
+
+    #[test]
+    fn test_abandoned_requests() {
+        let cluster = MockCluster::start(
+            1,
+            vec![MockTable::new("test_table", &[("c1", ObjType::Varchar)])],
+        )
+        .unwrap();
+        let addr = cluster.servers()[0].clone();
+        let runtimes = Arc::new(ObClientRuntimes::test_default());
+        runtimes.bg_runtime.block_on(async {
+            // no login, which would set the version of the server globally
+            let conn = Builder::new()
+                .ip(addr.ip())
+                .port(addr.svr_port() as u16)
+                .runtimes(runtimes.clone())
+                .build()
+                .await
+                .expect("fail to build connection");
+            let get = |timeout| {
+                let mut payload = ObTableOperationRequest::new(
+                    "test_table",
+                    ObTableOperationType::Get,
+                    vec![Value::from("k")],
+                    Some(vec!["c2".to_owned()]),
+                    None,
+                    timeout,
+                    DEFAULT_FLAG,
+                );
+                payload.set_partition_id(0);
+                payload
+            };
+            let mut result = ObTableOperationResult::new();
+
+            // a request dropped while waiting for its response
+            cluster.inject(
+                Fault::delay(Duration::from_millis(300))
+                    .on(RequestKind::Execute)
+                    .times(1),
+            );
+            let mut payload = get(Duration::from_secs(3));
+            let execute = conn.execute(&mut payload, &mut result);
+            assert!(tokio::time::timeout(Duration::from_millis(50), execute)
+                .await
+                .is_err());
+            assert!(conn.requests.lock().unwrap().is_empty());
+            assert_eq!(0, conn.load());
+
+            // a request not waiting for its response
+            let mut payload = get(Duration::ZERO);
+            conn.execute(&mut payload, &mut result).await.unwrap();
+            assert!(conn.requests.lock().unwrap().is_empty());
+
+            // the late responses are discarded
+            tokio::time::sleep(Duration::from_millis(400)).await;
+            let mut payload = get(Duration::from_secs(3));
+            conn.execute(&mut payload, &mut result).await.unwrap();
+            assert!(conn.requests.lock().unwrap().is_empty());
+            assert_eq!(0, conn.load());
+        });
+    }
+
+    #[test]
+    fn test_tls_connect() {
+        let runtimes = Arc::new(ObClientRuntimes::test_default());
+        runtimes.bg_runtime.block_on(async {
+            let addr = tls::test::start_echo_server(false).await;
+            let builder = Builder::new()
+                .ip(TEST_SERVER_IP)
+                .port(addr.port())
+                .runtimes(runtimes.clone());
+
+            let tls_connector = TlsConnector::new(&tls::test::ca_config()).unwrap();
+            let conn = builder
+                .clone()
+                .tls_connector(Some(Arc::new(tls_connector)))
+                .build()
+                .await;
+            assert!(conn.is_ok());
+
+            let tls_config = TlsConfig {
+                ca_cert_file: Some(tls::test::cert_path("other_ca.pem")),
+                ..Default::default()
+            };
+            let tls_connector = TlsConnector::new(&tls_config).unwrap();
+            let conn = builder
+                .tls_connector(Some(Arc::new(tls_connector)))
+                .build()
+                .await;
+            assert!(conn.is_err());
+        });
+    }
 }
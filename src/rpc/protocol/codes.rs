@@ -1455,6 +1455,15 @@ impl ResultCodes {
         )
     }
 
+    /// Whether the operation may have been applied although it failed with
+    /// the code.
+    pub fn is_ambiguous(self) -> bool {
+        matches!(
+            self,
+            ResultCodes::OB_TRANS_UNKNOWN | ResultCodes::OB_TRANS_RPC_TIMEOUT
+        )
+    }
+
     pub fn need_retry(self) -> bool {
         if self.need_refresh_table() {
             return true;
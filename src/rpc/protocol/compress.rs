@@ -0,0 +1,113 @@
+/*-
+ * #%L
+ * OBKV Table Client Framework
+ * %%
+ * Copyright (C) 2021 OceanBase
+ * %%
+ * OBKV Table Client Framework is licensed under Mulan PSL v2.
+ * You can use this software according to the terms and conditions of the
+ * Mulan PSL v2. You may obtain a copy of Mulan PSL v2 at:
+ *          http://license.coscl.org.cn/MulanPSL2
+ * THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ * KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ * NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ * See the Mulan PSL v2 for more details.
+ * #L%
+ */
+
+//! Packet compression in the formats of the observer compressors: raw lz4
+//! and snappy blocks, zlib streams and zstd frames.
+
+use std::io::{self, Read, Write};
+
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+
+use super::{ObCompressType, Result};
+
+const ZSTD_LEVEL: i32 = 1;
+
+pub fn is_compressed(compress_type: &ObCompressType) -> bool {
+    !matches!(
+        compress_type,
+        ObCompressType::Invalid | ObCompressType::None
+    )
+}
+
+/// Compress `src` with `compress_type`.
+pub fn compress(compress_type: &ObCompressType, src: &[u8]) -> Result<Vec<u8>> {
+    match compress_type {
+        ObCompressType::Invalid | ObCompressType::None => Ok(src.to_vec()),
+        ObCompressType::LZ4 | ObCompressType::LZ4191 => Ok(lz4_flex::block::compress(src)),
+        ObCompressType::Snappy => snap::raw::Encoder::new()
+            .compress_vec(src)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e)),
+        ObCompressType::Zlib => {
+            let mut encoder = ZlibEncoder::new(Vec::with_capacity(src.len()), Compression::fast());
+            encoder.write_all(src)?;
+            encoder.finish()
+        }
+        ObCompressType::Zstd | ObCompressType::Zstd138 => zstd::bulk::compress(src, ZSTD_LEVEL),
+    }
+}
+
+/// Decompress `src` with `compress_type`, the content must be
+/// `original_len` bytes long after decompression.
+pub fn decompress(
+    compress_type: &ObCompressType,
+    src: &[u8],
+    original_len: usize,
+) -> Result<Vec<u8>> {
+    let content = match compress_type {
+        ObCompressType::Invalid | ObCompressType::None => src.to_vec(),
+        ObCompressType::LZ4 | ObCompressType::LZ4191 => {
+            lz4_flex::block::decompress(src, original_len)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+        }
+        ObCompressType::Snappy => snap::raw::Decoder::new()
+            .decompress_vec(src)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+        ObCompressType::Zlib => {
+            let mut content = Vec::with_capacity(original_len);
+            ZlibDecoder::new(src).read_to_end(&mut content)?;
+            content
+        }
+        ObCompressType::Zstd | ObCompressType::Zstd138 => zstd::stream::decode_all(src)?,
+    };
+
+    if content.len() != original_len {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "decompress::{compress_type:?} invalid length, expect={original_len}, actual={}",
+                content.len()
+            ),
+        ));
+    }
+
+    Ok(content)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_compress_decompress() {
+        let src = r#"{"k":"v","arr":[1,2,3]}"#.repeat(64);
+        for compress_type in [
+            ObCompressType::LZ4,
+            ObCompressType::Snappy,
+            ObCompressType::Zlib,
+            ObCompressType::Zstd,
+        ] {
+            assert!(is_compressed(&compress_type));
+            let compressed = compress(&compress_type, src.as_bytes()).expect("fail to compress");
+            assert!(compressed.len() < src.len(), "{compress_type:?}");
+            let content =
+                decompress(&compress_type, &compressed, src.len()).expect("fail to decompress");
+            assert_eq!(src.as_bytes(), &content[..]);
+            assert!(decompress(&compress_type, &compressed, src.len() + 1).is_err());
+        }
+        assert!(!is_compressed(&ObCompressType::None));
+    }
+}
@@ -25,9 +25,12 @@ use std::{
 use bytes::{Buf, BufMut, BytesMut};
 use tokio_util::codec::{Decoder, Encoder};
 
-use crate::{error::Error, serde_obkv::util, util as u};
+use crate::{
+    error::Error, rpc::util::checksum::ob_crc64::ObCrc64Sse42, serde_obkv::util, util as u,
+};
 
 pub mod codes;
+pub mod compress;
 pub mod payloads;
 pub mod query;
 
@@ -52,6 +55,10 @@ pub enum ObCompressType {
     Snappy = 3,
     Zlib = 4,
     Zstd = 5,
+    /// `ZSTD_1_3_8_COMPRESSOR` of the observer, zstd frames too.
+    Zstd138 = 6,
+    /// `LZ4_1_9_1_COMPRESSOR` of the observer, raw lz4 blocks too.
+    LZ4191 = 7,
 }
 
 impl ObCompressType {
@@ -63,6 +70,8 @@ impl ObCompressType {
             3 => Ok(ObCompressType::Snappy),
             4 => Ok(ObCompressType::Zlib),
             5 => Ok(ObCompressType::Zstd),
+            6 => Ok(ObCompressType::Zstd138),
+            7 => Ok(ObCompressType::LZ4191),
             _ => Err(io::Error::new(
                 io::ErrorKind::InvalidData,
                 format!("ObCompressType::from_i32 invalid compress type, i={i}"),
@@ -291,9 +300,9 @@ impl Default for ObRpcPacketHeader {
     }
 }
 
-const STREAM_FLAG: u16 = 1 << 14;
-const RESP_FLAG: u16 = 1 << 15;
-const STREAM_LAST_FLAG: u16 = 1 << 13;
+pub(crate) const STREAM_FLAG: u16 = 1 << 14;
+pub(crate) const RESP_FLAG: u16 = 1 << 15;
+pub(crate) const STREAM_LAST_FLAG: u16 = 1 << 13;
 // OB_LOG_LEVEL_NONE 7
 // OB_LOG_LEVEL_NP -1 (no print log)
 // OB_LOG_LEVEL_ERROR 0
@@ -334,6 +343,11 @@ impl ObRpcPacketHeader {
         }
     }
 
+    #[inline]
+    pub fn pcode(&self) -> u32 {
+        self.pcode
+    }
+
     #[inline]
     pub fn set_pcode(&mut self, c: u32) {
         self.pcode = c;
@@ -370,6 +384,21 @@ impl ObRpcPacketHeader {
         self.checksum = checksum;
     }
 
+    #[inline]
+    pub fn set_compress_type(&mut self, compress_type: ObCompressType) {
+        self.compress_type = compress_type;
+    }
+
+    #[inline]
+    pub fn set_original_len(&mut self, original_len: i32) {
+        self.original_len = original_len;
+    }
+
+    #[inline]
+    pub fn tenant_id(&self) -> u64 {
+        self.tenant_id
+    }
+
     #[inline]
     pub fn set_tenant_id(&mut self, tenant_id: u64) {
         self.tenant_id = tenant_id;
@@ -380,6 +409,11 @@ impl ObRpcPacketHeader {
         self.session_id = session_id;
     }
 
+    #[inline]
+    pub fn flag(&self) -> u16 {
+        self.flag
+    }
+
     #[inline]
     pub fn set_flag(&mut self, flag: u16) {
         self.flag = flag;
@@ -660,6 +694,11 @@ pub trait ObPayload: ProtoEncoder + ProtoDecoder {
     fn flag(&self) -> u16 {
         self.base().flag
     }
+    // Whether the request can be applied twice safely, the requests not
+    // idempotent aren't sent again once they may have reached the server.
+    fn is_idempotent(&self) -> bool {
+        true
+    }
     //set tenant id
     fn set_tenant_id(&mut self, _tenant_id: Option<u64>) {}
     //set credential
@@ -721,6 +760,7 @@ pub enum TransportCode {
     Timeout = -20002,
     SendFailure = -20003,
     NullResponse = -20004,
+    ChecksumMismatch = -20005,
 }
 
 #[derive(Debug)]
@@ -744,8 +784,30 @@ pub struct ObTablePacketCodec {
     dlen: i32,
     //data length
     chid: i32, //channel id
+    verify_checksum: bool,
+}
+
+/// The checksum in the header of a response does not match its content,
+/// returned by [`ObTablePacketCodec::decode`] wrapped in an [`io::Error`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChecksumMismatch {
+    pub channel_id: i32,
+    pub expected: i64,
+    pub actual: i64,
+}
+
+impl fmt::Display for ChecksumMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "response checksum mismatch, chid={}, expected={}, actual={}",
+            self.channel_id, self.expected, self.actual
+        )
+    }
 }
 
+impl std::error::Error for ChecksumMismatch {}
+
 const API_VERSION: u8 = 1;
 static MAGIC_HEADER_FLAG: &[u8] = &[API_VERSION, 0xDB, 0xDB, 0xCE];
 static RESERVED: &[u8] = &[0, 0, 0, 0];
@@ -777,7 +839,17 @@ impl Default for ObTablePacketCodec {
 
 impl ObTablePacketCodec {
     pub fn new() -> ObTablePacketCodec {
-        ObTablePacketCodec { chid: -1, dlen: 0 }
+        ObTablePacketCodec {
+            chid: -1,
+            dlen: 0,
+            verify_checksum: true,
+        }
+    }
+
+    /// Whether to verify the checksums of responses, enabled by default.
+    pub fn verify_checksum(mut self, verify_checksum: bool) -> Self {
+        self.verify_checksum = verify_checksum;
+        self
     }
 }
 
@@ -841,22 +913,50 @@ impl Decoder for ObTablePacketCodec {
                 trace!("ObTablePacketCodec::decode decoding header, chid={}, dlen={}, header={:?}, content={:?}.",
                        id, data_len, header, content.to_vec());
 
+                // the checksum covers the payload as it is transferred
+                if self.verify_checksum {
+                    let checksum = ObCrc64Sse42::crc64(0, &content);
+                    if checksum != header.checksum {
+                        let mismatch = ChecksumMismatch {
+                            channel_id: id,
+                            expected: header.checksum,
+                            actual: checksum,
+                        };
+                        error!(
+                            "ObTablePacketCodec::decode {}, header={:?}",
+                            mismatch, header
+                        );
+                        return Err(io::Error::new(io::ErrorKind::InvalidData, mismatch));
+                    }
+                }
+
                 match header.compress_type {
-                    ObCompressType::Invalid => assert_eq!(header.original_len, 0),
-                    ObCompressType::None => (), //TODO assert?
-                    ObCompressType::Zstd => {
-                        let new_content = zstd::stream::decode_all(&content.to_vec()[..])?;
-                        assert_eq!(new_content.len(), header.original_len as usize);
+                    ObCompressType::Invalid if header.original_len != 0 => {
+                        error!(
+                            "ObTablePacketCodec::decode invalid compress type with original length, header={:?}",
+                            header
+                        );
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!(
+                                "ObTablePacketCodec::decode invalid compress type with original_len={}",
+                                header.original_len
+                            ),
+                        ));
+                    }
+                    ObCompressType::Invalid | ObCompressType::None => (),
+                    _ => {
+                        let new_content = compress::decompress(
+                            &header.compress_type,
+                            &content,
+                            header.original_len as usize,
+                        )?;
                         content.clear();
                         content.reserve(new_content.len());
                         content.extend_from_slice(&new_content);
-                        trace!("ObTablePacketCodec::decode decompress content by zstd, chid={}, dlen={}, header={:?}.",
-                               id, data_len, header);
+                        trace!("ObTablePacketCodec::decode decompress content by {:?}, chid={}, dlen={}, header={:?}.",
+                               header.compress_type, id, data_len, header);
                     }
-                    _ => panic!(
-                        "ObTablePacketCodec::decode unsupported compress type: {:?}",
-                        header.compress_type
-                    ),
                 }
 
                 return Ok(Some(ObTablePacket::ServerPacket {
@@ -924,6 +1024,7 @@ mod test {
 
         let mut header = ObRpcPacketHeader::new();
         header.timeout = 99;
+        header.set_checksum(ObCrc64Sse42::crc64(0, b"hello"));
         assert!(header.encode(&mut content).is_ok());
         content.reserve(5);
         content.put_slice(b"hello");
@@ -963,6 +1064,98 @@ mod test {
         assert_eq!(-1, codec.chid);
     }
 
+    fn encode_server_packet(id: i32, header: &ObRpcPacketHeader, payload: &[u8]) -> BytesMut {
+        let mut content = BytesMut::new();
+        assert!(header.encode(&mut content).is_ok());
+        content.put_slice(payload);
+        let mut buf = BytesMut::new();
+        let packet = ObTablePacket::ServerPacket {
+            code: None,
+            id,
+            content,
+            header: Box::new(None),
+        };
+        assert!(ObTablePacketCodec::new().encode(packet, &mut buf).is_ok());
+        buf
+    }
+
+    #[test]
+    fn test_verify_checksum() {
+        let mut header = ObRpcPacketHeader::new();
+        header.set_checksum(ObCrc64Sse42::crc64(0, b"hello"));
+        let mut buf = encode_server_packet(7, &header, b"hello");
+        let ret = ObTablePacketCodec::new().decode(&mut buf);
+        assert!(matches!(
+            ret,
+            Ok(Some(ObTablePacket::ServerPacket { id: 7, .. }))
+        ));
+
+        // corrupted payload
+        let mut buf = encode_server_packet(7, &header, b"hellO");
+        let err = ObTablePacketCodec::new().decode(&mut buf).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, err.kind());
+        let mismatch = err
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<ChecksumMismatch>())
+            .unwrap();
+        assert_eq!(7, mismatch.channel_id);
+        assert_eq!(header.checksum, mismatch.expected);
+
+        // verification disabled
+        let mut buf = encode_server_packet(7, &header, b"hellO");
+        let ret = ObTablePacketCodec::new()
+            .verify_checksum(false)
+            .decode(&mut buf);
+        assert!(matches!(
+            ret,
+            Ok(Some(ObTablePacket::ServerPacket { id: 7, .. }))
+        ));
+    }
+
+    #[test]
+    fn test_decode_compressed() {
+        let payload = b"hello world".repeat(16);
+        for compress_type in [
+            ObCompressType::LZ4,
+            ObCompressType::Snappy,
+            ObCompressType::Zlib,
+            ObCompressType::Zstd,
+            ObCompressType::Zstd138,
+            ObCompressType::LZ4191,
+        ] {
+            let compressed = compress::compress(&compress_type, &payload).unwrap();
+            let mut header = ObRpcPacketHeader::new();
+            header.set_checksum(ObCrc64Sse42::crc64(0, &compressed));
+            header.set_compress_type(compress_type);
+            header.set_original_len(payload.len() as i32);
+            let mut buf = encode_server_packet(7, &header, &compressed);
+            match ObTablePacketCodec::new().decode(&mut buf) {
+                Ok(Some(ObTablePacket::ServerPacket { content, .. })) => {
+                    assert_eq!(&payload[..], &content[..])
+                }
+                ret => panic!("unexpected decoded packet: {ret:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_decode_invalid_compress_type() {
+        assert_eq!(
+            ObCompressType::Zstd138,
+            ObCompressType::from_i32(6).unwrap()
+        );
+        assert_eq!(ObCompressType::LZ4191, ObCompressType::from_i32(7).unwrap());
+        assert!(ObCompressType::from_i32(8).is_err());
+
+        let mut header = ObRpcPacketHeader::new();
+        header.set_checksum(ObCrc64Sse42::crc64(0, b"hello"));
+        header.set_compress_type(ObCompressType::Invalid);
+        header.set_original_len(5);
+        let mut buf = encode_server_packet(7, &header, b"hello");
+        let err = ObTablePacketCodec::new().decode(&mut buf).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, err.kind());
+    }
+
     #[test]
     fn test_decompress() {
         let s = "hello world";
@@ -17,6 +17,8 @@
 
 use std::{clone::Clone, fmt::Debug};
 
+use chrono::{FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+
 use crate::{
     error::{CommonErrCode, Error::Common as CommonErr, Result},
     serde_obkv::value::{CollationLevel, CollationType, ObjMeta, ObjType, Value},
@@ -28,7 +30,9 @@ pub trait ObColumn: ObColumnClone + Debug + Send + Sync {
     fn get_ob_obj_type(&self) -> ObjType;
     fn get_ref_column_names(&self) -> Vec<String>;
     fn get_ob_collation_type(&self) -> &CollationType;
-    fn eval_value(&self, refs: &[Value]) -> Result<Value>;
+    /// Value of the column from the values of the columns it refers to,
+    /// temporal values are parsed in `time_zone`.
+    fn eval_value(&self, refs: &[Value], time_zone: FixedOffset) -> Result<Value>;
 }
 
 pub trait ObColumnClone {
@@ -101,7 +105,7 @@ impl ObColumn for ObGeneratedColumn {
     }
 
     // TODO: impl express, ObGeneratedColumn in java sdk
-    fn eval_value(&self, refs: &[Value]) -> Result<Value> {
+    fn eval_value(&self, refs: &[Value], _time_zone: FixedOffset) -> Result<Value> {
         Ok(refs[0].clone())
     }
 }
@@ -154,7 +158,7 @@ impl ObColumn for ObSimpleColumn {
         &self.ob_collation_type
     }
 
-    fn eval_value(&self, refs: &[Value]) -> Result<Value> {
+    fn eval_value(&self, refs: &[Value], time_zone: FixedOffset) -> Result<Value> {
         if refs.len() != 1 {
             error!("ObSimpleColumn::eval_value ObSimpleColumn is refer to itself so that the length of the refs must be 1. refs:{refs:?}");
             return Err(CommonErr(
@@ -163,10 +167,23 @@ impl ObColumn for ObSimpleColumn {
             ));
         }
         match self.ob_obj_type {
-            ObjType::Varchar => match &refs[0] {
-                Value::String(_v, _meta) => Ok(refs[0].clone()),
-                _ => unimplemented!(),
-            },
+            ObjType::Varchar | ObjType::Char => {
+                if refs[0].is_min() || refs[0].is_max() {
+                    return Ok(refs[0].clone());
+                }
+                // keep the same meta as partition bounds so that values are comparable
+                let meta = ObjMeta::new(
+                    self.ob_obj_type.clone(),
+                    CollationLevel::Numeric,
+                    self.ob_collation_type.clone(),
+                    10,
+                );
+                match &refs[0] {
+                    Value::String(v, _meta) => Ok(Value::String(v.clone(), meta)),
+                    Value::Bytes(v, _meta) => Ok(Value::Bytes(v.clone(), meta)),
+                    _ => unimplemented!(),
+                }
+            }
             ObjType::UInt64 | ObjType::Int64 | ObjType::UInt32 | ObjType::Int32 => {
                 if refs[0].is_min() || refs[0].is_max() {
                     return Ok(refs[0].clone());
@@ -220,7 +237,203 @@ impl ObColumn for ObSimpleColumn {
                     _ => unimplemented!(),
                 }
             }
+            ObjType::DateTime
+            | ObjType::Timestamp
+            | ObjType::Date
+            | ObjType::Time
+            | ObjType::Year => {
+                if refs[0].is_min() || refs[0].is_max() {
+                    return Ok(refs[0].clone());
+                }
+                eval_temporal_value(&self.ob_obj_type, &refs[0], time_zone)
+            }
             _ => unimplemented!(),
         }
     }
 }
+
+const DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.f";
+const DATE_FORMAT: &str = "%Y-%m-%d";
+const TIME_FORMAT: &str = "%H:%M:%S%.f";
+
+/// Convert `value` into the temporal `obj_type`, strings are parsed in the
+/// format used by partition bounds, e.g. `2023-01-01 00:00:00`.
+///
+/// Timestamps are instants, the wall clocks converted into them are in the
+/// server `time_zone`.
+fn eval_temporal_value(obj_type: &ObjType, value: &Value, time_zone: FixedOffset) -> Result<Value> {
+    let convert_failed = || {
+        CommonErr(
+            CommonErrCode::ConvertFailed,
+            format!("ObSimpleColumn::eval_value fail to convert {value:?} into {obj_type:?}"),
+        )
+    };
+    let meta = ObjMeta::new_numeric_meta(obj_type.clone());
+    match obj_type {
+        ObjType::DateTime | ObjType::Timestamp => {
+            let wall_clock = match value {
+                Value::String(s, _) => NaiveDateTime::parse_from_str(s, DATETIME_FORMAT)
+                    .or_else(|_| {
+                        NaiveDate::parse_from_str(s, DATE_FORMAT)
+                            .map(|d| d.and_hms_opt(0, 0, 0).unwrap())
+                    })
+                    .map_err(|_| convert_failed())?,
+                Value::DateTime(_, _) => value.as_naive_datetime(),
+                Value::Timestamp(v, _) if *obj_type == ObjType::Timestamp => {
+                    return Ok(Value::Timestamp(*v, meta));
+                }
+                Value::Timestamp(_, _) => value
+                    .as_naive_datetime()
+                    .checked_add_signed(chrono::Duration::seconds(
+                        time_zone.local_minus_utc() as i64
+                    ))
+                    .ok_or_else(convert_failed)?,
+                Value::Date(_, _) => value.as_naive_date().and_hms_opt(0, 0, 0).unwrap(),
+                _ => return Err(convert_failed()),
+            };
+            if *obj_type == ObjType::DateTime {
+                Ok(Value::DateTime(wall_clock.timestamp_micros(), meta))
+            } else {
+                let instant = time_zone
+                    .from_local_datetime(&wall_clock)
+                    .single()
+                    .ok_or_else(convert_failed)?;
+                Ok(Value::Timestamp(instant.timestamp_micros(), meta))
+            }
+        }
+        ObjType::Date => {
+            let date = match value {
+                Value::String(s, _) => {
+                    NaiveDate::parse_from_str(s, DATE_FORMAT).map_err(|_| convert_failed())?
+                }
+                Value::Date(_, _) => value.as_naive_date(),
+                Value::DateTime(_, _) => value.as_naive_datetime().date(),
+                Value::Timestamp(_, _) => time_zone
+                    .from_utc_datetime(&value.as_naive_datetime())
+                    .date_naive(),
+                _ => return Err(convert_failed()),
+            };
+            match Value::from(date) {
+                Value::Date(d, _) => Ok(Value::Date(d, meta)),
+                _ => unreachable!(),
+            }
+        }
+        ObjType::Time => match value {
+            Value::String(s, _) => {
+                match Value::from(
+                    NaiveTime::parse_from_str(s, TIME_FORMAT).map_err(|_| convert_failed())?,
+                ) {
+                    Value::Time(t, _) => Ok(Value::Time(t, meta)),
+                    _ => unreachable!(),
+                }
+            }
+            Value::Time(t, _) => Ok(Value::Time(*t, meta)),
+            _ => Err(convert_failed()),
+        },
+        ObjType::Year => {
+            let year = match value {
+                Value::String(s, _) => s.parse::<u16>()?,
+                Value::Year(_, _) => value.as_year(),
+                Value::Int32(_, _) | Value::UInt32(_, _) => {
+                    u16::try_from(value.as_i32()).map_err(|_| convert_failed())?
+                }
+                Value::Int64(_, _) | Value::UInt64(_, _) => {
+                    u16::try_from(value.as_i64()).map_err(|_| convert_failed())?
+                }
+                _ => return Err(convert_failed()),
+            };
+            match Value::year(year)? {
+                Value::Year(v, _) => Ok(Value::Year(v, meta)),
+                _ => unreachable!(),
+            }
+        }
+        _ => Err(convert_failed()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::util::timezone::utc;
+
+    #[test]
+    fn test_eval_datetime_value() {
+        let column =
+            ObSimpleColumn::new("c1".to_owned(), 0, ObjType::DateTime, CollationType::Binary);
+        let bound = column
+            .eval_value(&[Value::from("2023-01-01 00:00:00")], utc())
+            .unwrap();
+        let ndt = NaiveDate::from_ymd_opt(2023, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        assert_eq!(
+            bound,
+            column.eval_value(&[Value::from(ndt)], utc()).unwrap()
+        );
+        assert_eq!(
+            bound,
+            column
+                .eval_value(
+                    &[Value::from(NaiveDate::from_ymd_opt(2023, 1, 1).unwrap())],
+                    utc()
+                )
+                .unwrap()
+        );
+        let later = column
+            .eval_value(&[Value::from("2023-01-01 00:00:00.000001")], utc())
+            .unwrap();
+        assert!(bound < later);
+        assert!(column
+            .eval_value(&[Value::from("not a date")], utc())
+            .is_err());
+    }
+
+    #[test]
+    fn test_eval_timestamp_in_time_zone() {
+        let east_8 = FixedOffset::east_opt(8 * 3600).unwrap();
+        let bound = eval_temporal_value(
+            &ObjType::Timestamp,
+            &Value::from("2023-01-01 08:00:00"),
+            east_8,
+        )
+        .unwrap();
+        let utc_midnight = NaiveDate::from_ymd_opt(2023, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .timestamp_micros();
+        assert_eq!(
+            bound,
+            Value::Timestamp(utc_midnight, ObjMeta::new_numeric_meta(ObjType::Timestamp))
+        );
+        // An instant is kept as is, only wall clocks are shifted.
+        assert_eq!(
+            bound,
+            eval_temporal_value(&ObjType::Timestamp, &bound, east_8).unwrap()
+        );
+        let date = eval_temporal_value(
+            &ObjType::Date,
+            &bound,
+            FixedOffset::east_opt(-3600).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            date,
+            eval_temporal_value(
+                &ObjType::Date,
+                &Value::from("2022-12-31"),
+                FixedOffset::east_opt(0).unwrap()
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_eval_year_out_of_range() {
+        let utc = FixedOffset::east_opt(0).unwrap();
+        assert!(eval_temporal_value(&ObjType::Year, &Value::from(2023i32), utc).is_ok());
+        assert!(eval_temporal_value(&ObjType::Year, &Value::from(-1i32), utc).is_err());
+        assert!(eval_temporal_value(&ObjType::Year, &Value::from(67_959i64), utc).is_err());
+    }
+}
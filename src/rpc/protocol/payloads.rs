@@ -45,12 +45,39 @@ pub enum ObTableEntityType {
     HKV = 2,
 }
 
+impl ObTableEntityType {
+    pub fn from_i8(i: i8) -> Result<ObTableEntityType> {
+        match i {
+            0 => Ok(ObTableEntityType::Dynamic),
+            1 => Ok(ObTableEntityType::KV),
+            2 => Ok(ObTableEntityType::HKV),
+            _ => Err(io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Invalid entity type: {i}"),
+            )),
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum ObTableConsistencyLevel {
     Strong = 0,
     Eventual = 1,
 }
 
+impl ObTableConsistencyLevel {
+    pub fn from_i8(i: i8) -> Result<ObTableConsistencyLevel> {
+        match i {
+            0 => Ok(ObTableConsistencyLevel::Strong),
+            1 => Ok(ObTableConsistencyLevel::Eventual),
+            _ => Err(io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Invalid consistency level: {i}"),
+            )),
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum ObTableOperationType {
     Get = 0,
@@ -94,6 +121,16 @@ impl ObTableOperationType {
             ObTableOperationType::Append => "append",
         }
     }
+
+    /// Whether applying the operation twice has the effect of applying it
+    /// once.
+    #[inline]
+    pub fn is_idempotent(&self) -> bool {
+        !matches!(
+            self,
+            ObTableOperationType::Increment | ObTableOperationType::Append
+        )
+    }
 }
 
 /// OB row key list.
@@ -123,6 +160,17 @@ impl ObRowKey {
     }
 }
 
+impl ProtoDecoder for ObRowKey {
+    fn decode(&mut self, src: &mut BytesMut) -> Result<()> {
+        let len = util::decode_vi64(src)?;
+        self.keys = Vec::with_capacity(len.max(0) as usize);
+        for _ in 0..len {
+            self.keys.push(decode_value(src)?);
+        }
+        Ok(())
+    }
+}
+
 impl ProtoEncoder for ObRowKey {
     fn encode(&self, buf: &mut BytesMut) -> Result<()> {
         util::encode_vi64(self.keys.len() as i64, buf)?;
@@ -324,14 +372,20 @@ impl ProtoEncoder for ObTableOperation {
 }
 
 impl ProtoDecoder for ObTableOperation {
-    fn decode(&mut self, _src: &mut BytesMut) -> Result<()> {
-        unimplemented!();
+    fn decode(&mut self, src: &mut BytesMut) -> Result<()> {
+        self.decode_base(src)?;
+
+        self.op_type = ObTableOperationType::from_i8(util::decode_i8(src)?)?;
+        self.entity.decode(src)?;
+
+        Ok(())
     }
 }
 
 pub struct ObTableOperationRequest {
     base: BasePayLoad,
     credential: Vec<u8>,
+    idempotent: bool,
     table_name: String,
     table_id: i64,
     partition_id: i64,
@@ -360,6 +414,7 @@ impl ObTableOperationRequest {
         ObTableOperationRequest {
             base,
             credential: vec![],
+            idempotent: operation_type.is_idempotent(),
             table_name: table_name.to_owned(),
             table_id: OB_INVALID_ID,
             partition_id: OB_INVALID_ID,
@@ -379,6 +434,59 @@ impl ObTableOperationRequest {
     pub fn set_partition_id(&mut self, partition_id: i64) {
         self.partition_id = partition_id;
     }
+
+    pub fn table_name(&self) -> &str {
+        &self.table_name
+    }
+
+    pub fn table_id(&self) -> i64 {
+        self.table_id
+    }
+
+    pub fn partition_id(&self) -> i64 {
+        self.partition_id
+    }
+
+    pub fn entity_type(&self) -> ObTableEntityType {
+        self.entity_type
+    }
+
+    pub fn consistency_level(&self) -> ObTableConsistencyLevel {
+        self.consistency_level
+    }
+
+    pub fn set_consistency_level(&mut self, consistency_level: ObTableConsistencyLevel) {
+        self.consistency_level = consistency_level;
+    }
+
+    pub fn table_operation(&self) -> &ObTableOperation {
+        &self.table_operation
+    }
+
+    /// Treat the request as idempotent whatever its operation, so that it's
+    /// sent again after an ambiguous failure.
+    pub fn set_idempotent(&mut self, idempotent: bool) {
+        self.idempotent = idempotent;
+    }
+}
+
+impl Default for ObTableOperationRequest {
+    fn default() -> ObTableOperationRequest {
+        ObTableOperationRequest {
+            base: BasePayLoad::dummy(),
+            credential: vec![],
+            idempotent: true,
+            table_name: "".to_owned(),
+            table_id: OB_INVALID_ID,
+            partition_id: OB_INVALID_ID,
+            entity_type: ObTableEntityType::Dynamic,
+            table_operation: ObTableOperation::new(ObTableOperationType::Get, vec![], None, None),
+            consistency_level: ObTableConsistencyLevel::Strong,
+            return_row_key: false,
+            return_affected_entity: false,
+            return_affected_rows: true,
+        }
+    }
 }
 
 impl ObPayload for ObTableOperationRequest {
@@ -386,6 +494,10 @@ impl ObPayload for ObTableOperationRequest {
         self.credential = credential.to_owned();
     }
 
+    fn is_idempotent(&self) -> bool {
+        self.idempotent
+    }
+
     fn pcode(&self) -> ObTablePacketCode {
         ObTablePacketCode::Execute
     }
@@ -443,8 +555,27 @@ impl ProtoEncoder for ObTableOperationRequest {
 }
 
 impl ProtoDecoder for ObTableOperationRequest {
-    fn decode(&mut self, _src: &mut BytesMut) -> Result<()> {
-        unimplemented!()
+    fn decode(&mut self, src: &mut BytesMut) -> Result<()> {
+        self.decode_base(src)?;
+
+        self.credential = util::decode_bytes_string(src)?;
+        self.table_name = util::decode_vstring(src)?;
+        self.table_id = util::decode_vi64(src)?;
+
+        self.partition_id = if ob_vsn_major() >= 4 {
+            util::split_buf_to(src, 8)?.get_i64()
+        } else {
+            util::decode_vi64(src)?
+        };
+
+        self.entity_type = ObTableEntityType::from_i8(util::decode_i8(src)?)?;
+        self.table_operation.decode(src)?;
+        self.consistency_level = ObTableConsistencyLevel::from_i8(util::decode_i8(src)?)?;
+        self.return_row_key = util::decode_i8(src)? != 0;
+        self.return_affected_entity = util::decode_i8(src)? != 0;
+        self.return_affected_rows = util::decode_i8(src)? != 0;
+
+        Ok(())
     }
 }
 
@@ -534,6 +665,12 @@ impl ObTableBatchOperation {
         self.read_only
     }
 
+    /// Whether every operation of the batch is idempotent.
+    pub fn is_idempotent(&self) -> bool {
+        self.ops.iter().all(|op| op.get_type().is_idempotent())
+            && self.raw_ops.iter().all(|op| op.0.is_idempotent())
+    }
+
     pub fn is_same_type(&self) -> bool {
         self.same_type
     }
@@ -721,14 +858,34 @@ impl ProtoEncoder for ObTableBatchOperation {
 }
 
 impl ProtoDecoder for ObTableBatchOperation {
-    fn decode(&mut self, _src: &mut BytesMut) -> Result<()> {
-        unimplemented!();
+    fn decode(&mut self, src: &mut BytesMut) -> Result<()> {
+        self.decode_base(src)?;
+
+        let ops_num = util::decode_vi64(src)?;
+        if ops_num < 0 {
+            return Err(io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("invalid operations num:{ops_num}"),
+            ));
+        }
+        self.ops.reserve(ops_num as usize);
+        for _ in 0..ops_num {
+            let mut op = ObTableOperation::new(ObTableOperationType::Get, vec![], None, None);
+            op.decode(src)?;
+            self.ops.push(op);
+        }
+
+        self.read_only = util::decode_i8(src)? != 0;
+        self.same_type = util::decode_i8(src)? != 0;
+        self.same_properties_names = util::decode_i8(src)? != 0;
+        Ok(())
     }
 }
 
 pub struct ObTableBatchOperationRequest {
     base: BasePayLoad,
     credential: Vec<u8>,
+    idempotent: bool,
     table_name: String,
     table_id: i64,
     partition_id: i64,
@@ -749,6 +906,7 @@ impl ObTableBatchOperationRequest {
         Self {
             base,
             credential: vec![],
+            idempotent: batch_operation.is_idempotent(),
             table_name: batch_operation.table_name.to_owned(),
             table_id: batch_operation.table_id,
             partition_id: batch_operation.partition_id,
@@ -761,6 +919,49 @@ impl ObTableBatchOperationRequest {
             return_affected_rows: true,
         }
     }
+
+    pub fn table_name(&self) -> &str {
+        &self.table_name
+    }
+
+    pub fn table_id(&self) -> i64 {
+        self.table_id
+    }
+
+    pub fn partition_id(&self) -> i64 {
+        self.partition_id
+    }
+
+    pub fn consistency_level(&self) -> ObTableConsistencyLevel {
+        self.consistency_level
+    }
+
+    pub fn set_consistency_level(&mut self, consistency_level: ObTableConsistencyLevel) {
+        self.consistency_level = consistency_level;
+    }
+
+    pub fn batch_operation(&self) -> &ObTableBatchOperation {
+        &self.batch_operation
+    }
+
+    pub fn is_atomic_op(&self) -> bool {
+        self.atomic_op
+    }
+
+    /// Treat the request as idempotent whatever its operations, so that it's
+    /// sent again after an ambiguous failure.
+    pub fn set_idempotent(&mut self, idempotent: bool) {
+        self.idempotent = idempotent;
+    }
+}
+
+impl Default for ObTableBatchOperationRequest {
+    fn default() -> ObTableBatchOperationRequest {
+        let mut request =
+            ObTableBatchOperationRequest::new(ObTableBatchOperation::new(), Duration::ZERO, 0);
+        request.base = BasePayLoad::dummy();
+        request
+    }
 }
 
 impl ObPayload for ObTableBatchOperationRequest {
@@ -768,6 +969,10 @@ impl ObPayload for ObTableBatchOperationRequest {
         self.credential = credential.to_owned();
     }
 
+    fn is_idempotent(&self) -> bool {
+        self.idempotent
+    }
+
     fn pcode(&self) -> ObTablePacketCode {
         ObTablePacketCode::BatchExecute
     }
@@ -825,8 +1030,31 @@ impl ProtoEncoder for ObTableBatchOperationRequest {
 }
 
 impl ProtoDecoder for ObTableBatchOperationRequest {
-    fn decode(&mut self, _src: &mut BytesMut) -> Result<()> {
-        unimplemented!()
+    fn decode(&mut self, src: &mut BytesMut) -> Result<()> {
+        self.decode_base(src)?;
+
+        self.credential = util::decode_bytes_string(src)?;
+        self.table_name = util::decode_vstring(src)?;
+        self.table_id = util::decode_vi64(src)?;
+
+        self.entity_type = ObTableEntityType::from_i8(util::decode_i8(src)?)?;
+        self.batch_operation.decode(src)?;
+        self.consistency_level = ObTableConsistencyLevel::from_i8(util::decode_i8(src)?)?;
+        self.return_row_key = util::decode_i8(src)? != 0;
+        self.return_affected_entity = util::decode_i8(src)? != 0;
+        self.return_affected_rows = util::decode_i8(src)? != 0;
+        self.partition_id = if ob_vsn_major() >= 4 {
+            util::split_buf_to(src, 8)?.get_i64()
+        } else {
+            util::decode_vi64(src)?
+        };
+        self.atomic_op = util::decode_i8(src)? != 0;
+
+        self.batch_operation.table_name = self.table_name.clone();
+        self.batch_operation.table_id = self.table_id;
+        self.batch_operation.partition_id = self.partition_id;
+        self.batch_operation.atomic_op = self.atomic_op;
+        Ok(())
     }
 }
 
@@ -882,6 +1110,14 @@ impl ObPayload for ObRpcResultWarningMsg {
     fn base_mut(&mut self) -> &mut BasePayLoad {
         &mut self.base
     }
+
+    fn content_len(&self) -> Result<usize> {
+        Ok(util::encoded_length_bytes(&self.msg)
+            + util::encoded_length_vi64(self.timestamp)
+            + util::encoded_length_vi32(self.log_level)
+            + util::encoded_length_vi32(self.line_no)
+            + util::encoded_length_vi32(self.code))
+    }
 }
 
 impl ProtoDecoder for ObRpcResultWarningMsg {
@@ -899,8 +1135,16 @@ impl ProtoDecoder for ObRpcResultWarningMsg {
 }
 
 impl ProtoEncoder for ObRpcResultWarningMsg {
-    fn encode(&self, _buf: &mut BytesMut) -> Result<()> {
-        unimplemented!();
+    fn encode(&self, buf: &mut BytesMut) -> Result<()> {
+        self.encode_header(buf)?;
+
+        util::encode_bytes(&self.msg, buf)?;
+        util::encode_vi64(self.timestamp, buf)?;
+        util::encode_vi32(self.log_level, buf)?;
+        util::encode_vi32(self.line_no, buf)?;
+        util::encode_vi32(self.code, buf)?;
+
+        Ok(())
     }
 }
 
@@ -943,6 +1187,14 @@ impl ObRpcResultCode {
     pub fn warning_msgs(&self) -> Vec<ObRpcResultWarningMsg> {
         self.warning_msgs.clone()
     }
+
+    pub fn set_rcode(&mut self, rcode: ResultCodes) {
+        self.rcode = rcode;
+    }
+
+    pub fn set_message(&mut self, msg: &str) {
+        self.msg = msg.as_bytes().to_vec();
+    }
 }
 
 impl ObPayload for ObRpcResultCode {
@@ -953,6 +1205,16 @@ impl ObPayload for ObRpcResultCode {
     fn base_mut(&mut self) -> &mut BasePayLoad {
         &mut self.base
     }
+
+    fn content_len(&self) -> Result<usize> {
+        let mut len = util::encoded_length_vi32(self.rcode as i32)
+            + util::encoded_length_bytes(&self.msg)
+            + util::encoded_length_vi32(self.warning_msgs.len() as i32);
+        for warn_msg in &self.warning_msgs {
+            len += warn_msg.len()?;
+        }
+        Ok(len)
+    }
 }
 
 impl ProtoDecoder for ObRpcResultCode {
@@ -979,8 +1241,17 @@ impl ProtoDecoder for ObRpcResultCode {
 }
 
 impl ProtoEncoder for ObRpcResultCode {
-    fn encode(&self, _buf: &mut BytesMut) -> Result<()> {
-        unimplemented!();
+    fn encode(&self, buf: &mut BytesMut) -> Result<()> {
+        self.encode_header(buf)?;
+
+        util::encode_vi32(self.rcode as i32, buf)?;
+        util::encode_bytes(&self.msg, buf)?;
+
+        util::encode_vi32(self.warning_msgs.len() as i32, buf)?;
+        for warn_msg in &self.warning_msgs {
+            warn_msg.encode(buf)?;
+        }
+        Ok(())
     }
 }
 
@@ -1096,9 +1367,51 @@ impl ObTableLoginRequest {
     }
 }
 
+impl ObTableLoginRequest {
+    pub fn tenant_name(&self) -> &str {
+        &self.tenant_name
+    }
+
+    pub fn user_name(&self) -> &str {
+        &self.user_name
+    }
+
+    pub fn database_name(&self) -> &str {
+        &self.database_name
+    }
+}
+
+impl Default for ObTableLoginRequest {
+    fn default() -> ObTableLoginRequest {
+        let mut request = ObTableLoginRequest::new("", "", "", "");
+        request.base = BasePayLoad::dummy();
+        request
+    }
+}
+
 impl ProtoDecoder for ObTableLoginRequest {
-    fn decode(&mut self, _src: &mut BytesMut) -> Result<()> {
-        unimplemented!()
+    fn decode(&mut self, src: &mut BytesMut) -> Result<()> {
+        self.decode_base(src)?;
+
+        self.auth_method = util::decode_u8(src)?;
+        self.client_type = util::decode_u8(src)?;
+        self.client_version = util::decode_u8(src)?;
+        self.reserved1 = util::decode_u8(src)?;
+
+        self.client_capabilities = util::decode_vi32(src)?;
+        self.max_packet_size = util::decode_vi32(src)?;
+        self.reserved2 = util::decode_vi32(src)?;
+        self.reserved3 = util::decode_vi64(src)?;
+
+        self.tenant_name = util::decode_vstring(src)?;
+        self.user_name = util::decode_vstring(src)?;
+        self.pass_secret = util::decode_bytes_string(src)?;
+        self.pass_scramble = util::decode_vstring(src)?;
+        self.database_name = util::decode_vstring(src)?;
+
+        self.ttl_us = util::decode_vi64(src)?;
+
+        Ok(())
     }
 }
 
@@ -1142,6 +1455,18 @@ impl ObTableLoginResult {
     pub fn serer_version(&self) -> &str {
         &self.server_version
     }
+
+    pub fn set_server_version(&mut self, server_version: &str) {
+        self.server_version = server_version.to_owned();
+    }
+
+    pub fn set_login_credential(&mut self, credential: Vec<u8>) {
+        self.credential = credential;
+    }
+
+    pub fn set_login_tenant_id(&mut self, tenant_id: u64) {
+        self.tenant_id = tenant_id;
+    }
 }
 
 impl ObPayload for ObTableLoginResult {
@@ -1156,6 +1481,17 @@ impl ObPayload for ObTableLoginResult {
     fn base_mut(&mut self) -> &mut BasePayLoad {
         &mut self.base
     }
+
+    fn content_len(&self) -> Result<usize> {
+        Ok(util::encoded_length_vi32(self.server_capabilities)
+            + util::encoded_length_vi32(self.reserved1)
+            + util::encoded_length_vi64(self.reserved2)
+            + util::encoded_length_vstring(&self.server_version)
+            + util::encoded_length_bytes_string(&self.credential)
+            + util::encoded_length_vi64(self.tenant_id as i64)
+            + util::encoded_length_vi64(self.user_id)
+            + util::encoded_length_vi64(self.database_id))
+    }
 }
 
 impl ProtoDecoder for ObTableLoginResult {
@@ -1178,8 +1514,21 @@ impl ProtoDecoder for ObTableLoginResult {
 }
 
 impl ProtoEncoder for ObTableLoginResult {
-    fn encode(&self, _buf: &mut BytesMut) -> Result<()> {
-        unimplemented!();
+    fn encode(&self, buf: &mut BytesMut) -> Result<()> {
+        self.encode_header(buf)?;
+
+        util::encode_vi32(self.server_capabilities, buf)?;
+        util::encode_vi32(self.reserved1, buf)?;
+        util::encode_vi64(self.reserved2, buf)?;
+
+        util::encode_vstring(&self.server_version, buf)?;
+        util::encode_bytes_string(&self.credential, buf)?;
+
+        util::encode_vi64(self.tenant_id as i64, buf)?;
+        util::encode_vi64(self.user_id, buf)?;
+        util::encode_vi64(self.database_id, buf)?;
+
+        Ok(())
     }
 }
 
@@ -1200,11 +1549,22 @@ impl ObPayload for ObTableResult {
     fn base_mut(&mut self) -> &mut BasePayLoad {
         &mut self.base
     }
+
+    fn content_len(&self) -> Result<usize> {
+        Ok(util::encoded_length_vi32(self.errorno)
+            + util::encoded_length_bytes(&self.sql_state)
+            + util::encoded_length_bytes(&self.msg))
+    }
 }
 
 impl ProtoEncoder for ObTableResult {
-    fn encode(&self, _buf: &mut BytesMut) -> Result<()> {
-        unimplemented!();
+    fn encode(&self, buf: &mut BytesMut) -> Result<()> {
+        self.encode_header(buf)?;
+
+        util::encode_vi32(self.errorno, buf)?;
+        util::encode_bytes(&self.sql_state, buf)?;
+        util::encode_bytes(&self.msg, buf)?;
+        Ok(())
     }
 }
 
@@ -1236,6 +1596,14 @@ impl ObTableResult {
     pub fn errorno(&self) -> i32 {
         self.errorno
     }
+
+    pub fn set_errorno(&mut self, errorno: i32) {
+        self.errorno = errorno;
+    }
+
+    pub fn set_message(&mut self, msg: &str) {
+        self.msg = msg.as_bytes().to_vec();
+    }
 }
 
 #[derive(Debug)]
@@ -1292,6 +1660,22 @@ impl ObTableOperationResult {
     pub fn peer_addr(&self) -> Option<SocketAddr> {
         self.peer_addr
     }
+
+    pub fn set_result(&mut self, header: ObTableResult) {
+        self.header = header;
+    }
+
+    pub fn set_operation_type(&mut self, operation_type: ObTableOperationType) {
+        self.operation_type = operation_type;
+    }
+
+    pub fn set_entity(&mut self, entity: ObTableEntity) {
+        self.entity = entity;
+    }
+
+    pub fn set_affected_rows(&mut self, affected_rows: i64) {
+        self.affected_rows = affected_rows;
+    }
 }
 
 impl ObPayload for ObTableOperationResult {
@@ -1307,6 +1691,13 @@ impl ObPayload for ObTableOperationResult {
         &mut self.base
     }
 
+    fn content_len(&self) -> Result<usize> {
+        Ok(self.header.len()?
+            + util::encoded_length_i8(self.operation_type as i8)
+            + self.entity.len()?
+            + util::encoded_length_vi64(self.affected_rows))
+    }
+
     fn set_trace_id(&mut self, trace_id: TraceId) {
         self.trace_id = trace_id;
     }
@@ -1317,8 +1708,14 @@ impl ObPayload for ObTableOperationResult {
 }
 
 impl ProtoEncoder for ObTableOperationResult {
-    fn encode(&self, _buf: &mut BytesMut) -> Result<()> {
-        unimplemented!();
+    fn encode(&self, buf: &mut BytesMut) -> Result<()> {
+        self.encode_header(buf)?;
+
+        self.header.encode(buf)?;
+        buf.put_i8(self.operation_type as i8);
+        self.entity.encode(buf)?;
+        util::encode_vi64(self.affected_rows, buf)?;
+        Ok(())
     }
 }
 
@@ -1355,6 +1752,10 @@ impl ObTableBatchOperationResult {
     pub fn take_op_results(self) -> Vec<ObTableOperationResult> {
         self.op_results
     }
+
+    pub fn add_op_result(&mut self, op_result: ObTableOperationResult) {
+        self.op_results.push(op_result);
+    }
 }
 
 impl ObPayload for ObTableBatchOperationResult {
@@ -1369,11 +1770,25 @@ impl ObPayload for ObTableBatchOperationResult {
     fn base_mut(&mut self) -> &mut BasePayLoad {
         &mut self.base
     }
+
+    fn content_len(&self) -> Result<usize> {
+        let mut len = util::encoded_length_vi64(self.op_results.len() as i64);
+        for op_res in &self.op_results {
+            len += op_res.len()?;
+        }
+        Ok(len)
+    }
 }
 
 impl ProtoEncoder for ObTableBatchOperationResult {
-    fn encode(&self, _buf: &mut BytesMut) -> Result<()> {
-        unimplemented!();
+    fn encode(&self, buf: &mut BytesMut) -> Result<()> {
+        self.encode_header(buf)?;
+
+        util::encode_vi64(self.op_results.len() as i64, buf)?;
+        for op_res in &self.op_results {
+            op_res.encode(buf)?;
+        }
+        Ok(())
     }
 }
 
@@ -1429,6 +1844,7 @@ mod test {
         let req = ObTableOperationRequest {
             base: base.clone(),
             credential: "test".as_bytes().to_vec(),
+            idempotent: true,
             table_name: "test".to_owned(),
             table_id: 1,
             partition_id: 1,
@@ -1507,9 +1923,18 @@ mod test {
 
         let columns = vec![String::from("column-3"), String::from("column-4")];
         let properties = vec![Value::from("column-v3"), Value::from("column-v4")];
-        batch_op.update(row_keys, columns, properties);
+        batch_op.update(row_keys.clone(), columns.clone(), properties.clone());
         assert!(!batch_op.is_read_only());
         assert!(!batch_op.is_same_type());
         assert!(!batch_op.is_same_properties_names());
+        assert!(batch_op.is_idempotent());
+
+        batch_op.increment(row_keys, columns, properties);
+        assert!(!batch_op.is_idempotent());
+        let mut req =
+            ObTableBatchOperationRequest::new(batch_op, time::Duration::ZERO, DEFAULT_FLAG);
+        assert!(!req.is_idempotent());
+        req.set_idempotent(true);
+        assert!(req.is_idempotent());
     }
 }
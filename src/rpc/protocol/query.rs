@@ -17,7 +17,7 @@
 
 use std::{io, mem, time::Duration};
 
-use bytes::{BufMut, BytesMut};
+use bytes::{Buf, BufMut, BytesMut};
 
 use super::{
     payloads::{ObRowKey, ObTableConsistencyLevel, ObTableEntityType},
@@ -84,6 +84,23 @@ impl ObTableQueryResult {
     pub fn take_properties_rows(&mut self) -> Vec<Vec<Value>> {
         mem::take(&mut self.properties_rows)
     }
+
+    pub fn set_properties_names(&mut self, properties_names: Vec<String>) {
+        self.properties_names = properties_names;
+    }
+
+    pub fn add_properties_row(&mut self, row: Vec<Value>) {
+        self.properties_rows.push(row);
+        self.row_count = self.properties_rows.len() as i64;
+    }
+
+    fn rows_buf_len(&self) -> usize {
+        self.properties_rows
+            .iter()
+            .flat_map(|row| row.iter())
+            .map(|v| v.len())
+            .sum()
+    }
 }
 
 impl ObPayload for ObTableQueryResult {
@@ -96,7 +113,14 @@ impl ObPayload for ObTableQueryResult {
     }
 
     fn content_len(&self) -> Result<usize> {
-        unimplemented!()
+        let mut len = util::encoded_length_vi64(self.properties_names.len() as i64);
+        for name in &self.properties_names {
+            len += util::encoded_length_vstring(name);
+        }
+        let buf_len = self.rows_buf_len();
+        len += util::encoded_length_vi64(self.row_count);
+        len += util::encoded_length_vi64(buf_len as i64);
+        Ok(len + buf_len)
     }
 
     fn pcode(&self) -> ObTablePacketCode {
@@ -109,8 +133,22 @@ impl ObPayload for ObTableQueryResult {
 }
 
 impl ProtoEncoder for ObTableQueryResult {
-    fn encode(&self, _buf: &mut BytesMut) -> Result<()> {
-        unimplemented!();
+    fn encode(&self, buf: &mut BytesMut) -> Result<()> {
+        self.encode_header(buf)?;
+
+        util::encode_vi64(self.properties_names.len() as i64, buf)?;
+        for name in &self.properties_names {
+            util::encode_vstring(name, buf)?;
+        }
+
+        util::encode_vi64(self.row_count, buf)?;
+        util::encode_vi64(self.rows_buf_len() as i64, buf)?;
+        for row in &self.properties_rows {
+            for v in row {
+                v.encode(buf)?;
+            }
+        }
+        Ok(())
     }
 }
 
@@ -336,6 +374,19 @@ impl ProtoEncoder for ObNewRange {
     }
 }
 
+impl ProtoDecoder for ObNewRange {
+    fn decode(&mut self, src: &mut BytesMut) -> Result<()> {
+        self.table_id = util::decode_vi64(src)?;
+        self.border_flag = ObBorderFlag::from_i8(util::decode_i8(src)?);
+        self.start_key.decode(src)?;
+        self.end_key.decode(src)?;
+        if ob_vsn_major() >= 4 {
+            self.flag = util::decode_vi64(src)?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Default, Clone, PartialEq)]
 pub enum ObScanOrder {
     ImplementedOrder = 0,
@@ -381,7 +432,27 @@ pub struct ObHTableFilter {
     filter_string: String,
 }
 
-impl ObHTableFilter {}
+impl ObHTableFilter {
+    pub fn new() -> Self {
+        Self {
+            base: BasePayLoad::dummy(),
+            is_valid: false,
+            select_column_qualifier: vec![],
+            min_stamp: 0,
+            max_stamp: 0,
+            max_versions: 0,
+            limit_per_row_per_cf: 0,
+            offset_per_row_per_cf: 0,
+            filter_string: "".to_owned(),
+        }
+    }
+}
+
+impl Default for ObHTableFilter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl ObPayload for ObHTableFilter {
     fn base(&self) -> &BasePayLoad {
@@ -437,8 +508,25 @@ impl ProtoEncoder for ObHTableFilter {
 }
 
 impl ProtoDecoder for ObHTableFilter {
-    fn decode(&mut self, _src: &mut BytesMut) -> Result<()> {
-        unimplemented!();
+    fn decode(&mut self, src: &mut BytesMut) -> Result<()> {
+        self.decode_base(src)?;
+
+        self.is_valid = util::decode_i8(src)? != 0;
+        let len = util::decode_vi32(src)?;
+        self.select_column_qualifier = Vec::with_capacity(len.max(0) as usize);
+        for _ in 0..len {
+            self.select_column_qualifier
+                .push(util::decode_vstring(src)?);
+        }
+
+        self.min_stamp = util::decode_vi64(src)?;
+        self.max_stamp = util::decode_vi64(src)?;
+        self.max_versions = util::decode_vi32(src)?;
+        self.limit_per_row_per_cf = util::decode_vi32(src)?;
+        self.offset_per_row_per_cf = util::decode_vi32(src)?;
+        self.filter_string = util::decode_vstring(src)?;
+
+        Ok(())
     }
 }
 
@@ -573,8 +661,57 @@ impl ProtoEncoder for ObTableQuery {
 }
 
 impl ProtoDecoder for ObTableQuery {
-    fn decode(&mut self, _src: &mut BytesMut) -> Result<()> {
-        unimplemented!();
+    fn decode(&mut self, src: &mut BytesMut) -> Result<()> {
+        self.decode_base(src)?;
+
+        let len = util::decode_vi64(src)?;
+        self.key_ranges = Vec::with_capacity(len.max(0) as usize);
+        for _ in 0..len {
+            let mut range = ObNewRange::new();
+            range.decode(src)?;
+            self.key_ranges.push(range);
+        }
+
+        let len = util::decode_vi64(src)?;
+        self.select_columns = Vec::with_capacity(len.max(0) as usize);
+        for _ in 0..len {
+            self.select_columns.push(util::decode_vstring(src)?);
+        }
+
+        self.filter_string = util::decode_vstring(src)?;
+        self.limit = util::decode_vi32(src)?;
+        self.offset = util::decode_vi32(src)?;
+        self.scan_order = ObScanOrder::from_i32(util::decode_i8(src)? as i32)?;
+        self.index_name = util::decode_vstring(src)?;
+        self.batch_size = util::decode_vi32(src)?;
+        self.max_result_size = util::decode_vi64(src)?;
+
+        if src.starts_with(HTABLE_FILTER_DUMMY_BYTES) {
+            src.advance(HTABLE_FILTER_DUMMY_BYTES.len());
+            self.is_hbase_query = false;
+            self.htable_filter = None;
+        } else {
+            let mut filter = ObHTableFilter::new();
+            filter.decode(src)?;
+            self.is_hbase_query = true;
+            self.htable_filter = Some(filter);
+        }
+
+        let len = util::decode_vi64(src)?;
+        self.scan_range_columns = Vec::with_capacity(len.max(0) as usize);
+        for _ in 0..len {
+            self.scan_range_columns.push(util::decode_vstring(src)?);
+        }
+
+        let len = util::decode_vi64(src)?;
+        self.aggregations = Vec::with_capacity(len.max(0) as usize);
+        for _ in 0..len {
+            let mut agg = ObTableAggregationOp::default();
+            agg.decode(src)?;
+            self.aggregations.push(agg);
+        }
+
+        Ok(())
     }
 }
 
@@ -654,6 +791,38 @@ impl ObTableQuery {
         &self.key_ranges
     }
 
+    pub fn get_select_columns(&self) -> &[String] {
+        &self.select_columns
+    }
+
+    pub fn get_filter_string(&self) -> &str {
+        &self.filter_string
+    }
+
+    pub fn get_limit(&self) -> i32 {
+        self.limit
+    }
+
+    pub fn get_offset(&self) -> i32 {
+        self.offset
+    }
+
+    pub fn get_scan_order(&self) -> &ObScanOrder {
+        &self.scan_order
+    }
+
+    pub fn get_index_name(&self) -> &str {
+        &self.index_name
+    }
+
+    pub fn is_hbase_query(&self) -> bool {
+        self.is_hbase_query
+    }
+
+    pub fn get_aggregations(&self) -> &[ObTableAggregationOp] {
+        &self.aggregations
+    }
+
     /// Verify whether the query is valid.
     pub fn verify(&self) -> error::Result<()> {
         if self.select_columns.is_empty() {
@@ -710,6 +879,50 @@ impl ObTableQueryRequest {
             _return_affected_rows: true,
         }
     }
+
+    pub fn table_name(&self) -> &str {
+        &self.table_name
+    }
+
+    pub fn table_id(&self) -> i64 {
+        self.table_id
+    }
+
+    pub fn partition_id(&self) -> i64 {
+        self.partition_id
+    }
+
+    pub fn entity_type(&self) -> ObTableEntityType {
+        self.entity_type
+    }
+
+    pub fn consistency_level(&self) -> ObTableConsistencyLevel {
+        self.consistency_level
+    }
+
+    pub fn set_consistency_level(&mut self, consistency_level: ObTableConsistencyLevel) {
+        self.consistency_level = consistency_level;
+    }
+
+    pub fn table_query(&self) -> &ObTableQuery {
+        &self.table_query
+    }
+}
+
+impl Default for ObTableQueryRequest {
+    fn default() -> Self {
+        let mut request = Self::new(
+            "",
+            OB_INVALID_ID,
+            OB_INVALID_ID,
+            ObTableEntityType::Dynamic,
+            ObTableQuery::new(),
+            Duration::ZERO,
+            0,
+        );
+        request.base = BasePayLoad::dummy();
+        request
+    }
 }
 
 impl ObPayload for ObTableQueryRequest {
@@ -766,8 +979,23 @@ impl ProtoEncoder for ObTableQueryRequest {
 }
 
 impl ProtoDecoder for ObTableQueryRequest {
-    fn decode(&mut self, _src: &mut BytesMut) -> Result<()> {
-        unimplemented!()
+    fn decode(&mut self, src: &mut BytesMut) -> Result<()> {
+        self.decode_base(src)?;
+
+        self.credential = util::decode_bytes_string(src)?;
+        self.table_name = util::decode_vstring(src)?;
+        self.table_id = util::decode_vi64(src)?;
+        self.partition_id = if ob_vsn_major() >= 4 {
+            util::split_buf_to(src, 8)?.get_i64()
+        } else {
+            util::decode_vi64(src)?
+        };
+        self.entity_type = ObTableEntityType::from_i8(util::decode_i8(src)?)?;
+        self.consistency_level = ObTableConsistencyLevel::from_i8(util::decode_i8(src)?)?;
+
+        self.table_query.decode(src)?;
+
+        Ok(())
     }
 }
 
@@ -839,8 +1067,9 @@ impl ProtoEncoder for ObTableStreamRequest {
 }
 
 impl ProtoDecoder for ObTableStreamRequest {
-    fn decode(&mut self, _src: &mut BytesMut) -> Result<()> {
-        unimplemented!()
+    fn decode(&mut self, src: &mut BytesMut) -> Result<()> {
+        // The session id and the stream flags travel in the rpc header.
+        self.decode_base(src)
     }
 }
 
@@ -855,6 +1084,23 @@ pub enum ObTableAggregationType {
     AVG = 5,
 }
 
+impl ObTableAggregationType {
+    pub fn from_i8(i: i8) -> Result<ObTableAggregationType> {
+        match i {
+            0 => Ok(ObTableAggregationType::INVAILD),
+            1 => Ok(ObTableAggregationType::MAX),
+            2 => Ok(ObTableAggregationType::MIN),
+            3 => Ok(ObTableAggregationType::COUNT),
+            4 => Ok(ObTableAggregationType::SUM),
+            5 => Ok(ObTableAggregationType::AVG),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid aggregation type: {i}."),
+            )),
+        }
+    }
+}
+
 /// this struct is a single aggregation
 #[derive(Default, Debug, Clone, PartialEq)]
 pub struct ObTableAggregationOp {
@@ -872,6 +1118,14 @@ impl ObTableAggregationOp {
         }
     }
 
+    pub fn agg_type(&self) -> &ObTableAggregationType {
+        &self.agg_type
+    }
+
+    pub fn agg_column(&self) -> &str {
+        &self.agg_column
+    }
+
     /// Total size of aggregation op for encode
     pub fn encode_size(self) -> Result<usize> {
         Ok(util::encoded_length_vi64(1) // version
@@ -891,8 +1145,11 @@ impl ProtoEncoder for ObTableAggregationOp {
 }
 
 impl ProtoDecoder for ObTableAggregationOp {
-    fn decode(&mut self, _src: &mut BytesMut) -> Result<()> {
-        unimplemented!()
+    fn decode(&mut self, src: &mut BytesMut) -> Result<()> {
+        self.decode_base(src)?;
+        self.agg_type = ObTableAggregationType::from_i8(util::decode_i8(src)?)?;
+        self.agg_column = util::decode_vstring(src)?;
+        Ok(())
     }
 }
 
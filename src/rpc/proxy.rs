@@ -17,9 +17,12 @@
 
 use std::sync::Arc;
 
+use tokio::time::sleep;
+
 use super::{conn_pool::ConnPool, protocol::ObPayload};
 use crate::{
-    error::Result,
+    client::retry::{RetryContext, RetryOp, RetryPolicy},
+    error::{Error, Result},
     monitors::{prometheus::OBKV_CLIENT_REGISTRY, proxy_metrics::ProxyMetrics},
 };
 
@@ -32,11 +35,17 @@ lazy_static! {
 }
 
 #[derive(Clone)]
-pub struct Proxy(Arc<ConnPool>);
+pub struct Proxy {
+    conn_pool: Arc<ConnPool>,
+    retry_policy: Arc<dyn RetryPolicy>,
+}
 
 impl Proxy {
-    pub fn new(conn_pool: Arc<ConnPool>) -> Self {
-        Proxy(conn_pool)
+    pub fn new(conn_pool: Arc<ConnPool>, retry_policy: Arc<dyn RetryPolicy>) -> Self {
+        Proxy {
+            conn_pool,
+            retry_policy,
+        }
     }
 
     pub async fn execute<T: ObPayload, R: ObPayload>(
@@ -46,7 +55,7 @@ impl Proxy {
     ) -> Result<()> {
         // the connection is ensured to be active now by checking conn.is_active
         // but it may be actually broken already.
-        let conn = self.0.get()?;
+        let conn = self.conn_pool.get()?;
 
         OBKV_PROXY_METRICS.observe_proxy_misc("conn_load", conn.load() as f64);
 
@@ -54,12 +63,16 @@ impl Proxy {
         if res.is_ok() || conn.is_active() {
             return res;
         }
+        // not sent again, it may have been applied already
+        if let Err(e @ Error::AmbiguousOutcome(_)) = res {
+            return Err(e);
+        }
 
         let mut retry_cnt = 0;
-        // retry until all the idle connections are consumed and then a brand new
-        // connection is built or an intact connection is taken because all the
-        // connections may be broken together
-        let retry_limit = self.0.idle_conn_num() + 1;
+        // retry as the policy decides, at most until all the idle connections are
+        // consumed and then a brand new connection is built or an intact
+        // connection is taken because all the connections may be broken together
+        let retry_limit = self.conn_pool.idle_conn_num() + 1;
 
         OBKV_PROXY_METRICS.observe_proxy_misc("retry_idle_conns", (retry_limit - 1) as f64);
 
@@ -75,14 +88,32 @@ impl Proxy {
                 );
                 return Err(err);
             }
+            let ctx = RetryContext {
+                table_name: "",
+                op: RetryOp::Connection,
+                attempt: retry_cnt,
+                remaining: None,
+            };
+            match self.retry_policy.decide(&ctx, &err).retry_after {
+                Some(delay) if !delay.is_zero() => sleep(delay).await,
+                Some(_) => (),
+                None => {
+                    OBKV_PROXY_METRICS.observe_proxy_misc("retry_times", retry_cnt as f64);
+                    debug!(
+                        "Proxy::execute not retried by the retry policy, err:{}",
+                        err
+                    );
+                    return Err(err);
+                }
+            }
             debug!(
                 "Proxy::execute retry {} because connection broken, err:{}",
                 retry_cnt, err
             );
 
-            let conn = self.0.get()?;
+            let conn = self.conn_pool.get()?;
             let res = conn.execute(payload, response).await;
-            if res.is_ok() || conn.is_active() {
+            if res.is_ok() || conn.is_active() || matches!(res, Err(Error::AmbiguousOutcome(_))) {
                 OBKV_PROXY_METRICS.observe_proxy_misc("retry_times", retry_cnt as f64);
                 return res;
             }
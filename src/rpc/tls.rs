@@ -0,0 +1,322 @@
+/*-
+ * #%L
+ * OBKV Table Client Framework
+ * %%
+ * Copyright (C) 2021 OceanBase
+ * %%
+ * OBKV Table Client Framework is licensed under Mulan PSL v2.
+ * You can use this software according to the terms and conditions of the
+ * Mulan PSL v2. You may obtain a copy of Mulan PSL v2 at:
+ *          http://license.coscl.org.cn/MulanPSL2
+ * THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ * KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ * NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ * See the Mulan PSL v2 for more details.
+ * #L%
+ */
+
+use std::{
+    fmt,
+    fs::File,
+    io::BufReader,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+use rustls::{
+    client::{ServerCertVerified, ServerCertVerifier},
+    Certificate, ClientConfig as RustlsClientConfig, PrivateKey, RootCertStore, ServerName,
+};
+use tokio::net::TcpStream;
+use tokio_rustls::client::TlsStream;
+
+use crate::error::{CommonErrCode, Error::Common as CommonErr, Result};
+
+/// TLS settings of the RPC connections to the observers.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TlsConfig {
+    /// PEM bundle of the CA certificates which sign the observer certificates,
+    /// required unless `insecure` is set.
+    pub ca_cert_file: Option<PathBuf>,
+    /// PEM certificate chain presented to the observers for mutual TLS.
+    pub client_cert_file: Option<PathBuf>,
+    /// PEM private key of `client_cert_file`.
+    pub client_key_file: Option<PathBuf>,
+    /// Name sent in SNI and verified against the observer certificates, the
+    /// observer ip is used if absent.
+    pub server_name: Option<String>,
+    /// Skip verifying the observer certificates, for tests only.
+    pub insecure: bool,
+}
+
+/// Connector wrapping the plain RPC streams with TLS.
+pub struct TlsConnector {
+    connector: tokio_rustls::TlsConnector,
+    server_name: Option<ServerName>,
+}
+
+impl fmt::Debug for TlsConnector {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TlsConnector")
+            .field("server_name", &self.server_name)
+            .finish()
+    }
+}
+
+impl TlsConnector {
+    pub fn new(config: &TlsConfig) -> Result<Self> {
+        let builder = RustlsClientConfig::builder().with_safe_defaults();
+
+        let mut roots = RootCertStore::empty();
+        if !config.insecure {
+            let ca_cert_file = config.ca_cert_file.as_ref().ok_or_else(|| {
+                CommonErr(
+                    CommonErrCode::InvalidParam,
+                    "TlsConnector::new ca_cert_file is required unless insecure".to_owned(),
+                )
+            })?;
+            for cert in load_certs(ca_cert_file)? {
+                roots.add(&cert).map_err(|e| {
+                    CommonErr(
+                        CommonErrCode::InvalidParam,
+                        format!("TlsConnector::new invalid ca cert in {ca_cert_file:?}, err:{e}"),
+                    )
+                })?;
+            }
+        }
+        let builder = builder.with_root_certificates(roots);
+
+        let client_auth =
+            match (&config.client_cert_file, &config.client_key_file) {
+                (Some(cert_file), Some(key_file)) => {
+                    Some((load_certs(cert_file)?, load_private_key(key_file)?))
+                }
+                (None, None) => None,
+                _ => return Err(CommonErr(
+                    CommonErrCode::InvalidParam,
+                    "TlsConnector::new client_cert_file and client_key_file must be set together"
+                        .to_owned(),
+                )),
+            };
+        let mut tls_config = match client_auth {
+            Some((certs, key)) => builder.with_client_auth_cert(certs, key).map_err(|e| {
+                CommonErr(
+                    CommonErrCode::InvalidParam,
+                    format!("TlsConnector::new invalid client cert, err:{e}"),
+                )
+            })?,
+            None => builder.with_no_client_auth(),
+        };
+
+        if config.insecure {
+            warn!("TlsConnector::new the certificates of observers are not verified.");
+            tls_config
+                .dangerous()
+                .set_certificate_verifier(Arc::new(NoCertVerifier));
+        }
+
+        let server_name = match &config.server_name {
+            Some(name) => Some(ServerName::try_from(name.as_str()).map_err(|e| {
+                CommonErr(
+                    CommonErrCode::InvalidParam,
+                    format!("TlsConnector::new invalid server name {name}, err:{e}"),
+                )
+            })?),
+            None => None,
+        };
+
+        Ok(Self {
+            connector: tokio_rustls::TlsConnector::from(Arc::new(tls_config)),
+            server_name,
+        })
+    }
+
+    /// Handshake over the connected `stream` to `addr`.
+    pub async fn connect(
+        &self,
+        addr: &SocketAddr,
+        stream: TcpStream,
+    ) -> Result<TlsStream<TcpStream>> {
+        let server_name = self
+            .server_name
+            .clone()
+            .unwrap_or(ServerName::IpAddress(addr.ip()));
+        Ok(self.connector.connect(server_name, stream).await?)
+    }
+}
+
+fn open_pem(path: &Path) -> Result<BufReader<File>> {
+    File::open(path).map(BufReader::new).map_err(|e| {
+        CommonErr(
+            CommonErrCode::InvalidParam,
+            format!("Fail to open pem file {path:?}, err:{e}"),
+        )
+    })
+}
+
+fn load_certs(path: &Path) -> Result<Vec<Certificate>> {
+    let certs = rustls_pemfile::certs(&mut open_pem(path)?)?;
+    if certs.is_empty() {
+        return Err(CommonErr(
+            CommonErrCode::InvalidParam,
+            format!("No certificate found in {path:?}"),
+        ));
+    }
+    Ok(certs.into_iter().map(Certificate).collect())
+}
+
+fn load_private_key(path: &Path) -> Result<PrivateKey> {
+    let mut reader = open_pem(path)?;
+    while let Some(item) = rustls_pemfile::read_one(&mut reader)? {
+        match item {
+            rustls_pemfile::Item::PKCS8Key(key)
+            | rustls_pemfile::Item::RSAKey(key)
+            | rustls_pemfile::Item::ECKey(key) => return Ok(PrivateKey(key)),
+            _ => (),
+        }
+    }
+    Err(CommonErr(
+        CommonErrCode::InvalidParam,
+        format!("No private key found in {path:?}"),
+    ))
+}
+
+struct NoCertVerifier;
+
+impl ServerCertVerifier for NoCertVerifier {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &Certificate,
+        _intermediates: &[Certificate],
+        _server_name: &ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: SystemTime,
+    ) -> std::result::Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use rustls::{server::AllowAnyAuthenticatedClient, ServerConfig};
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+    use tokio_rustls::TlsAcceptor;
+
+    use super::*;
+
+    pub fn cert_path(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/certs")
+            .join(name)
+    }
+
+    /// A TLS terminating stand-in of an observer, which echoes what it reads.
+    pub async fn start_echo_server(client_auth: bool) -> SocketAddr {
+        let certs = load_certs(&cert_path("server.pem")).unwrap();
+        let key = load_private_key(&cert_path("server.key")).unwrap();
+        let builder = ServerConfig::builder().with_safe_defaults();
+        let builder = if client_auth {
+            let mut roots = RootCertStore::empty();
+            for cert in load_certs(&cert_path("ca.pem")).unwrap() {
+                roots.add(&cert).unwrap();
+            }
+            builder.with_client_cert_verifier(Arc::new(AllowAnyAuthenticatedClient::new(roots)))
+        } else {
+            builder.with_no_client_auth()
+        };
+        let acceptor = TlsAcceptor::from(Arc::new(builder.with_single_cert(certs, key).unwrap()));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move {
+                    if let Ok(mut stream) = acceptor.accept(stream).await {
+                        let mut buf = [0; 1024];
+                        while let Ok(n) = stream.read(&mut buf).await {
+                            if n == 0 || stream.write_all(&buf[..n]).await.is_err() {
+                                break;
+                            }
+                        }
+                    }
+                });
+            }
+        });
+        addr
+    }
+
+    pub fn ca_config() -> TlsConfig {
+        TlsConfig {
+            ca_cert_file: Some(cert_path("ca.pem")),
+            ..Default::default()
+        }
+    }
+
+    async fn echo(config: &TlsConfig, addr: &SocketAddr) -> Result<Vec<u8>> {
+        let connector = TlsConnector::new(config)?;
+        let stream = TcpStream::connect(addr).await?;
+        let mut stream = connector.connect(addr, stream).await?;
+        stream.write_all(b"ping").await?;
+        let mut buf = [0; 4];
+        stream.read_exact(&mut buf).await?;
+        Ok(buf.to_vec())
+    }
+
+    #[tokio::test]
+    async fn test_tls_connect() {
+        let addr = start_echo_server(false).await;
+        assert_eq!(b"ping".to_vec(), echo(&ca_config(), &addr).await.unwrap());
+
+        // hostname verification
+        let config = TlsConfig {
+            server_name: Some("observer.test".to_owned()),
+            ..ca_config()
+        };
+        assert!(echo(&config, &addr).await.is_ok());
+        let config = TlsConfig {
+            server_name: Some("other.test".to_owned()),
+            ..ca_config()
+        };
+        assert!(echo(&config, &addr).await.is_err());
+
+        // untrusted ca
+        let config = TlsConfig {
+            ca_cert_file: Some(cert_path("other_ca.pem")),
+            ..Default::default()
+        };
+        assert!(echo(&config, &addr).await.is_err());
+        let config = TlsConfig {
+            insecure: true,
+            ..Default::default()
+        };
+        assert!(echo(&config, &addr).await.is_ok());
+
+        assert!(TlsConnector::new(&TlsConfig::default()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_mutual_tls() {
+        let addr = start_echo_server(true).await;
+        assert!(echo(&ca_config(), &addr).await.is_err());
+
+        let config = TlsConfig {
+            client_cert_file: Some(cert_path("client.pem")),
+            client_key_file: Some(cert_path("client.key")),
+            ..ca_config()
+        };
+        assert_eq!(b"ping".to_vec(), echo(&config, &addr).await.unwrap());
+
+        let config = TlsConfig {
+            client_cert_file: Some(cert_path("client.pem")),
+            ..ca_config()
+        };
+        assert!(TlsConnector::new(&config).is_err());
+    }
+}
@@ -636,6 +636,37 @@ pub struct ObCrc64Sse42 {
 }
 
 impl ObCrc64Sse42 {
+    /// Computes the checksum by the sse4.2 crc32 instruction when the cpu
+    /// supports it, and by the portable table driven implementation otherwise.
+    pub fn crc64(crc: i64, data: &[u8]) -> i64 {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("sse4.2") {
+                // SAFETY: the cpu supports sse4.2
+                return unsafe { Self::crc64_sse42(crc, data) };
+            }
+        }
+        Self::fast_crc64_sse42_manually(crc, data)
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "sse4.2")]
+    unsafe fn crc64_sse42(crc: i64, data: &[u8]) -> i64 {
+        use std::arch::x86_64::{_mm_crc32_u64, _mm_crc32_u8};
+
+        let mut crc = crc as u64;
+        let mut chunks = data.chunks_exact(8);
+        for chunk in &mut chunks {
+            let mut word = [0u8; 8];
+            word.copy_from_slice(chunk);
+            crc = _mm_crc32_u64(crc, u64::from_le_bytes(word));
+        }
+        for &byte in chunks.remainder() {
+            crc = _mm_crc32_u8(crc as u32, byte) as u64;
+        }
+        crc as i64
+    }
+
     pub fn crc64_sse42_manually(mut crc: i64, data: &[u8]) -> i64 {
         for &byte in data {
             crc = CRC32_TAB[((crc ^ byte as i64) & 0xff) as usize] ^ (crc >> 8);
@@ -693,6 +724,16 @@ mod test {
         assert_eq!(n, 1566783161);
     }
 
+    #[test]
+    fn crc64_test() {
+        let s = String::from("StringNeedToBeCalculatedCheckSumAndTest,Make String longer longer longer longer longer to test");
+        assert_eq!(ObCrc64Sse42::crc64(0, s.as_bytes()), 1566783161);
+        for len in 0..s.len() {
+            let data = &s.as_bytes()[..len];
+            assert_eq!(ObCrc64Sse42::crc64(0x05010927, data), ObCrc64Sse42::crc64_sse42_manually(0x05010927, data));
+        }
+    }
+
     #[test]
     fn both_crc64_sse42_manually_test() {
         let s = String::from("String Need To Be Calculated CheckSum And Test,Make String longer longer longer longer longer to test");
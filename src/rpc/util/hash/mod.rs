@@ -15,5 +15,6 @@
  * #L%
  */
 
+pub mod ob_hash_sort_gbk;
  #[rustfmt::skip]
 pub mod ob_hash_sort_utf8mb4;
@@ -0,0 +1,109 @@
+/*-
+ * #%L
+ * OBKV Table Client Framework
+ * %%
+ * Copyright (C) 2021 OceanBase
+ * %%
+ * OBKV Table Client Framework is licensed under Mulan PSL v2.
+ * You can use this software according to the terms and conditions of the
+ * Mulan PSL v2. You may obtain a copy of Mulan PSL v2 at:
+ *          http://license.coscl.org.cn/MulanPSL2
+ * THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ * KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ * NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ * See the Mulan PSL v2 for more details.
+ * #L%
+ */
+
+//! Hash of gbk strings, the hash of gbk_bin is the binary hash of
+//! [`super::ob_hash_sort_utf8mb4`] on gbk encoded bytes.
+
+use super::ob_hash_sort_utf8mb4::ObHashSortUtf8mb4;
+
+/// Sort order of gbk_chinese_ci, which only folds ascii letters.
+#[rustfmt::skip]
+const SORT_ORDER_GBK: [u8; 256] = [
+    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A, 0x0B, 0x0C, 0x0D, 0x0E, 0x0F,
+    0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1A, 0x1B, 0x1C, 0x1D, 0x1E, 0x1F,
+    0x20, 0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x27, 0x28, 0x29, 0x2A, 0x2B, 0x2C, 0x2D, 0x2E, 0x2F,
+    0x30, 0x31, 0x32, 0x33, 0x34, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3A, 0x3B, 0x3C, 0x3D, 0x3E, 0x3F,
+    0x40, 0x41, 0x42, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49, 0x4A, 0x4B, 0x4C, 0x4D, 0x4E, 0x4F,
+    0x50, 0x51, 0x52, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5A, 0x5B, 0x5C, 0x5D, 0x5E, 0x5F,
+    0x60, 0x41, 0x42, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49, 0x4A, 0x4B, 0x4C, 0x4D, 0x4E, 0x4F,
+    0x50, 0x51, 0x52, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5A, 0x7B, 0x7C, 0x7D, 0x7E, 0x7F,
+    0x80, 0x81, 0x82, 0x83, 0x84, 0x85, 0x86, 0x87, 0x88, 0x89, 0x8A, 0x8B, 0x8C, 0x8D, 0x8E, 0x8F,
+    0x90, 0x91, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97, 0x98, 0x99, 0x9A, 0x9B, 0x9C, 0x9D, 0x9E, 0x9F,
+    0xA0, 0xA1, 0xA2, 0xA3, 0xA4, 0xA5, 0xA6, 0xA7, 0xA8, 0xA9, 0xAA, 0xAB, 0xAC, 0xAD, 0xAE, 0xAF,
+    0xB0, 0xB1, 0xB2, 0xB3, 0xB4, 0xB5, 0xB6, 0xB7, 0xB8, 0xB9, 0xBA, 0xBB, 0xBC, 0xBD, 0xBE, 0xBF,
+    0xC0, 0xC1, 0xC2, 0xC3, 0xC4, 0xC5, 0xC6, 0xC7, 0xC8, 0xC9, 0xCA, 0xCB, 0xCC, 0xCD, 0xCE, 0xCF,
+    0xD0, 0xD1, 0xD2, 0xD3, 0xD4, 0xD5, 0xD6, 0xD7, 0xD8, 0xD9, 0xDA, 0xDB, 0xDC, 0xDD, 0xDE, 0xDF,
+    0xE0, 0xE1, 0xE2, 0xE3, 0xE4, 0xE5, 0xE6, 0xE7, 0xE8, 0xE9, 0xEA, 0xEB, 0xEC, 0xED, 0xEE, 0xEF,
+    0xF0, 0xF1, 0xF2, 0xF3, 0xF4, 0xF5, 0xF6, 0xF7, 0xF8, 0xF9, 0xFA, 0xFB, 0xFC, 0xFD, 0xFE, 0xFF,
+];
+
+pub struct ObHashSortGbk {}
+
+impl ObHashSortGbk {
+    /// Hash of gbk_chinese_ci (`ob_hash_sort_simple`) on gbk encoded bytes,
+    /// trailing spaces are ignored and bytes are mapped by their sort order.
+    pub fn ob_hash_sort_gbk(s: &[u8], len: i32, n1: u64, n2: u64, hash_algo: bool) -> u64 {
+        let mut end = len as usize;
+        while end > 0 && s[end - 1] == b' ' {
+            end -= 1;
+        }
+
+        let mut n1 = n1;
+        let mut n2 = n2;
+        if hash_algo {
+            let mut data = [0u8; ObHashSortUtf8mb4::HASH_BUFFER_LENGTH];
+            for chunk in s[..end].chunks(ObHashSortUtf8mb4::HASH_BUFFER_LENGTH) {
+                for (i, b) in chunk.iter().enumerate() {
+                    data[i] = SORT_ORDER_GBK[*b as usize];
+                }
+                n1 = murmur2::murmur64a(&data[..chunk.len()], n1);
+            }
+        } else {
+            for b in &s[..end] {
+                n1 ^= (((n1 & 63) + n2).wrapping_mul(SORT_ORDER_GBK[*b as usize] as u64))
+                    .wrapping_add(n1 << 8);
+                n2 += 3;
+            }
+        }
+        n1
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SEED: u64 = 0xc6a4a7935bd1e995;
+
+    // the partitions the server puts the keys in are checked against a real
+    // server by `test_key_partition_collations`
+    #[test]
+    fn ob_hash_sort_gbk_test() {
+        let hash = |s: &[u8], hash_algo: bool| {
+            ObHashSortGbk::ob_hash_sort_gbk(s, s.len() as i32, 0, SEED, hash_algo)
+        };
+        // "中文Key" in gbk
+        let s = [0xD6, 0xD0, 0xCE, 0xC4, b'K', b'e', b'y'];
+        for hash_algo in [true, false] {
+            let n = hash(&s, hash_algo);
+            // case insensitive and trailing spaces are ignored
+            let padded = [0xD6, 0xD0, 0xCE, 0xC4, b'k', b'E', b'y', b' ', b' '];
+            assert_eq!(n, hash(&padded, hash_algo));
+            // but not the leading ones nor the other characters
+            let leading = [b' ', 0xD6, 0xD0, 0xCE, 0xC4, b'K', b'e', b'y'];
+            assert_ne!(n, hash(&leading, hash_algo));
+            let other = [0xD6, 0xD0, 0xCE, 0xC5, b'K', b'e', b'y'];
+            assert_ne!(n, hash(&other, hash_algo));
+        }
+
+        // long keys fold case past the hash buffer too
+        let mut long = [b'a'; 200];
+        let lower = hash(&long, true);
+        long[150] = b'A';
+        assert_eq!(lower, hash(&long, true));
+    }
+}
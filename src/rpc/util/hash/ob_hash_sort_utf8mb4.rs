@@ -3266,6 +3266,20 @@ impl ObHashSortUtf8mb4 {
         n1_tmp
     }
 
+    /// Sort key of `s` in utf8mb4_general_ci, strings equal in the collation
+    /// have the same key: trailing spaces are skipped (PAD SPACE), letters are
+    /// case and accent folded to the weights `ob_hash_sort_utf8_mb4` hashes.
+    pub fn ob_sort_key_utf8_mb4(s: &str) -> String {
+        s.trim_end_matches(' ')
+            .chars()
+            .map(|c| {
+                let mut wc = c as u32;
+                Self::ob_tosort_unicode(&UNI_PLANE_UTF8_MB4, &mut wc, OB_UTF8MB4_GENERAL_CI_STATE);
+                char::from_u32(wc).unwrap_or(char::REPLACEMENT_CHARACTER)
+            })
+            .collect()
+    }
+
     fn ob_mb_wc_utf8mb4(wc: &mut u32, s: &[u8], start: i32, end: i32) -> i32 {
         let start: usize = start as usize;
         let end: usize = end as usize;
@@ -3482,4 +3496,13 @@ mod test {
         let n = ObHashSortUtf8mb4::ob_hash_sort_utf8_mb4(&s.into_bytes(), 13, 0, seed, true);
         assert_eq!(n as i64, 668434772661134333);
     }
+
+    #[test]
+    fn ob_sort_key_utf8_mb4_test() {
+        let key = ObHashSortUtf8mb4::ob_sort_key_utf8_mb4("abc");
+        assert_eq!(key, ObHashSortUtf8mb4::ob_sort_key_utf8_mb4("ABC  "));
+        assert_eq!(key, ObHashSortUtf8mb4::ob_sort_key_utf8_mb4("\u{e0}bc"));
+        assert_ne!(key, ObHashSortUtf8mb4::ob_sort_key_utf8_mb4(" abc"));
+        assert_ne!(key, ObHashSortUtf8mb4::ob_sort_key_utf8_mb4("abd"));
+    }
 }
@@ -6,13 +6,14 @@
 use std::{
     future::Future,
     pin::Pin,
-    sync::Arc,
+    sync::{Arc, Mutex},
     task::{Context, Poll},
+    time::Duration,
 };
 
 use pin_project_lite::pin_project;
 use tokio::{
-    runtime::{Builder as RuntimeBuilder, Runtime as TokioRuntime},
+    runtime::{Builder as RuntimeBuilder, Handle, Runtime as TokioRuntime},
     task::JoinHandle as TokioJoinHandle,
 };
 
@@ -26,11 +27,28 @@ pub type RuntimeRef = Arc<Runtime>;
 /// A runtime to run future tasks
 #[derive(Debug)]
 pub struct Runtime {
-    rt: TokioRuntime,
+    // taken when the runtime is shut down, none if the runtime is borrowed
+    rt: Mutex<Option<TokioRuntime>>,
+    handle: Handle,
     metrics: Arc<RuntimeMetrics>,
 }
 
 impl Runtime {
+    /// Run the tasks on the runtime of `handle`, which is borrowed: it is
+    /// never shut down through the returned runtime.
+    pub fn from_handle(name: &str, handle: Handle) -> Runtime {
+        Runtime {
+            rt: Mutex::new(None),
+            handle,
+            metrics: Arc::new(RuntimeMetrics::new(name)),
+        }
+    }
+
+    /// Whether the tokio runtime is owned, rather than borrowed from a handle.
+    pub fn is_owned(&self) -> bool {
+        self.rt.lock().unwrap().is_some()
+    }
+
     /// Spawn a future and execute it in this thread pool
     ///
     /// Similar to tokio::runtime::Runtime::spawn()
@@ -40,7 +58,7 @@ impl Runtime {
         F::Output: Send + 'static,
     {
         JoinHandle {
-            inner: self.rt.spawn(future),
+            inner: self.handle.spawn(future),
         }
     }
 
@@ -52,13 +70,25 @@ impl Runtime {
         R: Send + 'static,
     {
         JoinHandle {
-            inner: self.rt.spawn_blocking(func),
+            inner: self.handle.spawn_blocking(func),
         }
     }
 
     /// Run a future to complete, this is the runtime's entry point
     pub fn block_on<F: Future>(&self, future: F) -> F::Output {
-        self.rt.block_on(future)
+        self.handle.block_on(future)
+    }
+
+    /// Shut the runtime down, waiting up to `timeout` for its blocking tasks
+    /// and its threads to finish. Tasks spawned afterwards never run. A
+    /// borrowed runtime is left running.
+    ///
+    /// It blocks the current thread, so it must not be called in an
+    /// asynchronous context.
+    pub fn shutdown_timeout(&self, timeout: Duration) {
+        if let Some(rt) = self.rt.lock().unwrap().take() {
+            rt.shutdown_timeout(timeout);
+        }
     }
 
     /// Returns the runtime stats
@@ -70,6 +100,17 @@ impl Runtime {
     }
 }
 
+impl Drop for Runtime {
+    fn drop(&mut self) {
+        if let Some(rt) = self.rt.get_mut().unwrap().take() {
+            // dropping a runtime blocks, which panics in an asynchronous context
+            if Handle::try_current().is_ok() {
+                rt.shutdown_background();
+            }
+        }
+    }
+}
+
 pin_project! {
     #[derive(Debug)]
     pub struct JoinHandle<T> {
@@ -178,7 +219,11 @@ impl Builder {
             }))
             .build()?;
 
-        Ok(Runtime { rt, metrics })
+        Ok(Runtime {
+            handle: rt.handle().clone(),
+            rt: Mutex::new(Some(rt)),
+            metrics,
+        })
     }
 }
 
@@ -262,6 +307,45 @@ mod tests {
         assert_eq!(out, "hello")
     }
 
+    #[test]
+    fn test_shutdown_timeout() {
+        let rt = rt();
+        let (tx, rx) = oneshot::channel::<()>();
+        let handle = rt.spawn(async move {
+            let _ = rx.await;
+        });
+
+        rt.shutdown_timeout(Duration::from_secs(1));
+        rt.shutdown_timeout(Duration::from_secs(1));
+        assert!(tx.is_closed());
+        let other = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        assert!(other.block_on(handle).is_err());
+        assert!(other.block_on(rt.spawn(async {})).is_err());
+    }
+
+    #[test]
+    fn test_from_handle() {
+        let owner = rt();
+        let rt = Runtime::from_handle("test_from_handle", owner.handle.clone());
+        assert!(owner.is_owned());
+        assert!(!rt.is_owned());
+        assert_eq!(2, rt.block_on(rt.spawn(async { 1 + 1 })).unwrap());
+
+        // the borrowed runtime keeps running
+        rt.shutdown_timeout(Duration::from_secs(1));
+        drop(rt);
+        assert_eq!(2, owner.block_on(owner.spawn(async { 1 + 1 })).unwrap());
+    }
+
+    #[test]
+    fn test_drop_in_async_context() {
+        let owner = rt();
+        let rt = rt();
+        owner.block_on(async move { drop(rt) });
+    }
+
     #[test]
     fn test_spawn_join() {
         let rt = rt();
@@ -81,9 +81,17 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
             Value::UInt64(i, _) => visitor.visit_u64(i),
             Value::Float(f, _) => visitor.visit_f32(f),
             Value::Double(f, _) => visitor.visit_f64(f),
+            Value::Date(d, _) => visitor.visit_i32(d),
+            Value::Time(t, _) => visitor.visit_i64(t),
+            Value::DateTime(t, _) => visitor.visit_i64(t),
+            Value::Timestamp(t, _) => visitor.visit_i64(t),
+            Value::Year(v, _) => visitor.visit_u8(v),
+            Value::Bit(v, _) => visitor.visit_u64(v),
+            Value::Enum(v, _) => visitor.visit_u64(v),
+            Value::Set(v, _) => visitor.visit_u64(v),
+            Value::Json(ref s, _) => visitor.visit_str(s),
             Value::Bytes(ref vc, _) => visitor.visit_bytes(&vc[..]),
             Value::String(ref s, _) => visitor.visit_str(s),
-            _ => self.deserialize_map(visitor),
         }
     }
 
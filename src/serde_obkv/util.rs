@@ -226,6 +226,17 @@ pub fn encode_bytes_string(v: &[u8], buf: &mut BytesMut) -> Result<()> {
     Ok(())
 }
 
+pub fn encoded_length_bytes(v: &[u8]) -> usize {
+    encoded_length_vi32(v.len() as i32) + v.len()
+}
+
+pub fn encode_bytes(v: &[u8], buf: &mut BytesMut) -> Result<()> {
+    buf.reserve(encoded_length_bytes(v));
+    encode_vi32(v.len() as i32, buf)?;
+    buf.put_slice(v);
+    Ok(())
+}
+
 pub fn decode_bytes(buf: &mut BytesMut) -> Result<Vec<u8>> {
     let len = decode_vi32(buf)? as usize;
     if len == 0 {
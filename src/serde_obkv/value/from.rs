@@ -17,7 +17,9 @@
 
 use std::borrow::Cow;
 
-use super::{ObjMeta, ObjType, Value};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike};
+
+use super::{epoch_date, ObjMeta, ObjType, Value, USECS_PER_SEC};
 
 macro_rules! from_i32 {
     ($($tx:ident,$ty:ident)*) => {
@@ -148,7 +150,59 @@ impl<'a> From<&'a [u8]> for Value {
     }
 }
 
-//TODO date and time
+impl From<NaiveDate> for Value {
+    fn from(d: NaiveDate) -> Self {
+        Value::Date(
+            d.signed_duration_since(epoch_date()).num_days() as i32,
+            ObjMeta::default_obj_meta(ObjType::Date),
+        )
+    }
+}
+
+impl From<NaiveTime> for Value {
+    fn from(t: NaiveTime) -> Self {
+        Value::Time(
+            t.num_seconds_from_midnight() as i64 * USECS_PER_SEC + (t.nanosecond() / 1000) as i64,
+            ObjMeta::default_obj_meta(ObjType::Time),
+        )
+    }
+}
+
+impl From<serde_json::Value> for Value {
+    fn from(v: serde_json::Value) -> Self {
+        Value::Json(v.to_string(), ObjMeta::default_obj_meta(ObjType::Json))
+    }
+}
+
+/// Durations are mapped to `time`, which may be negative or exceed one day.
+impl From<Duration> for Value {
+    fn from(d: Duration) -> Self {
+        Value::Time(
+            d.num_microseconds().unwrap_or(i64::MAX),
+            ObjMeta::default_obj_meta(ObjType::Time),
+        )
+    }
+}
+
+impl From<NaiveDateTime> for Value {
+    fn from(dt: NaiveDateTime) -> Self {
+        Value::DateTime(
+            dt.timestamp_micros(),
+            ObjMeta::default_obj_meta(ObjType::DateTime),
+        )
+    }
+}
+
+/// Datetimes with a time zone are absolute instants and are mapped to
+/// `timestamp`, use `dt.naive_local()` for a `datetime` column.
+impl<Tz: TimeZone> From<DateTime<Tz>> for Value {
+    fn from(dt: DateTime<Tz>) -> Self {
+        Value::Timestamp(
+            dt.timestamp_micros(),
+            ObjMeta::default_obj_meta(ObjType::Timestamp),
+        )
+    }
+}
 
 #[cfg(test)]
 mod test {
@@ -159,4 +213,21 @@ mod test {
         let v = Value::from(32);
         assert_eq!(32, v.as_i32());
     }
+
+    #[test]
+    fn test_from_chrono() {
+        let d = NaiveDate::from_ymd_opt(1970, 1, 2).unwrap();
+        assert_eq!(
+            Value::Date(1, ObjMeta::default_obj_meta(ObjType::Date)),
+            d.into()
+        );
+        assert_eq!(d, Value::from(d).as_naive_date());
+
+        let t = NaiveTime::from_hms_micro_opt(0, 0, 1, 1).unwrap();
+        assert_eq!(
+            Value::Time(1_000_001, ObjMeta::default_obj_meta(ObjType::Time)),
+            t.into()
+        );
+        assert_eq!(t, Value::from(t).as_naive_time());
+    }
 }
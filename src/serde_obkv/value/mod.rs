@@ -25,39 +25,70 @@ pub mod from;
 use std::hash::{Hash, Hasher};
 
 use bytes::{Buf, BufMut, BytesMut};
+use chrono::{DateTime, Duration, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
 use serde::ser::{Serialize, Serializer};
 
 use super::{
     error::{Error, Result},
     util::{
         self, decode_bytes_string, decode_f32, decode_f64, decode_i8, decode_u8, decode_vi32,
-        decode_vi64, decode_vstring, encode_f32, encode_f64, encode_vi32, encode_vi64,
-        encode_vstring, split_buf_to,
+        decode_vi64, decode_vstring, encode_bytes_string, encode_f32, encode_f64, encode_vi32,
+        encode_vi64, split_buf_to,
     },
 };
 
 const VALUE_MAX: i64 = -2i64;
 const VALUE_MIN: i64 = -3i64;
 
+const USECS_PER_SEC: i64 = 1_000_000;
+const USECS_PER_DAY: i64 = 86_400 * USECS_PER_SEC;
+// year is stored as the offset from 1900, and 0 stands for the zero year `0000`
+const YEAR_BASE: u16 = 1900;
+const YEAR_MIN: u16 = 1901;
+const YEAR_MAX: u16 = 2155;
+
+// the high bit of cs_level marks that a lob value is prefixed with a lob
+// header, the layout is checked against a real server by
+// `test_lob_and_misc_types_from_server`
+const LOB_HEADER_FLAG: u8 = 0x80;
+// ObLobCommon is a 4 bytes bit field: version(8), is_init(1), is_empty(1),
+// in_row(1), opt_encrypt(1), opt_compress(1), opt_deduplicate(1),
+// has_content_type(1), use_big_endian(1), is_mem_loc(1), read_only(1), reserved
+const LOB_COMMON_LEN: usize = 4;
+const LOB_DATA_VERSION: u32 = 1;
+const LOB_COMMON_IS_INIT: u32 = 1 << 8;
+const LOB_COMMON_IN_ROW: u32 = 1 << 10;
+const LOB_COMMON_USE_BIG_ENDIAN: u32 = 1 << 15;
+
 #[derive(Clone, Debug, Eq, Hash, PartialEq, PartialOrd)]
 pub enum CollationType {
     Invalid = 0,
+    GBKChineseCi = 28,
     UTF8MB4GeneralCi = 45, // default, case-insensitive,
     UTF8MB4Bin = 46,       // case sensitive
     Binary = 63,
+    GBKBin = 87,
     CollationFree = 100, // not supported in mysql
     Max = 101,
+    UTF8MB4UnicodeCi = 224,
+    GB18030ChineseCi = 248,
+    GB18030Bin = 249,
 }
 
 impl CollationType {
     pub fn from_u8(v: u8) -> Result<CollationType> {
         match v {
             0 => Ok(CollationType::Invalid),
+            28 => Ok(CollationType::GBKChineseCi),
             45 => Ok(CollationType::UTF8MB4GeneralCi),
             46 => Ok(CollationType::UTF8MB4Bin),
             63 => Ok(CollationType::Binary),
+            87 => Ok(CollationType::GBKBin),
             100 => Ok(CollationType::CollationFree),
             101 => Ok(CollationType::Max),
+            224 => Ok(CollationType::UTF8MB4UnicodeCi),
+            248 => Ok(CollationType::GB18030ChineseCi),
+            249 => Ok(CollationType::GB18030Bin),
             _ => Err(Error::Custom(
                 format!("CollationType::from_u8 invalid collation type, v={v}").into(),
             )),
@@ -101,6 +132,7 @@ pub struct ObjMeta {
     cs_level: CollationLevel,
     cs_type: CollationType,
     scale: i8, // when the type is ObBitType, this value keeps the bit's length.
+    has_lob_header: bool,
 }
 
 #[derive(Clone, Debug, Hash, Eq, PartialEq, PartialOrd)]
@@ -134,7 +166,11 @@ pub enum ObjType {
     Text = 28,
     MediumText = 29,
     LongText = 30,
-    Bit = 31, //TODO
+    Bit = 31,
+    Enum = 32,
+    Set = 33,
+    Lob = 46,
+    Json = 47,
 }
 
 impl ObjType {
@@ -163,20 +199,43 @@ impl ObjType {
             21 => Ok(ObjType::Year),
             22 => Ok(ObjType::Varchar),
             23 => Ok(ObjType::Char),
+            24 => Ok(ObjType::HexString),
             25 => Ok(ObjType::Extend),
             27 => Ok(ObjType::TinyText),
             28 => Ok(ObjType::Text),
             29 => Ok(ObjType::MediumText),
             30 => Ok(ObjType::LongText),
             31 => Ok(ObjType::Bit),
+            32 => Ok(ObjType::Enum),
+            33 => Ok(ObjType::Set),
+            46 => Ok(ObjType::Lob),
+            47 => Ok(ObjType::Json),
             _ => Err(Error::Custom(
                 format!("ObjType::from_u8 invalid ob obj type, v={v}").into(),
             )),
         }
     }
+
+    /// Whether values of this type are stored as lob and may carry a lob
+    /// header.
+    pub fn is_lob_storage(&self) -> bool {
+        matches!(
+            self,
+            ObjType::TinyText
+                | ObjType::Text
+                | ObjType::MediumText
+                | ObjType::LongText
+                | ObjType::Lob
+                | ObjType::Json
+        )
+    }
 }
 
 //Represent any valid OBKV value.
+//
+//The temporal variants hold what the server encodes, `Date` and `Time` changed
+//their units in the same release that introduced `DateTime` and `Timestamp`,
+//see the breaking changes in CHANGELOG.md.
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
 pub enum Value {
     Null(ObjMeta),
@@ -189,8 +248,15 @@ pub enum Value {
     UInt64(u64, ObjMeta),    //u64
     Float(f32, ObjMeta),     //f32, uf32
     Double(f64, ObjMeta),    //f64,uf64
-    Date(i32, ObjMeta),      //date, in seconds
-    Time(i64, ObjMeta),      //datetime, timestamp, in millseconds
+    Date(i32, ObjMeta),      //date, days since 1970-01-01
+    Time(i64, ObjMeta),      //time, in microseconds
+    DateTime(i64, ObjMeta),  //datetime, wall clock in microseconds since 1970-01-01 00:00:00
+    Timestamp(i64, ObjMeta), //timestamp, in microseconds since unix epoch (UTC)
+    Year(u8, ObjMeta),       //year, offset from 1900
+    Bit(u64, ObjMeta),       //bit, the length is kept in meta scale
+    Enum(u64, ObjMeta),      //enum, 1-based index of the element
+    Set(u64, ObjMeta),       //set, bitmap of the elements
+    Json(String, ObjMeta),   //json text
     Bytes(Vec<u8>, ObjMeta), //varchar
     String(String, ObjMeta), //text,char
 }
@@ -200,7 +266,11 @@ impl ObjMeta {
     fn encode(&self, buf: &mut BytesMut) -> Result<()> {
         buf.reserve(4);
         buf.put_i8(self.obj_type.to_owned() as i8);
-        buf.put_i8(self.cs_level.to_owned() as i8);
+        if self.has_lob_header {
+            buf.put_u8(self.cs_level.to_owned() as u8 | LOB_HEADER_FLAG);
+        } else {
+            buf.put_i8(self.cs_level.to_owned() as i8);
+        }
         buf.put_i8(self.cs_type.to_owned() as i8);
         buf.put_i8(self.scale.to_owned());
         Ok(())
@@ -216,7 +286,9 @@ impl ObjMeta {
         if buf.len() >= 4 {
             let mut buf = split_buf_to(buf, 4)?;
             let obj_type = ObjType::from_u8(buf.get_u8())?;
-            let cs_level = CollationLevel::from_u8(buf.get_u8())?;
+            let cs_level = buf.get_u8();
+            let has_lob_header = obj_type.is_lob_storage() && cs_level & LOB_HEADER_FLAG != 0;
+            let cs_level = CollationLevel::from_u8(cs_level & !LOB_HEADER_FLAG)?;
             let cs_type = CollationType::from_u8(buf.get_u8())?;
 
             Ok(ObjMeta {
@@ -224,6 +296,7 @@ impl ObjMeta {
                 cs_level,
                 cs_type,
                 scale: buf.get_i8(),
+                has_lob_header,
             })
         } else {
             Err(Error::Custom("Fail to decode obj meta".into()))
@@ -241,9 +314,14 @@ impl ObjMeta {
             cs_level,
             cs_type,
             scale,
+            has_lob_header: false,
         }
     }
 
+    pub fn has_lob_header(&self) -> bool {
+        self.has_lob_header
+    }
+
     pub fn new_numeric_meta(obj_type: ObjType) -> ObjMeta {
         ObjMeta::new(obj_type, CollationLevel::Numeric, CollationType::Binary, -1)
     }
@@ -284,14 +362,29 @@ impl ObjMeta {
                 10,
             ),
             ObjType::HexString => {
-                unimplemented!();
+                ObjMeta::new(t, CollationLevel::Numeric, CollationType::Binary, -1)
             }
             ObjType::Extend => ObjMeta::new_numeric_meta(t),
-            ObjType::TinyText => ObjMeta::new_numeric_meta(t),
-            ObjType::Text => ObjMeta::new_numeric_meta(t),
-            ObjType::MediumText => ObjMeta::new_numeric_meta(t),
-            ObjType::LongText => ObjMeta::new_numeric_meta(t),
+            ObjType::TinyText
+            | ObjType::Text
+            | ObjType::MediumText
+            | ObjType::LongText
+            | ObjType::Lob => ObjMeta::new(
+                t,
+                CollationLevel::Implicit,
+                CollationType::UTF8MB4GeneralCi,
+                10,
+            ),
             ObjType::Bit => ObjMeta::new_numeric_meta(t),
+            ObjType::Enum | ObjType::Set => ObjMeta::new(
+                t,
+                CollationLevel::Implicit,
+                CollationType::UTF8MB4GeneralCi,
+                10,
+            ),
+            ObjType::Json => {
+                ObjMeta::new(t, CollationLevel::Implicit, CollationType::UTF8MB4Bin, 10)
+            }
         }
     }
 }
@@ -362,7 +455,7 @@ impl Value {
 
     pub fn as_string(self) -> String {
         match self {
-            Value::String(s, _) => s,
+            Value::String(s, _) | Value::Json(s, _) => s,
             _ => panic!("Fail to cast: {self:?}"),
         }
     }
@@ -371,6 +464,7 @@ impl Value {
         match self {
             Value::Int64(i, _) => *i,
             Value::UInt64(i, _) => *i as i64,
+            Value::Bit(v, _) | Value::Enum(v, _) | Value::Set(v, _) => *v as i64,
             _ => panic!("Fail to cast: {self:?}"),
         }
     }
@@ -425,8 +519,186 @@ impl Value {
         self.as_i8() as u8
     }
 
+    pub fn is_date(&self) -> bool {
+        matches!(self, Value::Date(_, _))
+    }
+
+    pub fn is_time(&self) -> bool {
+        matches!(self, Value::Time(_, _))
+    }
+
+    pub fn is_datetime(&self) -> bool {
+        matches!(self, Value::DateTime(_, _))
+    }
+
+    pub fn is_timestamp(&self) -> bool {
+        matches!(self, Value::Timestamp(_, _))
+    }
+
+    pub fn is_year(&self) -> bool {
+        matches!(self, Value::Year(_, _))
+    }
+
+    /// Build a year value, `year` must be 0 or in [1901, 2155].
+    pub fn year(year: u16) -> Result<Value> {
+        let v = match year {
+            0 => 0,
+            YEAR_MIN..=YEAR_MAX => (year - YEAR_BASE) as u8,
+            _ => {
+                return Err(Error::Custom(
+                    format!("Value::year year out of range, year={year}").into(),
+                ))
+            }
+        };
+        Ok(Value::Year(v, ObjMeta::default_obj_meta(ObjType::Year)))
+    }
+
+    pub fn as_year(&self) -> u16 {
+        match self {
+            Value::Year(0, _) => 0,
+            Value::Year(v, _) => YEAR_BASE + *v as u16,
+            _ => panic!("Fail to cast: {self:?}"),
+        }
+    }
+
+    pub fn as_naive_date(&self) -> NaiveDate {
+        match self {
+            Value::Date(d, _) => epoch_date()
+                .checked_add_signed(Duration::days(*d as i64))
+                .unwrap_or_else(|| panic!("Fail to cast: {self:?}")),
+            _ => panic!("Fail to cast: {self:?}"),
+        }
+    }
+
+    /// Time of the day, panics if the time value is negative or exceeds one
+    /// day, use [`Value::as_duration`] for these values.
+    pub fn as_naive_time(&self) -> NaiveTime {
+        match self {
+            Value::Time(t, _) if (0..USECS_PER_DAY).contains(t) => {
+                NaiveTime::from_num_seconds_from_midnight_opt(
+                    (t / USECS_PER_SEC) as u32,
+                    (t % USECS_PER_SEC * 1000) as u32,
+                )
+                .unwrap_or_else(|| panic!("Fail to cast: {self:?}"))
+            }
+            _ => panic!("Fail to cast: {self:?}"),
+        }
+    }
+
+    pub fn as_duration(&self) -> Duration {
+        match self {
+            Value::Time(t, _) => Duration::microseconds(*t),
+            _ => panic!("Fail to cast: {self:?}"),
+        }
+    }
+
+    /// Wall clock of a datetime value, or the UTC wall clock of a timestamp
+    /// value.
+    pub fn as_naive_datetime(&self) -> NaiveDateTime {
+        match self {
+            Value::DateTime(t, _) | Value::Timestamp(t, _) => {
+                NaiveDateTime::from_timestamp_micros(*t)
+                    .unwrap_or_else(|| panic!("Fail to cast: {self:?}"))
+            }
+            _ => panic!("Fail to cast: {self:?}"),
+        }
+    }
+
+    /// Convert to a datetime in the time zone `tz`.
+    ///
+    /// A timestamp is an absolute instant and is converted into `tz`, while a
+    /// datetime is a wall clock and is interpreted as a local time of `tz`.
+    pub fn as_datetime<Tz: TimeZone>(&self, tz: &Tz) -> DateTime<Tz> {
+        match self {
+            Value::Timestamp(_, _) => Utc
+                .from_utc_datetime(&self.as_naive_datetime())
+                .with_timezone(tz),
+            Value::DateTime(_, _) => match tz.from_local_datetime(&self.as_naive_datetime()) {
+                LocalResult::Single(dt) => dt,
+                LocalResult::Ambiguous(earliest, _) => earliest,
+                LocalResult::None => panic!("Fail to cast: {self:?}"),
+            },
+            _ => panic!("Fail to cast: {self:?}"),
+        }
+    }
+
+    pub fn is_bit(&self) -> bool {
+        matches!(self, Value::Bit(_, _))
+    }
+
+    pub fn is_enum(&self) -> bool {
+        matches!(self, Value::Enum(_, _))
+    }
+
+    pub fn is_set(&self) -> bool {
+        matches!(self, Value::Set(_, _))
+    }
+
+    pub fn is_json(&self) -> bool {
+        matches!(self, Value::Json(_, _))
+    }
+
+    /// Build a bit value of `len` bits.
+    pub fn bit(v: u64, len: i8) -> Value {
+        let mut meta = ObjMeta::default_obj_meta(ObjType::Bit);
+        meta.scale = len;
+        Value::Bit(v, meta)
+    }
+
+    /// Build an enum value from the 1-based index of the element.
+    pub fn enum_value(idx: u64) -> Value {
+        Value::Enum(idx, ObjMeta::default_obj_meta(ObjType::Enum))
+    }
+
+    /// Build a set value from the bitmap of the elements.
+    pub fn set_value(bitmap: u64) -> Value {
+        Value::Set(bitmap, ObjMeta::default_obj_meta(ObjType::Set))
+    }
+
+    pub fn json<S: Into<String>>(s: S) -> Value {
+        Value::Json(s.into(), ObjMeta::default_obj_meta(ObjType::Json))
+    }
+
+    /// Build a text value of `obj_type`, which must be one of `TinyText`,
+    /// `Text`, `MediumText`, `LongText` or `Lob`.
+    pub fn text<S: Into<String>>(s: S, obj_type: ObjType) -> Result<Value> {
+        Self::check_text_type(&obj_type)?;
+        Ok(Value::String(s.into(), ObjMeta::default_obj_meta(obj_type)))
+    }
+
+    /// Build a blob value of `obj_type`, which must be one of `TinyText`,
+    /// `Text`, `MediumText`, `LongText` or `Lob`.
+    pub fn blob<B: Into<Vec<u8>>>(b: B, obj_type: ObjType) -> Result<Value> {
+        Self::check_text_type(&obj_type)?;
+        Ok(Value::Bytes(
+            b.into(),
+            ObjMeta::new(
+                obj_type,
+                CollationLevel::Implicit,
+                CollationType::Binary,
+                10,
+            ),
+        ))
+    }
+
+    fn check_text_type(obj_type: &ObjType) -> Result<()> {
+        if !obj_type.is_lob_storage() || *obj_type == ObjType::Json {
+            return Err(Error::Custom(
+                format!("Value::check_text_type invalid text type, obj_type={obj_type:?}").into(),
+            ));
+        }
+        Ok(())
+    }
+
     fn decode_binary(buf: &mut BytesMut, meta: ObjMeta) -> Result<Value> {
-        let v = if meta.cs_type == CollationType::Binary {
+        let v = if meta.has_lob_header {
+            let bs = Self::decode_lob(buf)?;
+            if meta.cs_type == CollationType::Binary {
+                Value::Bytes(bs, meta)
+            } else {
+                Value::String(Self::lob_to_string(bs)?, meta)
+            }
+        } else if meta.cs_type == CollationType::Binary {
             let bs = decode_bytes_string(buf)?;
             Value::Bytes(bs, meta)
         } else {
@@ -437,6 +709,65 @@ impl Value {
         Ok(v)
     }
 
+    fn decode_json(buf: &mut BytesMut, meta: ObjMeta) -> Result<Value> {
+        let bs = if meta.has_lob_header {
+            Self::decode_lob(buf)?
+        } else {
+            decode_bytes_string(buf)?
+        };
+        Ok(Value::Json(Self::lob_to_string(bs)?, meta))
+    }
+
+    fn lob_to_string(bs: Vec<u8>) -> Result<String> {
+        String::from_utf8(bs).map_err(|e| Error::Utf8(e.utf8_error()))
+    }
+
+    /// Decode the data of an inline lob, which is prefixed with ObLobCommon.
+    fn decode_lob(buf: &mut BytesMut) -> Result<Vec<u8>> {
+        let mut bs = decode_bytes_string(buf)?;
+        if bs.len() < LOB_COMMON_LEN {
+            return Err(Error::Custom(
+                format!(
+                    "Value::decode_lob lob header is truncated, len={}",
+                    bs.len()
+                )
+                .into(),
+            ));
+        }
+        let lob_common = u32::from_le_bytes(bs[..LOB_COMMON_LEN].try_into().unwrap());
+        if lob_common & LOB_COMMON_IN_ROW == 0 || lob_common & LOB_COMMON_IS_INIT != 0 {
+            return Err(Error::Custom(
+                format!(
+                    "Value::decode_lob out row lob is not supported, lob_common={lob_common:#x}"
+                )
+                .into(),
+            ));
+        }
+        Ok(bs.split_off(LOB_COMMON_LEN))
+    }
+
+    fn encoded_length_binary(len: usize, meta: &ObjMeta) -> usize {
+        let len = if meta.has_lob_header {
+            len + LOB_COMMON_LEN
+        } else {
+            len
+        };
+        meta.len() + util::encoded_length_vi32(len as i32) + len + 1
+    }
+
+    fn encode_binary(bs: &[u8], meta: &ObjMeta, buf: &mut BytesMut) -> Result<()> {
+        meta.encode(buf)?;
+        if meta.has_lob_header {
+            encode_vi32((bs.len() + LOB_COMMON_LEN) as i32, buf)?;
+            buf.put_u32_le(LOB_DATA_VERSION | LOB_COMMON_IN_ROW | LOB_COMMON_USE_BIG_ENDIAN);
+            buf.put_slice(bs);
+            buf.put_u8(0);
+            Ok(())
+        } else {
+            encode_bytes_string(bs, buf)
+        }
+    }
+
     /// Max value object
     pub fn get_max() -> Value {
         Value::Int64(VALUE_MAX, ObjMeta::default_obj_meta(ObjType::Extend))
@@ -480,13 +811,16 @@ impl Value {
                 meta.len() + util::encoded_length_vi64(f.to_bits() as i64)
             }
             Value::Date(d, ref meta) => meta.len() + util::encoded_length_vi32(d),
-            Value::Time(d, ref meta) => meta.len() + util::encoded_length_vi64(d),
-            Value::Bytes(ref vc, ref meta) => {
-                meta.len() + util::encoded_length_vi32(vc.len() as i32) + vc.len() + 1
-            }
-            Value::String(ref s, ref meta) => {
-                meta.len() + util::encoded_length_vi32(s.len() as i32) + s.len() + 1
-            }
+            Value::Time(t, ref meta) => meta.len() + util::encoded_length_vi64(t),
+            Value::DateTime(t, ref meta) => meta.len() + util::encoded_length_vi64(t),
+            Value::Timestamp(t, ref meta) => meta.len() + util::encoded_length_vi64(t),
+            Value::Year(_, ref meta) => meta.len() + 1,
+            Value::Bit(v, ref meta) => meta.len() + util::encoded_length_vi64(v as i64),
+            Value::Enum(v, ref meta) => meta.len() + util::encoded_length_vi64(v as i64),
+            Value::Set(v, ref meta) => meta.len() + util::encoded_length_vi64(v as i64),
+            Value::Json(ref s, ref meta) => Self::encoded_length_binary(s.len(), meta),
+            Value::Bytes(ref vc, ref meta) => Self::encoded_length_binary(vc.len(), meta),
+            Value::String(ref s, ref meta) => Self::encoded_length_binary(s.len(), meta),
         }
     }
 
@@ -509,21 +843,24 @@ impl Value {
             ObjType::Double => Ok(Value::Double(decode_f64(buf)?, meta)),
             ObjType::UFloat => Ok(Value::Float(decode_f32(buf)?, meta)),
             ObjType::UDouble => Ok(Value::Double(decode_f64(buf)?, meta)),
-            //FIXME date and time
-            ObjType::DateTime => unimplemented!(),
-            ObjType::Timestamp => unimplemented!(),
-            ObjType::Date => unimplemented!(),
-            ObjType::Time => unimplemented!(),
-            ObjType::Year => unimplemented!(),
+            ObjType::DateTime => Ok(Value::DateTime(decode_vi64(buf)?, meta)),
+            ObjType::Timestamp => Ok(Value::Timestamp(decode_vi64(buf)?, meta)),
+            ObjType::Date => Ok(Value::Date(decode_vi32(buf)?, meta)),
+            ObjType::Time => Ok(Value::Time(decode_vi64(buf)?, meta)),
+            ObjType::Year => Ok(Value::Year(decode_u8(buf)?, meta)),
             ObjType::Varchar => Self::decode_binary(buf, meta),
             ObjType::Char => Self::decode_binary(buf, meta),
-            // TODO: ObjType::HexString
+            ObjType::HexString => Ok(Value::Bytes(decode_bytes_string(buf)?, meta)),
             ObjType::Extend => Ok(Value::Int64(decode_vi64(buf)?, meta)),
             ObjType::TinyText => Self::decode_binary(buf, meta),
             ObjType::Text => Self::decode_binary(buf, meta),
             ObjType::MediumText => Self::decode_binary(buf, meta),
             ObjType::LongText => Self::decode_binary(buf, meta),
-            ObjType::Bit => Ok(Value::Int64(decode_vi64(buf)?, meta)),
+            ObjType::Lob => Self::decode_binary(buf, meta),
+            ObjType::Bit => Ok(Value::Bit(decode_vi64(buf)? as u64, meta)),
+            ObjType::Enum => Ok(Value::Enum(decode_vi64(buf)? as u64, meta)),
+            ObjType::Set => Ok(Value::Set(decode_vi64(buf)? as u64, meta)),
+            ObjType::Json => Self::decode_json(buf, meta),
             _ => Err(Error::Custom("Unsupported obj type.".into())),
         }
     }
@@ -578,26 +915,39 @@ impl Value {
                 meta.encode(buf)?;
                 encode_vi32(d, buf)
             }
-            Value::Time(d, ref meta) => {
+            Value::Time(t, ref meta) => {
+                meta.encode(buf)?;
+                encode_vi64(t, buf)
+            }
+            Value::DateTime(t, ref meta) => {
+                meta.encode(buf)?;
+                encode_vi64(t, buf)
+            }
+            Value::Timestamp(t, ref meta) => {
                 meta.encode(buf)?;
-                encode_vi64(d * 1000, buf)
+                encode_vi64(t, buf)
             }
-            Value::Bytes(ref vc, ref meta) => {
+            Value::Year(v, ref meta) => {
                 meta.encode(buf)?;
-                //refactor encode binary
-                encode_vi32(vc.len() as i32, buf)?;
-                buf.put_slice(&vc[..]);
-                buf.put_i8(0);
+                buf.put_u8(v);
                 Ok(())
             }
-            Value::String(ref s, ref meta) => {
+            Value::Bit(v, ref meta) | Value::Enum(v, ref meta) | Value::Set(v, ref meta) => {
                 meta.encode(buf)?;
-                encode_vstring(s, buf)
+                encode_vi64(v as i64, buf)
             }
+            Value::Json(ref s, ref meta) => Self::encode_binary(s.as_bytes(), meta, buf),
+            Value::Bytes(ref vc, ref meta) => Self::encode_binary(vc, meta, buf),
+            Value::String(ref s, ref meta) => Self::encode_binary(s.as_bytes(), meta, buf),
         }
     }
 }
 
+#[inline]
+fn epoch_date() -> NaiveDate {
+    NaiveDate::from_ymd_opt(1970, 1, 1).unwrap()
+}
+
 impl Default for Value {
     fn default() -> Value {
         Value::Null(ObjMeta::default_obj_meta(ObjType::Null))
@@ -621,6 +971,13 @@ impl Hash for Value {
             Value::Double(f, _) => f.to_bits().hash(state),
             Value::Date(d, _) => (5, d).hash(state),
             Value::Time(t, _) => (6, t).hash(state),
+            Value::DateTime(t, _) => (9, t).hash(state),
+            Value::Timestamp(t, _) => (10, t).hash(state),
+            Value::Year(v, _) => (11, v).hash(state),
+            Value::Bit(v, _) => (12, v).hash(state),
+            Value::Enum(v, _) => (13, v).hash(state),
+            Value::Set(v, _) => (14, v).hash(state),
+            Value::Json(ref s, _) => (15, s).hash(state),
             Value::Bytes(ref vc, _) => (7, vc).hash(state),
             Value::String(ref s, _) => (8, s).hash(state),
         }
@@ -645,6 +1002,13 @@ impl Serialize for Value {
             Value::Double(f, _) => serializer.serialize_f64(f),
             Value::Date(d, _) => serializer.serialize_i32(d),
             Value::Time(t, _) => serializer.serialize_i64(t),
+            Value::DateTime(t, _) => serializer.serialize_i64(t),
+            Value::Timestamp(t, _) => serializer.serialize_i64(t),
+            Value::Year(v, _) => serializer.serialize_u8(v),
+            Value::Bit(v, _) => serializer.serialize_u64(v),
+            Value::Enum(v, _) => serializer.serialize_u64(v),
+            Value::Set(v, _) => serializer.serialize_u64(v),
+            Value::Json(ref s, _) => serializer.serialize_str(s),
             Value::Bytes(ref vc, _) => serializer.serialize_bytes(vc),
             Value::String(ref s, _) => serializer.serialize_str(s),
         }
@@ -653,6 +1017,8 @@ impl Serialize for Value {
 
 #[cfg(test)]
 mod test {
+    use chrono::FixedOffset;
+
     use super::{
         super::ser::{serialize_len, to_bytes_mut},
         *,
@@ -678,4 +1044,118 @@ mod test {
         assert!(ret.is_ok());
         assert_eq!(35, ret.unwrap().len());
     }
+
+    #[test]
+    fn test_temporal_round_trip() {
+        let values = vec![
+            Value::from(NaiveDate::from_ymd_opt(2023, 9, 1).unwrap()),
+            Value::from(NaiveTime::from_hms_micro_opt(23, 59, 59, 999_999).unwrap()),
+            Value::from(
+                NaiveDate::from_ymd_opt(1969, 12, 31)
+                    .unwrap()
+                    .and_hms_micro_opt(12, 0, 0, 1)
+                    .unwrap(),
+            ),
+            Value::from(Utc.with_ymd_and_hms(2023, 9, 1, 8, 30, 0).unwrap()),
+            Value::year(2023).unwrap(),
+        ];
+        for v in values {
+            let mut buf = BytesMut::with_capacity(v.len());
+            v.encode(&mut buf).unwrap();
+            assert_eq!(v.len(), buf.len());
+            let obj_type = ObjType::from_u8(buf[0]).unwrap();
+            assert_eq!(v, Value::decode(&mut buf, obj_type).unwrap());
+            assert!(buf.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_lob_and_misc_types_round_trip() {
+        let values = vec![
+            Value::bit(0b1011, 4),
+            Value::enum_value(2),
+            Value::set_value(0b101),
+            Value::json(r#"{"k":[1,2]}"#),
+            Value::from(serde_json::json!({"k": "v"})),
+            Value::text("hello", ObjType::TinyText).unwrap(),
+            Value::text("hello", ObjType::LongText).unwrap(),
+            Value::blob(vec![0u8, 1, 2], ObjType::MediumText).unwrap(),
+        ];
+        for v in values {
+            let mut buf = BytesMut::with_capacity(v.len());
+            v.encode(&mut buf).unwrap();
+            assert_eq!(v.len(), buf.len());
+            let obj_type = ObjType::from_u8(buf[0]).unwrap();
+            assert_eq!(v, Value::decode(&mut buf, obj_type).unwrap());
+            assert!(buf.is_empty());
+        }
+        assert_eq!(0b1011, Value::bit(0b1011, 4).as_i64());
+        assert_eq!(2, Value::enum_value(2).as_i64());
+        assert_eq!(u64::MAX, Value::set_value(u64::MAX).as_u64());
+        assert!(Value::text("hello", ObjType::Varchar).is_err());
+        assert!(Value::text("hello", ObjType::Json).is_err());
+    }
+
+    #[test]
+    fn test_lob_header() {
+        // meta: text, implicit collation level with lob header flag, utf8mb4_general_ci
+        let mut buf = BytesMut::new();
+        buf.put_slice(&[ObjType::Text as u8, 0x82, 45, 10]);
+        encode_vi32(9, &mut buf).unwrap();
+        buf.put_u32_le(LOB_DATA_VERSION | LOB_COMMON_IN_ROW | LOB_COMMON_USE_BIG_ENDIAN);
+        buf.put_slice(b"hello");
+        buf.put_u8(0);
+        let raw = buf.clone();
+
+        let v = Value::decode(&mut buf, ObjType::Text).unwrap();
+        match v {
+            Value::String(ref s, ref meta) => {
+                assert_eq!("hello", s);
+                assert!(meta.has_lob_header());
+            }
+            _ => panic!("unexpected value: {v:?}"),
+        }
+        let mut encoded = BytesMut::new();
+        v.encode(&mut encoded).unwrap();
+        assert_eq!(raw, encoded);
+        assert_eq!(raw.len(), v.len());
+
+        // out row lob
+        let mut buf = BytesMut::new();
+        buf.put_slice(&[ObjType::Json as u8, 0x82, 46, 10]);
+        encode_vi32(4, &mut buf).unwrap();
+        buf.put_u32_le(LOB_DATA_VERSION | LOB_COMMON_IS_INIT);
+        buf.put_u8(0);
+        assert!(Value::decode(&mut buf, ObjType::Json).is_err());
+    }
+
+    #[test]
+    fn test_temporal_precision_and_time_zone() {
+        let ndt = NaiveDate::from_ymd_opt(2023, 9, 1)
+            .unwrap()
+            .and_hms_micro_opt(10, 20, 30, 123_456)
+            .unwrap();
+        let v = Value::from(ndt);
+        assert!(v.is_datetime());
+        assert_eq!(ndt, v.as_naive_datetime());
+
+        let tz = FixedOffset::east_opt(8 * 3600).unwrap();
+        // datetime is a wall clock
+        assert_eq!(ndt, v.as_datetime(&tz).naive_local());
+
+        // timestamp is an instant
+        let dt = tz.from_local_datetime(&ndt).unwrap();
+        let v = Value::from(dt);
+        assert!(v.is_timestamp());
+        assert_eq!(dt, v.as_datetime(&tz));
+        assert_eq!(ndt - Duration::hours(8), v.as_naive_datetime());
+
+        let v = Value::from(Duration::microseconds(-1_500_000));
+        assert!(v.is_time());
+        assert_eq!(Duration::microseconds(-1_500_000), v.as_duration());
+
+        assert_eq!(0, Value::year(0).unwrap().as_year());
+        assert_eq!(2155, Value::year(2155).unwrap().as_year());
+        assert!(Value::year(1900).is_err());
+    }
 }
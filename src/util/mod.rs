@@ -28,6 +28,7 @@ use crate::serde_obkv::value::{ObjType, Value};
 pub mod obversion;
 pub mod permit;
 pub mod security;
+pub mod timezone;
 
 #[inline]
 pub fn current_time_millis() -> i64 {
@@ -41,7 +42,7 @@ pub fn assert_not_empty(s: &str, msg: &'static str) {
 
 #[inline]
 pub fn duration_to_millis(duration: &Duration) -> i64 {
-    duration.as_secs() as i64 * 1000
+    duration.as_millis() as i64
 }
 
 #[inline]
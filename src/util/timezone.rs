@@ -0,0 +1,65 @@
+/*-
+ * #%L
+ * OBKV Table Client Framework
+ * %%
+ * Copyright (C) 2021 OceanBase
+ * %%
+ * OBKV Table Client Framework is licensed under Mulan PSL v2.
+ * You can use this software according to the terms and conditions of the
+ * Mulan PSL v2. You may obtain a copy of Mulan PSL v2 at:
+ *          http://license.coscl.org.cn/MulanPSL2
+ * THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ * KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ * NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ * See the Mulan PSL v2 for more details.
+ * #L%
+ */
+
+//! Time zone the server renders timestamps in, such as the bounds of the
+//! partitions on timestamp columns. It is resolved by each client, see
+//! [`ClientConfig::server_time_zone`](crate::client::ClientConfig).
+
+use chrono::FixedOffset;
+
+/// UTC, the time zone assumed until the one of the server is known.
+pub fn utc() -> FixedOffset {
+    FixedOffset::east_opt(0).unwrap()
+}
+
+/// Parse the `time_zone` system variable, which is an offset such as
+/// `+08:00`, return none for the named time zones.
+pub fn parse_time_zone(time_zone: &str) -> Option<FixedOffset> {
+    let time_zone = time_zone.trim();
+    if time_zone.eq_ignore_ascii_case("UTC") {
+        return FixedOffset::east_opt(0);
+    }
+    let (sign, offset) = match time_zone.as_bytes().first() {
+        Some(b'+') => (1, &time_zone[1..]),
+        Some(b'-') => (-1, &time_zone[1..]),
+        _ => return None,
+    };
+    let (hours, minutes) = offset.split_once(':')?;
+    let (hours, minutes): (i32, i32) = (hours.parse().ok()?, minutes.parse().ok()?);
+    if !(0..60).contains(&minutes) {
+        return None;
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_time_zone() {
+        assert_eq!(FixedOffset::east_opt(8 * 3600), parse_time_zone("+08:00"));
+        assert_eq!(
+            FixedOffset::west_opt(5 * 3600 + 30 * 60),
+            parse_time_zone("-05:30")
+        );
+        assert_eq!(FixedOffset::east_opt(0), parse_time_zone("UTC"));
+        assert_eq!(None, parse_time_zone("SYSTEM"));
+        assert_eq!(None, parse_time_zone("Asia/Shanghai"));
+        assert_eq!(None, parse_time_zone("+08:75"));
+    }
+}
@@ -0,0 +1,1721 @@
+/*-
+ * #%L
+ * OBKV Table Client Framework
+ * %%
+ * Copyright (C) 2021 OceanBase
+ * %%
+ * OBKV Table Client Framework is licensed under Mulan PSL v2.
+ * You can use this software according to the terms and conditions of the
+ * Mulan PSL v2. You may obtain a copy of Mulan PSL v2 at:
+ *          http://license.coscl.org.cn/MulanPSL2
+ * THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+ * KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+ * NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+ * See the Mulan PSL v2 for more details.
+ * #L%
+ */
+
+//! Tests of the client against the in-process mock cluster, runnable without
+//! an OceanBase deployment.
+
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use obkv::{
+    blocking,
+    error::{CommonErrCode, Error, InitStage, Result},
+    mock::{Fault, MockCluster, MockTable, RequestKind},
+    payloads::{ObTableConsistencyLevel, ObTableOperationType},
+    CircuitBreakerConfig, CircuitState, ClientConfig, HedgeConfig, LocationProvider,
+    ObClientRuntimes, ObServerAddr, ObTableClient, ObjType, PartitionScheme, ResultCodes,
+    RetryContext, RetryDecision, RetryOp, RetryPolicy, StaticLocationProvider, StaticTable,
+    TableEntry, TableEntryKey, Value,
+};
+use tokio::{runtime::Handle, task};
+
+const TABLE: &str = "mock_varchar_table";
+const HASH_TABLE: &str = "mock_hash_table";
+const KEY_TABLE: &str = "mock_key_table";
+const RANGE_TABLE: &str = "mock_range_table";
+const INT_TABLE: &str = "mock_int_table";
+
+fn tables() -> Vec<MockTable> {
+    vec![
+        MockTable::new(TABLE, &[("c1", ObjType::Varchar)]),
+        MockTable::new(HASH_TABLE, &[("c1", ObjType::Int64)]).partition(PartitionScheme::Hash {
+            column: "c1".to_owned(),
+            part_num: 4,
+        }),
+        MockTable::new(KEY_TABLE, &[("c1", ObjType::Varchar)]).partition(PartitionScheme::Key {
+            columns: vec!["c1".to_owned()],
+            part_num: 8,
+        }),
+        MockTable::new(RANGE_TABLE, &[("c1", ObjType::Int64)]).partition(PartitionScheme::Range {
+            column: "c1".to_owned(),
+            bounds: vec![Value::from(100i64), Value::from(200i64), Value::get_max()],
+        }),
+        MockTable::new(INT_TABLE, &[("c1", ObjType::Int64)]),
+    ]
+}
+
+async fn build_client(cluster: &MockCluster, config: ClientConfig) -> ObTableClient {
+    build(cluster.client_builder().config(config)).await
+}
+
+async fn build(builder: obkv::Builder) -> ObTableClient {
+    builder.build_async().await.expect("fail to build client")
+}
+
+#[tokio::test]
+async fn test_mock_single_operations() {
+    let cluster = MockCluster::start(3, tables()).unwrap();
+    let client = build_client(&cluster, ClientConfig::default()).await;
+    let c2 = || vec!["c2".to_owned()];
+
+    let affected = client
+        .insert(
+            TABLE,
+            vec![Value::from("k1")],
+            c2(),
+            vec![Value::from("v1")],
+        )
+        .await
+        .unwrap();
+    assert_eq!(1, affected);
+    let err = client
+        .insert(
+            TABLE,
+            vec![Value::from("k1")],
+            c2(),
+            vec![Value::from("v1")],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        Some(ResultCodes::OB_ERR_PRIMARY_KEY_DUPLICATE),
+        err.ob_result_code()
+    );
+
+    let mut row = client
+        .get(TABLE, vec![Value::from("k1")], c2())
+        .await
+        .unwrap();
+    assert_eq!("v1", row.remove("c2").unwrap().as_string());
+
+    client
+        .update(
+            TABLE,
+            vec![Value::from("k1")],
+            c2(),
+            vec![Value::from("v2")],
+        )
+        .await
+        .unwrap();
+    client
+        .append(
+            TABLE,
+            vec![Value::from("k1")],
+            c2(),
+            vec![Value::from("_x")],
+        )
+        .await
+        .unwrap();
+    let mut row = client
+        .get(TABLE, vec![Value::from("k1")], c2())
+        .await
+        .unwrap();
+    assert_eq!("v2_x", row.remove("c2").unwrap().as_string());
+
+    for _ in 0..3 {
+        client
+            .increment(
+                TABLE,
+                vec![Value::from("k1")],
+                vec!["c3".to_owned()],
+                vec![Value::from(5i64)],
+            )
+            .await
+            .unwrap();
+    }
+    let mut row = client
+        .get(TABLE, vec![Value::from("k1")], vec!["c3".to_owned()])
+        .await
+        .unwrap();
+    assert_eq!(15, row.remove("c3").unwrap().as_i64());
+
+    let affected = client
+        .replace(
+            TABLE,
+            vec![Value::from("k1")],
+            c2(),
+            vec![Value::from("v3")],
+        )
+        .await
+        .unwrap();
+    assert_eq!(2, affected);
+    assert_eq!(1, cluster.row_count(TABLE));
+
+    assert_eq!(
+        1,
+        client.delete(TABLE, vec![Value::from("k1")]).await.unwrap()
+    );
+    assert_eq!(
+        0,
+        client.delete(TABLE, vec![Value::from("k1")]).await.unwrap()
+    );
+    assert!(cluster.get_row(TABLE, &[Value::from("k1")]).is_none());
+}
+
+#[tokio::test]
+async fn test_mock_batch_and_query() {
+    let cluster = MockCluster::start(1, tables()).unwrap();
+    let client = build_client(&cluster, ClientConfig::default()).await;
+
+    let mut batch = client.batch_operation(10);
+    for i in 0..10 {
+        batch.insert(
+            vec![Value::from(format!("k{i}"))],
+            vec!["c2".to_owned()],
+            vec![Value::from(format!("v{i}"))],
+        );
+    }
+    let results = client.execute_batch(TABLE, batch).await.unwrap();
+    assert_eq!(10, results.len());
+    assert_eq!(10, cluster.row_count(TABLE));
+
+    // three batches of a stream
+    let mut result_set = client
+        .query(TABLE)
+        .select(vec!["c2".to_owned()])
+        .primary_index()
+        .batch_size(4)
+        .add_scan_range(vec![Value::get_min()], true, vec![Value::get_max()], true)
+        .execute()
+        .await
+        .unwrap();
+    let mut values = vec![];
+    while let Some(row) = result_set.next().await {
+        values.push(row.unwrap().remove("c2").unwrap().as_string());
+    }
+    let expected: Vec<_> = (0..10).map(|i| format!("v{i}")).collect();
+    assert_eq!(expected, values);
+
+    let mut result_set = client
+        .query(TABLE)
+        .select(vec!["c2".to_owned()])
+        .primary_index()
+        .scan_order(false)
+        .limit(None, 3)
+        .add_scan_range(
+            vec![Value::from("k2")],
+            true,
+            vec![Value::from("k8")],
+            false,
+        )
+        .execute()
+        .await
+        .unwrap();
+    let mut values = vec![];
+    while let Some(row) = result_set.next().await {
+        values.push(row.unwrap().remove("c2").unwrap().as_string());
+    }
+    assert_eq!(vec!["v7", "v6", "v5"], values);
+
+    // the sessions of a stream dropped unclosed are closed in the background
+    let mut result_set = client
+        .query(TABLE)
+        .select(vec!["c2".to_owned()])
+        .primary_index()
+        .batch_size(4)
+        .add_scan_range(vec![Value::get_min()], true, vec![Value::get_max()], true)
+        .execute()
+        .await
+        .unwrap();
+    assert!(result_set.next().await.unwrap().is_ok());
+    assert_eq!(1, cluster.open_sessions());
+    drop(result_set);
+    let start = Instant::now();
+    while cluster.open_sessions() > 0 && start.elapsed() < Duration::from_secs(1) {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(0, cluster.open_sessions());
+}
+
+#[tokio::test]
+async fn test_mock_partitioned_tables() {
+    let cluster = MockCluster::start(3, tables()).unwrap();
+    let client = build_client(&cluster, ClientConfig::default()).await;
+
+    for i in 0..300i64 {
+        client
+            .insert(
+                HASH_TABLE,
+                vec![Value::from(i)],
+                vec!["c2".to_owned()],
+                vec![Value::from(i)],
+            )
+            .await
+            .unwrap();
+        client
+            .insert(
+                RANGE_TABLE,
+                vec![Value::from(i)],
+                vec!["c2".to_owned()],
+                vec![Value::from(i)],
+            )
+            .await
+            .unwrap();
+        client
+            .insert(
+                KEY_TABLE,
+                vec![Value::from(format!("key{i}"))],
+                vec!["c2".to_owned()],
+                vec![Value::from(i)],
+            )
+            .await
+            .unwrap();
+    }
+    for table in [HASH_TABLE, RANGE_TABLE, KEY_TABLE] {
+        assert_eq!(300, cluster.row_count(table));
+    }
+    // partitions are spread over every server
+    for server in 0..3 {
+        assert!(cluster.served_requests(server) > 0);
+    }
+
+    let mut row = client
+        .get(HASH_TABLE, vec![Value::from(42i64)], vec!["c2".to_owned()])
+        .await
+        .unwrap();
+    assert_eq!(42, row.remove("c2").unwrap().as_i64());
+
+    let mut result_set = client
+        .query(RANGE_TABLE)
+        .select(vec!["c1".to_owned()])
+        .primary_index()
+        .add_scan_range(
+            vec![Value::from(90i64)],
+            true,
+            vec![Value::from(210i64)],
+            false,
+        )
+        .execute()
+        .await
+        .unwrap();
+    let mut keys = vec![];
+    while let Some(row) = result_set.next().await {
+        keys.push(row.unwrap().remove("c1").unwrap().as_i64());
+    }
+    keys.sort_unstable();
+    assert_eq!((90..210).collect::<Vec<_>>(), keys);
+}
+
+#[tokio::test]
+async fn test_mock_scan_splits() {
+    let cluster = MockCluster::start(2, tables()).unwrap();
+    let client = build_client(&cluster, ClientConfig::default()).await;
+    let keys: Vec<i64> = (-50..250).collect();
+    for table in [HASH_TABLE, RANGE_TABLE, INT_TABLE] {
+        client.add_row_key_element(table, vec!["c1".to_owned()]);
+        let mut batch = client.batch_operation(keys.len());
+        batch.set_atomic_op(false);
+        for key in &keys {
+            batch.insert(
+                vec![Value::from(*key)],
+                vec!["c2".to_owned()],
+                vec![Value::from(*key)],
+            );
+        }
+        client.execute_batch(table, batch).await.unwrap();
+    }
+
+    // (number of splits, number of partitions) of the tables split in 8
+    for (table, splits_num, parts_num) in
+        [(HASH_TABLE, 8, 4), (RANGE_TABLE, 9, 3), (INT_TABLE, 8, 1)]
+    {
+        let splits = client.scan_splits(table, 8).await.unwrap();
+        assert_eq!(splits_num, splits.len(), "{table}: {splits:?}");
+        let mut partitions: Vec<_> = splits.iter().map(|s| s.partition.id).collect();
+        partitions.dedup();
+        assert_eq!(parts_num, partitions.len(), "{table}: {splits:?}");
+
+        // every row is scanned once
+        let mut scanned = vec![];
+        for split in splits {
+            let mut result_set = client
+                .query(table)
+                .select(vec!["c1".to_owned()])
+                .primary_index()
+                .add_scan_range(
+                    split.start,
+                    split.start_inclusive,
+                    split.end,
+                    split.end_inclusive,
+                )
+                .partition(split.partition.id)
+                .execute()
+                .await
+                .unwrap();
+            while let Some(row) = result_set.next().await {
+                scanned.push(row.unwrap().remove("c1").unwrap().as_i64());
+            }
+        }
+        scanned.sort_unstable();
+        assert_eq!(keys, scanned, "{table}");
+    }
+
+    // a table not split further without an integer leading column
+    client.add_row_key_element(TABLE, vec!["c1".to_owned()]);
+    let splits = client.scan_splits(TABLE, 8).await.unwrap();
+    assert_eq!(1, splits.len());
+    assert_eq!(vec![Value::get_min()], splits[0].start);
+    assert_eq!(vec![Value::get_max()], splits[0].end);
+    assert!(client.scan_splits(TABLE, 0).await.is_err());
+}
+
+#[tokio::test]
+async fn test_mock_leader_switch() {
+    let cluster = MockCluster::start(2, tables()).unwrap();
+    let client = build_client(&cluster, ClientConfig::default()).await;
+    client
+        .insert(
+            TABLE,
+            vec![Value::from("k")],
+            vec!["c2".to_owned()],
+            vec![Value::from("v")],
+        )
+        .await
+        .unwrap();
+
+    let leader = cluster.leader(TABLE, 0).unwrap();
+    cluster.set_leader(TABLE, 0, 1 - leader).unwrap();
+
+    // the client refreshes the location after OB_NOT_MASTER
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        let result = client
+            .get(TABLE, vec![Value::from("k")], vec!["c2".to_owned()])
+            .await;
+        match result {
+            Ok(mut row) => {
+                assert_eq!("v", row.remove("c2").unwrap().as_string());
+                break;
+            }
+            Err(e) => {
+                assert!(Instant::now() < deadline, "leader never refreshed: {e}");
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_mock_faults() {
+    let cluster = MockCluster::start(1, tables()).unwrap();
+    let config = ClientConfig {
+        rpc_operation_timeout: Duration::from_secs(1),
+        rpc_retry_limit: 1,
+        ..Default::default()
+    };
+    let client = build_client(&cluster, config).await;
+
+    cluster.inject(
+        Fault::error(ResultCodes::OB_ERR_UNEXPECTED)
+            .on(RequestKind::Execute)
+            .table(TABLE)
+            .times(1),
+    );
+    let err = client
+        .get(TABLE, vec![Value::from("k")], vec!["c2".to_owned()])
+        .await
+        .unwrap_err();
+    assert_eq!(Some(ResultCodes::OB_ERR_UNEXPECTED), err.ob_result_code());
+    assert!(client
+        .get(TABLE, vec![Value::from("k")], vec!["c2".to_owned()])
+        .await
+        .is_ok());
+
+    cluster.inject(Fault::delay(Duration::from_secs(3)).table(TABLE));
+    let start = Instant::now();
+    let result = client
+        .get(TABLE, vec![Value::from("k")], vec!["c2".to_owned()])
+        .await;
+    assert!(result.is_err());
+    assert!(start.elapsed() < Duration::from_secs(3));
+
+    cluster.clear_faults();
+    assert!(client
+        .get(TABLE, vec![Value::from("k")], vec!["c2".to_owned()])
+        .await
+        .is_ok());
+}
+
+#[tokio::test]
+async fn test_static_location_provider() {
+    let cluster = MockCluster::start(2, tables()).unwrap();
+    let servers = cluster.servers().to_vec();
+    // the leaders of the mock cluster: partition idx % servers
+    let provider = StaticLocationProvider::new(servers.clone())
+        .table(TABLE, StaticTable::new(1, &[("c1", ObjType::Varchar)]))
+        .table(
+            HASH_TABLE,
+            (0..4).fold(
+                StaticTable::new(2, &[("c1", ObjType::Int64)]).partition(PartitionScheme::Hash {
+                    column: "c1".to_owned(),
+                    part_num: 4,
+                }),
+                |table, idx| {
+                    table.replicas(
+                        // tablet ids of the mock cluster
+                        500002 * 1000 + idx,
+                        vec![servers[idx as usize % 2].clone()],
+                    )
+                },
+            ),
+        );
+    let client = build(
+        cluster
+            .client_builder()
+            .location_provider(Arc::new(provider)),
+    )
+    .await;
+
+    client
+        .insert(
+            TABLE,
+            vec![Value::from("k")],
+            vec!["c2".to_owned()],
+            vec![Value::from("v")],
+        )
+        .await
+        .unwrap();
+    for i in 0..20i64 {
+        client
+            .insert(
+                HASH_TABLE,
+                vec![Value::from(i)],
+                vec!["c2".to_owned()],
+                vec![Value::from(i)],
+            )
+            .await
+            .unwrap();
+    }
+    assert_eq!(1, cluster.row_count(TABLE));
+    assert_eq!(20, cluster.row_count(HASH_TABLE));
+    assert!(cluster.served_requests(1) > 0);
+}
+
+#[tokio::test]
+async fn test_mock_odp_mode() {
+    let cluster = MockCluster::start(2, tables()).unwrap();
+    // the non-partitioned table is led by the first server
+    let client = build(cluster.odp_client_builder(0)).await;
+
+    let mut batch = client.batch_operation(10);
+    for i in 0..10i64 {
+        batch.insert(
+            vec![Value::from(format!("k{i}"))],
+            vec!["c2".to_owned(), "c3".to_owned()],
+            vec![Value::from(format!("v{i}")), Value::from(i)],
+        );
+    }
+    assert_eq!(10, client.execute_batch(TABLE, batch).await.unwrap().len());
+
+    // routing errors are retried on the endpoint
+    cluster.inject(
+        Fault::error(ResultCodes::OB_NOT_MASTER)
+            .on(RequestKind::Execute)
+            .times(1),
+    );
+    let mut row = client
+        .get(TABLE, vec![Value::from("k3")], vec!["c2".to_owned()])
+        .await
+        .unwrap();
+    assert_eq!("v3", row.remove("c2").unwrap().as_string());
+
+    let mut result_set = client
+        .query(TABLE)
+        .select(vec!["c2".to_owned()])
+        .primary_index()
+        .batch_size(4)
+        .add_scan_range(vec![Value::get_min()], true, vec![Value::get_max()], true)
+        .execute()
+        .await
+        .unwrap();
+    let mut values = vec![];
+    while let Some(row) = result_set.next().await {
+        values.push(row.unwrap().remove("c2").unwrap().as_string());
+    }
+    let expected: Vec<_> = (0..10).map(|i| format!("v{i}")).collect();
+    assert_eq!(expected, values);
+
+    let mut aggregation = client
+        .aggregate(TABLE)
+        .count()
+        .sum("c3".to_owned())
+        .add_scan_range(vec![Value::get_min()], true, vec![Value::get_max()], true)
+        .execute()
+        .await
+        .unwrap();
+    assert_eq!(10, aggregation.remove("count(*)").unwrap().as_i64());
+    assert_eq!(45, aggregation.remove("sum(c3)").unwrap().as_i64());
+
+    assert_eq!(0, cluster.served_requests(1));
+    assert!(client
+        .partition_of(TABLE, &[Value::from("k1")])
+        .await
+        .is_err());
+}
+
+const UNREACHABLE_TABLE: &str = "mock_unreachable_table";
+
+/// Counts the table entry loads, each taking a while as a sys tenant query.
+struct SlowProvider {
+    inner: StaticLocationProvider,
+    loads: AtomicUsize,
+}
+
+impl LocationProvider for SlowProvider {
+    fn load_servers(&self) -> Result<Vec<ObServerAddr>> {
+        self.inner.load_servers()
+    }
+
+    fn load_table_entry(&self, key: &TableEntryKey) -> Result<TableEntry> {
+        self.loads.fetch_add(1, Ordering::SeqCst);
+        thread::sleep(Duration::from_millis(300));
+        if key.table_name() == UNREACHABLE_TABLE {
+            return Err(Error::IO(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "sys tenant query timeout",
+            )));
+        }
+        self.inner.load_table_entry(key)
+    }
+}
+
+// a single runtime thread, which keeps ticking while the entry loads
+#[tokio::test]
+async fn test_mock_concurrent_table_entry_loads() {
+    let cluster = MockCluster::start(1, tables()).unwrap();
+    let provider = Arc::new(SlowProvider {
+        inner: StaticLocationProvider::new(cluster.servers().to_vec())
+            .table(TABLE, StaticTable::new(1, &[("c1", ObjType::Varchar)])),
+        loads: AtomicUsize::new(0),
+    });
+    let client =
+        Arc::new(build(cluster.client_builder().location_provider(provider.clone())).await);
+
+    let ticks = Arc::new(AtomicUsize::new(0));
+    let ticker = {
+        let ticks = ticks.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                ticks.fetch_add(1, Ordering::SeqCst);
+            }
+        })
+    };
+
+    let handles: Vec<_> = (0..20)
+        .map(|i| {
+            let client = client.clone();
+            tokio::spawn(async move {
+                client
+                    .insert(
+                        TABLE,
+                        vec![Value::from(format!("k{i}"))],
+                        vec!["c2".to_owned()],
+                        vec![Value::from("v")],
+                    )
+                    .await
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.await.unwrap().unwrap();
+    }
+
+    ticker.abort();
+
+    assert_eq!(20, cluster.row_count(TABLE));
+    assert_eq!(1, provider.loads.load(Ordering::SeqCst));
+    assert!(ticks.load(Ordering::SeqCst) >= 10);
+
+    // the callers waiting for a failed load get the error with its kind
+    let handles: Vec<_> = (0..10)
+        .map(|_| {
+            let client = client.clone();
+            tokio::spawn(async move {
+                client
+                    .get(
+                        UNREACHABLE_TABLE,
+                        vec![Value::from("k")],
+                        vec!["c2".to_owned()],
+                    )
+                    .await
+            })
+        })
+        .collect();
+    for handle in handles {
+        let err = handle.await.unwrap().unwrap_err();
+        let io_err = match &err {
+            Error::Shared(e) => matches!(**e, Error::IO(_)),
+            e => matches!(e, Error::IO(_)),
+        };
+        assert!(io_err, "{err:?}");
+        assert!(!err.is_common_err());
+    }
+}
+
+/// The sys tenant being unreachable.
+struct UnreachableProvider;
+
+impl LocationProvider for UnreachableProvider {
+    fn load_servers(&self) -> Result<Vec<ObServerAddr>> {
+        Err(Error::Common(
+            CommonErrCode::Rpc,
+            "sys tenant is unreachable".to_owned(),
+        ))
+    }
+
+    fn load_table_entry(&self, _key: &TableEntryKey) -> Result<TableEntry> {
+        Err(Error::Common(
+            CommonErrCode::Rpc,
+            "sys tenant is unreachable".to_owned(),
+        ))
+    }
+}
+
+#[tokio::test]
+async fn test_mock_table_entry_cache_file() {
+    let cluster = MockCluster::start(2, tables()).unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let cache_file = dir.path().join("table_entry_cache.json");
+    let config = ClientConfig {
+        table_entry_cache_file: Some(cache_file.to_str().unwrap().to_owned()),
+        rpc_retry_limit: 1,
+        table_entry_refresh_try_times: 1,
+        ..Default::default()
+    };
+
+    let client = build_client(&cluster, config.clone()).await;
+    client
+        .insert(
+            TABLE,
+            vec![Value::from("k")],
+            vec!["c2".to_owned()],
+            vec![Value::from("v")],
+        )
+        .await
+        .unwrap();
+    for i in 0..8i64 {
+        client
+            .insert(
+                HASH_TABLE,
+                vec![Value::from(i)],
+                vec!["c2".to_owned()],
+                vec![Value::from(format!("v{i}"))],
+            )
+            .await
+            .unwrap();
+    }
+    // the routing left to write is written at shutdown
+    client.shutdown(Duration::from_secs(1)).await.unwrap();
+    assert!(cache_file.exists());
+
+    // warm start from the cached routing
+    let client = build(
+        cluster
+            .client_builder()
+            .config(config.clone())
+            .location_provider(Arc::new(UnreachableProvider)),
+    )
+    .await;
+    let mut row = client
+        .get(TABLE, vec![Value::from("k")], vec!["c2".to_owned()])
+        .await
+        .unwrap();
+    assert_eq!("v", row.remove("c2").unwrap().as_string());
+    for i in 0..8i64 {
+        let mut row = client
+            .get(HASH_TABLE, vec![Value::from(i)], vec!["c2".to_owned()])
+            .await
+            .unwrap();
+        assert_eq!(format!("v{i}"), row.remove("c2").unwrap().as_string());
+    }
+    // tables never cached still need the sys tenant
+    assert!(client
+        .get(RANGE_TABLE, vec![Value::from(1i64)], vec!["c2".to_owned()])
+        .await
+        .is_err());
+    drop(client);
+
+    // stale routing is ignored
+    thread::sleep(Duration::from_millis(20));
+    let config = ClientConfig {
+        table_entry_cache_max_age: Duration::from_millis(10),
+        ..config
+    };
+    let builder = cluster
+        .client_builder()
+        .config(config)
+        .location_provider(Arc::new(UnreachableProvider));
+    let result = task::spawn_blocking(move || builder.build()?.init())
+        .await
+        .unwrap();
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_mock_shutdown() {
+    let cluster = MockCluster::start(1, tables()).unwrap();
+    let client = build_client(&cluster, ClientConfig::default()).await;
+    let c2 = || vec!["c2".to_owned()];
+    for i in 0..10 {
+        client
+            .insert(
+                TABLE,
+                vec![Value::from(format!("k{i}"))],
+                c2(),
+                vec![Value::from(format!("v{i}"))],
+            )
+            .await
+            .unwrap();
+    }
+
+    // a stream query left open
+    let mut result_set = client
+        .query(TABLE)
+        .select(c2())
+        .primary_index()
+        .batch_size(2)
+        .add_scan_range(vec![Value::get_min()], true, vec![Value::get_max()], true)
+        .execute()
+        .await
+        .unwrap();
+    assert!(result_set.next().await.unwrap().is_ok());
+    assert_eq!(1, cluster.open_sessions());
+
+    // a slow operation in flight
+    cluster.inject(
+        Fault::delay(Duration::from_millis(300))
+            .on(RequestKind::Execute)
+            .table(TABLE)
+            .times(1),
+    );
+    let slow = {
+        let client = client.clone();
+        task::spawn(async move { client.get(TABLE, vec![Value::from("k1")], c2()).await })
+    };
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    client.shutdown(Duration::from_secs(5)).await.unwrap();
+    let mut row = slow.await.unwrap().unwrap();
+    assert_eq!("v1", row.remove("c2").unwrap().as_string());
+    assert_eq!(0, cluster.open_sessions());
+    assert!(client.is_closed());
+
+    match client.get(TABLE, vec![Value::from("k1")], c2()).await {
+        Err(Error::Common(CommonErrCode::AlreadyClosed, _)) => (),
+        other => panic!("unexpected result after shutdown: {other:?}"),
+    }
+    // shutting down again does nothing
+    client.shutdown(Duration::from_secs(1)).await.unwrap();
+}
+
+#[test]
+fn test_mock_shutdown_on_own_runtime() {
+    let cluster = MockCluster::start(1, tables()).unwrap();
+    // the blocking client runs the shutdown on the runtimes shut down by it
+    let client = blocking::ObTableClient::build(cluster.client_builder()).unwrap();
+    let start = Instant::now();
+    client.shutdown(Duration::from_secs(3)).unwrap();
+    assert!(start.elapsed() < Duration::from_secs(3));
+    assert!(client.as_async().is_closed());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_mock_host_runtime() {
+    let cluster = MockCluster::start(2, tables()).unwrap();
+    let runtimes = Arc::new(ObClientRuntimes::from_handle(Handle::current()).unwrap());
+    let client1 = build(cluster.client_builder().runtimes(runtimes.clone())).await;
+    let client2 = build(cluster.client_builder().runtimes(runtimes)).await;
+
+    for (i, client) in [&client1, &client2].into_iter().enumerate() {
+        client
+            .insert(
+                HASH_TABLE,
+                vec![Value::from(i as i64)],
+                vec!["c2".to_owned()],
+                vec![Value::from(format!("v{i}"))],
+            )
+            .await
+            .unwrap();
+    }
+
+    // the shared runtime outlives the clients shut down
+    client1.shutdown(Duration::from_secs(1)).await.unwrap();
+    let mut row = client2
+        .get(HASH_TABLE, vec![Value::from(0i64)], vec!["c2".to_owned()])
+        .await
+        .unwrap();
+    assert_eq!("v0", row.remove("c2").unwrap().as_string());
+    client2.shutdown(Duration::from_secs(1)).await.unwrap();
+    assert_eq!(2, task::spawn(async { 1 + 1 }).await.unwrap());
+
+    // dropping a client in an asynchronous context
+    let client = build(
+        cluster
+            .client_builder()
+            .runtime_handle(Handle::current())
+            .unwrap(),
+    )
+    .await;
+    assert!(client
+        .get(TABLE, vec![Value::from("k")], vec!["c2".to_owned()])
+        .await
+        .is_ok());
+    drop(client);
+}
+
+#[tokio::test]
+async fn test_mock_current_thread_runtime() {
+    match ObClientRuntimes::from_handle(Handle::current()) {
+        Err(Error::Common(CommonErrCode::InvalidParam, _)) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+/// A provider taking its time to load the servers.
+struct SlowServersProvider(Duration);
+
+impl LocationProvider for SlowServersProvider {
+    fn load_servers(&self) -> Result<Vec<ObServerAddr>> {
+        thread::sleep(self.0);
+        Ok(vec![])
+    }
+
+    fn load_table_entry(&self, key: &TableEntryKey) -> Result<TableEntry> {
+        UnreachableProvider.load_table_entry(key)
+    }
+}
+
+#[tokio::test]
+async fn test_mock_build_async_errors() {
+    let cluster = MockCluster::start(1, tables()).unwrap();
+    let config = ClientConfig {
+        init_timeout: Duration::from_millis(500),
+        rslist_acquire_try_times: 1,
+        ..Default::default()
+    };
+
+    let err = cluster
+        .client_builder()
+        .config(config.clone())
+        .location_provider(Arc::new(UnreachableProvider))
+        .build_async()
+        .await
+        .err()
+        .expect("built with an unreachable cluster");
+    assert_eq!(Some(InitStage::LoadServers), err.init_stage());
+
+    let start = Instant::now();
+    let err = cluster
+        .client_builder()
+        .config(config.clone())
+        .location_provider(Arc::new(SlowServersProvider(Duration::from_secs(3))))
+        .build_async()
+        .await
+        .err()
+        .expect("built with an unreachable cluster");
+    assert!(matches!(err, Error::InitTimeout(InitStage::LoadServers, _)));
+    assert!(start.elapsed() < Duration::from_secs(2));
+
+    // no observer listens on the port
+    let mut addr = ObServerAddr::new();
+    addr.address("127.0.0.1".to_owned());
+    addr.set_svr_port(1);
+    addr.set_sql_port(1);
+    let err = cluster
+        .client_builder()
+        .config(config.clone())
+        .location_provider(Arc::new(StaticLocationProvider::new(vec![addr])))
+        .build_async()
+        .await
+        .err()
+        .expect("built with an unreachable cluster");
+    assert_eq!(Some(InitStage::WarmUpConnections), err.init_stage());
+
+    // nor a config server
+    let err = obkv::Builder::new()
+        .full_user_name("root@mock#mock_cluster")
+        .param_url("http://127.0.0.1:1/services?Action=ObRootServiceInfo&database=test")
+        .password("")
+        .config(config)
+        .build_async()
+        .await
+        .err()
+        .expect("built with an unreachable cluster");
+    assert_eq!(Some(InitStage::ConfigServer), err.init_stage());
+}
+
+#[test]
+fn test_mock_blocking_client() {
+    let cluster = MockCluster::start(2, tables()).unwrap();
+    let client = blocking::ObTableClient::build(cluster.client_builder()).unwrap();
+    let c2 = || vec!["c2".to_owned()];
+
+    let mut batch = client.batch_operation(10);
+    for i in 0..10 {
+        batch.insert(
+            vec![Value::from(format!("k{i}"))],
+            c2(),
+            vec![Value::from(format!("v{i}"))],
+        );
+    }
+    assert_eq!(10, client.execute_batch(TABLE, batch).unwrap().len());
+    assert_eq!(
+        1,
+        client
+            .update(TABLE, vec![Value::from("k0")], c2(), vec![Value::from("v")])
+            .unwrap()
+    );
+    let mut row = client.get(TABLE, vec![Value::from("k0")], c2()).unwrap();
+    assert_eq!("v", row.remove("c2").unwrap().as_string());
+
+    // three batches of a stream
+    let query = || {
+        client
+            .query(TABLE)
+            .select(c2())
+            .primary_index()
+            .batch_size(4)
+            .add_scan_range(vec![Value::get_min()], true, vec![Value::get_max()], true)
+    };
+    let values: Vec<_> = client
+        .execute_query(query())
+        .unwrap()
+        .map(|row| row.unwrap().remove("c2").unwrap().as_string())
+        .collect();
+    assert_eq!(10, values.len());
+    assert_eq!(0, cluster.open_sessions());
+
+    // the stream is closed when the iteration stops early
+    let mut rows = client.execute_query(query()).unwrap();
+    assert!(rows.next().unwrap().is_ok());
+    assert_eq!(1, cluster.open_sessions());
+    drop(rows);
+    // the last request of a stream isn't waited for
+    let start = Instant::now();
+    while cluster.open_sessions() > 0 && start.elapsed() < Duration::from_secs(1) {
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(0, cluster.open_sessions());
+
+    // inside a runtime
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    match rt.block_on(async { client.get(TABLE, vec![Value::from("k0")], c2()) }) {
+        Err(Error::Common(CommonErrCode::BlockingInRuntime, _)) => (),
+        other => panic!("unexpected result inside a runtime: {other:?}"),
+    }
+    // blocking on the async client instead in spawn_blocking
+    let blocking_client = client.clone();
+    let blocking = rt.spawn_blocking(move || {
+        let refused = blocking_client.get(TABLE, vec![Value::from("k0")], c2());
+        let row = Handle::current().block_on(blocking_client.as_async().get(
+            TABLE,
+            vec![Value::from("k0")],
+            c2(),
+        ));
+        (refused, row)
+    });
+    match rt.block_on(blocking).unwrap() {
+        (Err(Error::Common(CommonErrCode::BlockingInRuntime, _)), Ok(row)) => {
+            assert_eq!(1, row.len())
+        }
+        other => panic!("unexpected result in spawn_blocking: {other:?}"),
+    }
+
+    client.shutdown(Duration::from_secs(1)).unwrap();
+    assert!(client.as_async().is_closed());
+}
+
+/// Retries every retryable error without delay, recording the attempts.
+#[derive(Default)]
+struct RecordingPolicy {
+    attempts: Mutex<Vec<(RetryOp, usize)>>,
+}
+
+impl RetryPolicy for RecordingPolicy {
+    fn decide(&self, ctx: &RetryContext<'_>, error: &Error) -> RetryDecision {
+        self.attempts.lock().unwrap().push((ctx.op, ctx.attempt));
+        RetryDecision {
+            retry_after: error.need_retry().then_some(Duration::ZERO),
+            refresh: false,
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_mock_retry_policy() {
+    let cluster = MockCluster::start(1, tables()).unwrap();
+    let policy = Arc::new(RecordingPolicy::default());
+    let client = build(cluster.client_builder().retry_policy(policy.clone())).await;
+
+    cluster.inject(
+        Fault::error(ResultCodes::OB_TRY_LOCK_ROW_CONFLICT)
+            .on(RequestKind::Execute)
+            .table(TABLE)
+            .times(3),
+    );
+    client
+        .insert(
+            TABLE,
+            vec![Value::from("k1")],
+            vec!["c2".to_owned()],
+            vec![Value::from("v1")],
+        )
+        .await
+        .unwrap();
+    let insert = RetryOp::Execute(ObTableOperationType::Insert);
+    assert_eq!(
+        vec![(insert, 1), (insert, 2), (insert, 3)],
+        policy
+            .attempts
+            .lock()
+            .unwrap()
+            .drain(..)
+            .collect::<Vec<_>>()
+    );
+
+    cluster.inject(
+        Fault::error(ResultCodes::OB_TRY_LOCK_ROW_CONFLICT)
+            .on(RequestKind::BatchExecute)
+            .table(TABLE)
+            .times(1),
+    );
+    let mut batch = client.batch_operation(1);
+    batch.delete(vec![Value::from("k1")]);
+    client.execute_batch(TABLE, batch).await.unwrap();
+    assert_eq!(
+        vec![(RetryOp::ExecuteBatch, 1)],
+        policy
+            .attempts
+            .lock()
+            .unwrap()
+            .drain(..)
+            .collect::<Vec<_>>()
+    );
+
+    // the errors not retryable fail at once
+    cluster.inject(
+        Fault::error(ResultCodes::OB_ERR_UNEXPECTED)
+            .on(RequestKind::Execute)
+            .table(TABLE)
+            .times(1),
+    );
+    let err = client
+        .get(TABLE, vec![Value::from("k1")], vec!["c2".to_owned()])
+        .await
+        .unwrap_err();
+    assert_eq!(Some(ResultCodes::OB_ERR_UNEXPECTED), err.ob_result_code());
+    assert_eq!(1, policy.attempts.lock().unwrap().drain(..).len());
+
+    // a failed query asks the policy whether to refresh, but isn't resent
+    cluster.inject(
+        Fault::error(ResultCodes::OB_NOT_MASTER)
+            .on(RequestKind::ExecuteQuery)
+            .table(TABLE)
+            .times(1),
+    );
+    let err = client
+        .query(TABLE)
+        .select(vec!["c2".to_owned()])
+        .primary_index()
+        .add_scan_range(vec![Value::get_min()], true, vec![Value::get_max()], true)
+        .execute()
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(Some(ResultCodes::OB_NOT_MASTER), err.ob_result_code());
+    assert_eq!(
+        vec![(RetryOp::Query, 1)],
+        policy
+            .attempts
+            .lock()
+            .unwrap()
+            .drain(..)
+            .collect::<Vec<_>>()
+    );
+
+    // the default policy backs off without the whole second the interval used
+    // to need
+    let config = ClientConfig {
+        rpc_retry_interval: Duration::from_millis(200),
+        ..Default::default()
+    };
+    let client = build_client(&cluster, config).await;
+    cluster.inject(
+        Fault::error(ResultCodes::OB_TRY_LOCK_ROW_CONFLICT)
+            .on(RequestKind::Execute)
+            .table(TABLE)
+            .times(2),
+    );
+    let start = Instant::now();
+    client
+        .get(TABLE, vec![Value::from("k1")], vec!["c2".to_owned()])
+        .await
+        .unwrap();
+    // 100..=200ms then 200..=400ms
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_millis(300), "{elapsed:?}");
+}
+
+#[tokio::test]
+async fn test_mock_operation_deadline() {
+    let cluster = MockCluster::start(1, tables()).unwrap();
+    let config = ClientConfig {
+        rpc_operation_timeout: Duration::from_secs(3),
+        rpc_retry_limit: 100,
+        rpc_retry_interval: Duration::from_millis(50),
+        rpc_retry_max_interval: Duration::from_millis(50),
+        operation_deadline: Some(Duration::from_millis(500)),
+        ..Default::default()
+    };
+    let client = build_client(&cluster, config).await;
+    let get = |client: &ObTableClient| {
+        let client = client.clone();
+        async move {
+            client
+                .get(TABLE, vec![Value::from("k")], vec!["c2".to_owned()])
+                .await
+        }
+    };
+
+    // a slow attempt is given the time left
+    cluster.inject(
+        Fault::delay(Duration::from_secs(2))
+            .on(RequestKind::Execute)
+            .table(TABLE)
+            .times(1),
+    );
+    let start = Instant::now();
+    let err = get(&client).await.unwrap_err();
+    assert!(err.is_deadline_exceeded(), "{err}");
+    assert!(start.elapsed() < Duration::from_secs(1));
+
+    // the retries stop at the deadline
+    cluster.inject(
+        Fault::error(ResultCodes::OB_TRY_LOCK_ROW_CONFLICT)
+            .on(RequestKind::Execute)
+            .table(TABLE)
+            .times(100),
+    );
+    let start = Instant::now();
+    match get(&client).await {
+        Err(Error::DeadlineExceeded(deadline, attempts)) => {
+            assert_eq!(Duration::from_millis(500), deadline);
+            assert!(attempts > 1 && attempts < 100, "{attempts}");
+        }
+        res => panic!("unexpected result: {res:?}"),
+    }
+    assert!(start.elapsed() < Duration::from_secs(1));
+    cluster.clear_faults();
+
+    // a deadline per call
+    cluster.inject(
+        Fault::delay(Duration::from_millis(500))
+            .on(RequestKind::BatchExecute)
+            .table(TABLE)
+            .times(1),
+    );
+    let mut batch = client.batch_operation(1);
+    batch.delete(vec![Value::from("k")]);
+    let err = client
+        .with_deadline(Duration::from_millis(200))
+        .execute_batch(TABLE, batch.clone())
+        .await
+        .unwrap_err();
+    assert!(err.is_deadline_exceeded(), "{err}");
+    cluster.inject(
+        Fault::delay(Duration::from_millis(800))
+            .on(RequestKind::Execute)
+            .table(TABLE)
+            .times(1),
+    );
+    assert!(get(&client.with_deadline(Duration::from_secs(2)))
+        .await
+        .is_ok());
+    client.execute_batch(TABLE, batch).await.unwrap();
+
+    // sub-second rpc timeouts used to be truncated to no timeout at all
+    let config = ClientConfig {
+        rpc_operation_timeout: Duration::from_millis(300),
+        rpc_retry_limit: 1,
+        ..Default::default()
+    };
+    let client = build_client(&cluster, config).await;
+    cluster.inject(
+        Fault::delay(Duration::from_secs(1))
+            .on(RequestKind::Execute)
+            .table(TABLE)
+            .times(1),
+    );
+    let start = Instant::now();
+    assert!(get(&client).await.is_err());
+    assert!(start.elapsed() < Duration::from_secs(1));
+}
+
+#[tokio::test]
+async fn test_mock_query_deadline() {
+    let cluster = MockCluster::start(2, tables()).unwrap();
+    // the connections to the second server are never logged in
+    cluster.inject(Fault::no_reply().on(RequestKind::Login).server(1));
+    let config = ClientConfig {
+        rpc_connect_timeout: Duration::from_secs(1),
+        rpc_login_timeout: Duration::from_secs(1),
+        operation_deadline: Some(Duration::from_millis(300)),
+        ..Default::default()
+    };
+    let client = build_client(&cluster, config).await;
+    let query = |client: &ObTableClient| {
+        client
+            .query(INT_TABLE)
+            .select(vec!["c2".to_owned()])
+            .primary_index()
+            .add_scan_range(vec![Value::get_min()], true, vec![Value::get_max()], true)
+    };
+
+    let mut batch = client.batch_operation(10);
+    for i in 0..10i64 {
+        batch.insert(
+            vec![Value::from(i)],
+            vec!["c2".to_owned()],
+            vec![Value::from(format!("v{i}"))],
+        );
+    }
+    client.execute_batch(INT_TABLE, batch).await.unwrap();
+
+    // the execution of a query
+    cluster.inject(
+        Fault::delay(Duration::from_secs(1))
+            .on(RequestKind::ExecuteQuery)
+            .times(1),
+    );
+    let start = Instant::now();
+    let err = query(&client).execute().await.err().unwrap();
+    assert!(err.is_deadline_exceeded(), "{err}");
+    assert!(start.elapsed() < Duration::from_millis(800));
+
+    // every fetch of the next rows, the stream is closed once given up
+    let mut result_set = query(&client.with_deadline(Duration::from_secs(2)))
+        .batch_size(4)
+        .deadline(Duration::from_millis(300))
+        .execute()
+        .await
+        .unwrap();
+    for _ in 0..4 {
+        assert!(result_set.next().await.unwrap().is_ok());
+    }
+    cluster.inject(
+        Fault::delay(Duration::from_secs(1))
+            .on(RequestKind::ExecuteQuery)
+            .times(1),
+    );
+    let start = Instant::now();
+    let err = result_set.next().await.unwrap().unwrap_err();
+    assert!(err.is_deadline_exceeded(), "{err}");
+    assert!(start.elapsed() < Duration::from_millis(800));
+    let err = result_set.next().await.unwrap().unwrap_err();
+    assert_eq!(Some(CommonErrCode::AlreadyClosed), err.common_err_code());
+    let start = Instant::now();
+    while cluster.open_sessions() > 0 && start.elapsed() < Duration::from_secs(2) {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(0, cluster.open_sessions());
+
+    // the connections to a server made by an operation
+    cluster.set_leader(TABLE, 0, 1).unwrap();
+    let start = Instant::now();
+    let err = client
+        .get(TABLE, vec![Value::from("k")], vec!["c2".to_owned()])
+        .await
+        .unwrap_err();
+    assert!(err.is_deadline_exceeded(), "{err}");
+    assert!(start.elapsed() < Duration::from_millis(800));
+}
+
+#[tokio::test]
+async fn test_mock_ambiguous_outcome() {
+    let cluster = MockCluster::start(1, tables()).unwrap();
+    let config = ClientConfig {
+        rpc_operation_timeout: Duration::from_millis(300),
+        rpc_retry_interval: Duration::ZERO,
+        ..Default::default()
+    };
+    let client = build_client(&cluster, config.clone()).await;
+    let increment = |client: &ObTableClient| {
+        let client = client.clone();
+        async move {
+            client
+                .increment(
+                    TABLE,
+                    vec![Value::from("k1")],
+                    vec!["c3".to_owned()],
+                    vec![Value::from(1i64)],
+                )
+                .await
+        }
+    };
+    let counter = || {
+        cluster.get_row(TABLE, &[Value::from("k1")]).unwrap()["c3"]
+            .clone()
+            .as_i64()
+    };
+    increment(&client).await.unwrap();
+
+    // the retryable errors are retried for the idempotent operations only
+    let trans_rpc_timeout = || {
+        Fault::error(ResultCodes::OB_TRANS_RPC_TIMEOUT)
+            .on(RequestKind::Execute)
+            .table(TABLE)
+            .times(1)
+    };
+    cluster.inject(trans_rpc_timeout());
+    client
+        .update(
+            TABLE,
+            vec![Value::from("k1")],
+            vec!["c2".to_owned()],
+            vec![Value::from("v1")],
+        )
+        .await
+        .unwrap();
+    cluster.inject(trans_rpc_timeout());
+    let err = increment(&client).await.unwrap_err();
+    assert!(err.is_ambiguous_outcome(), "{err}");
+    assert_eq!(1, counter());
+
+    // a timeout once sent, the increment is applied after all
+    cluster.inject(
+        Fault::delay(Duration::from_secs(1))
+            .on(RequestKind::Execute)
+            .table(TABLE)
+            .times(1),
+    );
+    let err = increment(&client).await.unwrap_err();
+    assert!(err.is_ambiguous_outcome(), "{err}");
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    assert_eq!(2, counter());
+
+    // the deadline exceeded once sent, the increment is applied after all
+    cluster.inject(
+        Fault::delay(Duration::from_millis(500))
+            .on(RequestKind::Execute)
+            .table(TABLE)
+            .times(1),
+    );
+    let err = increment(&client.with_deadline(Duration::from_millis(100)))
+        .await
+        .unwrap_err();
+    assert!(err.is_ambiguous_outcome(), "{err}");
+    assert!(err.is_deadline_exceeded(), "{err}");
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert_eq!(3, counter());
+
+    // the batches including operations not idempotent
+    cluster.inject(
+        Fault::error(ResultCodes::OB_TRANS_UNKNOWN)
+            .on(RequestKind::BatchExecute)
+            .table(TABLE)
+            .times(1),
+    );
+    let mut batch = client.batch_operation(2);
+    batch.update(
+        vec![Value::from("k1")],
+        vec!["c2".to_owned()],
+        vec![Value::from("v2")],
+    );
+    batch.append(
+        vec![Value::from("k1")],
+        vec!["c2".to_owned()],
+        vec![Value::from("_x")],
+    );
+    let err = client.execute_batch(TABLE, batch).await.unwrap_err();
+    assert!(err.is_ambiguous_outcome(), "{err}");
+
+    // retried anyway on demand
+    let config = ClientConfig {
+        retry_ambiguous_operations: true,
+        ..config
+    };
+    let client = build_client(&cluster, config).await;
+    cluster.inject(trans_rpc_timeout());
+    increment(&client).await.unwrap();
+    assert_eq!(4, counter());
+}
+
+#[tokio::test]
+async fn test_mock_ambiguous_partitioned_batch() {
+    let cluster = MockCluster::start(2, tables()).unwrap();
+    cluster.set_leader(RANGE_TABLE, 0, 0).unwrap();
+    cluster.set_leader(RANGE_TABLE, 1, 1).unwrap();
+    let config = ClientConfig {
+        rpc_retry_interval: Duration::ZERO,
+        ..Default::default()
+    };
+    let client = build_client(&cluster, config.clone()).await;
+    let increments = |client: &ObTableClient| {
+        let mut batch = client.batch_operation(2);
+        batch.set_atomic_op(false);
+        for key in [1i64, 150] {
+            batch.increment(
+                vec![Value::from(key)],
+                vec!["c3".to_owned()],
+                vec![Value::from(1i64)],
+            );
+        }
+        batch
+    };
+    let counter = |key: i64| {
+        cluster
+            .get_row(RANGE_TABLE, &[Value::from(key)])
+            .map(|row| row["c3"].as_i64())
+    };
+    let not_master = || {
+        Fault::error(ResultCodes::OB_NOT_MASTER)
+            .on(RequestKind::BatchExecute)
+            .table(RANGE_TABLE)
+            .server(1)
+            .times(1)
+    };
+
+    // the partition succeeded is not applied twice by a retry
+    cluster.inject(not_master());
+    let err = client
+        .execute_batch(RANGE_TABLE, increments(&client))
+        .await
+        .unwrap_err();
+    assert!(err.is_ambiguous_outcome(), "{err}");
+    assert_eq!(Some(1), counter(1));
+    assert_eq!(None, counter(150));
+
+    // the idempotent batches are retried
+    cluster.inject(not_master());
+    let mut batch = client.batch_operation(2);
+    batch.set_atomic_op(false);
+    for key in [1i64, 150] {
+        batch.insert_or_update(
+            vec![Value::from(key)],
+            vec!["c3".to_owned()],
+            vec![Value::from(10i64)],
+        );
+    }
+    client.execute_batch(RANGE_TABLE, batch).await.unwrap();
+    assert_eq!(Some(10), counter(1));
+    assert_eq!(Some(10), counter(150));
+
+    // retried anyway on demand
+    let config = ClientConfig {
+        retry_ambiguous_operations: true,
+        ..config
+    };
+    let client = build_client(&cluster, config).await;
+    cluster.inject(not_master());
+    client
+        .execute_batch(RANGE_TABLE, increments(&client))
+        .await
+        .unwrap();
+    assert_eq!(Some(12), counter(1));
+    assert_eq!(Some(11), counter(150));
+}
+
+#[tokio::test]
+async fn test_mock_circuit_breaker() {
+    let cluster = MockCluster::start(3, tables()).unwrap();
+    let config = ClientConfig {
+        rpc_operation_timeout: Duration::from_millis(200),
+        circuit_breaker: Some(CircuitBreakerConfig {
+            window_size: 10,
+            min_requests: 4,
+            failure_rate_percent: 50,
+            consecutive_failures: 3,
+            open_duration: Duration::from_millis(500),
+            half_open_probes: 2,
+        }),
+        ..Default::default()
+    };
+    let client = build_client(&cluster, config).await;
+    let get = |client: &ObTableClient| {
+        let client = client.clone();
+        async move {
+            client
+                .get(TABLE, vec![Value::from("k1")], vec!["c2".to_owned()])
+                .await
+        }
+    };
+    client
+        .insert(
+            TABLE,
+            vec![Value::from("k1")],
+            vec!["c2".to_owned()],
+            vec![Value::from("v1")],
+        )
+        .await
+        .unwrap();
+    let leader = cluster.leader(TABLE, 0).unwrap();
+    let leader_addr = &cluster.servers()[leader];
+    assert_eq!(
+        Some(CircuitState::Closed),
+        client.circuit_state(leader_addr)
+    );
+
+    // the timeouts of the leader open its breaker
+    cluster.inject(Fault::no_reply().on(RequestKind::Execute).server(leader));
+    for _ in 0..3 {
+        assert!(get(&client).await.is_err());
+    }
+    assert_eq!(Some(CircuitState::Open), client.circuit_state(leader_addr));
+
+    // the strong reads fail fast, the eventual ones are served by a follower
+    let start = Instant::now();
+    let err = get(&client).await.unwrap_err();
+    assert_eq!(
+        Some(CommonErrCode::CircuitBreakerOpen),
+        err.common_err_code(),
+        "{err}"
+    );
+    assert!(start.elapsed() < Duration::from_millis(100));
+    let served = |cluster: &MockCluster| {
+        (0..3)
+            .filter(|i| *i != leader)
+            .map(|i| cluster.served_requests(i))
+            .sum::<usize>()
+    };
+    let followers_served = served(&cluster);
+    let eventual = client.with_read_consistency(ObTableConsistencyLevel::Eventual);
+    let row = get(&eventual).await.unwrap();
+    assert_eq!(Value::from("v1"), row["c2"]);
+    assert!(served(&cluster) > followers_served);
+    let metrics = obkv::dump_metrics().unwrap();
+    assert!(metrics.contains("circuit_breaker_rejected"), "{metrics}");
+
+    // a failed probe opens the breaker again
+    tokio::time::sleep(Duration::from_millis(600)).await;
+    assert_eq!(
+        Some(CircuitState::HalfOpen),
+        client.circuit_state(leader_addr)
+    );
+    assert!(get(&client).await.is_err());
+    assert_eq!(Some(CircuitState::Open), client.circuit_state(leader_addr));
+
+    // the probes close it once the leader is back
+    cluster.clear_faults();
+    tokio::time::sleep(Duration::from_millis(600)).await;
+    for _ in 0..2 {
+        assert_eq!(Value::from("v1"), get(&client).await.unwrap()["c2"]);
+    }
+    assert_eq!(
+        Some(CircuitState::Closed),
+        client.circuit_state(leader_addr)
+    );
+}
+
+#[tokio::test]
+async fn test_mock_hedged_reads() {
+    let cluster = MockCluster::start(3, tables()).unwrap();
+    let config = ClientConfig {
+        rpc_operation_timeout: Duration::from_secs(5),
+        hedged_reads: Some(HedgeConfig {
+            min_delay: Duration::from_millis(50),
+            max_delay: Duration::from_millis(50),
+            max_query_limit: 10,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let client = build_client(&cluster, config).await;
+    let mut batch = client.batch_operation(10);
+    for i in 0..10 {
+        batch.insert(
+            vec![Value::from(format!("k{i}"))],
+            vec!["c2".to_owned()],
+            vec![Value::from(format!("v{i}"))],
+        );
+    }
+    client.execute_batch(TABLE, batch).await.unwrap();
+    let eventual = client.with_read_consistency(ObTableConsistencyLevel::Eventual);
+    let leader = cluster.leader(TABLE, 0).unwrap();
+    let slow_leader = |kind| {
+        Fault::delay(Duration::from_secs(2))
+            .on(kind)
+            .server(leader)
+            .times(1)
+    };
+
+    // the eventual get is answered by a follower
+    cluster.inject(slow_leader(RequestKind::Execute));
+    let start = Instant::now();
+    let row = eventual
+        .get(TABLE, vec![Value::from("k1")], vec!["c2".to_owned()])
+        .await
+        .unwrap();
+    assert_eq!(Value::from("v1"), row["c2"]);
+    assert!(start.elapsed() < Duration::from_secs(1));
+
+    // so are the multi-gets
+    cluster.inject(slow_leader(RequestKind::BatchExecute));
+    let mut batch = client.batch_operation(2);
+    batch.get(vec![Value::from("k2")], vec!["c2".to_owned()]);
+    batch.get(vec![Value::from("k3")], vec!["c2".to_owned()]);
+    let start = Instant::now();
+    let results = eventual.execute_batch(TABLE, batch).await.unwrap();
+    assert_eq!(2, results.len());
+    assert!(start.elapsed() < Duration::from_secs(1));
+
+    // and the small queries
+    let query = |client: &ObTableClient, limit| {
+        client
+            .query(TABLE)
+            .select(vec!["c2".to_owned()])
+            .primary_index()
+            .limit(None, limit)
+            .add_scan_range(vec![Value::get_min()], true, vec![Value::get_max()], true)
+    };
+    cluster.inject(slow_leader(RequestKind::ExecuteQuery));
+    let start = Instant::now();
+    let mut result_set = query(&eventual, 3).execute().await.unwrap();
+    let mut values = vec![];
+    while let Some(row) = result_set.next().await {
+        values.push(row.unwrap().remove("c2").unwrap().as_string());
+    }
+    assert_eq!(vec!["v0", "v1", "v2"], values);
+    assert!(start.elapsed() < Duration::from_secs(1));
+
+    // the larger queries and the strong reads wait for the leader
+    cluster.inject(slow_leader(RequestKind::ExecuteQuery));
+    let start = Instant::now();
+    let mut result_set = query(&eventual, 100).execute().await.unwrap();
+    result_set.close().await.unwrap();
+    assert!(start.elapsed() >= Duration::from_secs(2));
+    cluster.inject(slow_leader(RequestKind::Execute));
+    let start = Instant::now();
+    client
+        .get(TABLE, vec![Value::from("k1")], vec!["c2".to_owned()])
+        .await
+        .unwrap();
+    assert!(start.elapsed() >= Duration::from_secs(2));
+
+    let metrics = obkv::dump_metrics().unwrap();
+    assert!(metrics.contains(r#"hedged_read_type="Won""#), "{metrics}");
+}
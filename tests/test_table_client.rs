@@ -225,3 +225,69 @@ async fn test_obtable_client_batch_op() {
     assert!(value.is_string());
     assert_eq!("p4", value.as_string());
 }
+
+/// The values are written by sql and read by the client, so the lob header
+/// and the encodings of the server are decoded from real server bytes.
+#[tokio::test]
+async fn test_lob_and_misc_types_from_server() {
+    let client_handle = task::spawn_blocking(utils::common::build_normal_client);
+    let client = client_handle.await.unwrap();
+    const TABLE_NAME: &str = "TEST_LOB_AND_MISC_TYPES";
+    client
+        .execute_sql(&format!("drop table IF EXISTS {TABLE_NAME};"))
+        .expect("fail to drop table");
+    client
+        .execute_sql(&format!(
+            "create table {TABLE_NAME} (c1 varchar(20) NOT NULL, c2 text, c3 json, c4 bit(4), \
+             c5 enum('a', 'b', 'c'), c6 set('x', 'y', 'z'), PRIMARY KEY (c1));"
+        ))
+        .expect("fail to create table");
+    client
+        .execute_sql(&format!(
+            "insert into {TABLE_NAME} values ('foo', 'hello', '{{\"k\": [1, 2]}}', b'1011', 'b', 'x,z');"
+        ))
+        .expect("fail to insert");
+
+    let columns = ["c2", "c3", "c4", "c5", "c6"].map(str::to_owned).to_vec();
+    let mut result = client
+        .get(TABLE_NAME, vec![Value::from("foo")], columns.clone())
+        .await
+        .expect("fail to get");
+    assert_eq!(5, result.len());
+    assert_eq!("hello", result.remove("c2").unwrap().as_string());
+    let json: serde_json::Value =
+        serde_json::from_str(&result.remove("c3").unwrap().as_string()).unwrap();
+    assert_eq!(serde_json::json!({"k": [1, 2]}), json);
+    assert_eq!(0b1011, result.remove("c4").unwrap().as_u64());
+    assert_eq!(2, result.remove("c5").unwrap().as_u64());
+    assert_eq!(0b101, result.remove("c6").unwrap().as_u64());
+
+    // and written back by the client
+    let values = vec![
+        Value::from("world"),
+        Value::json(r#"{"k":"v"}"#),
+        Value::bit(0b0110, 4),
+        Value::enum_value(3),
+        Value::set_value(0b010),
+    ];
+    client
+        .insert(
+            TABLE_NAME,
+            vec![Value::from("bar")],
+            columns.clone(),
+            values,
+        )
+        .await
+        .expect("fail to insert");
+    let mut result = client
+        .get(TABLE_NAME, vec![Value::from("bar")], columns)
+        .await
+        .expect("fail to get");
+    assert_eq!("world", result.remove("c2").unwrap().as_string());
+    let json: serde_json::Value =
+        serde_json::from_str(&result.remove("c3").unwrap().as_string()).unwrap();
+    assert_eq!(serde_json::json!({"k": "v"}), json);
+    assert_eq!(0b0110, result.remove("c4").unwrap().as_u64());
+    assert_eq!(3, result.remove("c5").unwrap().as_u64());
+    assert_eq!(0b010, result.remove("c6").unwrap().as_u64());
+}
@@ -22,6 +22,7 @@ mod utils;
 
 use std::sync::Arc;
 
+use chrono::{Duration, NaiveDate, NaiveTime};
 use obkv::{ObTableClient, Value};
 use rand::{distributions::Alphanumeric, thread_rng, Rng};
 use serial_test_derive::serial;
@@ -472,6 +473,41 @@ async fn test_partition_varchar_general_ci() {
     }
 }
 
+#[tokio::test]
+async fn test_partition_routing() {
+    let client_handle = task::spawn_blocking(utils::common::build_normal_client);
+    let client = client_handle.await.unwrap();
+    const VARCHAR_TABLE_NAME: &str = "TEST_TABLE_PARTITION_VARCHAR_KEY";
+    client.add_row_key_element(VARCHAR_TABLE_NAME, vec!["c1".to_string()]);
+
+    let all = client
+        .partitions_for_range(VARCHAR_TABLE_NAME, &[Value::get_min()], &[Value::get_max()])
+        .await
+        .expect("fail to get partitions");
+    assert_eq!(16, all.len());
+
+    for i in 0..64 {
+        let row_key = vec![Value::from(format!("key{i}"))];
+        let id = client
+            .partition_of(VARCHAR_TABLE_NAME, &row_key)
+            .await
+            .expect("fail to get partition");
+        let (part_info, _) = client
+            .get_table(VARCHAR_TABLE_NAME, &row_key, false)
+            .await
+            .expect("fail to get table");
+        assert_eq!(part_info.part_id, id.tablet_id.unwrap_or(id.partition_id));
+
+        let partitions = client
+            .partitions_for_range(VARCHAR_TABLE_NAME, &row_key, &row_key)
+            .await
+            .expect("fail to get partitions");
+        assert_eq!(1, partitions.len());
+        assert_eq!(id, partitions[0].id);
+        assert!(all.contains(&partitions[0]));
+    }
+}
+
 #[tokio::test]
 async fn test_partition_complex() {
     let client_handle = task::spawn_blocking(utils::common::build_normal_client);
@@ -594,3 +630,153 @@ async fn test_sub_partition_complex() {
         assert_eq!(1, result.len());
     }
 }
+
+/// Each key is inserted by sql into the partition the client routes it to,
+/// which the server refuses if it puts the key in another partition.
+#[tokio::test]
+async fn test_key_partition_collations() {
+    let client_handle = task::spawn_blocking(utils::common::build_normal_client);
+    let client = client_handle.await.unwrap();
+    let keys = [
+        "key",
+        "KEY1 ",
+        "Key2",
+        "中文",
+        "中文KEY3",
+        "àéî",
+        "straße",
+        "😀",
+    ];
+    for (charset, collation) in [
+        ("utf8mb4", "utf8mb4_general_ci"),
+        ("utf8mb4", "utf8mb4_bin"),
+        ("utf8mb4", "utf8mb4_unicode_ci"),
+        ("gbk", "gbk_chinese_ci"),
+        ("gbk", "gbk_bin"),
+        ("gb18030", "gb18030_chinese_ci"),
+        ("gb18030", "gb18030_bin"),
+    ] {
+        let table_name = format!("TEST_KEY_PARTITION_{}", collation.to_uppercase());
+        client
+            .execute_sql(&format!("drop table IF EXISTS {table_name};"))
+            .expect("fail to drop table");
+        client
+            .execute_sql(&format!(
+                "create table {table_name} (c1 varchar(20) NOT NULL, c2 varchar(20) DEFAULT NULL, \
+                 PRIMARY KEY (c1)) DEFAULT CHARSET = {charset} COLLATE = {collation} \
+                 partition by key(c1) partitions 16;"
+            ))
+            .expect("fail to create table");
+        client.add_row_key_element(&table_name, vec!["c1".to_string()]);
+
+        for key in keys {
+            if charset == "gbk" && key == "😀" {
+                continue;
+            }
+            let partition = match client.partition_of(&table_name, &[Value::from(key)]).await {
+                Ok(partition) => partition,
+                // the weights of these collations are not ported, they are
+                // refused rather than misrouted
+                Err(_) if ["utf8mb4_unicode_ci", "gb18030_chinese_ci"].contains(&collation) => {
+                    continue
+                }
+                Err(e) => panic!("fail to route {key:?} in {collation}: {e}"),
+            };
+            let insert = format!(
+                "insert into {table_name} partition (p{}) (c1, c2) values ('{key}', 'v');",
+                partition.partition_id
+            );
+            if let Err(e) = client.execute_sql(&insert) {
+                panic!(
+                    "{key:?} in {collation} is not in p{}: {e}",
+                    partition.partition_id
+                );
+            }
+        }
+    }
+}
+
+/// Each temporal key is inserted by sql into the partition the client routes
+/// it to, which the server refuses if it hashes the key to another partition.
+#[tokio::test]
+#[serial]
+async fn test_key_partition_temporal() {
+    let client_handle = task::spawn_blocking(utils::common::build_normal_client);
+    let client = client_handle.await.unwrap();
+    let date = NaiveDate::from_ymd_opt(2023, 9, 1).unwrap();
+    let keys = [
+        (
+            "date",
+            vec![
+                (Value::from(date), "'2023-09-01'"),
+                (
+                    Value::from(NaiveDate::from_ymd_opt(1969, 12, 31).unwrap()),
+                    "'1969-12-31'",
+                ),
+            ],
+        ),
+        (
+            "year",
+            vec![
+                (Value::year(2023).unwrap(), "2023"),
+                (Value::year(1901).unwrap(), "1901"),
+            ],
+        ),
+        (
+            "datetime(6)",
+            vec![
+                (
+                    Value::from(date.and_hms_micro_opt(12, 34, 56, 789_012).unwrap()),
+                    "'2023-09-01 12:34:56.789012'",
+                ),
+                (
+                    Value::from(date.and_hms_opt(0, 0, 0).unwrap()),
+                    "'2023-09-01 00:00:00'",
+                ),
+            ],
+        ),
+        (
+            "time(6)",
+            vec![
+                (
+                    Value::from(NaiveTime::from_hms_micro_opt(12, 34, 56, 500_000).unwrap()),
+                    "'12:34:56.5'",
+                ),
+                (Value::from(Duration::hours(-30)), "'-30:00:00'"),
+            ],
+        ),
+    ];
+    for (column_type, keys) in keys {
+        let table_name = format!(
+            "TEST_KEY_PARTITION_{}",
+            column_type.trim_end_matches("(6)").to_uppercase()
+        );
+        client
+            .execute_sql(&format!("drop table IF EXISTS {table_name};"))
+            .expect("fail to drop table");
+        client
+            .execute_sql(&format!(
+                "create table {table_name} (c1 {column_type} NOT NULL, c2 varchar(20) DEFAULT NULL, \
+                 PRIMARY KEY (c1)) partition by key(c1) partitions 16;"
+            ))
+            .expect("fail to create table");
+        client.add_row_key_element(&table_name, vec!["c1".to_string()]);
+
+        for (key, literal) in keys {
+            let partition = client
+                .partition_of(&table_name, &[key])
+                .await
+                .unwrap_or_else(|e| panic!("fail to route {literal} of {column_type}: {e}"));
+            let insert = format!(
+                "insert into {table_name} partition (p{}) (c1, c2) values ({literal}, 'v');",
+                partition.partition_id
+            );
+            if let Err(e) = client.execute_sql(&insert) {
+                panic!(
+                    "{literal} of {column_type} is not in p{}: {e}",
+                    partition.partition_id
+                );
+            }
+        }
+    }
+}
@@ -297,3 +297,56 @@ async fn test_obtable_client_batch_atomic_op() {
     let result = client.execute_batch(TABLE_NAME_COMPLEX, batch_op).await;
     assert!(result.is_ok());
 }
+
+#[tokio::test]
+#[serial]
+async fn test_scan_splits() {
+    let client_handle = task::spawn_blocking(utils::common::build_normal_client);
+    let client = client_handle.await.unwrap();
+    const TABLE_NAME: &str = "TEST_QUERY_TABLE_RANGE";
+    client.add_row_key_element(TABLE_NAME, vec!["c1".to_string()]);
+
+    for key in ["A", "b", "x"] {
+        let _ = client.delete(TABLE_NAME, vec![Value::from(key)]).await;
+        client
+            .insert(
+                TABLE_NAME,
+                vec![Value::from(key)],
+                vec!["c2".to_owned()],
+                vec![Value::from("value")],
+            )
+            .await
+            .expect("fail to insert");
+    }
+
+    let splits = client
+        .scan_splits(TABLE_NAME, 3)
+        .await
+        .expect("fail to get scan splits");
+    assert_eq!(3, splits.len());
+
+    let mut total = 0;
+    for split in splits {
+        let partitions = client
+            .partitions_for_range(TABLE_NAME, &split.start, &split.start)
+            .await
+            .expect("fail to get partitions");
+        assert_eq!(vec![split.partition.clone()], partitions);
+
+        let result_set = client
+            .query(TABLE_NAME)
+            .select(vec!["c1".to_owned()])
+            .add_scan_range(
+                split.start,
+                split.start_inclusive,
+                split.end,
+                split.end_inclusive,
+            )
+            .partition(split.partition.id)
+            .execute()
+            .await
+            .expect("fail to query");
+        total += result_set.cache_size();
+    }
+    assert_eq!(3, total);
+}